@@ -0,0 +1,165 @@
+use crossbeam_channel as channel;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
+
+use crate::midi;
+
+#[derive(Clone)]
+pub enum Event {
+    MsgBatch(Arc<[midi::msg::Result]>),
+    /// Messages were dropped by the `backpressure::Policy::Summarize` policy.
+    MsgsSummarized {
+        port_nb: midi::PortNb,
+        count: u64,
+    },
+    PortsChanged(Arc<crate::ports::DirectionalPorts>),
+    Throughput(midi::throughput::Rates),
+    Error(Arc<anyhow::Error>),
+    /// The Midi backend couldn't be initialized, `None` once it is available.
+    BackendUnavailable(Option<Arc<anyhow::Error>>),
+}
+
+impl Event {
+    pub fn is_msg_batch(&self) -> bool {
+        matches!(self, Event::MsgBatch(_))
+    }
+
+    pub fn is_msgs_summarized(&self) -> bool {
+        matches!(self, Event::MsgsSummarized { .. })
+    }
+
+    pub fn is_ports_changed(&self) -> bool {
+        matches!(self, Event::PortsChanged(_))
+    }
+
+    pub fn is_throughput(&self) -> bool {
+        matches!(self, Event::Throughput(_))
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Event::Error(_))
+    }
+
+    pub fn is_backend_unavailable(&self) -> bool {
+        matches!(self, Event::BackendUnavailable(_))
+    }
+}
+
+/// Calls `handle` with the `Origin` of each message in the batches pending on `rx`.
+///
+/// Other events are discarded.
+pub fn drain_msgs(rx: &channel::Receiver<Event>, mut handle: impl FnMut(&midi::msg::Origin)) {
+    for event in rx.try_iter() {
+        if let Event::MsgBatch(batch) = event {
+            for res in batch.iter() {
+                handle(midi::msg::origin(res));
+            }
+        }
+    }
+}
+
+/// Selects the `Event`s a subscriber is interested in.
+pub type Filter = fn(&Event) -> bool;
+
+enum Sink {
+    Sync(channel::Sender<Event>),
+    #[cfg(feature = "net")]
+    Async(tokio::sync::mpsc::UnboundedSender<Event>),
+    #[cfg(feature = "stream")]
+    Stream(futures::channel::mpsc::UnboundedSender<Event>),
+}
+
+impl Sink {
+    /// Sends the `event`, returns `false` if the receiver is gone.
+    fn send(&self, event: Event) -> bool {
+        match self {
+            Sink::Sync(tx) => tx.send(event).is_ok(),
+            #[cfg(feature = "net")]
+            Sink::Async(tx) => tx.send(event).is_ok(),
+            #[cfg(feature = "stream")]
+            Sink::Stream(tx) => tx.unbounded_send(event).is_ok(),
+        }
+    }
+}
+
+struct Subscriber {
+    filter: Filter,
+    sink: Sink,
+    /// The receiver is gone.
+    is_gone: AtomicBool,
+}
+
+/// Event bus between the Controller and the UI panels.
+///
+/// Publishers push `Event`s which are forwarded to the subscribers
+/// whose `Filter` accepts them.
+///
+/// Each subscriber gets its own lock-free channel, which it drains at its
+/// own pace, e.g. on each UI update. Publishers only share a read lock on
+/// the subscribers list, so they don't block each other.
+#[derive(Clone, Default)]
+pub struct Bus {
+    subscribers: Arc<RwLock<Vec<Subscriber>>>,
+}
+
+impl Bus {
+    pub fn subscribe(&self, filter: Filter) -> channel::Receiver<Event> {
+        let (tx, rx) = channel::unbounded();
+        self.add_subscriber(filter, Sink::Sync(tx));
+
+        rx
+    }
+
+    /// Subscribes from an async context, e.g. a network transport.
+    #[cfg(feature = "net")]
+    pub fn subscribe_async(&self, filter: Filter) -> tokio::sync::mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.add_subscriber(filter, Sink::Async(tx));
+
+        rx
+    }
+
+    /// Subscribes with a runtime agnostic `futures` channel.
+    #[cfg(feature = "stream")]
+    pub fn subscribe_stream(
+        &self,
+        filter: Filter,
+    ) -> futures::channel::mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        self.add_subscriber(filter, Sink::Stream(tx));
+
+        rx
+    }
+
+    fn add_subscriber(&self, filter: Filter, sink: Sink) {
+        self.subscribers.write().unwrap().push(Subscriber {
+            filter,
+            sink,
+            is_gone: AtomicBool::new(false),
+        });
+    }
+
+    pub fn publish(&self, event: Event) {
+        let mut has_gone = false;
+        for sub in self.subscribers.read().unwrap().iter() {
+            if (sub.filter)(&event) && !sub.sink.send(event.clone()) {
+                sub.is_gone.store(true, Ordering::Relaxed);
+                has_gone = true;
+            }
+        }
+
+        if has_gone {
+            self.subscribers
+                .write()
+                .unwrap()
+                .retain(|sub| !sub.is_gone.load(Ordering::Relaxed));
+        }
+    }
+
+    pub fn publish_err(&self, err: anyhow::Error) {
+        log::error!("{err}");
+        self.publish(Event::Error(Arc::new(err)));
+    }
+}
@@ -0,0 +1,140 @@
+use std::{borrow::Cow, fmt, num::ParseIntError};
+
+/// How `Displayable` renders each byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Radix {
+    /// e.g. `f0`.
+    #[default]
+    Hex,
+    /// e.g. `0xf0`.
+    PrefixedHex,
+    Decimal,
+    /// e.g. `11110000`, useful with bit-packed SysEx payloads.
+    Binary,
+}
+
+impl Radix {
+    pub const ALL: [Radix; 4] = [
+        Radix::Hex,
+        Radix::PrefixedHex,
+        Radix::Decimal,
+        Radix::Binary,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Radix::Hex => "hex",
+            Radix::PrefixedHex => "0x hex",
+            Radix::Decimal => "decimal",
+            Radix::Binary => "binary",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Radix::Hex => "hex",
+            Radix::PrefixedHex => "prefixed_hex",
+            Radix::Decimal => "decimal",
+            Radix::Binary => "binary",
+        }
+    }
+
+    pub fn from_storage_str(radix: &str) -> Option<Self> {
+        Radix::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == radix)
+    }
+
+    /// Writes `val` padded to the width of the largest byte, so that bytes line up.
+    fn write(self, w: &mut impl fmt::Write, val: u8) -> fmt::Result {
+        match self {
+            Radix::Hex => write!(w, "{:02x}", val),
+            Radix::PrefixedHex => write!(w, "0x{:02x}", val),
+            Radix::Decimal => write!(w, "{:3}", val),
+            Radix::Binary => write!(w, "{:08b}", val),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Displayable<'a> {
+    bytes: Cow<'a, [u8]>,
+    radix: Radix,
+}
+
+impl<'a> From<&'a [u8]> for Displayable<'a> {
+    fn from(msg: &'a [u8]) -> Self {
+        Self {
+            bytes: Cow::Borrowed(msg),
+            radix: Radix::default(),
+        }
+    }
+}
+
+impl From<Box<[u8]>> for Displayable<'static> {
+    fn from(msg: Box<[u8]>) -> Self {
+        Self {
+            bytes: Cow::Owned(msg.into()),
+            radix: Radix::default(),
+        }
+    }
+}
+
+impl<'a> Displayable<'a> {
+    pub fn to_owned(&self) -> Displayable<'static> {
+        Displayable::from(Box::<[u8]>::from(self.bytes.as_ref())).radix(self.radix)
+    }
+
+    #[must_use]
+    pub fn radix(mut self, radix: Radix) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    /// Formats the bytes as `Display` does, with an extra space after each
+    /// `group_len` bytes, so that the groups line up in a monospace font.
+    pub fn grouped(&self, group_len: usize) -> String {
+        use std::fmt::Write;
+
+        let mut grouped = String::with_capacity(self.bytes.len() * 4);
+        for (idx, val) in self.bytes.iter().enumerate() {
+            if idx > 0 {
+                grouped.push_str(", ");
+                if group_len > 0 && idx % group_len == 0 {
+                    grouped.push(' ');
+                }
+            }
+            let _ = self.radix.write(&mut grouped, *val);
+        }
+
+        grouped
+    }
+}
+
+impl<'a> fmt::Display for Displayable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.bytes.iter();
+
+        match iter.next() {
+            Some(first) => self.radix.write(f, *first)?,
+            None => return Ok(()),
+        };
+
+        for val in iter {
+            f.write_str(", ")?;
+            self.radix.write(f, *val)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses bytes formatted as hex values, as `Displayable` does.
+///
+/// Values can be separated with commas and / or spaces.
+pub fn parse_hex(hex: &str) -> Result<Box<[u8]>, ParseIntError> {
+    hex.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|val| !val.is_empty())
+        .map(|val| u8::from_str_radix(val, 16))
+        .collect()
+}
@@ -0,0 +1,697 @@
+//! The capture engine.
+//!
+//! The Controller owns the `midi::Ports`, handles the `Request`s and publishes
+//! the captured messages as well as the ports changes on the `bus::Bus`.
+//!
+//! ```no_run
+//! use midi_sniffer_core::{bus, controller, midi};
+//! use std::sync::Arc;
+//!
+//! let bus = bus::Bus::default();
+//! let msg_rx = bus.subscribe(bus::Event::is_msg_batch);
+//! let (req_tx, req_rx) = crossbeam_channel::unbounded();
+//!
+//! controller::Spawner {
+//!     req_rx,
+//!     bus,
+//!     client_name: "my tool".into(),
+//!     backend: Default::default(),
+//!     backpressure: Arc::new(midi::backpressure::State::new(Default::default())),
+//!     batching: Default::default(),
+//!     notifier: Arc::new(|| ()),
+//! }
+//! .spawn();
+//!
+//! req_tx
+//!     .send(controller::Request::Connect((midi::PortNb::One, "My device".into())))
+//!     .unwrap();
+//!
+//! for event in msg_rx.iter() {
+//!     if let bus::Event::MsgBatch(batch) = event {
+//!         println!("{batch:?}");
+//!     }
+//! }
+//! ```
+
+use anyhow::Context;
+use crossbeam_channel as channel;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+};
+use std::{ops::ControlFlow, sync::Arc, time::Duration};
+
+use crate::{
+    bus,
+    midi::{self, backpressure, batching, throughput},
+    ports,
+    time::Instant,
+};
+
+/// Capacity of the channel between the Midi callbacks and the Controller.
+const MIDI_CHANNEL_CAPACITY: usize = 8192;
+
+/// Interval between two port list refreshes, used to detect (re)appearing ports.
+const PORTS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval between two throughput updates.
+const THROUGHPUT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Interval between two attempts to initialize an unavailable Midi backend.
+#[cfg(not(target_arch = "wasm32"))]
+const BACKEND_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum number of consecutive Controller restarts after a panic.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RESTARTS: usize = 5;
+/// Running time after which a Controller is considered stable, resetting the restart count.
+#[cfg(not(target_arch = "wasm32"))]
+const STABLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Delay before restarting the Controller after a panic.
+#[cfg(not(target_arch = "wasm32"))]
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+/// Requests handled by the Controller.
+#[derive(Debug)]
+pub enum Request {
+    Connect((midi::PortNb, Arc<str>)),
+    Disconnect(midi::PortNb),
+    RefreshPorts,
+    SetBackend(midi::port::Backend),
+    /// Sets the pattern of the ports to connect to automatically.
+    SetPortPattern((midi::PortNb, Option<midi::PortPattern>)),
+    Shutdown,
+}
+
+/// Called when something changed, e.g. to request a UI repaint.
+pub type Notifier = Arc<dyn Fn() + Send + Sync>;
+
+pub struct Spawner {
+    pub req_rx: channel::Receiver<Request>,
+    pub bus: bus::Bus,
+    pub client_name: Arc<str>,
+    pub backend: midi::port::Backend,
+    #[cfg(feature = "agent")]
+    pub agents: Vec<std::net::SocketAddr>,
+    pub backpressure: Arc<backpressure::State>,
+    pub batching: Arc<batching::State>,
+    pub notifier: Notifier,
+}
+
+impl Spawner {
+    /// Returns a `Spawner` with the default backend & backpressure policy.
+    pub fn new(req_rx: channel::Receiver<Request>, bus: bus::Bus, client_name: Arc<str>) -> Self {
+        Spawner {
+            req_rx,
+            bus,
+            client_name,
+            backend: Default::default(),
+            #[cfg(feature = "agent")]
+            agents: Vec::new(),
+            backpressure: Arc::new(backpressure::State::new(Default::default())),
+            batching: Default::default(),
+            notifier: Arc::new(|| ()),
+        }
+    }
+
+    /// Starts the Controller on the UI thread, see `Local`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn start_local(self) -> Option<Local> {
+        let bus = self.bus.clone();
+        let (mut controller, midi_rx) = Controller::try_new(
+            self.bus,
+            self.client_name,
+            self.backend,
+            #[cfg(feature = "agent")]
+            self.agents,
+            self.backpressure,
+            self.batching,
+            self.notifier.clone(),
+        )
+        .map_err(|err| {
+            bus.publish_err(err);
+            (self.notifier)();
+        })
+        .ok()?;
+        controller.start(Vec::new());
+
+        let now = Instant::now();
+        Some(Local {
+            controller,
+            req_rx: self.req_rx,
+            midi_rx,
+            next_refresh: now + PORTS_REFRESH_INTERVAL,
+            next_throughput: now + THROUGHPUT_INTERVAL,
+        })
+    }
+
+    /// Spawns the Controller thread.
+    ///
+    /// The Controller is watched: if it panics, the error is reported to the UI
+    /// and the Controller is restarted, reconnecting previously connected ports.
+    /// It is given up on after `MAX_RESTARTS` panics in a row, i.e. each within
+    /// `STABLE_INTERVAL` of its start.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn(self) -> std::thread::JoinHandle<()> {
+        std::thread::spawn(move || {
+            let ports_rx = self.bus.subscribe(bus::Event::is_ports_changed);
+            let mut last_ports = None;
+            let mut backend = self.backend;
+            let mut reconnect = Vec::new();
+            let mut restarts = 0;
+
+            loop {
+                let started = Instant::now();
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    Controller::run(
+                        self.req_rx.clone(),
+                        self.bus.clone(),
+                        self.client_name.clone(),
+                        backend,
+                        #[cfg(feature = "agent")]
+                        self.agents.clone(),
+                        self.backpressure.clone(),
+                        self.batching.clone(),
+                        self.notifier.clone(),
+                        std::mem::take(&mut reconnect),
+                    )
+                }));
+
+                let payload = match res {
+                    Ok(()) => break,
+                    Err(payload) => payload,
+                };
+
+                let reason = panic_reason(payload.as_ref());
+
+                if started.elapsed() >= STABLE_INTERVAL {
+                    // Only give up on consecutive panics
+                    restarts = 0;
+                }
+                restarts += 1;
+                if restarts > MAX_RESTARTS {
+                    self.bus.publish_err(anyhow::anyhow!(
+                        "Controller panicked: {reason}. Too many restarts, giving up"
+                    ));
+                    (self.notifier)();
+                    break;
+                }
+
+                self.bus
+                    .publish_err(anyhow::anyhow!("Controller panicked: {reason}. Restarting"));
+                (self.notifier)();
+
+                if let Some(bus::Event::PortsChanged(ports)) = ports_rx.try_iter().last() {
+                    last_ports = Some(ports);
+                }
+                if let Some(ref ports) = last_ports {
+                    backend = ports.backend;
+                    reconnect = ports.desired_ports().collect();
+                }
+
+                std::thread::sleep(RESTART_DELAY);
+                log::info!("Restarting Controller ({restarts}/{MAX_RESTARTS})");
+            }
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn panic_reason(payload: &(dyn Any + Send)) -> &str {
+    if let Some(reason) = payload.downcast_ref::<&str>() {
+        reason
+    } else if let Some(reason) = payload.downcast_ref::<String>() {
+        reason.as_str()
+    } else {
+        "unknown reason"
+    }
+}
+
+struct Controller {
+    bus: bus::Bus,
+
+    midi_tx: backpressure::Sender,
+    batching: Arc<batching::State>,
+    parser: midi::msg::Parser,
+    /// Messages waiting for the batch to fill up, see `batching::Settings::batch_delay`.
+    pending: Vec<midi::msg::Origin>,
+    /// When the pending batch must be published.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    batch_deadline: Option<Instant>,
+
+    midi_ports: midi::Ports,
+    ports: ports::DirectionalPorts,
+
+    throughput: throughput::Meter,
+    last_rates: throughput::Rates,
+
+    must_repaint: bool,
+    last_repaint: Instant,
+    notifier: Notifier,
+}
+
+impl Controller {
+    /// Runs the Controller until it is requested to shut down.
+    ///
+    /// If the Midi backend can't be initialized, e.g. no ALSA sequencer,
+    /// initialization is retried until the backend shows up.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    fn run(
+        req_rx: channel::Receiver<Request>,
+        bus: bus::Bus,
+        client_name: Arc<str>,
+        mut backend: midi::port::Backend,
+        #[cfg(feature = "agent")] agents: Vec<std::net::SocketAddr>,
+        backpressure: Arc<backpressure::State>,
+        batching: Arc<batching::State>,
+        notifier: Notifier,
+        mut reconnect: Vec<(midi::PortNb, Arc<str>)>,
+    ) {
+        let mut patterns = Vec::new();
+        let mut is_unavailable = false;
+
+        let (mut this, midi_rx) = loop {
+            let res = Self::try_new(
+                bus.clone(),
+                client_name.clone(),
+                backend,
+                #[cfg(feature = "agent")]
+                agents.clone(),
+                backpressure.clone(),
+                batching.clone(),
+                notifier.clone(),
+            );
+            let err = match res {
+                Ok(res) => break res,
+                Err(err) => err,
+            };
+
+            if !is_unavailable {
+                log::error!("{err:#}");
+                bus.publish(bus::Event::BackendUnavailable(Some(Arc::new(err))));
+                notifier();
+                is_unavailable = true;
+            }
+
+            // Keep track of the requests until the backend shows up
+            match req_rx.recv_timeout(BACKEND_RETRY_INTERVAL) {
+                Ok(Request::Connect((port_nb, port_name))) => {
+                    reconnect.retain(|(cur, _)| *cur != port_nb);
+                    reconnect.push((port_nb, port_name));
+                }
+                Ok(Request::Disconnect(port_nb)) => {
+                    reconnect.retain(|(cur, _)| *cur != port_nb);
+                }
+                Ok(Request::SetBackend(new_backend)) => backend = new_backend,
+                Ok(Request::SetPortPattern(pattern)) => patterns.push(pattern),
+                Ok(Request::RefreshPorts) | Err(channel::RecvTimeoutError::Timeout) => (),
+                Ok(Request::Shutdown) | Err(channel::RecvTimeoutError::Disconnected) => return,
+            }
+        };
+
+        if is_unavailable {
+            log::info!("Midi backend is now available");
+            bus.publish(bus::Event::BackendUnavailable(None));
+            this.must_repaint = true;
+        }
+        for (port_nb, pattern) in patterns {
+            this.midi_ports.set_pattern(port_nb, pattern);
+        }
+
+        this.run_loop(req_rx, midi_rx, reconnect);
+    }
+
+    fn try_new(
+        bus: bus::Bus,
+        client_name: Arc<str>,
+        backend: midi::port::Backend,
+        #[cfg(feature = "agent")] agents: Vec<std::net::SocketAddr>,
+        backpressure: Arc<backpressure::State>,
+        batching: Arc<batching::State>,
+        notifier: Notifier,
+    ) -> anyhow::Result<(Self, backpressure::Receiver)> {
+        #[allow(unused_mut)]
+        let mut midi_ports = midi::Ports::try_new(client_name, backend)
+            .context("Failed to initialize the Midi backend")?;
+        #[cfg(feature = "agent")]
+        midi_ports.set_agents(agents);
+
+        let (midi_tx, midi_rx) = backpressure::bounded(MIDI_CHANNEL_CAPACITY, backpressure);
+
+        let this = Self {
+            bus,
+
+            midi_tx,
+            batching,
+            parser: midi::msg::Parser::default(),
+            pending: Vec::new(),
+            batch_deadline: None,
+
+            midi_ports,
+            ports: ports::DirectionalPorts::default(),
+
+            throughput: throughput::Meter::default(),
+            last_rates: throughput::Rates::default(),
+
+            must_repaint: false,
+            last_repaint: Instant::now(),
+            notifier,
+        };
+
+        Ok((this, midi_rx))
+    }
+
+    fn report_err(&mut self, err: anyhow::Error) {
+        self.bus.publish_err(err);
+        self.must_repaint = true;
+    }
+
+    fn handle(&mut self, request: Request) -> anyhow::Result<ControlFlow<(), ()>> {
+        use Request::*;
+        match request {
+            Connect((port_nb, port_name)) => self.connect(port_nb, port_name)?,
+            Disconnect(port_nb) => self.disconnect(port_nb)?,
+            RefreshPorts => self.refresh_ports()?,
+            SetBackend(backend) => self.set_backend(backend)?,
+            SetPortPattern((port_nb, pattern)) => {
+                self.midi_ports.set_pattern(port_nb, pattern);
+                self.refresh_ports()?;
+            }
+            Shutdown => return Ok(ControlFlow::Break(())),
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn connect(&mut self, port_nb: midi::PortNb, port_name: Arc<str>) -> anyhow::Result<()> {
+        let midi_tx = self.midi_tx.clone();
+        // The pending SysEx, if any, is flushed when the port is disconnected
+        let mut reassembling = midi::syx::Reassembling::new(move |ts, buf: &[u8]| {
+            midi_tx.send(midi::msg::Origin::new(ts, port_nb, buf));
+        });
+        let callback = move |ts, buf: &[u8]| reassembling.push(ts, buf);
+
+        self.midi_ports.connect(port_nb, port_name, callback)?;
+        self.parser.reset(port_nb);
+        self.refresh_ports()?;
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self, port_nb: midi::PortNb) -> anyhow::Result<()> {
+        self.midi_ports.disconnect(port_nb)?;
+        self.parser.reset(port_nb);
+        self.refresh_ports()?;
+
+        Ok(())
+    }
+
+    /// Reinitializes the Midi ports with `backend`.
+    ///
+    /// Ports which are also available with the new backend are reconnected.
+    fn set_backend(&mut self, backend: midi::port::Backend) -> anyhow::Result<()> {
+        if backend == self.midi_ports.backend() {
+            return Ok(());
+        }
+
+        let res = midi::Ports::try_new(self.midi_ports.client_name.clone(), backend)
+            .with_context(|| format!("Failed to switch to {}", backend.as_str()));
+        let mut midi_ports = match res {
+            Ok(midi_ports) => midi_ports,
+            Err(err) => {
+                // Let subscribers know which backend is still in use
+                self.bus
+                    .publish(bus::Event::PortsChanged(Arc::new(self.ports.clone())));
+                return Err(err);
+            }
+        };
+        #[cfg(feature = "agent")]
+        midi_ports.set_agents(self.midi_ports.agents().to_vec());
+        for port_nb in [midi::PortNb::One, midi::PortNb::Two] {
+            midi_ports.set_pattern(port_nb, self.midi_ports.pattern(port_nb).cloned());
+        }
+
+        let desired: Vec<_> = [midi::PortNb::One, midi::PortNb::Two]
+            .into_iter()
+            .filter_map(|port_nb| Some((port_nb, self.midi_ports.desired(port_nb)?.clone())))
+            .collect();
+        for port_nb in [midi::PortNb::One, midi::PortNb::Two] {
+            self.midi_ports.disconnect(port_nb)?;
+        }
+
+        log::info!("Switching to {}", backend.as_str());
+        self.midi_ports = midi_ports;
+        self.refresh_ports()?;
+
+        for (port_nb, port_name) in desired {
+            if self.midi_ports.list().any(|name| *name == port_name) {
+                self.connect(port_nb, port_name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn refresh_ports(&mut self) -> anyhow::Result<()> {
+        let reconnectable = self
+            .midi_ports
+            .refresh()
+            .context("Failed to refresh ports")?;
+
+        for (port_nb, port_name) in reconnectable {
+            log::info!("Reconnecting {port_nb} to {port_name}");
+            if let Err(err) = self.connect(port_nb, port_name) {
+                self.report_err(err);
+            }
+        }
+
+        if self.ports.update_from(&self.midi_ports) {
+            self.bus
+                .publish(bus::Event::PortsChanged(Arc::new(self.ports.clone())));
+            self.must_repaint = true;
+        }
+
+        Ok(())
+    }
+
+    /// Publishes the messages summarized by the backpressure policy, if any.
+    fn publish_summary(&mut self) {
+        for (port_nb, count) in self.midi_tx.take_summary() {
+            self.bus
+                .publish(bus::Event::MsgsSummarized { port_nb, count });
+            self.must_repaint = true;
+        }
+    }
+
+    /// Publishes the throughput rates, unless they are still idle.
+    fn update_throughput(&mut self) {
+        // The flood might have stopped right after the last drops
+        self.publish_summary();
+
+        let rates = self.throughput.rates();
+        if rates == self.last_rates && rates.iter().all(throughput::Rate::is_idle) {
+            return;
+        }
+
+        self.bus.publish(bus::Event::Throughput(rates));
+        self.last_rates = rates;
+        self.must_repaint = true;
+    }
+
+    fn start(&mut self, reconnect: Vec<(midi::PortNb, Arc<str>)>) {
+        if let Err(err) = self.refresh_ports() {
+            self.report_err(err);
+        }
+
+        for (port_nb, port_name) in reconnect {
+            log::info!("Reconnecting {port_nb} to {port_name}");
+            if let Err(err) = self.connect(port_nb, port_name) {
+                self.report_err(err);
+            }
+        }
+
+        // Subscribers waiting for the initial ports get them even if there are none
+        self.bus
+            .publish(bus::Event::PortsChanged(Arc::new(self.ports.clone())));
+    }
+
+    fn handle_request(&mut self, request: Request) -> ControlFlow<(), ()> {
+        match self.handle(request) {
+            Ok(flow) => flow,
+            Err(err) => {
+                self.report_err(err);
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    /// Adds `origin` & the messages available on `midi_rx` to the batch.
+    ///
+    /// The batch is published once it is full or the batch delay has elapsed.
+    fn handle_midi(
+        &mut self,
+        origin: midi::msg::Origin,
+        midi_rx: &channel::Receiver<midi::msg::Origin>,
+    ) {
+        let settings = self.batching.settings();
+
+        self.pending.push(origin);
+        let room = settings.batch_size.saturating_sub(self.pending.len());
+        self.pending.extend(midi_rx.try_iter().take(room));
+
+        // Without threads, the batch delay would delay the whole UI.
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.pending.len() < settings.batch_size && !settings.batch_delay.is_zero() {
+            // The run loop handles the requests while the batch fills up
+            let deadline = *self
+                .batch_deadline
+                .get_or_insert_with(|| Instant::now() + settings.batch_delay);
+            if Instant::now() < deadline {
+                return;
+            }
+        }
+
+        self.publish_batch();
+    }
+
+    /// Publishes the pending messages, if any.
+    fn publish_batch(&mut self) {
+        self.batch_deadline = None;
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let mut batch: Vec<midi::msg::Result> = Vec::with_capacity(self.pending.len());
+        for origin in self.pending.drain(..) {
+            self.throughput.record(origin.port_nb, origin.buffer.len());
+            self.parser.parse_resync(origin, &mut batch);
+        }
+
+        self.bus.publish(bus::Event::MsgBatch(batch.into()));
+        self.publish_summary();
+
+        if self.batching.repaints_on_msgs() {
+            self.must_repaint = true;
+        }
+    }
+
+    fn tick_refresh(&mut self) {
+        if let Err(err) = self.refresh_ports() {
+            self.report_err(err);
+        }
+        // Also refreshes the session timer in the window title
+        self.must_repaint = true;
+    }
+
+    /// Returns the delay before the pending repaint can be requested, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pending_repaint_delay(&self) -> Option<Duration> {
+        self.must_repaint
+            .then(|| batching::REPAINT_INTERVAL.saturating_sub(self.last_repaint.elapsed()))
+    }
+
+    /// Requests a repaint if needed, coalescing the requests within `REPAINT_INTERVAL`.
+    fn repaint_if_needed(&mut self) {
+        if self.must_repaint && self.last_repaint.elapsed() >= batching::REPAINT_INTERVAL {
+            (self.notifier)();
+            self.must_repaint = false;
+            self.last_repaint = Instant::now();
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_loop(
+        mut self,
+        req_rx: channel::Receiver<Request>,
+        midi_rx: backpressure::Receiver,
+        reconnect: Vec<(midi::PortNb, Arc<str>)>,
+    ) {
+        self.start(reconnect);
+
+        let refresh_ticker = channel::tick(PORTS_REFRESH_INTERVAL);
+        let throughput_ticker = channel::tick(THROUGHPUT_INTERVAL);
+
+        loop {
+            let repaint_timer = self
+                .pending_repaint_delay()
+                .map_or_else(channel::never, channel::after);
+            let batch_timer = self.batch_deadline.map_or_else(channel::never, channel::at);
+
+            channel::select! {
+                recv(req_rx) -> request =>  {
+                    match request {
+                        Ok(request) => if self.handle_request(request).is_break() {
+                            break;
+                        }
+                        Err(err) => {
+                            log::error!("Error UI request channel: {err}");
+                            break;
+                        }
+                    }
+                }
+                recv(*midi_rx) -> midi_msg =>  {
+                    match midi_msg {
+                        Ok(origin) => self.handle_midi(origin, &midi_rx),
+                        Err(err) => {
+                            log::error!("Error MIDI message channel: {err}");
+                            break;
+                        }
+                    }
+                }
+                recv(refresh_ticker) -> _ => self.tick_refresh(),
+                recv(throughput_ticker) -> _ => self.update_throughput(),
+                recv(batch_timer) -> _ => self.publish_batch(),
+                recv(repaint_timer) -> _ => (),
+            }
+
+            self.repaint_if_needed();
+        }
+
+        log::debug!("Shutting down Sniffer Controller loop");
+    }
+}
+
+/// Runs the Controller on the UI thread, for targets without threads.
+#[cfg(target_arch = "wasm32")]
+pub struct Local {
+    controller: Controller,
+    req_rx: channel::Receiver<Request>,
+    midi_rx: backpressure::Receiver,
+    next_refresh: Instant,
+    next_throughput: Instant,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Local {
+    /// Handles the pending requests, messages and timers.
+    ///
+    /// Returns `false` once the Controller is shut down.
+    pub fn poll(&mut self) -> bool {
+        for request in self.req_rx.try_iter() {
+            if self.controller.handle_request(request).is_break() {
+                log::debug!("Shutting down Sniffer Controller");
+                return false;
+            }
+        }
+
+        while let Ok(origin) = self.midi_rx.try_recv() {
+            self.controller.handle_midi(origin, &self.midi_rx);
+        }
+
+        let now = Instant::now();
+        if now >= self.next_refresh {
+            self.controller.tick_refresh();
+            self.next_refresh = now + PORTS_REFRESH_INTERVAL;
+        }
+        if now >= self.next_throughput {
+            self.controller.update_throughput();
+            self.next_throughput = now + THROUGHPUT_INTERVAL;
+        }
+
+        self.controller.repaint_if_needed();
+
+        true
+    }
+}
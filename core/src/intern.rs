@@ -0,0 +1,119 @@
+//! Interning of the strings & buffers which repeat across a capture.
+//!
+//! Captures are usually dominated by a few distinct messages, e.g. Timing Clock,
+//! so sharing their descriptions saves most of the memory of large captures.
+
+use std::{collections::HashSet, sync::Arc};
+
+/// Longer values are unlikely to repeat, e.g. SysEx dumps, and are not interned.
+const MAX_INTERNED_LEN: usize = 64;
+/// Values are no longer interned past this number of distinct values.
+const MAX_ENTRIES: usize = 1 << 16;
+
+#[derive(Debug, Default)]
+pub struct Interner {
+    strs: HashSet<Arc<str>>,
+    bufs: HashSet<Arc<[u8]>>,
+}
+
+impl Interner {
+    /// Returns a shared `Arc<str>` equal to `s`.
+    pub fn str(&mut self, s: &str) -> Arc<str> {
+        intern(&mut self.strs, s)
+    }
+
+    /// Returns a shared `Arc<[u8]>` equal to `buf`.
+    pub fn buf(&mut self, buf: &Arc<[u8]>) -> Arc<[u8]> {
+        if buf.len() > MAX_INTERNED_LEN {
+            return buf.clone();
+        }
+
+        intern(&mut self.bufs, buf.as_ref())
+    }
+
+    /// Number of distinct values interned.
+    pub fn len(&self) -> usize {
+        self.strs.len() + self.bufs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.strs.clear();
+        self.bufs.clear();
+    }
+}
+
+fn intern<T>(set: &mut HashSet<Arc<T>>, val: &T) -> Arc<T>
+where
+    T: ?Sized + Eq + std::hash::Hash,
+    Arc<T>: for<'a> From<&'a T>,
+{
+    if let Some(interned) = set.get(val) {
+        return interned.clone();
+    }
+
+    let val = Arc::<T>::from(val);
+    if std::mem::size_of_val(val.as_ref()) <= MAX_INTERNED_LEN && set.len() < MAX_ENTRIES {
+        set.insert(val.clone());
+    }
+
+    val
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup() {
+        let mut interner = Interner::default();
+
+        let note_on = interner.str("Note On");
+        assert!(Arc::ptr_eq(&note_on, &interner.str("Note On")));
+        assert!(!Arc::ptr_eq(&note_on, &interner.str("Note Off")));
+
+        let clock: Arc<[u8]> = Arc::from([0xf8].as_slice());
+        let interned = interner.buf(&clock);
+        assert!(Arc::ptr_eq(
+            &interned,
+            &interner.buf(&Arc::from([0xf8].as_slice()))
+        ));
+
+        assert_eq!(interner.len(), 3);
+    }
+
+    #[test]
+    fn long_values() {
+        let mut interner = Interner::default();
+
+        let sysex: Arc<[u8]> = vec![0; MAX_INTERNED_LEN + 1].into();
+        assert!(Arc::ptr_eq(&interner.buf(&sysex), &sysex));
+
+        let desc = "x".repeat(MAX_INTERNED_LEN + 1);
+        let first = interner.str(&desc);
+        assert!(!Arc::ptr_eq(&first, &interner.str(&desc)));
+
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut interner = Interner::default();
+
+        let note_on = interner.str("Note On");
+        interner.buf(&Arc::from([0xf8].as_slice()));
+        assert!(!interner.is_empty());
+
+        interner.clear();
+        assert!(interner.is_empty());
+
+        // Values are interned again after a clear
+        let new_note_on = interner.str("Note On");
+        assert_eq!(note_on, new_note_on);
+        assert!(!Arc::ptr_eq(&note_on, &new_note_on));
+        assert_eq!(interner.len(), 1);
+    }
+}
@@ -0,0 +1,30 @@
+//! Capture engine of `midi-sniffer`.
+//!
+//! This crate lists and captures Midi ports, parses and annotates the messages
+//! and formats them for humans, so that other tools can embed the sniffer.
+//!
+//! - `controller`: the capture engine, driven with `controller::Request`s.
+//! - `bus`: the `Event`s published by the capture engine.
+//! - `midi`: port management, parsing and analysis of the Midi messages.
+//! - `format`: human readable descriptions of the Midi messages.
+//! - `stream`: an async `Stream` of the captured messages (`stream` feature).
+
+pub mod bus;
+
+pub mod bytes;
+
+pub mod controller;
+
+pub mod format;
+
+pub mod intern;
+
+pub mod midi;
+pub use midi::MidiIn;
+
+pub mod ports;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+
+pub mod time;
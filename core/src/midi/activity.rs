@@ -0,0 +1,112 @@
+//! Channel activity per message class.
+
+use super::PortNb;
+use crate::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    NoteOff,
+    NoteOn,
+    PolyPressure,
+    Cc,
+    Program,
+    ChannelPressure,
+    PitchBend,
+}
+
+impl Class {
+    pub const ALL: [Class; 7] = [
+        Class::NoteOff,
+        Class::NoteOn,
+        Class::PolyPressure,
+        Class::Cc,
+        Class::Program,
+        Class::ChannelPressure,
+        Class::PitchBend,
+    ];
+
+    /// Returns the class of the channel message with `status`.
+    pub fn from_status(status: u8) -> Option<Self> {
+        use Class::*;
+        let class = match status & 0xf0 {
+            0x80 => NoteOff,
+            0x90 => NoteOn,
+            0xa0 => PolyPressure,
+            0xb0 => Cc,
+            0xc0 => Program,
+            0xd0 => ChannelPressure,
+            0xe0 => PitchBend,
+            _ => return None,
+        };
+
+        Some(class)
+    }
+
+    /// Short name, suitable for a column header.
+    pub fn as_str(self) -> &'static str {
+        use Class::*;
+        match self {
+            NoteOff => "Off",
+            NoteOn => "On",
+            PolyPressure => "PAT",
+            Cc => "CC",
+            Program => "PC",
+            ChannelPressure => "CAT",
+            PitchBend => "PB",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        use Class::*;
+        match self {
+            NoteOff => "Note Off",
+            NoteOn => "Note On",
+            PolyPressure => "Polyphonic aftertouch",
+            Cc => "Control Change",
+            Program => "Program Change",
+            ChannelPressure => "Channel aftertouch",
+            PitchBend => "Pitch bend",
+        }
+    }
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cell {
+    pub count: u64,
+    pub last: Option<Instant>,
+}
+
+/// Activity by port, channel & message class.
+#[derive(Debug, Default)]
+pub struct Grid {
+    cells: [[[Cell; Class::ALL.len()]; 16]; 2],
+}
+
+impl Grid {
+    /// Accounts for the message in `buf` received `at`.
+    pub fn handle(&mut self, port_nb: PortNb, at: Instant, buf: &[u8]) {
+        let status = match buf.first() {
+            Some(&status) => status,
+            None => return,
+        };
+
+        if let Some(class) = Class::from_status(status) {
+            let cell = &mut self.cells[port_nb.idx()][(status & 0x0f) as usize][class.idx()];
+            cell.count += 1;
+            cell.last = Some(at);
+        }
+    }
+
+    /// `channel` in the range 0..=15.
+    pub fn get(&self, port_nb: PortNb, channel: u8, class: Class) -> &Cell {
+        &self.cells[port_nb.idx()][channel as usize & 0x0f][class.idx()]
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,291 @@
+//! Byte layout of Midi messages.
+
+/// A byte of a Midi message with its layout and meaning.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub byte: u8,
+    /// Bit layout, e.g. `1001nnnn` for a Note On status byte.
+    pub layout: &'static str,
+    pub name: &'static str,
+    pub meaning: String,
+}
+
+impl Field {
+    fn new(byte: u8, layout: &'static str, name: &'static str, meaning: impl ToString) -> Self {
+        Self {
+            byte,
+            layout,
+            name,
+            meaning: meaning.to_string(),
+        }
+    }
+
+    /// Formats the byte as binary, splitting the nibbles.
+    pub fn bits(&self) -> String {
+        format!("{:04b} {:04b}", self.byte >> 4, self.byte & 0x0f)
+    }
+}
+
+/// Describes each byte of the Midi message in `buf`.
+pub fn describe(buf: &[u8]) -> Vec<Field> {
+    let (status, data) = match buf.split_first() {
+        Some((status, data)) if *status >= 0x80 => (*status, data),
+        _ => {
+            return buf
+                .iter()
+                .map(|byte| {
+                    Field::new(
+                        *byte,
+                        "0ddddddd",
+                        "Data",
+                        format!("{byte} (running status, status byte omitted)"),
+                    )
+                })
+                .collect();
+        }
+    };
+
+    let mut fields = Vec::with_capacity(buf.len());
+
+    if status < 0xf0 {
+        let channel = (status & 0x0f) + 1;
+        let (layout, msg_type, data_layouts): (_, _, &[(&'static str, &'static str)]) =
+            match status & 0xf0 {
+                0x80 => (
+                    "1000nnnn",
+                    "Note Off",
+                    &[("0kkkkkkk", "Note"), ("0vvvvvvv", "Velocity")],
+                ),
+                0x90 => (
+                    "1001nnnn",
+                    "Note On",
+                    &[("0kkkkkkk", "Note"), ("0vvvvvvv", "Velocity")],
+                ),
+                0xa0 => (
+                    "1010nnnn",
+                    "Poly Pressure",
+                    &[("0kkkkkkk", "Note"), ("0vvvvvvv", "Pressure")],
+                ),
+                0xb0 if data.first().is_some_and(|ctrl| *ctrl >= 120) => (
+                    "1011nnnn",
+                    "Channel Mode",
+                    &[("0ccccccc", "Mode"), ("0vvvvvvv", "Value")],
+                ),
+                0xb0 => (
+                    "1011nnnn",
+                    "Control Change",
+                    &[("0ccccccc", "Controller"), ("0vvvvvvv", "Value")],
+                ),
+                0xc0 => ("1100nnnn", "Program Change", &[("0ppppppp", "Program")]),
+                0xd0 => ("1101nnnn", "Channel Pressure", &[("0vvvvvvv", "Pressure")]),
+                _ => (
+                    "1110nnnn",
+                    "Pitch Bend",
+                    &[("0lllllll", "LSB"), ("0mmmmmmm", "MSB")],
+                ),
+            };
+
+        fields.push(Field::new(
+            status,
+            layout,
+            "Status",
+            format!("{msg_type}, channel {channel}"),
+        ));
+
+        for (byte, (layout, name)) in data.iter().zip(data_layouts.iter()) {
+            let meaning = match *name {
+                "Note" => format!("{byte} ({})", note_name(*byte)),
+                _ => byte.to_string(),
+            };
+            fields.push(Field::new(*byte, layout, name, meaning));
+        }
+
+        if status & 0xf0 == 0xe0 && data.len() >= 2 {
+            let bend = (((data[1] as i32) << 7) | data[0] as i32) - 0x2000;
+            if let Some(msb) = fields.last_mut() {
+                msb.meaning = format!("{} (bend {bend:+})", data[1]);
+            }
+        }
+
+        describe_extra(&mut fields, data.iter().skip(data_layouts.len()));
+
+        return fields;
+    }
+
+    match status {
+        0xf0 => {
+            fields.push(Field::new(status, "11110000", "Status", "SysEx start"));
+            let mut data = data.iter();
+            if let Some(id) = data.next() {
+                let meaning = match *id {
+                    0x00 => "Extended manufacturer id (2 more bytes)".to_string(),
+                    0x7d => "Non-commercial".to_string(),
+                    0x7e => "Universal Non Real Time".to_string(),
+                    0x7f => "Universal Real Time".to_string(),
+                    id => format!("Manufacturer 0x{id:02x}"),
+                };
+                fields.push(Field::new(*id, "0iiiiiii", "Id", meaning));
+            }
+            for byte in data {
+                if *byte == 0xf7 {
+                    fields.push(Field::new(*byte, "11110111", "Status", "SysEx end"));
+                } else {
+                    fields.push(Field::new(*byte, "0ddddddd", "Data", byte));
+                }
+            }
+        }
+        0xf1 => {
+            fields.push(Field::new(
+                status,
+                "11110001",
+                "Status",
+                "Time Code Quarter Frame",
+            ));
+            if let Some(byte) = data.first() {
+                fields.push(Field::new(
+                    *byte,
+                    "0tttvvvv",
+                    "Piece",
+                    format!("piece {} value {}", byte >> 4, byte & 0x0f),
+                ));
+            }
+            describe_extra(&mut fields, data.iter().skip(1));
+        }
+        0xf2 => {
+            fields.push(Field::new(status, "11110010", "Status", "Song Position"));
+            for (byte, (layout, name)) in
+                data.iter().zip([("0lllllll", "LSB"), ("0mmmmmmm", "MSB")])
+            {
+                fields.push(Field::new(*byte, layout, name, byte));
+            }
+            if data.len() >= 2 {
+                let beats = ((data[1] as u16) << 7) | data[0] as u16;
+                if let Some(msb) = fields.last_mut() {
+                    msb.meaning = format!("{} ({beats} beats)", data[1]);
+                }
+            }
+            describe_extra(&mut fields, data.iter().skip(2));
+        }
+        0xf3 => {
+            fields.push(Field::new(status, "11110011", "Status", "Song Select"));
+            if let Some(byte) = data.first() {
+                fields.push(Field::new(*byte, "0sssssss", "Song", byte));
+            }
+            describe_extra(&mut fields, data.iter().skip(1));
+        }
+        _ => {
+            let meaning = match status {
+                0xf6 => "Tune Request",
+                0xf7 => "SysEx end",
+                0xf8 => "Timing Clock",
+                0xfa => "Start",
+                0xfb => "Continue",
+                0xfc => "Stop",
+                0xfe => "Active Sensing",
+                0xff => "System Reset",
+                _ => "Undefined",
+            };
+            fields.push(Field::new(status, "1111ssss", "Status", meaning));
+            describe_extra(&mut fields, data.iter());
+        }
+    }
+
+    fields
+}
+
+/// Describes bytes which are not expected for the message type.
+fn describe_extra<'a>(fields: &mut Vec<Field>, extra: impl Iterator<Item = &'a u8>) {
+    for byte in extra {
+        fields.push(Field::new(*byte, "????????", "Unexpected", byte));
+    }
+}
+
+/// Returns the name of the note, using C4 for middle C (60).
+pub fn note_name(note: u8) -> String {
+    format!("{}{}", pitch_class_name(note), note as i32 / 12 - 1)
+}
+
+/// Returns the name of the note regardless of the octave.
+pub fn pitch_class_name(note: u8) -> &'static str {
+    const NAMES: [&str; 12] = [
+        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+    ];
+
+    NAMES[note as usize % 12]
+}
+
+/// A step of the byte by byte decoding of a Midi message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Index of the byte in the message.
+    pub byte_idx: usize,
+    pub explanation: String,
+}
+
+/// Splits the decoding of the Midi message in `buf` into teaching steps.
+///
+/// Status bytes are explained nibble by nibble.
+pub fn steps(buf: &[u8]) -> Vec<Step> {
+    let mut steps = Vec::new();
+
+    for (byte_idx, field) in describe(buf).into_iter().enumerate() {
+        let byte = field.byte;
+        let high = byte >> 4;
+        let low = byte & 0x0f;
+
+        if field.name == "Status" {
+            if high < 0xf {
+                steps.push(Step {
+                    byte_idx,
+                    explanation: format!(
+                        "High nibble {high:04b} (0x{high:x}): bit 7 is set, so this is a status byte. \
+                         The 3 other bits select the message type: {}.",
+                        field.meaning.split(',').next().unwrap_or_default(),
+                    ),
+                });
+                steps.push(Step {
+                    byte_idx,
+                    explanation: format!(
+                        "Low nibble {low:04b} ({low}): the channel, numbered 0 to 15 on the wire \
+                         and usually displayed as 1 to 16, i.e. channel {}.",
+                        low + 1,
+                    ),
+                });
+            } else {
+                steps.push(Step {
+                    byte_idx,
+                    explanation: format!(
+                        "High nibble {high:04b} (0xf): this is a System message, \
+                         which doesn't apply to a particular channel."
+                    ),
+                });
+                steps.push(Step {
+                    byte_idx,
+                    explanation: format!(
+                        "Low nibble {low:04b} (0x{low:x}): selects the System message: {}.",
+                        field.meaning,
+                    ),
+                });
+            }
+        } else if byte < 0x80 {
+            steps.push(Step {
+                byte_idx,
+                explanation: format!(
+                    "Data byte {byte:08b}: bit 7 is cleared, leaving 7 bits for values 0 to 127. \
+                     {}: {}.",
+                    field.name, field.meaning,
+                ),
+            });
+        } else {
+            steps.push(Step {
+                byte_idx,
+                explanation: format!(
+                    "Byte {byte:08b}: bit 7 is set but a data byte was expected here. {}: {}.",
+                    field.name, field.meaning,
+                ),
+            });
+        }
+    }
+
+    steps
+}
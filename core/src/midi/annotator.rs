@@ -0,0 +1,189 @@
+//! Annotations depending on the state built from the previous messages.
+
+#[cfg(feature = "profile")]
+use std::sync::Arc;
+
+#[cfg(feature = "profile")]
+use super::profile::Profile;
+use super::{gm, identity, mpe, rpn, surface, PortNb};
+
+/// Default pitch bend sensitivity, in cents.
+pub const DEFAULT_BEND_RANGE: u32 = 200;
+
+const CC_BANK_SELECT_MSB: u8 = 0;
+const CC_BANK_SELECT_LSB: u8 = 32;
+
+/// Last Bank Select received on a channel.
+#[derive(Clone, Copy, Debug, Default)]
+struct Bank {
+    msb: Option<u8>,
+    lsb: Option<u8>,
+}
+
+impl Bank {
+    fn is_selected(&self) -> bool {
+        self.msb.is_some() || self.lsb.is_some()
+    }
+}
+
+/// Tracks the channel state and annotates the messages accordingly.
+#[derive(Debug)]
+pub struct Annotator {
+    /// Annotates with the General MIDI names.
+    pub gm_names: bool,
+    /// Device profile per port.
+    #[cfg(feature = "profile")]
+    pub profiles: [Option<Arc<Profile>>; 2],
+    /// Control surface decoder per port.
+    pub surfaces: [surface::Decoder; 2],
+    rpns: rpn::Tracker,
+    /// MPE configuration per port.
+    mpe_zones: [mpe::Zones; 2],
+    /// Pitch bend sensitivity per port and channel, in cents.
+    bend_ranges: [[u32; 16]; 2],
+    banks: [[Bank; 16]; 2],
+}
+
+impl Default for Annotator {
+    fn default() -> Self {
+        Self {
+            gm_names: false,
+            #[cfg(feature = "profile")]
+            profiles: [None, None],
+            surfaces: Default::default(),
+            rpns: rpn::Tracker::default(),
+            mpe_zones: Default::default(),
+            bend_ranges: [[DEFAULT_BEND_RANGE; 16]; 2],
+            banks: Default::default(),
+        }
+    }
+}
+
+impl Annotator {
+    /// Forgets the channel state, keeping the settings.
+    pub fn reset(&mut self) {
+        *self = Self {
+            gm_names: self.gm_names,
+            #[cfg(feature = "profile")]
+            profiles: std::mem::take(&mut self.profiles),
+            surfaces: self.surfaces.map(|decoder| surface::Decoder {
+                protocol: decoder.protocol,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+    }
+
+    /// Updates the state with the message in `buf` and returns its annotations.
+    pub fn annotate(&mut self, port_nb: PortNb, buf: &[u8]) -> Vec<String> {
+        let mut annotations = Vec::new();
+
+        // Control surface messages don't follow the channel message semantics
+        if let Some(surface_msg) = self.surfaces[port_nb.idx()].decode(buf) {
+            annotations.push(surface_msg);
+            return annotations;
+        }
+
+        if let Some(change) = self.rpns.handle(port_nb, buf) {
+            annotations.extend(self.mpe_zones[port_nb.idx()].handle(&change));
+            annotations.extend(self.handle_bend_range(port_nb, &change));
+            #[cfg(feature = "profile")]
+            annotations.extend(self.profile_nrpn_name(port_nb, &change));
+        }
+
+        if let Some(reply) = identity::Reply::parse(buf) {
+            annotations.push(format!("Identity: {reply}"));
+            return annotations;
+        }
+
+        let status = match buf.first() {
+            Some(status) if (0x80..0xf0).contains(status) => *status,
+            _ => return annotations,
+        };
+        let channel = status & 0x0f;
+
+        if let Some(role) = self.mpe_zones[port_nb.idx()].role(channel) {
+            annotations.push(role.to_string());
+        }
+
+        let bank = &mut self.banks[port_nb.idx()][channel as usize];
+        match *buf {
+            [0xb0..=0xbf, CC_BANK_SELECT_MSB, value, ..] => bank.msb = Some(value),
+            [0xb0..=0xbf, CC_BANK_SELECT_LSB, value, ..] => bank.lsb = Some(value),
+            _ => (),
+        }
+        let bank = *bank;
+
+        match *buf {
+            [0xb0..=0xbf, cc, ..] => {
+                #[cfg(feature = "profile")]
+                if let Some(name) = self.profile(port_nb).and_then(|prof| prof.cc_name(cc)) {
+                    annotations.push(name.to_string());
+                    return annotations;
+                }
+
+                if self.gm_names {
+                    annotations.extend(gm::controller_name(cc).map(str::to_string));
+                }
+            }
+            [0xc0..=0xcf, program, ..] => {
+                if bank.is_selected() {
+                    annotations.push(format!(
+                        "Bank {}:{} Program {program}",
+                        bank.msb.unwrap_or(0),
+                        bank.lsb.unwrap_or(0),
+                    ));
+                }
+                if self.gm_names && channel != gm::PERCUSSION_CHANNEL {
+                    annotations.push(gm::program_name(program).to_string());
+                }
+            }
+            [0xe0..=0xef, lsb, msb, ..] => {
+                annotations.push(self.describe_bend(port_nb, channel, lsb, msb));
+            }
+            _ => (),
+        }
+
+        annotations
+    }
+
+    #[cfg(feature = "profile")]
+    fn profile(&self, port_nb: PortNb) -> Option<&Profile> {
+        self.profiles[port_nb.idx()].as_deref()
+    }
+
+    #[cfg(feature = "profile")]
+    fn profile_nrpn_name(&self, port_nb: PortNb, change: &rpn::Change) -> Option<String> {
+        match change.param {
+            rpn::Param::NonRegistered(nrpn) => self
+                .profile(port_nb)?
+                .nrpn_name(nrpn)
+                .map(|name| format!("NRPN {name}")),
+            rpn::Param::Registered(_) => None,
+        }
+    }
+
+    fn handle_bend_range(&mut self, port_nb: PortNb, change: &rpn::Change) -> Option<String> {
+        if change.param != rpn::Param::Registered(rpn::RPN_PITCH_BEND_SENSITIVITY) {
+            return None;
+        }
+
+        let range = u32::from(change.msb) * 100 + u32::from(change.lsb.unwrap_or(0));
+        self.bend_ranges[port_nb.idx()][change.channel as usize] = range;
+
+        Some(format!("Bend range ±{:.2} st", range as f32 / 100.0))
+    }
+
+    fn describe_bend(&self, port_nb: PortNb, channel: u8, lsb: u8, msb: u8) -> String {
+        let value = (u16::from(msb) << 7) | u16::from(lsb);
+        let range = self.bend_ranges[port_nb.idx()][channel as usize];
+        let cents = (i32::from(value) - 0x2000) as f32 * range as f32 / 8192.0;
+
+        format!(
+            "{value}: {:+.2} st ({:+.0} ct of ±{:.2} st)",
+            cents / 100.0,
+            cents,
+            range as f32 / 100.0,
+        )
+    }
+}
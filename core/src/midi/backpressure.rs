@@ -0,0 +1,253 @@
+use crossbeam_channel as channel;
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use super::{msg::Origin, PortNb};
+
+/// Maximum duration the Midi callback is blocked with `Policy::Block`.
+const BLOCK_TIMEOUT: Duration = Duration::from_millis(5);
+
+/// What happens when the capture consumer can't keep up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Policy {
+    /// Block the Midi callback briefly, then drop the message.
+    #[default]
+    Block,
+    /// Drop the oldest pending message to make room for the new one.
+    DropOldest,
+    /// Drop the new message.
+    DropNewest,
+    /// Drop the new message and report the number of dropped messages in the list.
+    Summarize,
+}
+
+impl Policy {
+    pub const ALL: [Policy; 4] = [
+        Policy::Block,
+        Policy::DropOldest,
+        Policy::DropNewest,
+        Policy::Summarize,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Policy::Block => "Block briefly",
+            Policy::DropOldest => "Drop oldest",
+            Policy::DropNewest => "Drop newest",
+            Policy::Summarize => "Summarize",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Policy::Block => "block",
+            Policy::DropOldest => "drop_oldest",
+            Policy::DropNewest => "drop_newest",
+            Policy::Summarize => "summarize",
+        }
+    }
+
+    pub fn from_storage_str(policy: &str) -> Option<Self> {
+        Policy::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == policy)
+    }
+
+    fn from_u8(val: u8) -> Self {
+        Policy::ALL.get(val as usize).copied().unwrap_or_default()
+    }
+
+    fn as_u8(self) -> u8 {
+        Policy::ALL
+            .iter()
+            .position(|candidate| *candidate == self)
+            .unwrap() as u8
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics {
+    /// Messages which had to wait with `Policy::Block`.
+    pub blocked: u64,
+    /// Messages dropped after waiting with `Policy::Block`.
+    pub timed_out: u64,
+    pub dropped_oldest: u64,
+    pub dropped_newest: u64,
+    pub summarized: u64,
+    /// Messages dropped because too many were pending while the list was paused.
+    pub paused_overflow: u64,
+}
+
+impl Metrics {
+    pub fn dropped(&self) -> u64 {
+        self.timed_out
+            + self.dropped_oldest
+            + self.dropped_newest
+            + self.summarized
+            + self.paused_overflow
+    }
+}
+
+/// Backpressure state shared between the UI and the capture pipeline.
+#[derive(Debug, Default)]
+pub struct State {
+    policy: AtomicU8,
+    blocked: AtomicU64,
+    timed_out: AtomicU64,
+    dropped_oldest: AtomicU64,
+    dropped_newest: AtomicU64,
+    summarized: AtomicU64,
+    paused_overflow: AtomicU64,
+    pending_summary: [AtomicU64; 2],
+}
+
+impl State {
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy: AtomicU8::new(policy.as_u8()),
+            ..Default::default()
+        }
+    }
+
+    pub fn policy(&self) -> Policy {
+        Policy::from_u8(self.policy.load(Ordering::Relaxed))
+    }
+
+    pub fn set_policy(&self, policy: Policy) {
+        self.policy.store(policy.as_u8(), Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            blocked: self.blocked.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            dropped_oldest: self.dropped_oldest.load(Ordering::Relaxed),
+            dropped_newest: self.dropped_newest.load(Ordering::Relaxed),
+            summarized: self.summarized.load(Ordering::Relaxed),
+            paused_overflow: self.paused_overflow.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset_metrics(&self) {
+        self.blocked.store(0, Ordering::Relaxed);
+        self.timed_out.store(0, Ordering::Relaxed);
+        self.dropped_oldest.store(0, Ordering::Relaxed);
+        self.dropped_newest.store(0, Ordering::Relaxed);
+        self.summarized.store(0, Ordering::Relaxed);
+        self.paused_overflow.store(0, Ordering::Relaxed);
+    }
+
+    /// Accounts for `count` messages dropped while the list was paused.
+    pub fn add_paused_overflow(&self, count: u64) {
+        self.paused_overflow.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// Sending side of the bounded capture channel which applies the backpressure `Policy`.
+///
+/// The `Sender` holds a receiver in order to drop the oldest message, so the channel
+/// can't disconnect while it is alive: the `Sender` checks the `Receiver` liveness instead.
+#[derive(Clone)]
+pub struct Sender {
+    tx: channel::Sender<Origin>,
+    // Used to drop the oldest message
+    rx: channel::Receiver<Origin>,
+    consumer: Weak<()>,
+    state: Arc<State>,
+}
+
+/// Receiving side of the bounded capture channel.
+///
+/// Dropping it stops the `Sender`s from queuing messages.
+pub struct Receiver {
+    rx: channel::Receiver<Origin>,
+    _alive: Arc<()>,
+}
+
+impl Deref for Receiver {
+    type Target = channel::Receiver<Origin>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rx
+    }
+}
+
+pub fn bounded(cap: usize, state: Arc<State>) -> (Sender, Receiver) {
+    let (tx, rx) = channel::bounded(cap);
+    let alive = Arc::new(());
+
+    (
+        Sender {
+            tx,
+            rx: rx.clone(),
+            consumer: Arc::downgrade(&alive),
+            state,
+        },
+        Receiver { rx, _alive: alive },
+    )
+}
+
+impl Sender {
+    pub fn send(&self, origin: Origin) {
+        use channel::TrySendError;
+
+        if self.is_consumer_gone() {
+            return;
+        }
+
+        let origin = match self.tx.try_send(origin) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+            Err(TrySendError::Full(origin)) => origin,
+        };
+
+        let state = &self.state;
+        match state.policy() {
+            Policy::Block => {
+                state.blocked.fetch_add(1, Ordering::Relaxed);
+                if let Err(channel::SendTimeoutError::Timeout(_)) =
+                    self.tx.send_timeout(origin, BLOCK_TIMEOUT)
+                {
+                    state.timed_out.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Policy::DropOldest => {
+                if self.rx.try_recv().is_ok() {
+                    state.dropped_oldest.fetch_add(1, Ordering::Relaxed);
+                }
+                if self.tx.try_send(origin).is_err() {
+                    state.dropped_newest.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Policy::DropNewest => {
+                state.dropped_newest.fetch_add(1, Ordering::Relaxed);
+            }
+            Policy::Summarize => {
+                state.summarized.fetch_add(1, Ordering::Relaxed);
+                state.pending_summary[origin.port_nb.idx()].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Whether the capture consumer dropped its `Receiver`.
+    pub fn is_consumer_gone(&self) -> bool {
+        self.consumer.strong_count() == 0
+    }
+
+    /// Takes the number of messages summarized per port since last call.
+    pub fn take_summary(&self) -> impl Iterator<Item = (PortNb, u64)> + '_ {
+        [PortNb::One, PortNb::Two]
+            .into_iter()
+            .filter_map(|port_nb| {
+                match self.state.pending_summary[port_nb.idx()].swap(0, Ordering::Relaxed) {
+                    0 => None,
+                    count => Some((port_nb, count)),
+                }
+            })
+    }
+}
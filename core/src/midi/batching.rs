@@ -0,0 +1,106 @@
+//! Tuning of the message batches published by the Controller and consumed by the UI.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// Minimum interval between two repaints requested by the Controller.
+pub const REPAINT_INTERVAL: Duration = Duration::from_millis(30);
+
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+pub const MAX_BATCH_SIZE: usize = 4096;
+/// By default, batches are published as soon as a message is available.
+pub const DEFAULT_BATCH_DELAY: Duration = Duration::ZERO;
+pub const MAX_BATCH_DELAY: Duration = Duration::from_millis(200);
+pub const DEFAULT_MAX_BATCHES_PER_UPDATE: usize = 16;
+pub const MAX_BATCHES_PER_UPDATE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Maximum number of Midi messages published in a single `Event::MsgBatch`.
+    pub batch_size: usize,
+    /// Maximum duration the Controller waits for a batch to fill up before publishing it.
+    pub batch_delay: Duration,
+    /// Maximum number of batches the message list handles per UI update.
+    pub max_batches_per_update: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_delay: DEFAULT_BATCH_DELAY,
+            max_batches_per_update: DEFAULT_MAX_BATCHES_PER_UPDATE,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns the `Settings` with each value in its valid range.
+    pub fn clamped(self) -> Self {
+        Settings {
+            batch_size: self.batch_size.clamp(1, MAX_BATCH_SIZE),
+            batch_delay: self.batch_delay.min(MAX_BATCH_DELAY),
+            max_batches_per_update: self.max_batches_per_update.clamp(1, MAX_BATCHES_PER_UPDATE),
+        }
+    }
+}
+
+/// Batching settings shared between the UI and the Controller.
+#[derive(Debug)]
+pub struct State {
+    batch_size: AtomicUsize,
+    batch_delay_us: AtomicU64,
+    max_batches_per_update: AtomicUsize,
+    /// Whether the UI must be repainted when messages are received.
+    repaints_on_msgs: AtomicBool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new(Settings::default())
+    }
+}
+
+impl State {
+    pub fn new(settings: Settings) -> Self {
+        let this = Self {
+            batch_size: AtomicUsize::new(0),
+            batch_delay_us: AtomicU64::new(0),
+            max_batches_per_update: AtomicUsize::new(0),
+            repaints_on_msgs: AtomicBool::new(true),
+        };
+        this.set_settings(settings);
+
+        this
+    }
+
+    pub fn settings(&self) -> Settings {
+        Settings {
+            batch_size: self.batch_size.load(Ordering::Relaxed),
+            batch_delay: Duration::from_micros(self.batch_delay_us.load(Ordering::Relaxed)),
+            max_batches_per_update: self.max_batches_per_update.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn set_settings(&self, settings: Settings) {
+        let settings = settings.clamped();
+        self.batch_size
+            .store(settings.batch_size, Ordering::Relaxed);
+        self.batch_delay_us
+            .store(settings.batch_delay.as_micros() as u64, Ordering::Relaxed);
+        self.max_batches_per_update
+            .store(settings.max_batches_per_update, Ordering::Relaxed);
+    }
+
+    pub fn repaints_on_msgs(&self) -> bool {
+        self.repaints_on_msgs.load(Ordering::Relaxed)
+    }
+
+    /// Set to `false` when nothing displays the incoming messages, e.g. list paused.
+    pub fn set_repaints_on_msgs(&self, repaints_on_msgs: bool) {
+        self.repaints_on_msgs
+            .store(repaints_on_msgs, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,311 @@
+//! Bluetooth Low Energy MIDI input backend.
+//!
+//! Scans for devices advertising the BLE-MIDI service, connects to them
+//! (which pairs the device if the OS requires it) and subscribes
+//! to the MIDI I/O characteristic.
+
+use btleplug::{
+    api::{Central, Manager as _, Peripheral as _, ScanFilter},
+    platform::{Adapter, Manager, Peripheral},
+};
+use futures::StreamExt;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{runtime, task::JoinHandle};
+use uuid::Uuid;
+
+use super::PortNb;
+
+pub const MIDI_SERVICE: Uuid = Uuid::from_u128(0x03b8_0e5a_ede8_4b33_a751_6ce3_4ec4_c700);
+pub const MIDI_CHARACTERISTIC: Uuid = Uuid::from_u128(0x7772_e5db_3868_4112_a1a9_f266_9d10_6bf3);
+
+/// Prefix of the BLE port names, so they can't be confused with the other backends.
+const PORT_NAME_PREFIX: &str = "BLE";
+/// Interval between two updates of the discovered devices.
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Bluetooth error: {}", .0)]
+    Btle(#[from] btleplug::Error),
+
+    #[error("No Bluetooth adapter found")]
+    NoAdapter,
+
+    #[error("Bluetooth device {} not found", .0)]
+    DeviceNotFound(Arc<str>),
+
+    #[error("Bluetooth device {} has no MIDI characteristic", .0)]
+    NoMidiCharacteristic(Arc<str>),
+
+    #[error("Couldn't start Bluetooth runtime: {}", .0)]
+    Runtime(#[from] std::io::Error),
+}
+
+/// Extracts the Midi messages from BLE-MIDI packets.
+///
+/// The BLE-MIDI timestamps are ignored: messages are timestamped on reception.
+#[derive(Debug, Default)]
+pub struct PacketDecoder {
+    running_status: Option<u8>,
+    /// SysEx being received, possibly spanning multiple packets.
+    sysex: Option<Vec<u8>>,
+}
+
+impl PacketDecoder {
+    pub fn decode(&mut self, packet: &[u8]) -> Vec<Vec<u8>> {
+        let mut msgs = Vec::new();
+
+        // The header byte has its msb set
+        match packet.first() {
+            Some(header) if header & 0x80 != 0 => (),
+            _ => return msgs,
+        }
+
+        let mut idx = 1;
+        while idx < packet.len() {
+            let byte = packet[idx];
+
+            if let Some(sysex) = self.sysex.as_mut() {
+                if byte & 0x80 == 0 {
+                    sysex.push(byte);
+                    idx += 1;
+                    continue;
+                }
+
+                // Timestamp followed by the end of the SysEx or a real time message
+                match packet.get(idx + 1) {
+                    Some(0xf7) => {
+                        sysex.push(0xf7);
+                        msgs.push(self.sysex.take().unwrap());
+                        idx += 2;
+                    }
+                    Some(status) if *status >= 0xf8 => {
+                        msgs.push(vec![*status]);
+                        idx += 2;
+                    }
+                    _ => {
+                        log::warn!("BLE-MIDI: interrupted SysEx");
+                        self.sysex = None;
+                    }
+                }
+                continue;
+            }
+
+            if byte & 0x80 != 0 {
+                // Timestamp
+                idx += 1;
+                let status = match packet.get(idx) {
+                    Some(status) if status & 0x80 != 0 => *status,
+                    // Running status
+                    Some(_) => match self.running_status {
+                        Some(status) => {
+                            idx = self.take_msg(status, packet, idx, &mut msgs);
+                            continue;
+                        }
+                        None => {
+                            idx += 1;
+                            continue;
+                        }
+                    },
+                    None => break,
+                };
+                idx += 1;
+
+                match status {
+                    0xf0 => self.sysex = Some(vec![0xf0]),
+                    0xf8..=0xff => msgs.push(vec![status]),
+                    _ => {
+                        self.running_status = (status < 0xf0).then_some(status);
+                        idx = self.take_msg(status, packet, idx, &mut msgs);
+                    }
+                }
+            } else if let Some(status) = self.running_status {
+                // Running status without timestamp
+                idx = self.take_msg(status, packet, idx, &mut msgs);
+            } else {
+                idx += 1;
+            }
+        }
+
+        msgs
+    }
+
+    /// Takes the data bytes for `status` starting at `idx`, returns the next index.
+    fn take_msg(&self, status: u8, packet: &[u8], idx: usize, msgs: &mut Vec<Vec<u8>>) -> usize {
+        let data_len = super::msg::data_len(status);
+
+        let data = packet[idx..]
+            .iter()
+            .take(data_len)
+            .take_while(|byte| **byte & 0x80 == 0);
+
+        let mut msg = vec![status];
+        msg.extend(data);
+        let next = idx + msg.len() - 1;
+        if msg.len() == data_len + 1 {
+            msgs.push(msg);
+        }
+
+        next
+    }
+}
+
+struct Connection {
+    peripheral: Peripheral,
+    task: JoinHandle<()>,
+}
+
+/// Discovers BLE-MIDI devices and captures their messages.
+pub struct Backend {
+    rt: runtime::Runtime,
+    /// Updated in the background, so that refreshing the ports doesn't block.
+    discovered: Arc<Mutex<BTreeMap<Arc<str>, Peripheral>>>,
+    devices: BTreeMap<Arc<str>, Peripheral>,
+    connections: [Option<Connection>; 2],
+}
+
+impl Backend {
+    /// Starts scanning for BLE-MIDI devices using the first adapter.
+    pub fn try_new() -> Result<Self, Error> {
+        let rt = runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("midi-sniffer-ble")
+            .enable_all()
+            .build()?;
+
+        let adapter = rt.block_on(async {
+            let adapter = Manager::new()
+                .await?
+                .adapters()
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(Error::NoAdapter)?;
+
+            adapter
+                .start_scan(ScanFilter {
+                    services: vec![MIDI_SERVICE],
+                })
+                .await?;
+
+            Ok::<_, Error>(adapter)
+        })?;
+
+        let discovered = Arc::new(Mutex::new(BTreeMap::new()));
+        // Aborted when the runtime is dropped
+        rt.spawn({
+            let discovered = discovered.clone();
+            async move {
+                loop {
+                    match discover(&adapter).await {
+                        Ok(devices) => *discovered.lock().unwrap() = devices,
+                        Err(err) => log::warn!("BLE-MIDI discovery failed: {err}"),
+                    }
+                    tokio::time::sleep(DISCOVERY_INTERVAL).await;
+                }
+            }
+        });
+
+        Ok(Self {
+            rt,
+            discovered,
+            devices: BTreeMap::new(),
+            connections: [None, None],
+        })
+    }
+
+    /// Updates the discovered devices and returns their port names.
+    ///
+    /// Devices are discovered in the background, every `DISCOVERY_INTERVAL`.
+    pub fn refresh(&mut self) -> impl Iterator<Item = &Arc<str>> {
+        self.devices = self.discovered.lock().unwrap().clone();
+
+        self.devices.keys()
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: PortNb,
+        port_name: Arc<str>,
+        mut callback: C,
+    ) -> Result<(), Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.disconnect(port_nb);
+
+        let peripheral = self
+            .devices
+            .get(&port_name)
+            .ok_or_else(|| Error::DeviceNotFound(port_name.clone()))?
+            .clone();
+
+        let mut notifications = self.rt.block_on(async {
+            if !peripheral.is_connected().await? {
+                peripheral.connect().await?;
+            }
+            peripheral.discover_services().await?;
+
+            let characteristic = peripheral
+                .characteristics()
+                .into_iter()
+                .find(|characteristic| characteristic.uuid == MIDI_CHARACTERISTIC)
+                .ok_or_else(|| Error::NoMidiCharacteristic(port_name.clone()))?;
+            peripheral.subscribe(&characteristic).await?;
+
+            Ok::<_, Error>(peripheral.notifications().await?)
+        })?;
+
+        let start = Instant::now();
+        let task = self.rt.spawn(async move {
+            let mut decoder = PacketDecoder::default();
+            while let Some(notification) = notifications.next().await {
+                if notification.uuid != MIDI_CHARACTERISTIC {
+                    continue;
+                }
+
+                let ts = start.elapsed().as_micros() as u64;
+                for msg in decoder.decode(&notification.value) {
+                    callback(ts, &msg);
+                }
+            }
+
+            log::info!("BLE-MIDI notifications closed for {port_nb}");
+        });
+
+        self.connections[port_nb.idx()] = Some(Connection { peripheral, task });
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: PortNb) {
+        if let Some(conn) = self.connections[port_nb.idx()].take() {
+            conn.task.abort();
+            if let Err(err) = self.rt.block_on(conn.peripheral.disconnect()) {
+                log::warn!("Couldn't disconnect BLE device: {err}");
+            }
+        }
+    }
+}
+
+/// Returns the BLE-MIDI devices known to `adapter` by port name.
+async fn discover(adapter: &Adapter) -> Result<BTreeMap<Arc<str>, Peripheral>, Error> {
+    let mut devices = BTreeMap::new();
+    for peripheral in adapter.peripherals().await? {
+        let props = match peripheral.properties().await? {
+            Some(props) if props.services.contains(&MIDI_SERVICE) => props,
+            _ => continue,
+        };
+
+        let name = props
+            .local_name
+            .unwrap_or_else(|| peripheral.address().to_string());
+        devices.insert(Arc::from(format!("{PORT_NAME_PREFIX} {name}")), peripheral);
+    }
+
+    Ok(devices)
+}
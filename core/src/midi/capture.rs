@@ -0,0 +1,353 @@
+//! Compact binary capture format, for long sessions.
+//!
+//! A file starts with `MAGIC` and the format `VERSION`, followed by a zstd stream
+//! of records. Each record holds the port, the backend & wall-clock timestamps
+//! and the raw bytes of a Midi message, as well as the number of consecutive
+//! repetitions of the message and the timestamp of the last one.
+//!
+//! Record layout, little endian:
+//!
+//! | field       | type |                                   |
+//! |-------------|------|-----------------------------------|
+//! | port        | u8   | 0 for Port 1, 1 for Port 2        |
+//! | ts          | u64  | backend timestamp (µs)            |
+//! | wall_ts     | u64  | µs since the Unix epoch           |
+//! | repetitions | u32  | at least 1                        |
+//! | last_ts     | u64  | backend timestamp of the last one |
+//! | len         | u32  | length of the raw bytes           |
+//! | raw         | [u8] |                                   |
+//!
+//! Version 1 records have no `repetitions` & `last_ts` fields.
+
+use std::io::{self, BufReader, Read, Write};
+
+use super::{
+    msg::{Origin, Repetitions},
+    PortNb,
+};
+use crate::time::{Duration, UNIX_EPOCH};
+
+pub const MAGIC: &[u8; 8] = b"MIDISNIF";
+pub const VERSION: u8 = 2;
+pub const EXTENSION: &str = "msnf";
+
+const COMPRESSION_LEVEL: i32 = 3;
+/// Protects from allocating huge buffers on corrupted files.
+const MAX_MSG_LEN: u32 = 1 << 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Not a Midi sniffer capture")]
+    InvalidMagic,
+    #[error("Unsupported capture version {}", .0)]
+    UnsupportedVersion(u8),
+    #[error("Invalid port {}", .0)]
+    InvalidPort(u8),
+    #[error("Invalid message length {}", .0)]
+    InvalidLength(u32),
+    #[error("{}", .0)]
+    Io(#[from] io::Error),
+}
+
+/// Writes the Midi messages as a compressed capture.
+pub struct Writer<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        Ok(Self {
+            encoder: zstd::Encoder::new(writer, COMPRESSION_LEVEL)?,
+        })
+    }
+
+    pub fn write(&mut self, origin: &Origin) -> io::Result<()> {
+        self.write_repeated(origin, 1, origin.ts)
+    }
+
+    /// Writes `repetitions` of the message, the last one at `last_ts`.
+    pub fn write_repeated(
+        &mut self,
+        origin: &Origin,
+        repetitions: u32,
+        last_ts: u64,
+    ) -> io::Result<()> {
+        let wall_ts = origin
+            .wall_ts
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let mut record = Vec::with_capacity(33 + origin.buffer.len());
+        record.push(origin.port_nb.idx() as u8);
+        record.extend(origin.ts.to_le_bytes());
+        record.extend(wall_ts.to_le_bytes());
+        record.extend(repetitions.max(1).to_le_bytes());
+        record.extend(last_ts.to_le_bytes());
+        record.extend((origin.buffer.len() as u32).to_le_bytes());
+        record.extend(origin.buffer.iter());
+
+        self.encoder.write_all(&record)
+    }
+
+    /// Writes the pending records, which can be read back even if the capture
+    /// is not `finish`ed, e.g. after a crash.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.encoder.get_ref()
+    }
+
+    /// Terminates the compressed stream and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.encoder.finish()
+    }
+}
+
+/// Reads the Midi messages of a capture.
+///
+/// Repeated messages are returned once per repetition, see `Origin::repeat`.
+pub struct Reader<R: Read> {
+    decoder: zstd::Decoder<'static, BufReader<R>>,
+    version: u8,
+    repetitions: Option<Repetitions>,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                Error::InvalidMagic
+            } else {
+                err.into()
+            }
+        })?;
+        if &magic != MAGIC {
+            return Err(Error::InvalidMagic);
+        }
+
+        let mut version = [0];
+        reader.read_exact(&mut version)?;
+        if !(1..=VERSION).contains(&version[0]) {
+            return Err(Error::UnsupportedVersion(version[0]));
+        }
+
+        Ok(Self {
+            decoder: zstd::Decoder::new(reader)?,
+            version: version[0],
+            repetitions: None,
+        })
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0; 4];
+        self.decoder.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.decoder.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_record(&mut self) -> Result<Option<Repetitions>, Error> {
+        let mut port = [0];
+        if self.decoder.read(&mut port)? == 0 {
+            return Ok(None);
+        }
+        let port_nb = match port[0] {
+            0 => PortNb::One,
+            1 => PortNb::Two,
+            other => return Err(Error::InvalidPort(other)),
+        };
+
+        let ts = self.read_u64()?;
+        let wall_ts = self.read_u64()?;
+        let (repetitions, last_ts) = if self.version > 1 {
+            (self.read_u32()?, self.read_u64()?)
+        } else {
+            (1, ts)
+        };
+        let len = self.read_u32()?;
+        if len > MAX_MSG_LEN {
+            return Err(Error::InvalidLength(len));
+        }
+
+        let mut buffer = vec![0; len as usize];
+        self.decoder.read_exact(&mut buffer)?;
+
+        let origin = Origin {
+            ts,
+            wall_ts: UNIX_EPOCH + Duration::from_micros(wall_ts),
+            port_nb,
+            buffer: buffer.into(),
+        };
+
+        Ok(Some(origin.repeat(repetitions, last_ts)))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Origin, Error>;
+
+    /// Returns `None` at the end of the capture.
+    ///
+    /// A truncated capture, e.g. after a crash, ends with an error
+    /// after the last complete record.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(origin) = self.repetitions.as_mut().and_then(Iterator::next) {
+                return Some(Ok(origin));
+            }
+
+            match self.read_record() {
+                Ok(Some(repetitions)) => self.repetitions = Some(repetitions),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Reads all the messages of a capture.
+///
+/// The messages read before an error are returned along with the error,
+/// so that truncated captures can still be used.
+pub fn read_all(reader: impl Read) -> Result<(Vec<Origin>, Option<Error>), Error> {
+    let mut origins = Vec::new();
+    for res in Reader::new(reader)? {
+        match res {
+            Ok(origin) => origins.push(origin),
+            Err(err) => return Ok((origins, Some(err))),
+        }
+    }
+
+    Ok((origins, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins() -> Vec<Origin> {
+        vec![
+            Origin {
+                ts: 1_000,
+                wall_ts: UNIX_EPOCH + Duration::from_micros(1_650_000_000_000_001),
+                port_nb: PortNb::One,
+                buffer: [0x90, 0x3c, 0x40][..].into(),
+            },
+            Origin {
+                ts: 2_000,
+                wall_ts: UNIX_EPOCH + Duration::from_micros(1_650_000_000_001_002),
+                port_nb: PortNb::Two,
+                buffer: [0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7][..].into(),
+            },
+        ]
+    }
+
+    fn assert_same(read: &[Origin], expected: &[Origin]) {
+        assert_eq!(read.len(), expected.len());
+        for (read, expected) in read.iter().zip(expected) {
+            assert_eq!(read.ts, expected.ts);
+            assert_eq!(read.wall_ts, expected.wall_ts);
+            assert_eq!(read.port_nb, expected.port_nb);
+            assert_eq!(read.buffer, expected.buffer);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let origins = origins();
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        for origin in &origins {
+            writer.write(origin).unwrap();
+        }
+        let data = writer.finish().unwrap();
+
+        let (read, err) = read_all(&data[..]).unwrap();
+        assert!(err.is_none());
+        assert_same(&read, &origins);
+    }
+
+    #[test]
+    fn repetitions() {
+        let origins = origins();
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer.write_repeated(&origins[0], 3, 1_300).unwrap();
+        writer.write(&origins[1]).unwrap();
+        let data = writer.finish().unwrap();
+
+        let (read, err) = read_all(&data[..]).unwrap();
+        assert!(err.is_none());
+        assert_eq!(read.len(), 4);
+        let ts: Vec<u64> = read.iter().map(|origin| origin.ts).collect();
+        assert_eq!(ts, [1_000, 1_150, 1_300, 2_000]);
+        assert_eq!(
+            read[2].wall_ts,
+            origins[0].wall_ts + Duration::from_micros(300)
+        );
+        assert_same(&read[3..], &origins[1..]);
+    }
+
+    #[test]
+    fn version_1() {
+        let origin = &origins()[0];
+        let mut record = vec![0];
+        record.extend(origin.ts.to_le_bytes());
+        record.extend(1_650_000_000_000_001u64.to_le_bytes());
+        record.extend(3u32.to_le_bytes());
+        record.extend(origin.buffer.iter());
+
+        let mut data = MAGIC.to_vec();
+        data.push(1);
+        data.extend(zstd::encode_all(&record[..], COMPRESSION_LEVEL).unwrap());
+
+        let (read, err) = read_all(&data[..]).unwrap();
+        assert!(err.is_none());
+        assert_same(&read, std::slice::from_ref(origin));
+    }
+
+    #[test]
+    fn truncated() {
+        let origins = origins();
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer.write(&origins[0]).unwrap();
+        writer.flush().unwrap();
+        let first_len = writer.get_ref().len();
+        writer.write(&origins[1]).unwrap();
+        writer.flush().unwrap();
+
+        // Not finished & cut in the middle of the second record, e.g. after a crash
+        let data = writer.get_ref();
+        let data = &data[..first_len + (data.len() - first_len) / 2];
+
+        let (read, err) = read_all(data).unwrap();
+        assert!(err.is_some());
+        assert_same(&read, &origins[..1]);
+    }
+
+    #[test]
+    fn invalid_header() {
+        assert!(matches!(read_all(&b"MIDI"[..]), Err(Error::InvalidMagic)));
+        assert!(matches!(
+            read_all(&b"NOTSNIFF\x01"[..]),
+            Err(Error::InvalidMagic)
+        ));
+        assert!(matches!(
+            read_all(&b"MIDISNIF\x00"[..]),
+            Err(Error::UnsupportedVersion(0))
+        ));
+        assert!(matches!(
+            read_all(&b"MIDISNIF\x03"[..]),
+            Err(Error::UnsupportedVersion(3))
+        ));
+    }
+}
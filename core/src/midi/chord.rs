@@ -0,0 +1,116 @@
+//! Chord recognition from Note On messages received in a short window.
+
+use std::collections::HashMap;
+
+use super::{anatomy::pitch_class_name, PortNb};
+use crate::time::{Duration, SystemTime};
+
+/// Maximum interval between two Note On messages of the same chord.
+pub const CHORD_WINDOW: Duration = Duration::from_millis(40);
+
+/// Chord qualities as intervals from the root, in semitones.
+const QUALITIES: &[(&str, &[u8])] = &[
+    ("", &[0, 4, 7]),
+    ("m", &[0, 3, 7]),
+    ("dim", &[0, 3, 6]),
+    ("aug", &[0, 4, 8]),
+    ("sus2", &[0, 2, 7]),
+    ("sus4", &[0, 5, 7]),
+    ("6", &[0, 4, 7, 9]),
+    ("m6", &[0, 3, 7, 9]),
+    ("7", &[0, 4, 7, 10]),
+    ("maj7", &[0, 4, 7, 11]),
+    ("m7", &[0, 3, 7, 10]),
+    ("mMaj7", &[0, 3, 7, 11]),
+    ("m7b5", &[0, 3, 6, 10]),
+    ("dim7", &[0, 3, 6, 9]),
+    ("add9", &[0, 2, 4, 7]),
+    ("9", &[0, 2, 4, 7, 10]),
+    ("maj9", &[0, 2, 4, 7, 11]),
+    ("m9", &[0, 2, 3, 7, 10]),
+];
+
+/// Returns the name of the chord formed by `notes`, e.g. `Cmaj7` or `C/E`.
+pub fn recognize(notes: &[u8]) -> Option<String> {
+    let bass = *notes.iter().min()?;
+
+    let pitch_classes = notes
+        .iter()
+        .fold(0u16, |mask, note| mask | (1 << (note % 12)));
+    if pitch_classes.count_ones() < 3 {
+        return None;
+    }
+
+    // Try the bass as the root first so as to favour root positions.
+    let roots = std::iter::once(bass % 12).chain((0..12).filter(|root| *root != bass % 12));
+    for root in roots {
+        if pitch_classes & (1 << root) == 0 {
+            continue;
+        }
+
+        let intervals = (0..12)
+            .filter(|pc| pitch_classes & (1 << pc) != 0)
+            .fold(0u16, |mask, pc| mask | (1 << ((pc + 12 - root) % 12)));
+
+        for (quality, chord) in QUALITIES {
+            let chord_mask = chord.iter().fold(0u16, |mask, itv| mask | (1 << itv));
+            if chord_mask == intervals {
+                let mut name = format!("{}{quality}", pitch_class_name(root));
+                if root != bass % 12 {
+                    name.push('/');
+                    name.push_str(pitch_class_name(bass));
+                }
+
+                return Some(name);
+            }
+        }
+    }
+
+    None
+}
+
+#[derive(Debug)]
+struct Group {
+    last: SystemTime,
+    notes: Vec<u8>,
+}
+
+/// Groups the Note On messages per port and channel.
+#[derive(Debug, Default)]
+pub struct Detector {
+    groups: HashMap<(PortNb, u8), Group>,
+}
+
+impl Detector {
+    /// Handles the message in `buf`, returning the chord if one is recognized.
+    pub fn handle(&mut self, port_nb: PortNb, wall_ts: SystemTime, buf: &[u8]) -> Option<String> {
+        let (status, note) = match *buf {
+            [status, note, velocity, ..] if status & 0xf0 == 0x90 && velocity > 0 => (status, note),
+            _ => return None,
+        };
+
+        let group = self
+            .groups
+            .entry((port_nb, status & 0x0f))
+            .or_insert_with(|| Group {
+                last: wall_ts,
+                notes: Vec::new(),
+            });
+
+        let is_new_chord =
+            matches!(wall_ts.duration_since(group.last), Ok(elapsed) if elapsed > CHORD_WINDOW);
+        if is_new_chord {
+            group.notes.clear();
+        }
+        group.last = wall_ts;
+        if !group.notes.contains(&note) {
+            group.notes.push(note);
+        }
+
+        recognize(&group.notes)
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+}
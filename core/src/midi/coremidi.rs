@@ -0,0 +1,218 @@
+//! CoreMIDI device, entity & external device names of the Midi sources.
+//!
+//! Endpoint names alone are ambiguous, e.g. multi-port interfaces usually
+//! name their endpoints "Port 1", "Port 2", ... See Apple's QA1374.
+//!
+//! The descriptions are cached and only computed again when CoreMIDI notifies
+//! a change of the setup.
+
+use core_foundation_sys::{
+    base::{kCFAllocatorDefault, CFRelease},
+    data::{CFDataGetBytePtr, CFDataGetLength, CFDataRef},
+    runloop::CFRunLoopRun,
+    string::{
+        kCFStringEncodingUTF8, CFStringCreateWithCString, CFStringGetCString, CFStringGetLength,
+        CFStringGetMaximumSizeForEncoding, CFStringRef,
+    },
+};
+use coremidi_sys::{
+    kMIDIPropertyConnectionUniqueID, kMIDIPropertyDisplayName, kMIDIPropertyName, MIDIClientCreate,
+    MIDIEndpointGetEntity, MIDIEntityGetDevice, MIDIGetNumberOfSources, MIDIGetSource,
+    MIDINotification, MIDIObjectFindByUniqueID, MIDIObjectGetDataProperty,
+    MIDIObjectGetIntegerProperty, MIDIObjectGetStringProperty, MIDIObjectRef,
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::BTreeMap,
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once,
+    },
+    thread,
+};
+
+/// Flag of the `MIDIObjectType`s describing external devices, entities & endpoints.
+const OBJECT_TYPE_EXTERNAL_MASK: i32 = 0x10;
+
+static DESCRIPTIONS: Lazy<Mutex<BTreeMap<Arc<str>, Arc<str>>>> = Lazy::new(Default::default);
+/// Set when CoreMIDI notifies a change of the setup.
+static IS_STALE: AtomicBool = AtomicBool::new(true);
+/// Whether the notifications are received, otherwise the cache can't be used.
+static IS_NOTIFIED: AtomicBool = AtomicBool::new(false);
+static LISTEN: Once = Once::new();
+
+/// Returns the description of the Midi sources, by display name.
+///
+/// The display name is the port name `midir` reports.
+pub fn source_descriptions() -> BTreeMap<Arc<str>, Arc<str>> {
+    LISTEN.call_once(listen);
+
+    let mut descriptions = DESCRIPTIONS.lock().unwrap();
+    if IS_STALE.swap(false, Ordering::AcqRel) || !IS_NOTIFIED.load(Ordering::Acquire) {
+        *descriptions = describe_sources();
+    }
+
+    descriptions.clone()
+}
+
+/// Spawns a thread with a CoreMIDI client which marks the descriptions as stale on changes.
+///
+/// Notifications are delivered on the run loop of the thread which created the client.
+fn listen() {
+    let res = thread::Builder::new()
+        .name("coremidi-notify".to_string())
+        .spawn(|| {
+            // Safety: the name is released once the client is created.
+            unsafe {
+                let name = CFStringCreateWithCString(
+                    kCFAllocatorDefault,
+                    b"midi-sniffer notifications\0".as_ptr().cast(),
+                    kCFStringEncodingUTF8,
+                );
+                let mut client = 0;
+                let status =
+                    MIDIClientCreate(name, Some(notify), std::ptr::null_mut(), &mut client);
+                CFRelease(name.cast());
+                if status != 0 {
+                    log::warn!("Couldn't create CoreMIDI notification client: {status}");
+                    return;
+                }
+
+                // Changes might have occurred before the client was created
+                IS_STALE.store(true, Ordering::Release);
+                IS_NOTIFIED.store(true, Ordering::Release);
+                CFRunLoopRun();
+            }
+
+            log::warn!("CoreMIDI notifications stopped");
+            IS_NOTIFIED.store(false, Ordering::Release);
+        });
+
+    if let Err(err) = res {
+        log::warn!("Couldn't spawn the CoreMIDI notification thread: {err}");
+    }
+}
+
+unsafe extern "C" fn notify(_notification: *const MIDINotification, _ref_con: *mut c_void) {
+    IS_STALE.store(true, Ordering::Release);
+}
+
+fn describe_sources() -> BTreeMap<Arc<str>, Arc<str>> {
+    let mut descriptions = BTreeMap::new();
+
+    // Safety: the CoreMIDI objects are only used while enumerating the sources.
+    unsafe {
+        for idx in 0..MIDIGetNumberOfSources() {
+            let source = MIDIGetSource(idx);
+            if source == 0 {
+                continue;
+            }
+            let name = match string_property(source, kMIDIPropertyDisplayName) {
+                Some(name) => name,
+                None => continue,
+            };
+            if let Some(desc) = describe(source) {
+                descriptions.insert(name.into(), desc.into());
+            }
+        }
+    }
+
+    descriptions
+}
+
+/// Describes the device & entity of `endpoint` and the external devices it is connected to.
+unsafe fn describe(endpoint: MIDIObjectRef) -> Option<String> {
+    let mut parts = Vec::new();
+
+    let mut entity = 0;
+    if MIDIEndpointGetEntity(endpoint, &mut entity) == 0 && entity != 0 {
+        let mut device = 0;
+        let device_name = if MIDIEntityGetDevice(entity, &mut device) == 0 && device != 0 {
+            string_property(device, kMIDIPropertyName)
+        } else {
+            None
+        };
+        let entity_name = string_property(entity, kMIDIPropertyName);
+
+        if let Some(ref device_name) = device_name {
+            parts.push(format!("Device: {device_name}"));
+        }
+        if let Some(entity_name) = entity_name.filter(|name| Some(name) != device_name.as_ref()) {
+            parts.push(format!("Entity: {entity_name}"));
+        }
+    }
+
+    let external = connected_ids(endpoint)
+        .into_iter()
+        .filter_map(|id| {
+            let (mut object, mut object_type) = (0, 0);
+            if MIDIObjectFindByUniqueID(id, &mut object, &mut object_type) != 0 || object == 0 {
+                return None;
+            }
+            if object_type as i32 & OBJECT_TYPE_EXTERNAL_MASK == 0 {
+                return None;
+            }
+
+            string_property(object, kMIDIPropertyName)
+        })
+        .collect::<Vec<_>>();
+    if !external.is_empty() {
+        parts.push(format!("Connected to: {}", external.join(", ")));
+    }
+
+    (!parts.is_empty()).then(|| parts.join("\n"))
+}
+
+/// Returns the unique ids of the objects `endpoint` is connected to.
+///
+/// The property is either an array of big endian ids or a single id.
+unsafe fn connected_ids(endpoint: MIDIObjectRef) -> Vec<i32> {
+    let mut data: CFDataRef = std::ptr::null();
+    if MIDIObjectGetDataProperty(endpoint, kMIDIPropertyConnectionUniqueID, &mut data) == 0
+        && !data.is_null()
+    {
+        let len = CFDataGetLength(data) as usize;
+        let bytes = std::slice::from_raw_parts(CFDataGetBytePtr(data), len);
+        let ids = bytes
+            .chunks_exact(4)
+            .map(|id| i32::from_be_bytes([id[0], id[1], id[2], id[3]]))
+            .filter(|id| *id != 0)
+            .collect();
+        CFRelease(data.cast());
+
+        return ids;
+    }
+
+    let mut id = 0;
+    if MIDIObjectGetIntegerProperty(endpoint, kMIDIPropertyConnectionUniqueID, &mut id) == 0
+        && id != 0
+    {
+        return vec![id];
+    }
+
+    Vec::new()
+}
+
+unsafe fn string_property(object: MIDIObjectRef, property: CFStringRef) -> Option<String> {
+    let mut string: CFStringRef = std::ptr::null();
+    if MIDIObjectGetStringProperty(object, property, &mut string) != 0 || string.is_null() {
+        return None;
+    }
+
+    let max_len =
+        CFStringGetMaximumSizeForEncoding(CFStringGetLength(string), kCFStringEncodingUTF8) + 1;
+    let mut buf = vec![0 as c_char; max_len as usize];
+    let res = CFStringGetCString(string, buf.as_mut_ptr(), max_len, kCFStringEncodingUTF8);
+    CFRelease(string.cast());
+    if res == 0 {
+        return None;
+    }
+
+    let name = CStr::from_ptr(buf.as_ptr())
+        .to_string_lossy()
+        .trim()
+        .to_string();
+    (!name.is_empty()).then_some(name)
+}
@@ -0,0 +1,431 @@
+//! Aligns two sequences of Midi messages and reports their differences.
+//!
+//! Messages are aligned on their raw bytes using Myers' diff algorithm.
+//! A message missing on one side and an extra message of the same kind,
+//! e.g. the same controller, on the other side are reported as a change.
+
+use std::ops::Range;
+
+/// Maximum number of missing & extra messages before giving up.
+pub const MAX_EDIT_DISTANCE: usize = 20_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Same,
+    /// Only in the left sequence.
+    Missing,
+    /// Only in the right sequence.
+    Extra,
+    /// The value of the left message changed in the right one.
+    Changed,
+}
+
+impl Kind {
+    pub fn as_char(self) -> char {
+        match self {
+            Kind::Same => '=',
+            Kind::Missing => '-',
+            Kind::Extra => '+',
+            Kind::Changed => '~',
+        }
+    }
+}
+
+/// An aligned pair of messages: their indices in the left & right sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub kind: Kind,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("The sequences differ by more than {} messages", MAX_EDIT_DISTANCE)]
+pub struct TooDifferent;
+
+/// Returns the alignment of the `left` & `right` messages.
+pub fn diff<T: AsRef<[u8]>>(left: &[T], right: &[T]) -> Result<Vec<Entry>, TooDifferent> {
+    let mut entries = Vec::new();
+    let mut hunk = Hunk::default();
+
+    for op in edit_script(left, right, MAX_EDIT_DISTANCE)? {
+        match op {
+            Op::Same(left_idx, right_idx) => {
+                hunk.flush(left, right, &mut entries);
+                entries.push(Entry {
+                    kind: Kind::Same,
+                    left: Some(left_idx),
+                    right: Some(right_idx),
+                });
+            }
+            Op::Missing(left_idx) => hunk.missing.push(left_idx),
+            Op::Extra(right_idx) => hunk.extra.push(right_idx),
+        }
+    }
+    hunk.flush(left, right, &mut entries);
+
+    Ok(entries)
+}
+
+/// Messages with the same key are considered as a value change of one another.
+fn key(msg: &[u8]) -> (u8, Option<u8>) {
+    match msg.first() {
+        // Note, poly pressure & controller number
+        Some(&status) if matches!(status & 0xf0, 0x80 | 0x90 | 0xa0 | 0xb0) => {
+            (status, msg.get(1).copied())
+        }
+        // Manufacturer
+        Some(&0xf0) => (0xf0, msg.get(1).copied()),
+        Some(&status) => (status, None),
+        None => (0, None),
+    }
+}
+
+/// Consecutive missing & extra messages.
+#[derive(Default)]
+struct Hunk {
+    missing: Vec<usize>,
+    extra: Vec<usize>,
+}
+
+impl Hunk {
+    /// Pairs the missing & extra messages with the same key, in order.
+    fn flush<T: AsRef<[u8]>>(&mut self, left: &[T], right: &[T], entries: &mut Vec<Entry>) {
+        let mut pairs = Vec::new();
+        let mut next_extra = 0;
+        for (missing_pos, missing) in self.missing.iter().enumerate() {
+            let missing_key = key(left[*missing].as_ref());
+            if let Some(offset) = self.extra[next_extra..]
+                .iter()
+                .position(|extra| key(right[*extra].as_ref()) == missing_key)
+            {
+                pairs.push((missing_pos, next_extra + offset));
+                next_extra += offset + 1;
+            }
+        }
+
+        let push_missing = |entries: &mut Vec<Entry>, pos: usize| {
+            entries.push(Entry {
+                kind: Kind::Missing,
+                left: Some(self.missing[pos]),
+                right: None,
+            })
+        };
+        let push_extra = |entries: &mut Vec<Entry>, pos: usize| {
+            entries.push(Entry {
+                kind: Kind::Extra,
+                left: None,
+                right: Some(self.extra[pos]),
+            })
+        };
+
+        let (mut missing_pos, mut extra_pos) = (0, 0);
+        for (paired_missing, paired_extra) in pairs {
+            (missing_pos..paired_missing).for_each(|pos| push_missing(entries, pos));
+            (extra_pos..paired_extra).for_each(|pos| push_extra(entries, pos));
+            entries.push(Entry {
+                kind: Kind::Changed,
+                left: Some(self.missing[paired_missing]),
+                right: Some(self.extra[paired_extra]),
+            });
+            missing_pos = paired_missing + 1;
+            extra_pos = paired_extra + 1;
+        }
+        (missing_pos..self.missing.len()).for_each(|pos| push_missing(entries, pos));
+        (extra_pos..self.extra.len()).for_each(|pos| push_extra(entries, pos));
+
+        self.missing.clear();
+        self.extra.clear();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Op {
+    Same(usize, usize),
+    Missing(usize),
+    Extra(usize),
+}
+
+/// Returns the shortest edit script from `left` to `right`.
+///
+/// Uses the linear space variant of the algorithm: the sequences are split
+/// on the middle snake of the edit graph and each half is processed recursively.
+/// See "An O(ND) Difference Algorithm and Its Variations", E. Myers.
+fn edit_script<T: AsRef<[u8]>>(
+    left: &[T],
+    right: &[T],
+    max_d: usize,
+) -> Result<Vec<Op>, TooDifferent> {
+    let mut script = Script {
+        left,
+        right,
+        // The middle snake of an edit script of length D is found after D/2 steps
+        max_steps: max_d.div_ceil(2) + 1,
+        forward: Frontier::new(left.len() + right.len()),
+        backward: Frontier::new(left.len() + right.len()),
+        ops: Vec::with_capacity(left.len().max(right.len())),
+    };
+    script.conquer(0..left.len(), 0..right.len())?;
+
+    let edits = script
+        .ops
+        .iter()
+        .filter(|op| !matches!(op, Op::Same(..)))
+        .count();
+    if edits > max_d {
+        return Err(TooDifferent);
+    }
+
+    Ok(script.ops)
+}
+
+/// Furthest x reached on each diagonal k = x - y.
+struct Frontier {
+    offset: isize,
+    x: Vec<usize>,
+}
+
+impl Frontier {
+    fn new(len: usize) -> Self {
+        let max_steps = len / 2 + 2;
+        Frontier {
+            offset: max_steps as isize,
+            x: vec![0; 2 * max_steps + 1],
+        }
+    }
+}
+
+impl std::ops::Index<isize> for Frontier {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.x[(k + self.offset) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for Frontier {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.x[(k + self.offset) as usize]
+    }
+}
+
+struct Script<'a, T> {
+    left: &'a [T],
+    right: &'a [T],
+    max_steps: usize,
+    forward: Frontier,
+    backward: Frontier,
+    ops: Vec<Op>,
+}
+
+impl<'a, T: AsRef<[u8]>> Script<'a, T> {
+    fn is_same(&self, left_idx: usize, right_idx: usize) -> bool {
+        self.left[left_idx].as_ref() == self.right[right_idx].as_ref()
+    }
+
+    fn prefix_len(&self, left: Range<usize>, right: Range<usize>) -> usize {
+        left.zip(right)
+            .take_while(|(left_idx, right_idx)| self.is_same(*left_idx, *right_idx))
+            .count()
+    }
+
+    fn suffix_len(&self, left: Range<usize>, right: Range<usize>) -> usize {
+        left.rev()
+            .zip(right.rev())
+            .take_while(|(left_idx, right_idx)| self.is_same(*left_idx, *right_idx))
+            .count()
+    }
+
+    fn conquer(
+        &mut self,
+        mut left: Range<usize>,
+        mut right: Range<usize>,
+    ) -> Result<(), TooDifferent> {
+        let prefix_len = self.prefix_len(left.clone(), right.clone());
+        self.ops
+            .extend((0..prefix_len).map(|idx| Op::Same(left.start + idx, right.start + idx)));
+        left.start += prefix_len;
+        right.start += prefix_len;
+
+        let suffix_len = self.suffix_len(left.clone(), right.clone());
+        left.end -= suffix_len;
+        right.end -= suffix_len;
+        let suffix = (left.end, right.end);
+
+        if left.is_empty() {
+            self.ops.extend(right.map(Op::Extra));
+        } else if right.is_empty() {
+            self.ops.extend(left.map(Op::Missing));
+        } else {
+            let (left_mid, right_mid) = self
+                .middle_snake(left.clone(), right.clone())
+                .ok_or(TooDifferent)?;
+            self.conquer(left.start..left_mid, right.start..right_mid)?;
+            self.conquer(left_mid..left.end, right_mid..right.end)?;
+        }
+
+        self.ops
+            .extend((0..suffix_len).map(|idx| Op::Same(suffix.0 + idx, suffix.1 + idx)));
+
+        Ok(())
+    }
+
+    /// Returns the start of the middle snake, which splits an optimal edit script
+    /// of `left` & `right` in two halves.
+    ///
+    /// Returns `None` if it is not found within `max_steps`.
+    fn middle_snake(&mut self, left: Range<usize>, right: Range<usize>) -> Option<(usize, usize)> {
+        let (n, m) = (left.len(), right.len());
+        let delta = n as isize - m as isize;
+        let is_odd = delta & 1 == 1;
+
+        self.forward[1] = 0;
+        self.backward[1] = 0;
+
+        let steps = ((n + m).div_ceil(2) + 1).min(self.max_steps);
+        for d in 0..steps as isize {
+            for k in (-d..=d).rev().step_by(2) {
+                let mut x = if k == -d || (k != d && self.forward[k - 1] < self.forward[k + 1]) {
+                    self.forward[k + 1]
+                } else {
+                    self.forward[k - 1] + 1
+                };
+                let y = (x as isize - k) as usize;
+                let (x0, y0) = (x, y);
+                if x < n && y < m {
+                    x += self.prefix_len(left.start + x..left.end, right.start + y..right.end);
+                }
+                self.forward[k] = x;
+
+                if is_odd
+                    && (k - delta).abs() < d
+                    && self.forward[k] + self.backward[-(k - delta)] >= n
+                {
+                    return Some((left.start + x0, right.start + y0));
+                }
+            }
+
+            for k in (-d..=d).rev().step_by(2) {
+                let mut x = if k == -d || (k != d && self.backward[k - 1] < self.backward[k + 1]) {
+                    self.backward[k + 1]
+                } else {
+                    self.backward[k - 1] + 1
+                };
+                let mut y = (x as isize - k) as usize;
+                if x < n && y < m {
+                    let len = self.suffix_len(
+                        left.start..left.start + n - x,
+                        right.start..right.start + m - y,
+                    );
+                    x += len;
+                    y += len;
+                }
+                self.backward[k] = x;
+
+                if !is_odd
+                    && (k - delta).abs() <= d
+                    && self.backward[k] + self.forward[-(k - delta)] >= n
+                {
+                    return Some((left.start + n - x, right.start + m - y));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(left: &str, right: &str) -> Vec<Op> {
+        let left: Vec<[u8; 1]> = left.bytes().map(|byte| [byte]).collect();
+        let right: Vec<[u8; 1]> = right.bytes().map(|byte| [byte]).collect();
+        let ops = edit_script(&left, &right, MAX_EDIT_DISTANCE).unwrap();
+
+        // Applying the script to `left` must produce `right`
+        let mut left_idx = 0;
+        let mut right_idx = 0;
+        for op in &ops {
+            match *op {
+                Op::Same(left_pos, right_pos) => {
+                    assert_eq!((left_pos, right_pos), (left_idx, right_idx));
+                    assert_eq!(left[left_pos], right[right_pos]);
+                    left_idx += 1;
+                    right_idx += 1;
+                }
+                Op::Missing(left_pos) => {
+                    assert_eq!(left_pos, left_idx);
+                    left_idx += 1;
+                }
+                Op::Extra(right_pos) => {
+                    assert_eq!(right_pos, right_idx);
+                    right_idx += 1;
+                }
+            }
+        }
+        assert_eq!((left_idx, right_idx), (left.len(), right.len()));
+
+        ops
+    }
+
+    fn edits(ops: &[Op]) -> usize {
+        ops.iter().filter(|op| !matches!(op, Op::Same(..))).count()
+    }
+
+    #[test]
+    fn identical() {
+        assert_eq!(
+            script("abc", "abc"),
+            [Op::Same(0, 0), Op::Same(1, 1), Op::Same(2, 2)]
+        );
+    }
+
+    #[test]
+    fn empty_sides() {
+        assert!(script("", "").is_empty());
+        assert_eq!(script("ab", ""), [Op::Missing(0), Op::Missing(1)]);
+        assert_eq!(script("", "ab"), [Op::Extra(0), Op::Extra(1)]);
+    }
+
+    #[test]
+    fn insert_delete() {
+        assert_eq!(
+            script("ac", "abc"),
+            [Op::Same(0, 0), Op::Extra(1), Op::Same(1, 2)]
+        );
+        assert_eq!(
+            script("abc", "ac"),
+            [Op::Same(0, 0), Op::Missing(1), Op::Same(2, 1)]
+        );
+    }
+
+    #[test]
+    fn interleaved() {
+        // The example of the paper
+        assert_eq!(edits(&script("abcabba", "cbabac")), 5);
+        assert_eq!(edits(&script("xaybzc", "abc")), 3);
+        assert_eq!(edits(&script("abcdefgh", "axcyefzh")), 6);
+        assert_eq!(edits(&script("abcd", "dcba")), 6);
+    }
+
+    #[test]
+    fn too_different() {
+        let left = [[1u8], [2], [3]];
+        let right = [[4u8], [5], [6]];
+        assert!(edit_script(&left, &right, 6).is_ok());
+        assert!(edit_script(&left, &right, 5).is_err());
+        assert!(edit_script(&left, &[], 2).is_err());
+    }
+
+    #[test]
+    fn changed() {
+        let left = [&[0xb0, 0x07, 0x10][..], &[0x90, 0x3c, 0x40][..]];
+        let right = [&[0xb0, 0x07, 0x20][..], &[0x90, 0x3c, 0x40][..]];
+        let entries = diff(&left, &right).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, Kind::Changed);
+        assert_eq!(entries[1].kind, Kind::Same);
+    }
+}
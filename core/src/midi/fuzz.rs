@@ -0,0 +1,184 @@
+//! Randomized & pathological byte sequences, to stress the devices under test.
+
+/// Realtime bytes, which can be inserted anywhere, even inside a SysEx.
+const REALTIME: [u8; 6] = [0xf8, 0xfa, 0xfb, 0xfc, 0xfe, 0xff];
+
+const MAX_RANDOM_LEN: usize = 16;
+const MAX_SYSEX_LEN: usize = 64;
+const MAX_RUNNING_MSGS: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pattern {
+    /// Any of the other patterns.
+    #[default]
+    Mixed,
+    /// Random bytes.
+    Random,
+    /// Channel messages & SysEx interleaved with realtime bytes.
+    InterleavedRealtime,
+    /// SysEx messages missing their End Of Exclusive.
+    TruncatedSysEx,
+    /// Long runs of running status, with missing data bytes.
+    RunningStatus,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 5] = [
+        Pattern::Mixed,
+        Pattern::Random,
+        Pattern::InterleavedRealtime,
+        Pattern::TruncatedSysEx,
+        Pattern::RunningStatus,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Pattern::Mixed => "Mixed",
+            Pattern::Random => "Random bytes",
+            Pattern::InterleavedRealtime => "Interleaved realtime",
+            Pattern::TruncatedSysEx => "Truncated SysEx",
+            Pattern::RunningStatus => "Running status abuse",
+        }
+    }
+}
+
+/// Small xorshift generator: fuzzing doesn't need a strong generator
+/// but it must be reproducible from the seed.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must not be 0.
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a value in `0..max`.
+    fn below(&mut self, max: usize) -> usize {
+        (self.next_u64() % max as u64) as usize
+    }
+
+    fn byte(&mut self) -> u8 {
+        self.next_u64() as u8
+    }
+
+    fn data(&mut self) -> u8 {
+        self.byte() & 0x7f
+    }
+
+    /// Returns a status in `0x80..=0xef`, excluding the System messages.
+    fn channel_status(&mut self) -> u8 {
+        0x80 | (self.byte() % 0x70)
+    }
+
+    fn realtime(&mut self) -> u8 {
+        REALTIME[self.below(REALTIME.len())]
+    }
+}
+
+/// Generates byte sequences following a `Pattern`.
+#[derive(Clone, Debug)]
+pub struct Fuzzer {
+    pattern: Pattern,
+    rng: Rng,
+}
+
+impl Fuzzer {
+    pub fn new(pattern: Pattern, seed: u64) -> Self {
+        Fuzzer {
+            pattern,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn next_chunk(&mut self) -> Vec<u8> {
+        let pattern = match self.pattern {
+            Pattern::Mixed => Pattern::ALL[1 + self.rng.below(Pattern::ALL.len() - 1)],
+            pattern => pattern,
+        };
+
+        match pattern {
+            Pattern::Mixed | Pattern::Random => self.random(),
+            Pattern::InterleavedRealtime => self.interleaved_realtime(),
+            Pattern::TruncatedSysEx => self.truncated_sysex(),
+            Pattern::RunningStatus => self.running_status(),
+        }
+    }
+
+    fn random(&mut self) -> Vec<u8> {
+        let len = 1 + self.rng.below(MAX_RANDOM_LEN);
+        (0..len).map(|_| self.rng.byte()).collect()
+    }
+
+    fn interleaved_realtime(&mut self) -> Vec<u8> {
+        let mut chunk = if self.rng.below(4) == 0 {
+            let len = self.rng.below(MAX_SYSEX_LEN);
+            let mut sysex = vec![0xf0];
+            sysex.extend((0..len).map(|_| self.rng.data()));
+            sysex.push(0xf7);
+            sysex
+        } else {
+            let status = self.rng.channel_status();
+            let mut msg = vec![status];
+            msg.extend((0..super::msg::data_len(status)).map(|_| self.rng.data()));
+            msg
+        };
+
+        // Never before the status byte, which would be valid.
+        for _ in 0..1 + self.rng.below(3) {
+            let pos = 1 + self.rng.below(chunk.len());
+            let realtime = self.rng.realtime();
+            chunk.insert(pos, realtime);
+        }
+
+        chunk
+    }
+
+    fn truncated_sysex(&mut self) -> Vec<u8> {
+        let len = self.rng.below(MAX_SYSEX_LEN);
+        let mut chunk = vec![0xf0];
+        chunk.extend((0..len).map(|_| self.rng.data()));
+
+        // Terminate with a new message instead of an End Of Exclusive.
+        if self.rng.below(2) == 0 {
+            let status = self.rng.channel_status();
+            chunk.push(status);
+            chunk.extend((0..super::msg::data_len(status)).map(|_| self.rng.data()));
+        }
+
+        chunk
+    }
+
+    fn running_status(&mut self) -> Vec<u8> {
+        let status = self.rng.channel_status();
+        let data_len = super::msg::data_len(status);
+
+        let mut chunk = Vec::new();
+        // Sometimes no status at all.
+        if self.rng.below(4) != 0 {
+            chunk.push(status);
+        }
+        for _ in 0..1 + self.rng.below(MAX_RUNNING_MSGS) {
+            // Sometimes a data byte is missing.
+            let len = if self.rng.below(4) == 0 {
+                data_len.saturating_sub(1)
+            } else {
+                data_len
+            };
+            chunk.extend((0..len).map(|_| self.rng.data()));
+        }
+
+        if chunk.is_empty() {
+            chunk.push(self.rng.data());
+        }
+
+        chunk
+    }
+}
@@ -0,0 +1,108 @@
+//! Builds channel messages, e.g. to inject test traffic.
+
+/// Kind of the channel message to generate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kind {
+    #[default]
+    NoteOn,
+    NoteOff,
+    PolyPressure,
+    ControlChange,
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 7] = [
+        Kind::NoteOn,
+        Kind::NoteOff,
+        Kind::PolyPressure,
+        Kind::ControlChange,
+        Kind::ProgramChange,
+        Kind::ChannelPressure,
+        Kind::PitchBend,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::NoteOn => "Note On",
+            Kind::NoteOff => "Note Off",
+            Kind::PolyPressure => "Poly Pressure",
+            Kind::ControlChange => "Control Change",
+            Kind::ProgramChange => "Program Change",
+            Kind::ChannelPressure => "Channel Pressure",
+            Kind::PitchBend => "Pitch Bend",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Kind::NoteOn => "note_on",
+            Kind::NoteOff => "note_off",
+            Kind::PolyPressure => "poly_pressure",
+            Kind::ControlChange => "control_change",
+            Kind::ProgramChange => "program_change",
+            Kind::ChannelPressure => "channel_pressure",
+            Kind::PitchBend => "pitch_bend",
+        }
+    }
+
+    pub fn from_storage_str(kind: &str) -> Option<Self> {
+        Kind::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == kind)
+    }
+
+    /// Returns the names of the data values, `None` if unused.
+    ///
+    /// The Pitch Bend uses a single 14 bits value.
+    pub fn data_names(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            Kind::NoteOn | Kind::NoteOff => ("Note", Some("Velocity")),
+            Kind::PolyPressure => ("Note", Some("Pressure")),
+            Kind::ControlChange => ("Controller", Some("Value")),
+            Kind::ProgramChange => ("Program", None),
+            Kind::ChannelPressure => ("Pressure", None),
+            Kind::PitchBend => ("Bend", None),
+        }
+    }
+
+    /// Returns the range of the first data value.
+    pub fn data1_range(self) -> std::ops::RangeInclusive<i16> {
+        match self {
+            Kind::PitchBend => -8_192..=8_191,
+            _ => 0..=127,
+        }
+    }
+
+    fn status(self) -> u8 {
+        match self {
+            Kind::NoteOff => 0x80,
+            Kind::NoteOn => 0x90,
+            Kind::PolyPressure => 0xa0,
+            Kind::ControlChange => 0xb0,
+            Kind::ProgramChange => 0xc0,
+            Kind::ChannelPressure => 0xd0,
+            Kind::PitchBend => 0xe0,
+        }
+    }
+
+    /// Builds the message for `channel` (0 based).
+    ///
+    /// Values are clamped to their ranges.
+    pub fn build(self, channel: u8, data1: i16, data2: u8) -> Vec<u8> {
+        let status = self.status() | (channel & 0x0f);
+        let data1 = data1.clamp(*self.data1_range().start(), *self.data1_range().end());
+        let data2 = data2.min(0x7f);
+
+        match self {
+            Kind::PitchBend => {
+                let value = (data1 + 8_192) as u16;
+                vec![status, (value & 0x7f) as u8, (value >> 7) as u8]
+            }
+            Kind::ProgramChange | Kind::ChannelPressure => vec![status, data1 as u8],
+            _ => vec![status, data1 as u8, data2],
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Device Identity Request & Reply (Universal Non-Real Time, General Information).
+
+use std::fmt;
+
+/// Identity Request addressed to all the devices.
+pub const REQUEST: [u8; 6] = [0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7];
+
+/// Decoded Identity Reply.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    pub device_id: u8,
+    /// 1 byte id, or 3 bytes for extended ids starting with `0x00`.
+    pub manufacturer: Vec<u8>,
+    pub family: u16,
+    pub model: u16,
+    pub version: [u8; 4],
+}
+
+impl Reply {
+    /// Parses `buf` if it is an Identity Reply.
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        let (device_id, body) = match buf {
+            [0xf0, 0x7e, device_id, 0x06, 0x02, body @ .., 0xf7] => (*device_id, body),
+            _ => return None,
+        };
+
+        let (manufacturer, rest) = match body {
+            [0x00, id1, id2, rest @ ..] => (vec![0x00, *id1, *id2], rest),
+            [id, rest @ ..] => (vec![*id], rest),
+            [] => return None,
+        };
+
+        match *rest {
+            [fam_lsb, fam_msb, mod_lsb, mod_msb, v1, v2, v3, v4] => Some(Reply {
+                device_id,
+                manufacturer,
+                family: u16::from(fam_lsb) | (u16::from(fam_msb) << 7),
+                model: u16::from(mod_lsb) | (u16::from(mod_msb) << 7),
+                version: [v1, v2, v3, v4],
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn manufacturer_name(&self) -> Option<&'static str> {
+        manufacturer_name(&self.manufacturer)
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.manufacturer_name() {
+            Some(name) => f.write_str(name)?,
+            None => {
+                f.write_str("Manufacturer")?;
+                for id in self.manufacturer.iter() {
+                    write!(f, " {id:02x}")?;
+                }
+            }
+        }
+
+        let [v1, v2, v3, v4] = self.version;
+        write!(
+            f,
+            ", family {:04x}, model {:04x}, firmware {v1}.{v2}.{v3}.{v4}",
+            self.family, self.model,
+        )
+    }
+}
+
+/// Returns the name of some common manufacturers.
+pub fn manufacturer_name(id: &[u8]) -> Option<&'static str> {
+    let name = match id {
+        [0x01] => "Sequential Circuits",
+        [0x04] => "Moog",
+        [0x06] => "Lexicon",
+        [0x07] => "Kurzweil",
+        [0x0f] => "Ensoniq",
+        [0x10] => "Oberheim",
+        [0x18] => "E-mu",
+        [0x1c] => "Eventide",
+        [0x40] => "Kawai",
+        [0x41] => "Roland",
+        [0x42] => "Korg",
+        [0x43] => "Yamaha",
+        [0x44] => "Casio",
+        [0x47] => "Akai",
+        [0x00, 0x00, 0x0e] => "Alesis",
+        [0x00, 0x00, 0x66] => "Mackie",
+        [0x00, 0x01, 0x05] => "M-Audio",
+        [0x00, 0x20, 0x29] => "Novation",
+        [0x00, 0x20, 0x32] => "Behringer",
+        [0x00, 0x20, 0x33] => "Access",
+        [0x00, 0x20, 0x3c] => "Elektron",
+        [0x00, 0x20, 0x6b] => "Arturia",
+        [0x00, 0x21, 0x09] => "Native Instruments",
+        [0x00, 0x21, 0x1d] => "Teenage Engineering",
+        _ => return None,
+    };
+
+    Some(name)
+}
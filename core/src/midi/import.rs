@@ -0,0 +1,523 @@
+//! Imports the textual logs of other Midi monitors.
+//!
+//! Supported formats:
+//!
+//! - MIDI-OX monitor logs, as saved from its input monitor window.
+//! - ReceiveMIDI dumps & SendMIDI command files, which share the same syntax.
+
+use std::fmt;
+
+use super::{msg::Origin, PortNb};
+use crate::time::{Duration, SystemTime};
+
+#[derive(Debug, thiserror::Error)]
+#[error("Line {line}: {reason}")]
+pub struct Error {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Error {
+    fn new(line: usize, reason: impl ToString) -> Self {
+        Error {
+            line,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    MidiOx,
+    SendMidi,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::MidiOx => "MIDI-OX",
+            Format::SendMidi => "ReceiveMIDI / SendMIDI",
+        })
+    }
+}
+
+impl Format {
+    /// Detects the format of `text`.
+    ///
+    /// MIDI-OX logs start with a column header.
+    pub fn detect(text: &str) -> Format {
+        let is_midi_ox = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or(false, |line| line.starts_with("TIMESTAMP"));
+
+        if is_midi_ox {
+            Format::MidiOx
+        } else {
+            Format::SendMidi
+        }
+    }
+}
+
+/// Imports the messages of `text`, detecting its `Format`.
+///
+/// The wall-clock timestamps are offset from `start`.
+pub fn import(text: &str, start: SystemTime) -> Result<(Format, Vec<Origin>), Error> {
+    let format = Format::detect(text);
+    let origins = match format {
+        Format::MidiOx => midi_ox(text, start)?,
+        Format::SendMidi => send_midi(text, start)?,
+    };
+
+    Ok((format, origins))
+}
+
+/// Returns whether the wall-clock timestamp of `ts` can be represented.
+fn is_valid_ts(ts: u64, start: SystemTime) -> bool {
+    start.checked_add(Duration::from_micros(ts)).is_some()
+}
+
+/// `ts` must be valid, see `is_valid_ts`.
+fn origin(ts: u64, start: SystemTime, port_nb: PortNb, buffer: &[u8]) -> Origin {
+    Origin {
+        ts,
+        wall_ts: start + Duration::from_micros(ts),
+        port_nb,
+        buffer: buffer.into(),
+    }
+}
+
+/// Imports a MIDI-OX monitor log.
+///
+/// ```text
+///  TIMESTAMP IN PORT STATUS DATA1 DATA2 CHAN NOTE EVENT
+///  0000162A   1   3     90    3C    4A    1  C  4 Note On
+///  00003A7B   1  --     F0  Buffer:     6 Bytes   System Exclusive
+///  SYSX: F0 7E 7F 06 01 F7
+/// ```
+///
+/// Timestamps are in ms, using the default hexadecimal display.
+/// Messages from input port 1 are assigned to `PortNb::One`, others to `PortNb::Two`.
+pub fn midi_ox(text: &str, start: SystemTime) -> Result<Vec<Origin>, Error> {
+    let mut origins = Vec::new();
+    // SysEx being received: ts, port & bytes
+    let mut sysex: Option<(u64, PortNb, Vec<u8>)> = None;
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_nb = idx + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("TIMESTAMP") {
+            continue;
+        }
+
+        if let Some(bytes) = line.strip_prefix("SYSX:") {
+            let (_, _, buffer) = sysex
+                .as_mut()
+                .ok_or_else(|| Error::new(line_nb, "SysEx data without header"))?;
+            for byte in bytes.split_whitespace() {
+                buffer.push(hex_byte(byte).ok_or_else(|| Error::new(line_nb, "Invalid byte"))?);
+            }
+
+            if buffer.last() == Some(&0xf7) {
+                let (ts, port_nb, buffer) = sysex.take().expect("checked above");
+                origins.push(origin(ts, start, port_nb, &buffer));
+            }
+
+            continue;
+        }
+
+        // Incomplete SysEx
+        if let Some((ts, port_nb, buffer)) = sysex.take() {
+            origins.push(origin(ts, start, port_nb, &buffer));
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut next = |name: &str| {
+            fields
+                .next()
+                .ok_or_else(|| Error::new(line_nb, format!("Missing {}", name)))
+        };
+
+        let ts = next("timestamp")?;
+        let ts = u64::from_str_radix(ts, 16)
+            .ok()
+            .and_then(|ts| ts.checked_mul(1_000))
+            .filter(|ts| is_valid_ts(*ts, start))
+            .ok_or_else(|| Error::new(line_nb, format!("Invalid timestamp {}", ts)))?;
+        let port_nb = match next("input port")? {
+            "1" => PortNb::One,
+            _ => PortNb::Two,
+        };
+        // Output port
+        next("output port")?;
+
+        let status = next("status")?;
+        let status = hex_byte(status)
+            .filter(|status| *status >= 0x80)
+            .ok_or_else(|| Error::new(line_nb, format!("Invalid status {}", status)))?;
+
+        if status == 0xf0 {
+            sysex = Some((ts, port_nb, Vec::new()));
+            continue;
+        }
+
+        let mut buffer = vec![status];
+        for _ in 0..super::msg::data_len(status) {
+            let data = next("data")?;
+            buffer.push(
+                hex_byte(data)
+                    .filter(|data| *data < 0x80)
+                    .ok_or_else(|| Error::new(line_nb, format!("Invalid data {}", data)))?,
+            );
+        }
+
+        origins.push(origin(ts, start, port_nb, &buffer));
+    }
+
+    if let Some((ts, port_nb, buffer)) = sysex {
+        origins.push(origin(ts, start, port_nb, &buffer));
+    }
+
+    Ok(origins)
+}
+
+fn hex_byte(byte: &str) -> Option<u8> {
+    u8::from_str_radix(byte, 16).ok()
+}
+
+/// Imports a ReceiveMIDI dump or a SendMIDI command file.
+///
+/// ```text
+/// 00:00:01.250   channel  1   note-on          C3 100
+/// ch 2 cc 7 100 pb 8192
+/// syx hex 43 10 4C 00 00 7E 00
+/// ```
+///
+/// Both the long and the abbreviated command names are supported. Notes can
+/// be numbers or names, with middle C as C3. Numbers are decimal unless
+/// following the `hex` command or suffixed with `H`. Messages are assigned to
+/// `PortNb::One` and timestamps (ReceiveMIDI `ts` option) are optional.
+/// Lines starting with `#` and the text following `//` are comments.
+pub fn send_midi(text: &str, start: SystemTime) -> Result<Vec<Origin>, Error> {
+    let mut parser = SendMidiParser::default();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let line = line.split_once("//").map_or(line, |(line, _)| line);
+        parser
+            .parse_line(line, start)
+            .map_err(|reason| Error::new(idx + 1, reason))?;
+    }
+
+    Ok(parser.origins)
+}
+
+#[derive(Default)]
+struct SendMidiParser {
+    origins: Vec<Origin>,
+    /// Channel of the next channel messages (0-based).
+    channel: u8,
+    is_hex: bool,
+    ts: u64,
+}
+
+impl SendMidiParser {
+    fn parse_line(&mut self, line: &str, start: SystemTime) -> Result<(), String> {
+        let mut tokens = line.split_whitespace().peekable();
+
+        while let Some(token) = tokens.next() {
+            let command = token.to_ascii_lowercase();
+
+            if let Some(ts) = parse_ts(&command) {
+                if !is_valid_ts(ts, start) {
+                    return Err(format!("Invalid timestamp {}", token));
+                }
+                self.ts = ts;
+                continue;
+            }
+
+            let mut next = || {
+                tokens
+                    .next()
+                    .ok_or_else(|| format!("Missing argument for {}", command))
+            };
+
+            let channel = self.channel;
+            let is_hex = self.is_hex;
+            let buffer: Vec<u8> = match command.as_str() {
+                "hex" => {
+                    self.is_hex = true;
+                    continue;
+                }
+                "dec" => {
+                    self.is_hex = false;
+                    continue;
+                }
+                "channel" | "ch" => {
+                    let channel = number(next()?, is_hex, 16)?;
+                    if channel == 0 {
+                        return Err("Invalid channel 0".to_string());
+                    }
+                    self.channel = channel as u8 - 1;
+                    continue;
+                }
+                "note-on" | "on" => vec![
+                    0x90 | channel,
+                    note(next()?, is_hex)?,
+                    number(next()?, is_hex, 127)? as u8,
+                ],
+                "note-off" | "off" => vec![
+                    0x80 | channel,
+                    note(next()?, is_hex)?,
+                    number(next()?, is_hex, 127)? as u8,
+                ],
+                "poly-pressure" | "pp" => vec![
+                    0xa0 | channel,
+                    note(next()?, is_hex)?,
+                    number(next()?, is_hex, 127)? as u8,
+                ],
+                "control-change" | "cc" => vec![
+                    0xb0 | channel,
+                    number(next()?, is_hex, 127)? as u8,
+                    number(next()?, is_hex, 127)? as u8,
+                ],
+                "program-change" | "pc" => {
+                    vec![0xc0 | channel, number(next()?, is_hex, 127)? as u8]
+                }
+                "channel-pressure" | "cp" => {
+                    vec![0xd0 | channel, number(next()?, is_hex, 127)? as u8]
+                }
+                "pitch-bend" | "pb" => {
+                    let val = number(next()?, is_hex, 0x3fff)?;
+                    vec![0xe0 | channel, (val & 0x7f) as u8, (val >> 7) as u8]
+                }
+                "rpn" | "nrpn" => {
+                    let (msb_cc, lsb_cc) = if command == "rpn" {
+                        (101, 100)
+                    } else {
+                        (99, 98)
+                    };
+                    let param = number(next()?, is_hex, 0x3fff)?;
+                    let val = number(next()?, is_hex, 0x3fff)?;
+                    for (cc, val) in [
+                        (msb_cc, param >> 7),
+                        (lsb_cc, param & 0x7f),
+                        (6, val >> 7),
+                        (38, val & 0x7f),
+                    ] {
+                        self.push(start, &[0xb0 | channel, cc, val as u8]);
+                    }
+                    continue;
+                }
+                "system-exclusive" | "syx" => {
+                    let mut buffer = vec![0xf0];
+                    while let Some(token) = tokens.peek() {
+                        match token.to_ascii_lowercase().as_str() {
+                            "hex" => self.is_hex = true,
+                            "dec" => self.is_hex = false,
+                            // Also a number with the `H` suffix
+                            "ch" => break,
+                            token => match number(token, self.is_hex, 0xff) {
+                                Ok(byte) if byte < 0x80 || byte == 0xf0 || byte == 0xf7 => {
+                                    buffer.push(byte as u8)
+                                }
+                                _ => break,
+                            },
+                        }
+                        tokens.next();
+                    }
+                    if buffer.get(1) == Some(&0xf0) {
+                        buffer.remove(0);
+                    }
+                    if buffer.last() != Some(&0xf7) {
+                        buffer.push(0xf7);
+                    }
+                    buffer
+                }
+                "mtc-quarter-frame" | "mtc" => {
+                    let first = number(next()?, is_hex, 127)?;
+                    // ReceiveMIDI dumps the piece type & value
+                    let val = match tokens.peek().map(|token| number(token, is_hex, 15)) {
+                        Some(Ok(val)) => {
+                            tokens.next();
+                            (first.min(7) << 4) | val
+                        }
+                        _ => first,
+                    };
+                    vec![0xf1, val as u8]
+                }
+                "song-position" | "spp" => {
+                    let val = number(next()?, is_hex, 0x3fff)?;
+                    vec![0xf2, (val & 0x7f) as u8, (val >> 7) as u8]
+                }
+                "song-select" | "ss" => vec![0xf3, number(next()?, is_hex, 127)? as u8],
+                "tune-request" | "tun" => vec![0xf6],
+                "timing-clock" | "clock" => vec![0xf8],
+                "start" => vec![0xfa],
+                "continue" | "cont" => vec![0xfb],
+                "stop" => vec![0xfc],
+                "active-sensing" => vec![0xfe],
+                "reset" | "rst" => vec![0xff],
+                _ => return Err(format!("Unknown command {}", token)),
+            };
+
+            self.push(start, &buffer);
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, start: SystemTime, buffer: &[u8]) {
+        self.origins
+            .push(origin(self.ts, start, PortNb::One, buffer));
+    }
+}
+
+/// Parses a ReceiveMIDI timestamp: `HH:MM:SS.mmm`.
+fn parse_ts(token: &str) -> Option<u64> {
+    let (hms, millis) = token.split_once('.')?;
+    let mut parts = hms.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let mins: u64 = parts.next()?.parse().ok()?;
+    let secs: u64 = parts.next()?.parse().ok()?;
+    let millis: u64 = millis.parse().ok()?;
+
+    hours
+        .checked_mul(60)?
+        .checked_add(mins)?
+        .checked_mul(60)?
+        .checked_add(secs)?
+        .checked_mul(1_000)?
+        .checked_add(millis)?
+        .checked_mul(1_000)
+}
+
+fn number(token: &str, is_hex: bool, max: u16) -> Result<u16, String> {
+    let (digits, radix) = match token.strip_suffix(['h', 'H']) {
+        Some(digits) => (digits, 16),
+        None if is_hex => (token, 16),
+        None => (token, 10),
+    };
+
+    u16::from_str_radix(digits, radix)
+        .ok()
+        .filter(|val| *val <= max)
+        .ok_or_else(|| format!("Invalid value {}", token))
+}
+
+/// Parses a note number or name, e.g. `C#3`, with middle C as C3.
+fn note(token: &str, is_hex: bool) -> Result<u8, String> {
+    if let Ok(note) = number(token, is_hex, 127) {
+        return Ok(note as u8);
+    }
+
+    let invalid = || format!("Invalid note {}", token);
+
+    let mut chars = token.chars();
+    let pitch_class: i16 = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return Err(invalid()),
+    };
+    let rest = chars.as_str();
+    let (alteration, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let octave: i16 = octave.parse().map_err(|_| invalid())?;
+
+    u8::try_from((octave + 2) * 12 + pitch_class + alteration)
+        .ok()
+        .filter(|note| *note <= 127)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::UNIX_EPOCH;
+
+    fn bufs(origins: &[Origin]) -> Vec<&[u8]> {
+        origins.iter().map(|origin| &*origin.buffer).collect()
+    }
+
+    #[test]
+    fn midi_ox() {
+        let text = "\
+             TIMESTAMP IN PORT STATUS DATA1 DATA2 CHAN NOTE EVENT
+             0000162A   1   3     90    3C    4A    1  C  4 Note On
+             00003A7B   2  --     F0  Buffer:     6 Bytes   System Exclusive
+             SYSX: F0 7E 7F 06 01 F7
+            ";
+
+        let (format, origins) = import(text, UNIX_EPOCH).unwrap();
+        assert_eq!(format, Format::MidiOx);
+        assert_eq!(
+            bufs(&origins),
+            [
+                &[0x90, 0x3c, 0x4a][..],
+                &[0xf0, 0x7e, 0x7f, 0x06, 0x01, 0xf7]
+            ]
+        );
+        assert_eq!(origins[0].ts, 0x162a * 1_000);
+        assert_eq!(origins[0].port_nb, PortNb::One);
+        assert_eq!(
+            origins[1].wall_ts,
+            UNIX_EPOCH + Duration::from_millis(0x3a7b)
+        );
+        assert_eq!(origins[1].port_nb, PortNb::Two);
+    }
+
+    #[test]
+    fn midi_ox_invalid_ts() {
+        let text = "\
+             TIMESTAMP IN PORT STATUS DATA1 DATA2 CHAN NOTE EVENT
+             FFFFFFFFFFFFFFFF   1   3     90    3C    4A    1  C  4 Note On
+            ";
+
+        let err = super::midi_ox(text, UNIX_EPOCH).unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn send_midi() {
+        let text = "\
+            # Comment
+            00:00:01.250   channel  1   note-on          C3 100
+            ch 2 cc 7 100 pb 8192 // Comment
+            syx hex 43 10 4C 00 00 7E 00
+            ";
+
+        let (format, origins) = import(text, UNIX_EPOCH).unwrap();
+        assert_eq!(format, Format::SendMidi);
+        assert_eq!(
+            bufs(&origins),
+            [
+                &[0x90, 60, 100][..],
+                &[0xb1, 7, 100],
+                &[0xe1, 0x00, 0x40],
+                &[0xf0, 0x43, 0x10, 0x4c, 0x00, 0x00, 0x7e, 0x00, 0xf7],
+            ]
+        );
+        assert!(origins.iter().all(|origin| origin.ts == 1_250_000));
+        assert!(origins.iter().all(|origin| origin.port_nb == PortNb::One));
+    }
+
+    #[test]
+    fn send_midi_errors() {
+        let err = super::send_midi("on C3 100\nfoo 1", UNIX_EPOCH).unwrap_err();
+        assert_eq!(err.line, 2);
+
+        assert!(super::send_midi("99999999999999999:00:00.000 start", UNIX_EPOCH).is_err());
+    }
+}
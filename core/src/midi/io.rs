@@ -67,6 +67,39 @@ impl MidiIn {
         Ok(())
     }
 
+    /// Creates a virtual destination named `port_name` other applications can send to.
+    #[cfg(target_os = "macos")]
+    pub fn connect_virtual<C>(&mut self, port_name: &str, mut callback: C) -> Result<(), Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        use midir::os::unix::VirtualInput;
+
+        self.disconnect();
+        match std::mem::take(self) {
+            Self::Disconnected(midi_input) => {
+                match midi_input.create_virtual(
+                    port_name,
+                    move |ts, buf, _port_name| callback(ts, buf),
+                    (),
+                ) {
+                    Ok(conn) => {
+                        *self = Self::Connected(conn);
+                    }
+                    Err(err) => {
+                        *self = Self::Disconnected(err.into_inner());
+                        let err = Error::Connection(port_name.into());
+                        log::error!("{}", err);
+                        return Err(err);
+                    }
+                };
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
     pub fn disconnect(&mut self) {
         if self.is_connected() {
             match std::mem::take(self) {
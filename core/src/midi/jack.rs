@@ -0,0 +1,115 @@
+//! JACK Midi input backend.
+//!
+//! Allows selecting JACK at runtime, while `midir` keeps using the native backend.
+
+use jack::PortSpec;
+use std::{collections::BTreeMap, sync::Arc};
+
+use super::{port::Kind, PortNb};
+
+type Callback = Box<dyn FnMut(u64, &[u8]) + Send>;
+
+struct Handler {
+    port: jack::Port<jack::MidiIn>,
+    callback: Callback,
+}
+
+impl jack::ProcessHandler for Handler {
+    fn process(&mut self, client: &jack::Client, ps: &jack::ProcessScope) -> jack::Control {
+        let frame = ps.last_frame_time();
+        for event in self.port.iter(ps) {
+            let ts = client.frames_to_time(frame + event.time);
+            (self.callback)(ts, event.bytes);
+        }
+
+        jack::Control::Continue
+    }
+}
+
+/// Lists the JACK Midi output ports and captures them.
+pub struct Backend {
+    client_name: Arc<str>,
+    /// Client used to list the ports.
+    client: jack::Client,
+    connections: [Option<jack::AsyncClient<(), Handler>>; 2],
+}
+
+impl Backend {
+    pub fn try_new(client_name: &Arc<str>) -> Result<Self, jack::Error> {
+        let (client, _) = jack::Client::new(
+            &format!("{client_name} ports"),
+            jack::ClientOptions::NO_START_SERVER,
+        )?;
+
+        Ok(Self {
+            client_name: client_name.clone(),
+            client,
+            connections: Default::default(),
+        })
+    }
+
+    /// Returns the available Midi sources and their `Kind`.
+    pub fn list(&self) -> Result<BTreeMap<Arc<str>, Kind>, jack::Error> {
+        let names = self.client.ports(
+            None,
+            Some(jack::MidiIn::default().jack_port_type()),
+            jack::PortFlags::IS_OUTPUT,
+        );
+
+        Ok(names
+            .into_iter()
+            .filter(|name| !name.starts_with(self.client_name.as_ref()))
+            .map(|name| {
+                let kind = match self.client.port_by_name(&name) {
+                    Some(port) if port.flags().contains(jack::PortFlags::IS_PHYSICAL) => {
+                        Kind::Hardware
+                    }
+                    Some(_) => Kind::Virtual,
+                    None => Kind::Unknown,
+                };
+
+                (Arc::from(name), kind)
+            })
+            .collect())
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: PortNb,
+        port_name: &str,
+        callback: C,
+    ) -> Result<(), jack::Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.disconnect(port_nb);
+
+        let (client, _) = jack::Client::new(
+            &format!("{} {}", self.client_name, port_nb),
+            jack::ClientOptions::NO_START_SERVER,
+        )?;
+        let port = client.register_port("in", jack::MidiIn::default())?;
+        let dest = port.name()?;
+
+        let active = client.activate_async(
+            (),
+            Handler {
+                port,
+                callback: Box::new(callback),
+            },
+        )?;
+        active.as_client().connect_ports_by_name(port_name, &dest)?;
+
+        self.connections[port_nb.idx()] = Some(active);
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: PortNb) {
+        if let Some(active) = self.connections[port_nb.idx()].take() {
+            if let Err(err) = active.deactivate() {
+                log::warn!("Failed to deactivate JACK client for {port_nb}: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! State of the keys of a keyboard per channel, including the sustain pedal.
+
+use super::PortNb;
+
+const CC_SUSTAIN: u8 = 64;
+const CC_ALL_SOUND_OFF: u8 = 120;
+const CC_ALL_NOTES_OFF: u8 = 123;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    Released,
+    /// Key held down with the given velocity.
+    Pressed(u8),
+    /// Key released while the sustain pedal is down.
+    Sustained(u8),
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    keys: [KeyState; 128],
+    is_sustained: bool,
+    /// Whether a note was played on this channel.
+    is_active: bool,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel {
+            keys: [KeyState::Released; 128],
+            is_sustained: false,
+            is_active: false,
+        }
+    }
+}
+
+impl Channel {
+    pub fn key(&self, note: u8) -> KeyState {
+        self.keys[note as usize & 0x7f]
+    }
+
+    pub fn is_sustained(&self) -> bool {
+        self.is_sustained
+    }
+
+    fn release(&mut self, note: u8) {
+        let key = &mut self.keys[note as usize];
+        if let KeyState::Pressed(velocity) = *key {
+            *key = if self.is_sustained {
+                KeyState::Sustained(velocity)
+            } else {
+                KeyState::Released
+            };
+        }
+    }
+
+    fn set_sustain(&mut self, is_sustained: bool) {
+        self.is_sustained = is_sustained;
+        if !is_sustained {
+            for key in self.keys.iter_mut() {
+                if let KeyState::Sustained(_) = key {
+                    *key = KeyState::Released;
+                }
+            }
+        }
+    }
+
+    fn all_off(&mut self) {
+        self.keys = [KeyState::Released; 128];
+    }
+}
+
+/// Keeps track of the keys of each port & channel.
+#[derive(Debug, Default)]
+pub struct Keyboards {
+    channels: [[Channel; 16]; 2],
+}
+
+impl Keyboards {
+    /// Updates the keys with the message in `buf`.
+    pub fn handle(&mut self, port_nb: PortNb, buf: &[u8]) {
+        let (status, data1, data2) = match *buf {
+            [status, data1, data2] => (status, data1 & 0x7f, data2),
+            _ => return,
+        };
+
+        let channel = &mut self.channels[port_nb.idx()][(status & 0x0f) as usize];
+        match status & 0xf0 {
+            0x90 if data2 > 0 => {
+                channel.keys[data1 as usize] = KeyState::Pressed(data2);
+                channel.is_active = true;
+            }
+            0x80 | 0x90 => channel.release(data1),
+            0xb0 => match data1 {
+                CC_SUSTAIN => channel.set_sustain(data2 >= 64),
+                CC_ALL_SOUND_OFF | CC_ALL_NOTES_OFF => channel.all_off(),
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    /// `channel` in the range 0..=15.
+    pub fn get(&self, port_nb: PortNb, channel: u8) -> &Channel {
+        &self.channels[port_nb.idx()][channel as usize & 0x0f]
+    }
+
+    /// Iterates over the ports & channels on which notes were played.
+    pub fn active(&self) -> impl Iterator<Item = (PortNb, u8)> + '_ {
+        [PortNb::One, PortNb::Two]
+            .into_iter()
+            .flat_map(|port_nb| (0..16).map(move |channel| (port_nb, channel)))
+            .filter(|&(port_nb, channel)| self.get(port_nb, channel).is_active)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
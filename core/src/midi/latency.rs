@@ -0,0 +1,225 @@
+//! Round-trip latency measurement.
+//!
+//! A marker message is sent to an output port, which is expected to come back
+//! on an input port, e.g. through a cable loop or some software routing.
+
+use crossbeam_channel as channel;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use super::{port::Error, MidiOut, PortNb};
+use crate::{
+    bus,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Maximum duration between two checks of `Tester::stop` while waiting.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the marker for iteration `idx`: a non-commercial SysEx.
+pub fn marker(idx: u32) -> [u8; 7] {
+    [
+        0xf0,
+        0x7d,
+        b'L',
+        ((idx >> 14) & 0x7f) as u8,
+        ((idx >> 7) & 0x7f) as u8,
+        (idx & 0x7f) as u8,
+        0xf7,
+    ]
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    /// Port on which the markers are expected.
+    pub port_nb: PortNb,
+    pub iterations: u32,
+    /// Maximum duration to wait for a marker.
+    pub timeout: Duration,
+    /// Delay between receiving a marker and sending the next one.
+    pub interval: Duration,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub std_dev: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Results {
+    pub samples: Vec<Duration>,
+    /// Markers which didn't come back before the timeout.
+    pub lost: u32,
+}
+
+impl Results {
+    pub fn stats(&self) -> Option<Stats> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let len = sorted.len();
+        let percentile = |pct: usize| sorted[((len - 1) * pct + 50) / 100];
+
+        let mean = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / len as f64;
+        let variance = sorted
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / len as f64;
+
+        Some(Stats {
+            min: sorted[0],
+            max: sorted[len - 1],
+            mean: Duration::from_secs_f64(mean),
+            median: percentile(50),
+            p95: percentile(95),
+            std_dev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+/// Runs the measurement on a dedicated thread.
+pub struct Tester {
+    settings: Settings,
+    results: Arc<Mutex<Results>>,
+    must_stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<Result<(), Error>>>,
+}
+
+impl Tester {
+    pub fn spawn(mut midi_out: MidiOut, bus: &bus::Bus, settings: Settings) -> Self {
+        let msg_rx = bus.subscribe(bus::Event::is_msg_batch);
+        let results = Arc::new(Mutex::new(Results::default()));
+        let must_stop = Arc::new(AtomicBool::new(false));
+
+        let handle = thread::spawn({
+            let results = results.clone();
+            let must_stop = must_stop.clone();
+            move || {
+                log::info!(
+                    "Measuring latency from {} to {}",
+                    midi_out.port_name(),
+                    settings.port_nb,
+                );
+
+                for idx in 0..settings.iterations {
+                    if must_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    // Ignore markers which arrived after their timeout.
+                    while msg_rx.try_recv().is_ok() {}
+
+                    let marker = marker(idx);
+                    let sent_at = SystemTime::now();
+                    midi_out.send(&marker)?;
+
+                    let latency = wait_for(&msg_rx, settings, &marker, sent_at, &must_stop);
+                    if must_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    match latency {
+                        Some(latency) => results.lock().unwrap().samples.push(latency),
+                        None => results.lock().unwrap().lost += 1,
+                    }
+
+                    sleep(settings.interval, &must_stop);
+                }
+
+                Ok(())
+            }
+        });
+
+        Tester {
+            settings,
+            results,
+            must_stop,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn results(&self) -> Results {
+        self.results.lock().unwrap().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .map_or(true, thread::JoinHandle::is_finished)
+    }
+
+    /// Stops the measurement and returns its result.
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.must_stop.store(true, Ordering::Relaxed);
+        match self.handle.take() {
+            Some(handle) => handle.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Tester {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Waits for `marker` and returns the round-trip latency, `None` on timeout or stop.
+fn wait_for(
+    msg_rx: &channel::Receiver<bus::Event>,
+    settings: Settings,
+    marker: &[u8],
+    sent_at: SystemTime,
+    must_stop: &AtomicBool,
+) -> Option<Duration> {
+    let deadline = Instant::now() + settings.timeout;
+    loop {
+        if must_stop.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let timeout = deadline.checked_duration_since(Instant::now())?;
+        match msg_rx.recv_timeout(timeout.min(STOP_CHECK_INTERVAL)) {
+            Ok(bus::Event::MsgBatch(batch)) => {
+                for res in batch.iter() {
+                    let origin = super::msg::origin(res);
+                    if origin.port_nb == settings.port_nb && origin.buffer.as_ref() == marker {
+                        // Use the arrival time so that the UI load doesn't matter.
+                        return Some(origin.wall_ts.duration_since(sent_at).unwrap_or_default());
+                    }
+                }
+            }
+            Ok(_) | Err(channel::RecvTimeoutError::Timeout) => (),
+            Err(channel::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+/// Sleeps for `duration`, unless the `Tester` is stopped in the meantime.
+fn sleep(duration: Duration, must_stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while !must_stop.load(Ordering::Relaxed) {
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) => thread::sleep(remaining.min(STOP_CHECK_INTERVAL)),
+            None => break,
+        }
+    }
+}
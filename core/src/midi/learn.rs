@@ -0,0 +1,120 @@
+//! MIDI-learn: maps the controls of a device as they are moved one at a time.
+
+use std::fmt;
+
+use super::PortNb;
+
+/// A control as seen from the Midi messages it sends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "save",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum Control {
+    Cc { number: u8 },
+    Note { number: u8 },
+    PitchBend,
+    ChannelPressure,
+    ProgramChange,
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Control::Cc { number } => write!(f, "CC{number}"),
+            Control::Note { number } => write!(f, "Note {}", super::anatomy::note_name(*number)),
+            Control::PitchBend => f.write_str("Pitch bend"),
+            Control::ChannelPressure => f.write_str("Channel pressure"),
+            Control::ProgramChange => f.write_str("Program change"),
+        }
+    }
+}
+
+impl Control {
+    /// Returns the control, its channel (0 based) & value for `buffer`.
+    pub fn parse(buffer: &[u8]) -> Option<(Control, u8, u16)> {
+        let status = *buffer.first()?;
+        let channel = status & 0x0f;
+        let (control, value) = match (status & 0xf0, buffer) {
+            (0xb0, &[_, number, value]) => (Control::Cc { number }, value.into()),
+            // Note Off & Note On with velocity 0 don't tell much about the control.
+            (0x90, &[_, number, velocity]) if velocity > 0 => {
+                (Control::Note { number }, velocity.into())
+            }
+            (0xe0, &[_, lsb, msb]) => (Control::PitchBend, u16::from(msb) << 7 | u16::from(lsb)),
+            (0xd0, &[_, value]) => (Control::ChannelPressure, value.into()),
+            (0xc0, &[_, program]) => (Control::ProgramChange, program.into()),
+            _ => return None,
+        };
+
+        Some((control, channel, value))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize))]
+pub struct Mapping {
+    pub name: String,
+    pub port: PortNb,
+    /// 1 based.
+    pub channel: u8,
+    pub control: Control,
+    pub min: u16,
+    pub max: u16,
+}
+
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} = {} ch{} range {}..{}",
+            self.name, self.control, self.channel, self.min, self.max,
+        )
+    }
+}
+
+/// Builds the `Mapping`s from the incoming messages.
+#[derive(Debug, Default)]
+pub struct Learner {
+    pub mappings: Vec<Mapping>,
+}
+
+impl Learner {
+    /// Handles `buffer` and returns the index of the matching `Mapping`.
+    ///
+    /// A `Mapping` is added for a control which wasn't seen before.
+    pub fn handle(&mut self, port: PortNb, buffer: &[u8]) -> Option<usize> {
+        let (control, channel, value) = Control::parse(buffer)?;
+        let channel = channel + 1;
+
+        let found = self.mappings.iter().position(|mapping| {
+            mapping.port == port && mapping.channel == channel && mapping.control == control
+        });
+        match found {
+            Some(idx) => {
+                let mapping = &mut self.mappings[idx];
+                mapping.min = mapping.min.min(value);
+                mapping.max = mapping.max.max(value);
+
+                Some(idx)
+            }
+            None => {
+                self.mappings.push(Mapping {
+                    name: format!("Control {}", self.mappings.len() + 1),
+                    port,
+                    channel,
+                    control,
+                    min: value,
+                    max: value,
+                });
+
+                Some(self.mappings.len() - 1)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mappings.clear();
+    }
+}
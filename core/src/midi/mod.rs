@@ -0,0 +1,90 @@
+pub mod activity;
+
+pub mod anatomy;
+
+pub mod annotator;
+
+pub mod backpressure;
+
+pub mod batching;
+
+#[cfg(feature = "save")]
+pub mod capture;
+
+#[cfg(feature = "ble")]
+pub mod ble;
+
+pub mod chord;
+
+#[cfg(target_os = "macos")]
+pub mod coremidi;
+
+pub mod diff;
+
+pub mod fuzz;
+
+pub mod generator;
+
+pub mod gm;
+
+pub mod identity;
+
+pub mod import;
+
+pub mod io;
+pub use io::MidiIn;
+
+#[cfg(feature = "jack")]
+pub mod jack;
+
+pub mod keyboard;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod latency;
+
+pub mod learn;
+
+pub mod mpe;
+
+pub mod msg;
+pub use msg::Msg;
+
+pub mod notes;
+
+pub mod output;
+pub use output::MidiOut;
+
+pub mod pcapng;
+
+pub mod port;
+pub use port::{PortNb, Ports};
+
+pub mod port_pattern;
+pub use port_pattern::PortPattern;
+
+#[cfg(feature = "profile")]
+pub mod profile;
+
+#[cfg(feature = "agent")]
+pub mod remote;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
+
+pub mod rpn;
+
+#[cfg(feature = "serial")]
+pub mod serial;
+
+pub mod surface;
+
+pub mod syx;
+
+pub mod template;
+
+pub mod throughput;
+
+pub mod velocity;
+
+#[cfg(all(feature = "winrt", target_os = "windows"))]
+pub mod winrt;
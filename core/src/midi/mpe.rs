@@ -0,0 +1,109 @@
+//! MIDI Polyphonic Expression zones.
+
+use std::fmt;
+
+use super::rpn;
+
+/// Manager channel of the Lower Zone (channel 1).
+const LOWER_MANAGER: u8 = 0;
+/// Manager channel of the Upper Zone (channel 16).
+const UPPER_MANAGER: u8 = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Zone {
+    Lower,
+    Upper,
+}
+
+impl Zone {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Zone::Lower => "Lower",
+            Zone::Upper => "Upper",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Manager(Zone),
+    Member(Zone),
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Manager(zone) => write!(f, "MPE {} manager", zone.as_str()),
+            Role::Member(zone) => write!(f, "MPE {} member", zone.as_str()),
+        }
+    }
+}
+
+/// MPE configuration of a port, as declared by the MPE Configuration Messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Zones {
+    lower_members: u8,
+    upper_members: u8,
+}
+
+impl Zones {
+    pub fn is_enabled(&self) -> bool {
+        self.lower_members > 0 || self.upper_members > 0
+    }
+
+    /// Updates the zones with the parameter `change`.
+    ///
+    /// Returns a description of the new configuration for MPE Configuration Messages.
+    pub fn handle(&mut self, change: &rpn::Change) -> Option<String> {
+        if change.param != rpn::Param::Registered(rpn::RPN_MPE_CONFIGURATION)
+            || change.lsb.is_some()
+        {
+            return None;
+        }
+
+        let members = change.msb.min(15);
+        let zone = match change.channel {
+            LOWER_MANAGER => {
+                self.lower_members = members;
+                // The Upper Zone shrinks if the zones overlap
+                self.upper_members = self.upper_members.min(14 - members.min(14));
+                Zone::Lower
+            }
+            UPPER_MANAGER => {
+                self.upper_members = members;
+                self.lower_members = self.lower_members.min(14 - members.min(14));
+                Zone::Upper
+            }
+            _ => return None,
+        };
+
+        Some(if members == 0 {
+            format!("MPE {} zone off", zone.as_str())
+        } else {
+            format!("MPE {} zone: {members} member(s)", zone.as_str())
+        })
+    }
+
+    /// Returns the role of `channel` (0..=15) in the zones.
+    pub fn role(&self, channel: u8) -> Option<Role> {
+        if self.lower_members > 0 {
+            if channel == LOWER_MANAGER {
+                return Some(Role::Manager(Zone::Lower));
+            }
+            if channel <= self.lower_members {
+                return Some(Role::Member(Zone::Lower));
+            }
+        }
+
+        if self.upper_members > 0 {
+            if channel == UPPER_MANAGER {
+                return Some(Role::Manager(Zone::Upper));
+            }
+            if channel >= UPPER_MANAGER - self.upper_members {
+                return Some(Role::Member(Zone::Upper));
+            }
+        }
+
+        None
+    }
+}
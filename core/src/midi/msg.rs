@@ -0,0 +1,433 @@
+use std::{error, fmt, sync::Arc};
+
+use crate::time::{Duration, SystemTime};
+
+#[derive(Clone, Debug)]
+pub struct Origin {
+    /// Timestamp as provided by the Midi backend (µs).
+    pub ts: u64,
+    /// Wall-clock arrival time.
+    pub wall_ts: SystemTime,
+    pub port_nb: super::PortNb,
+    pub buffer: Arc<[u8]>,
+}
+
+impl Origin {
+    pub fn new(ts: u64, port_nb: super::PortNb, buffer: &[u8]) -> Self {
+        Self {
+            ts,
+            wall_ts: SystemTime::now(),
+            port_nb,
+            buffer: buffer.into(),
+        }
+    }
+
+    /// Returns `count` repetitions of the message, spread evenly from `ts` to `last_ts`.
+    pub fn repeat(self, count: u32, last_ts: u64) -> Repetitions {
+        Repetitions {
+            span: last_ts.saturating_sub(self.ts),
+            origin: self,
+            count: u64::from(count.max(1)),
+            idx: 0,
+        }
+    }
+}
+
+/// Repetitions of a message, see `Origin::repeat`.
+#[derive(Debug)]
+pub struct Repetitions {
+    origin: Origin,
+    span: u64,
+    count: u64,
+    idx: u64,
+}
+
+impl Iterator for Repetitions {
+    type Item = Origin;
+
+    fn next(&mut self) -> Option<Origin> {
+        if self.idx >= self.count {
+            return None;
+        }
+
+        let intervals = (self.count - 1).max(1);
+        let offset = (u128::from(self.span) * u128::from(self.idx) / u128::from(intervals)) as u64;
+        self.idx += 1;
+
+        Some(Origin {
+            ts: self.origin.ts + offset,
+            wall_ts: self.origin.wall_ts + Duration::from_micros(offset),
+            port_nb: self.origin.port_nb,
+            buffer: self.origin.buffer.clone(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.count - self.idx) as usize;
+        (len, Some(len))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Msg {
+    pub origin: Origin,
+    pub msg: midi_msg::MidiMsg,
+}
+
+#[derive(Clone, Debug)]
+pub struct Error {
+    pub origin: Origin,
+    pub err: midi_msg::ParseError,
+    /// Offset of the offending byte, see `error_offset`.
+    pub offset: Option<usize>,
+    /// Number of bytes skipped to resynchronize on the next message, if any.
+    pub skipped: Option<usize>,
+}
+
+impl Error {
+    /// Describes where parsing failed, e.g. "at byte 3".
+    pub fn location(&self) -> Option<String> {
+        let offset = self.offset?;
+        if offset < self.origin.buffer.len() {
+            Some(format!("at byte {offset}"))
+        } else {
+            Some("after the last byte".to_string())
+        }
+    }
+
+    /// Describes the error, its location & the skipped bytes, if any.
+    pub fn description(&self) -> String {
+        let mut desc = self.err.to_string();
+        if let Some(location) = self.location() {
+            desc = format!("{desc} {location}");
+        }
+        if let Some(skipped) = self.skipped {
+            desc = format!("{desc} ({skipped} bytes skipped)");
+        }
+
+        desc
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} @ {} for {}",
+            self.description(),
+            self.origin.ts,
+            self.origin.port_nb
+        )
+    }
+}
+
+impl error::Error for Error {}
+
+pub type Result = std::result::Result<Msg, self::Error>;
+
+/// Returns the number of data bytes following `status`, SysEx excluded.
+pub fn data_len(status: u8) -> usize {
+    match status {
+        0x80..=0xbf | 0xe0..=0xef | 0xf2 => 2,
+        0xc0..=0xdf | 0xf1 | 0xf3 => 1,
+        _ => 0,
+    }
+}
+
+/// Returns the offset of the first byte which doesn't fit the structure of a Midi message.
+///
+/// `buffer.len()` means the message ends prematurely. Returns `None` if
+/// the structure is fine, e.g. for a SysEx with unsupported content.
+pub fn error_offset(buffer: &[u8]) -> Option<usize> {
+    let status = match buffer.first() {
+        Some(status) => *status,
+        None => return Some(0),
+    };
+
+    match status {
+        // Data byte without a status to run from
+        0x00..=0x7f => Some(0),
+        0xf0 => {
+            for (offset, byte) in buffer.iter().enumerate().skip(1) {
+                match byte {
+                    0xf7 => return None,
+                    0x80..=0xff => return Some(offset),
+                    _ => (),
+                }
+            }
+
+            Some(buffer.len())
+        }
+        // Undefined or End of Exclusive without a SysEx
+        0xf4 | 0xf5 | 0xf7 | 0xf9 | 0xfd => Some(0),
+        _ => {
+            let len = 1 + data_len(status);
+            if let Some(offset) = buffer[1..len.min(buffer.len())]
+                .iter()
+                .position(|byte| *byte >= 0x80)
+            {
+                return Some(1 + offset);
+            }
+
+            (buffer.len() < len).then_some(buffer.len())
+        }
+    }
+}
+
+/// Returns the length of the well-formed message at the start of `buffer`.
+fn msg_len(buffer: &[u8]) -> usize {
+    match buffer.first() {
+        Some(0xf0) => buffer
+            .iter()
+            .position(|byte| *byte == 0xf7)
+            .map_or(buffer.len(), |pos| pos + 1),
+        Some(status) => (1 + data_len(*status)).min(buffer.len()),
+        None => 0,
+    }
+}
+
+pub fn origin(res: &Result) -> &Origin {
+    match res {
+        Ok(ok) => &ok.origin,
+        Err(err) => &err.origin,
+    }
+}
+
+/// Parses the Midi message contained in the `origin` buffer.
+///
+/// The message is parsed on its own, use a `Parser` for a stream of messages.
+pub fn parse(origin: Origin) -> Result {
+    let res = midi_msg::MidiMsg::from_midi(&origin.buffer);
+    to_result(origin, res)
+}
+
+/// Parses the Midi messages of each port in the context of the previous ones.
+///
+/// This allows buffers relying on running status to be parsed and
+/// High Resolution controller pairs to be reported as such.
+pub struct Parser {
+    contexts: [midi_msg::ReceiverContext; 2],
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            contexts: [
+                midi_msg::ReceiverContext::new(),
+                midi_msg::ReceiverContext::new(),
+            ],
+        }
+    }
+}
+
+impl Parser {
+    pub fn parse(&mut self, origin: Origin) -> Result {
+        let ctx = &mut self.contexts[origin.port_nb.idx()];
+        let res = midi_msg::MidiMsg::from_midi_with_context(&origin.buffer, ctx);
+        to_result(origin, res)
+    }
+
+    /// Parses all the messages in `origin`, resynchronizing after malformed data.
+    ///
+    /// Each message is pushed to `results` with its own bytes. Malformed
+    /// bytes are pushed as an error up to the next status byte, then the
+    /// remainder of the buffer is parsed.
+    pub fn parse_resync(&mut self, origin: Origin, results: &mut Vec<Result>) {
+        let mut origin = origin;
+        loop {
+            let buffer = origin.buffer.clone();
+            let ctx = &mut self.contexts[origin.port_nb.idx()];
+            let res = midi_msg::MidiMsg::from_midi_with_context(&buffer, ctx);
+
+            let (len, skipped) = match res {
+                Ok((_, len)) => (len, None),
+                Err(_) => match error_offset(&buffer) {
+                    // Resynchronize on the next status byte from the offending one
+                    Some(offset) => {
+                        let next = buffer
+                            .iter()
+                            .enumerate()
+                            .skip(offset.max(1))
+                            .find_map(|(pos, byte)| (*byte >= 0x80).then_some(pos))
+                            .unwrap_or(buffer.len());
+                        (next, Some(next))
+                    }
+                    // Well-formed message with unsupported content
+                    None => (msg_len(&buffer), None),
+                },
+            };
+
+            if len == 0 || len >= buffer.len() {
+                results.push(to_result(origin, res));
+                return;
+            }
+
+            let mut res = to_result(
+                Origin {
+                    buffer: buffer[..len].into(),
+                    ..origin.clone()
+                },
+                res,
+            );
+            if let (Err(err), Some(skipped)) = (&mut res, skipped) {
+                log::warn!("Skipped {skipped} bytes to resynchronize on the next status byte");
+                err.skipped = Some(skipped);
+            }
+            results.push(res);
+
+            origin.buffer = buffer[len..].into();
+        }
+    }
+
+    /// Forgets the context of `port_nb`, e.g. when another device is connected.
+    pub fn reset(&mut self, port_nb: super::PortNb) {
+        self.contexts[port_nb.idx()] = midi_msg::ReceiverContext::new();
+    }
+}
+
+fn to_result(
+    origin: Origin,
+    res: std::result::Result<(midi_msg::MidiMsg, usize), midi_msg::ParseError>,
+) -> Result {
+    match res {
+        Ok((msg, _len)) => Ok(Msg { origin, msg }),
+        Err(err) => {
+            log::error!("Failed to parse Midi buffer: {err}");
+            let offset = error_offset(&origin.buffer);
+            Err(Error {
+                origin,
+                err,
+                offset,
+                skipped: None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::PortNb;
+
+    fn parse_resync(buffers: &[&[u8]]) -> Vec<Result> {
+        let mut parser = Parser::default();
+        let mut results = Vec::new();
+        for buffer in buffers {
+            parser.parse_resync(Origin::new(0, PortNb::One, buffer), &mut results);
+        }
+
+        results
+    }
+
+    fn buffers(results: &[Result]) -> Vec<&[u8]> {
+        results
+            .iter()
+            .map(|res| origin(res).buffer.as_ref())
+            .collect()
+    }
+
+    fn skipped(res: &Result) -> Option<usize> {
+        res.as_ref().err().and_then(|err| err.skipped)
+    }
+
+    #[test]
+    fn error_offset_of_bad_status() {
+        assert_eq!(error_offset(&[]), Some(0));
+        // Data byte without running status
+        assert_eq!(error_offset(&[0x3c, 0x40]), Some(0));
+        // Undefined
+        assert_eq!(error_offset(&[0xf4]), Some(0));
+        assert_eq!(error_offset(&[0xfd]), Some(0));
+        // End of Exclusive without a SysEx
+        assert_eq!(error_offset(&[0xf7]), Some(0));
+    }
+
+    #[test]
+    fn error_offset_of_data_out_of_range() {
+        assert_eq!(error_offset(&[0x90, 0x3c, 0x80]), Some(2));
+        assert_eq!(error_offset(&[0x90, 0xbc, 0x40]), Some(1));
+        assert_eq!(error_offset(&[0xc0, 0x90]), Some(1));
+        assert_eq!(error_offset(&[0xf0, 0x01, 0x90, 0xf7]), Some(2));
+    }
+
+    #[test]
+    fn error_offset_of_truncated_msg() {
+        assert_eq!(error_offset(&[0x90, 0x3c]), Some(2));
+        assert_eq!(error_offset(&[0xb0]), Some(1));
+        assert_eq!(error_offset(&[0xf2, 0x00]), Some(2));
+        assert_eq!(error_offset(&[0xf0, 0x01, 0x02]), Some(3));
+    }
+
+    #[test]
+    fn error_offset_of_well_formed_msg() {
+        assert_eq!(error_offset(&[0x90, 0x3c, 0x40]), None);
+        assert_eq!(error_offset(&[0xc0, 0x05]), None);
+        assert_eq!(error_offset(&[0xf8]), None);
+        assert_eq!(error_offset(&[0xf0, 0x7e, 0x7f, 0xf7]), None);
+    }
+
+    #[test]
+    fn msg_len_of_first_msg() {
+        assert_eq!(msg_len(&[]), 0);
+        assert_eq!(msg_len(&[0x90, 0x3c, 0x40, 0x80, 0x3c, 0x00]), 3);
+        assert_eq!(msg_len(&[0xc0, 0x05, 0x90]), 2);
+        assert_eq!(msg_len(&[0xf8, 0x90]), 1);
+        assert_eq!(msg_len(&[0x90, 0x3c]), 2);
+        assert_eq!(msg_len(&[0xf0, 0x01, 0xf7, 0x90]), 3);
+        assert_eq!(msg_len(&[0xf0, 0x01, 0x02]), 3);
+    }
+
+    #[test]
+    fn consecutive_msgs() {
+        let results = parse_resync(&[&[0x90, 0x3c, 0x40, 0x80, 0x3c, 0x00]]);
+        assert_eq!(
+            buffers(&results),
+            [&[0x90, 0x3c, 0x40][..], &[0x80, 0x3c, 0x00]]
+        );
+        assert!(results.iter().all(|res| res.is_ok()));
+    }
+
+    #[test]
+    fn running_status() {
+        let results = parse_resync(&[&[0x90, 0x3c, 0x40, 0x3e, 0x40], &[0x40, 0x00]]);
+        assert_eq!(
+            buffers(&results),
+            [&[0x90, 0x3c, 0x40][..], &[0x3e, 0x40], &[0x40, 0x00]]
+        );
+        assert!(results.iter().all(|res| res.is_ok()));
+    }
+
+    #[test]
+    fn interrupted_msg() {
+        let results = parse_resync(&[&[0x90, 0x3c, 0x90, 0x3c, 0x40]]);
+        assert_eq!(buffers(&results), [&[0x90, 0x3c][..], &[0x90, 0x3c, 0x40]]);
+        assert!(results[0].is_err());
+        assert_eq!(skipped(&results[0]), Some(2));
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    fn stray_data_bytes() {
+        let results = parse_resync(&[&[0x3c, 0x40, 0x7f, 0x90, 0x3c, 0x40]]);
+        assert_eq!(
+            buffers(&results),
+            [&[0x3c, 0x40, 0x7f][..], &[0x90, 0x3c, 0x40]]
+        );
+        assert!(results[0].is_err());
+        assert_eq!(skipped(&results[0]), Some(3));
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    fn unsupported_sysex() {
+        // Well-formed, whether its content is supported or not: nothing is skipped
+        let sysex = [0xf0, 0x7e, 0x7f, 0x7f, 0x7f, 0x01, 0xf7];
+        let mut buffer = sysex.to_vec();
+        buffer.extend([0x90, 0x3c, 0x40]);
+
+        let results = parse_resync(&[&buffer[..]]);
+        assert_eq!(buffers(&results), [&sysex[..], &[0x90, 0x3c, 0x40]]);
+        assert_eq!(skipped(&results[0]), None);
+        assert!(results[1].is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+//! Note On / Note Off pairing.
+
+use std::collections::HashMap;
+
+use super::PortNb;
+use crate::time::{Duration, SystemTime};
+
+/// Controller numbers which release all the notes of a channel.
+const CC_ALL_SOUND_OFF: u8 = 120;
+const CC_ALL_NOTES_OFF: u8 = 123;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub port_nb: PortNb,
+    /// Channel in the range 0..=15.
+    pub channel: u8,
+    pub note: u8,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Held {
+    pub start: SystemTime,
+    pub velocity: u8,
+}
+
+impl Held {
+    /// Returns for how long the note has been held at `now`.
+    pub fn duration(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.start).unwrap_or_default()
+    }
+}
+
+/// Keeps track of the notes being held.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    held: HashMap<Key, Held>,
+}
+
+impl Tracker {
+    /// Updates the held notes with the message in `buf`.
+    ///
+    /// Returns the released notes along with their duration.
+    pub fn handle(
+        &mut self,
+        port_nb: PortNb,
+        wall_ts: SystemTime,
+        buf: &[u8],
+    ) -> Vec<(Key, Held, Duration)> {
+        let (status, data1, data2) = match *buf {
+            [status, data1, data2, ..] => (status, data1, data2),
+            _ => return Vec::new(),
+        };
+
+        let channel = status & 0x0f;
+        let key = Key {
+            port_nb,
+            channel,
+            note: data1,
+        };
+
+        match status & 0xf0 {
+            0x90 if data2 > 0 => {
+                self.held.insert(
+                    key,
+                    Held {
+                        start: wall_ts,
+                        velocity: data2,
+                    },
+                );
+                Vec::new()
+            }
+            0x80 | 0x90 => self
+                .held
+                .remove(&key)
+                .map(|held| (key, held, held.duration(wall_ts)))
+                .into_iter()
+                .collect(),
+            0xb0 if data1 == CC_ALL_SOUND_OFF || data1 == CC_ALL_NOTES_OFF => {
+                let mut released = Vec::new();
+                self.held.retain(|key, held| {
+                    if key.port_nb == port_nb && key.channel == channel {
+                        released.push((*key, *held, held.duration(wall_ts)));
+                        false
+                    } else {
+                        true
+                    }
+                });
+                released
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the notes held for longer than `threshold` at `now`, longest first.
+    pub fn held_longer_than(&self, threshold: Duration, now: SystemTime) -> Vec<(Key, Held)> {
+        let mut stuck: Vec<(Key, Held)> = self
+            .held
+            .iter()
+            .filter(|(_, held)| held.duration(now) >= threshold)
+            .map(|(key, held)| (*key, *held))
+            .collect();
+        stuck.sort_by_key(|(_, held)| held.start);
+
+        stuck
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
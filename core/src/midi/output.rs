@@ -0,0 +1,59 @@
+//! Midi output ports, e.g. to replay captured messages.
+
+use std::sync::Arc;
+
+use super::port::Error;
+
+/// Lists the output ports, except for those of `client_name`.
+pub fn list(client_name: &str) -> Result<Vec<Arc<str>>, Error> {
+    let midi_out = midir::MidiOutput::new(&format!("{client_name} list outputs"))?;
+
+    let mut list = Vec::new();
+    for port in midi_out.ports() {
+        let name = midi_out.port_name(&port)?;
+        if !name.starts_with(client_name) {
+            list.push(name.into());
+        }
+    }
+
+    Ok(list)
+}
+
+/// A connection to an output port.
+pub struct MidiOut {
+    conn: midir::MidiOutputConnection,
+    port_name: Arc<str>,
+}
+
+impl MidiOut {
+    pub fn connect(client_name: &str, port_name: Arc<str>) -> Result<Self, Error> {
+        let midi_out = midir::MidiOutput::new(client_name)?;
+
+        let mut port = None;
+        for cur in midi_out.ports() {
+            if midi_out.port_name(&cur)? == port_name.as_ref() {
+                port = Some(cur);
+                break;
+            }
+        }
+        let port = port.ok_or_else(|| Error::PortNotFound(port_name.clone()))?;
+
+        let conn = midi_out
+            .connect(&port, &format!("{client_name} out"))
+            .map_err(|_| Error::PortConnection)?;
+
+        log::info!("Connected Output to {port_name}");
+
+        Ok(MidiOut { conn, port_name })
+    }
+
+    pub fn port_name(&self) -> &Arc<str> {
+        &self.port_name
+    }
+
+    pub fn send(&mut self, msg: &[u8]) -> Result<(), Error> {
+        self.conn.send(msg)?;
+
+        Ok(())
+    }
+}
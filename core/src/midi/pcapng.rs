@@ -0,0 +1,118 @@
+//! Writes captures in the pcapng format, e.g. to analyze them with Wireshark.
+//!
+//! There is no link type dedicated to raw Midi, so packets use `LINKTYPE_USER0`:
+//! one packet per Midi message, one interface per port. In Wireshark, map
+//! `DLT_USER0` to a Midi dissector in the "DLT User" preferences.
+//!
+//! See <https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-01.html>.
+
+use std::io::{self, Write};
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+/// Private use link type.
+pub const LINKTYPE_USER0: u16 = 147;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_IF_NAME: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+/// Timestamps in µs.
+const TS_RESOLUTION_MICROS: u8 = 6;
+
+/// Writes a pcapng section with one interface per Midi port.
+pub struct Writer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the section header and an interface for each name in `interfaces`.
+    pub fn new(mut writer: W, interfaces: &[&str]) -> io::Result<Self> {
+        let mut body = Vec::new();
+        body.extend(BYTE_ORDER_MAGIC.to_le_bytes());
+        // Version 1.0
+        body.extend(1u16.to_le_bytes());
+        body.extend(0u16.to_le_bytes());
+        // Section length not specified
+        body.extend((-1i64).to_le_bytes());
+        write_block(&mut writer, SECTION_HEADER_BLOCK, &body)?;
+
+        for name in interfaces {
+            let mut body = Vec::new();
+            body.extend(LINKTYPE_USER0.to_le_bytes());
+            // Reserved
+            body.extend(0u16.to_le_bytes());
+            // No snap length limit
+            body.extend(0u32.to_le_bytes());
+            push_option(&mut body, OPT_IF_NAME, name.as_bytes());
+            push_option(&mut body, OPT_IF_TSRESOL, &[TS_RESOLUTION_MICROS]);
+            push_option(&mut body, OPT_END_OF_OPT, &[]);
+            write_block(&mut writer, INTERFACE_DESCRIPTION_BLOCK, &body)?;
+        }
+
+        Ok(Self { writer })
+    }
+
+    /// Writes the Midi message `data` received on `interface` at `wall_ts`.
+    ///
+    /// `comment` is displayed as the packet comment in Wireshark.
+    pub fn write_packet(
+        &mut self,
+        interface: u32,
+        wall_ts: SystemTime,
+        data: &[u8],
+        comment: Option<&str>,
+    ) -> io::Result<()> {
+        let ts = wall_ts
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let mut body = Vec::with_capacity(32 + data.len());
+        body.extend(interface.to_le_bytes());
+        body.extend(((ts >> 32) as u32).to_le_bytes());
+        body.extend((ts as u32).to_le_bytes());
+        // Captured & original lengths
+        body.extend((data.len() as u32).to_le_bytes());
+        body.extend((data.len() as u32).to_le_bytes());
+        body.extend(data);
+        pad(&mut body);
+        if let Some(comment) = comment {
+            push_option(&mut body, OPT_COMMENT, comment.as_bytes());
+            push_option(&mut body, OPT_END_OF_OPT, &[]);
+        }
+
+        write_block(&mut self.writer, ENHANCED_PACKET_BLOCK, &body)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn write_block(writer: &mut impl Write, block_type: u32, body: &[u8]) -> io::Result<()> {
+    // Block type, 2 × total length & body
+    let total_len = (12 + body.len()) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&total_len.to_le_bytes())
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, val: &[u8]) {
+    body.extend(code.to_le_bytes());
+    body.extend((val.len() as u16).to_le_bytes());
+    body.extend(val);
+    pad(body);
+}
+
+/// Pads `body` to 32 bits.
+fn pad(body: &mut Vec<u8>) {
+    body.resize((body.len() + 3) & !3, 0);
+}
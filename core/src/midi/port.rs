@@ -0,0 +1,830 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+/// Name of the port which creates a virtual destination other applications can send to.
+#[cfg(target_os = "macos")]
+pub const VIRTUAL_PORT_NAME: &str = "Virtual destination";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Midi initialization failed")]
+    Init(#[from] midir::InitError),
+
+    #[error("Failed to parse Midi message")]
+    ParseError(#[from] midi_msg::ParseError),
+
+    #[error("Midi port connection failed")]
+    PortConnection,
+
+    #[error("Couldn't retrieve a port name")]
+    PortInfoError(#[from] midir::PortInfoError),
+
+    #[error("Invalid Midi port name {}", .0)]
+    PortNotFound(Arc<str>),
+
+    #[error("Failed to send Midi message")]
+    Send(#[from] midir::SendError),
+
+    #[cfg(feature = "ble")]
+    #[error(transparent)]
+    Ble(#[from] super::ble::Error),
+
+    #[cfg(feature = "serial")]
+    #[error(transparent)]
+    Serial(#[from] super::serial::Error),
+
+    #[cfg(feature = "jack")]
+    #[error("JACK error: {}", .0)]
+    Jack(#[from] jack::Error),
+
+    #[cfg(all(feature = "winrt", target_os = "windows"))]
+    #[error("WinRT error: {}", .0)]
+    WinRt(#[from] super::winrt::Error),
+
+    #[cfg(feature = "agent")]
+    #[error(transparent)]
+    Remote(#[from] super::remote::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "save", derive(serde::Deserialize, serde::Serialize))]
+pub enum PortNb {
+    One,
+    Two,
+}
+
+impl fmt::Display for PortNb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PortNb {
+    pub fn idx(self) -> usize {
+        match self {
+            PortNb::One => 0,
+            PortNb::Two => 1,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            PortNb::One => "Port 1",
+            PortNb::Two => "Port 2",
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            PortNb::One => '1',
+            PortNb::Two => '2',
+        }
+    }
+}
+
+/// Backend used to list and capture the system's Midi ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// ALSA on Linux, CoreMIDI on macOS, WinMM on Windows.
+    #[default]
+    Native,
+    #[cfg(feature = "jack")]
+    Jack,
+    /// Also lists the BLE-MIDI devices paired with Windows.
+    #[cfg(all(feature = "winrt", target_os = "windows"))]
+    WinRt,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[
+        Backend::Native,
+        #[cfg(feature = "jack")]
+        Backend::Jack,
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        Backend::WinRt,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(target_os = "linux")]
+            Backend::Native => "ALSA",
+            #[cfg(target_os = "macos")]
+            Backend::Native => "CoreMIDI",
+            #[cfg(target_os = "windows")]
+            Backend::Native => "WinMM",
+            #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+            Backend::Native => "Native",
+            #[cfg(feature = "jack")]
+            Backend::Jack => "JACK",
+            #[cfg(all(feature = "winrt", target_os = "windows"))]
+            Backend::WinRt => "WinRT",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Backend::Native => "native",
+            #[cfg(feature = "jack")]
+            Backend::Jack => "jack",
+            #[cfg(all(feature = "winrt", target_os = "windows"))]
+            Backend::WinRt => "winrt",
+        }
+    }
+
+    /// Parses a stored `Backend`, also accepts `alsa` for the CLI.
+    pub fn from_storage_str(val: &str) -> Option<Self> {
+        match val {
+            "native" | "alsa" => Some(Backend::Native),
+            #[cfg(feature = "jack")]
+            "jack" => Some(Backend::Jack),
+            #[cfg(all(feature = "winrt", target_os = "windows"))]
+            "winrt" => Some(Backend::WinRt),
+            _ => None,
+        }
+    }
+}
+
+/// How a port is backed, as far as the backend tells.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Kind {
+    Hardware,
+    Virtual,
+    Bluetooth,
+    Serial,
+    Remote,
+    #[default]
+    Unknown,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Hardware => "Hardware",
+            Kind::Virtual => "Virtual",
+            Kind::Bluetooth => "Bluetooth LE",
+            Kind::Serial => "Serial",
+            Kind::Remote => "Remote agent",
+            Kind::Unknown => "Unknown kind",
+        }
+    }
+
+    /// ALSA port names end with `client_id:port_id`,
+    /// kernel clients (i.e. hardware) use ids below 128.
+    #[cfg(target_os = "linux")]
+    fn from_port_name(name: &str) -> Self {
+        const MIDI_THROUGH_CLIENT_ID: u32 = 14;
+        const FIRST_USER_CLIENT_ID: u32 = 128;
+
+        let client_id = name
+            .rsplit_once(' ')
+            .and_then(|(_, ids)| ids.split_once(':'))
+            .and_then(|(client_id, _)| client_id.parse::<u32>().ok());
+
+        match client_id {
+            Some(MIDI_THROUGH_CLIENT_ID) => Kind::Virtual,
+            Some(id) if id < FIRST_USER_CLIENT_ID => Kind::Hardware,
+            Some(_) => Kind::Virtual,
+            None => Kind::Unknown,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn from_port_name(_name: &str) -> Self {
+        Kind::Unknown
+    }
+}
+
+/// User defined names for the ports, by port name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Aliases(BTreeMap<Arc<str>, Arc<str>>);
+
+impl Aliases {
+    pub fn get(&self, port_name: &str) -> Option<&Arc<str>> {
+        self.0.get(port_name)
+    }
+
+    /// Returns the alias for `port_name` if any, `port_name` otherwise.
+    pub fn display<'a>(&'a self, port_name: &'a str) -> &'a str {
+        self.get(port_name).map_or(port_name, AsRef::as_ref)
+    }
+
+    /// Sets the alias for `port_name`, removes it if `alias` is blank.
+    pub fn set(&mut self, port_name: Arc<str>, alias: &str) {
+        let alias = alias.trim();
+        if alias.is_empty() {
+            self.0.remove(&port_name);
+        } else {
+            self.0.insert(port_name, alias.into());
+        }
+    }
+
+    /// Serializes the aliases as one `port name<TAB>alias` per line.
+    pub fn storage_string(&self) -> String {
+        self.0
+            .iter()
+            .map(|(port_name, alias)| format!("{port_name}\t{alias}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn from_storage_str(val: &str) -> Self {
+        Aliases(
+            val.lines()
+                .filter_map(|line| line.split_once('\t'))
+                .map(|(port_name, alias)| (port_name.into(), alias.into()))
+                .collect(),
+        )
+    }
+}
+
+/// What the backend reports about an input port.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    /// The port is also available as an output.
+    pub is_output: bool,
+    pub kind: Kind,
+    /// Universal Midi Packet support, `None` if the backend doesn't tell.
+    pub is_ump: Option<bool>,
+    /// The port refused a capture attempt.
+    ///
+    /// Automatically cleared when the port disappears, e.g. when the device is unplugged.
+    pub is_refused: bool,
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_output {
+            f.write_str("Input / Output")?;
+        } else {
+            f.write_str("Input")?;
+        }
+
+        write!(f, " - {}", self.kind.as_str())?;
+
+        match self.is_ump {
+            Some(true) => f.write_str(" - UMP")?,
+            Some(false) => f.write_str(" - No UMP")?,
+            None => f.write_str(" - UMP unknown")?,
+        }
+
+        if self.is_refused {
+            f.write_str("\nRefused capture, replug the device to retry")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Ports {
+    pub map: BTreeMap<Arc<str>, midir::MidiInputPort>,
+    pub caps: BTreeMap<Arc<str>, Capabilities>,
+    /// Details about the ports by port name, e.g. the device they belong to.
+    pub descriptions: BTreeMap<Arc<str>, Arc<str>>,
+    pub cur: [Option<Arc<str>>; 2],
+    desired: [Option<Arc<str>>; 2],
+    /// Patterns of the ports to connect to automatically.
+    patterns: [Option<super::PortPattern>; 2],
+    midi_in: [crate::MidiIn; 2],
+    backend: Backend,
+    /// `Some` if the JACK `Backend` is selected.
+    #[cfg(feature = "jack")]
+    jack: Option<super::jack::Backend>,
+    #[cfg(feature = "jack")]
+    jack_ports: std::collections::BTreeSet<Arc<str>>,
+    /// `Some` if the WinRT `Backend` is selected.
+    #[cfg(all(feature = "winrt", target_os = "windows"))]
+    winrt: Option<super::winrt::Backend>,
+    #[cfg(all(feature = "winrt", target_os = "windows"))]
+    winrt_ports: std::collections::BTreeSet<Arc<str>>,
+    /// Virtual destination other applications can send to.
+    #[cfg(target_os = "macos")]
+    virtual_ports: std::collections::BTreeSet<Arc<str>>,
+    /// `None` if Bluetooth is not available.
+    #[cfg(feature = "ble")]
+    ble: Option<super::ble::Backend>,
+    #[cfg(feature = "ble")]
+    ble_ports: std::collections::BTreeSet<Arc<str>>,
+    #[cfg(feature = "serial")]
+    serial: super::serial::Backend,
+    #[cfg(feature = "serial")]
+    serial_ports: std::collections::BTreeSet<Arc<str>>,
+    #[cfg(feature = "agent")]
+    remote: super::remote::Backend,
+    #[cfg(feature = "agent")]
+    remote_ports: std::collections::BTreeSet<Arc<str>>,
+    pub client_name: Arc<str>,
+}
+
+impl Ports {
+    pub fn try_new(client_name: Arc<str>, backend: Backend) -> Result<Self, Error> {
+        let midi_in1 = crate::MidiIn::new(&client_name)?;
+        let midi_in2 = crate::MidiIn::new(&client_name)?;
+
+        Ok(Self {
+            map: BTreeMap::new(),
+            caps: BTreeMap::new(),
+            descriptions: BTreeMap::new(),
+            cur: [None, None],
+            desired: [None, None],
+            patterns: [None, None],
+            midi_in: [midi_in1, midi_in2],
+            backend,
+            #[cfg(feature = "jack")]
+            jack: match backend {
+                Backend::Jack => Some(super::jack::Backend::try_new(&client_name)?),
+                _ => None,
+            },
+            #[cfg(feature = "jack")]
+            jack_ports: Default::default(),
+            #[cfg(all(feature = "winrt", target_os = "windows"))]
+            winrt: (backend == Backend::WinRt).then(super::winrt::Backend::default),
+            #[cfg(all(feature = "winrt", target_os = "windows"))]
+            winrt_ports: Default::default(),
+            #[cfg(target_os = "macos")]
+            virtual_ports: Default::default(),
+            #[cfg(feature = "ble")]
+            ble: super::ble::Backend::try_new()
+                .map_err(|err| log::warn!("BLE-MIDI unavailable: {err}"))
+                .ok(),
+            #[cfg(feature = "ble")]
+            ble_ports: Default::default(),
+            #[cfg(feature = "serial")]
+            serial: Default::default(),
+            #[cfg(feature = "serial")]
+            serial_ports: Default::default(),
+            #[cfg(feature = "agent")]
+            remote: Default::default(),
+            #[cfg(feature = "agent")]
+            remote_ports: Default::default(),
+            client_name,
+        })
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = &Arc<str>> {
+        let list = self.map.keys();
+        #[cfg(feature = "jack")]
+        let list = list.chain(self.jack_ports.iter());
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        let list = list.chain(self.winrt_ports.iter());
+        #[cfg(target_os = "macos")]
+        let list = list.chain(self.virtual_ports.iter());
+        #[cfg(feature = "ble")]
+        let list = list.chain(self.ble_ports.iter());
+        #[cfg(feature = "serial")]
+        let list = list.chain(self.serial_ports.iter());
+        #[cfg(feature = "agent")]
+        let list = list.chain(self.remote_ports.iter());
+
+        list
+    }
+
+    fn contains(&self, port_name: &Arc<str>) -> bool {
+        #[cfg(feature = "jack")]
+        if self.jack_ports.contains(port_name) {
+            return true;
+        }
+
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        if self.winrt_ports.contains(port_name) {
+            return true;
+        }
+
+        #[cfg(target_os = "macos")]
+        if self.virtual_ports.contains(port_name) {
+            return true;
+        }
+
+        #[cfg(feature = "ble")]
+        if self.ble_ports.contains(port_name) {
+            return true;
+        }
+
+        #[cfg(feature = "serial")]
+        if self.serial_ports.contains(port_name) {
+            return true;
+        }
+
+        #[cfg(feature = "agent")]
+        if self.remote_ports.contains(port_name) {
+            return true;
+        }
+
+        self.map.contains_key(port_name)
+    }
+
+    pub fn cur(&self, port_nb: PortNb) -> Option<&Arc<str>> {
+        self.cur[port_nb.idx()].as_ref()
+    }
+
+    /// Returns the port the user wants to be connected to `port_nb`.
+    ///
+    /// This port might not be currently connected, e.g. if the device is unplugged.
+    pub fn desired(&self, port_nb: PortNb) -> Option<&Arc<str>> {
+        self.desired[port_nb.idx()].as_ref()
+    }
+
+    pub fn pattern(&self, port_nb: PortNb) -> Option<&super::PortPattern> {
+        self.patterns[port_nb.idx()].as_ref()
+    }
+
+    /// Sets the pattern of the ports to connect to `port_nb` automatically.
+    ///
+    /// The pattern is used when `port_nb` is not connected, after the desired port.
+    pub fn set_pattern(&mut self, port_nb: PortNb, pattern: Option<super::PortPattern>) {
+        self.patterns[port_nb.idx()] = pattern;
+    }
+
+    fn midi_in_mut(&mut self, port_nb: super::PortNb) -> &mut crate::MidiIn {
+        &mut self.midi_in[port_nb.idx()]
+    }
+
+    /// Refreshes the port list.
+    ///
+    /// Returns the desired ports which are available but not connected.
+    pub fn refresh(&mut self) -> Result<Vec<(PortNb, Arc<str>)>, Error> {
+        self.map.clear();
+        let prev_caps = std::mem::take(&mut self.caps);
+
+        let mut prev1 = self.cur[0].take();
+        let mut prev2 = self.cur[1].take();
+        if self.backend == Backend::Native {
+            let temp_midi_in =
+                midir::MidiInput::new(&format!("{} referesh ports", self.client_name.as_ref()))?;
+
+            let temp_midi_out =
+                midir::MidiOutput::new(&format!("{} referesh ports", self.client_name.as_ref()))?;
+            let outputs = temp_midi_out
+                .ports()
+                .iter()
+                .filter_map(|port| temp_midi_out.port_name(port).ok())
+                .collect::<HashSet<String>>();
+
+            for port in temp_midi_in.ports() {
+                let name = temp_midi_in.port_name(&port)?;
+                if !name.starts_with(self.client_name.as_ref()) {
+                    if let Some(ref prev1_ref) = prev1 {
+                        if prev1_ref.as_ref() == name {
+                            self.cur[0] = prev1.take();
+                        }
+                    }
+
+                    if let Some(ref prev2_ref) = prev2 {
+                        if prev2_ref.as_ref() == name {
+                            self.cur[1] = prev2.take();
+                        }
+                    }
+
+                    let name: Arc<str> = name.into();
+                    let caps = Capabilities {
+                        is_output: outputs.contains(name.as_ref()),
+                        kind: Kind::from_port_name(&name),
+                        is_ump: None,
+                        is_refused: prev_caps.get(&name).is_some_and(|caps| caps.is_refused),
+                    };
+
+                    self.caps.insert(name.clone(), caps);
+                    self.map.insert(name, port);
+                }
+            }
+
+            #[cfg(target_os = "macos")]
+            {
+                self.descriptions = super::coremidi::source_descriptions();
+
+                let name: Arc<str> = VIRTUAL_PORT_NAME.into();
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(&name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output: false,
+                    kind: Kind::Virtual,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(&name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+                self.virtual_ports.clear();
+                self.virtual_ports.insert(name);
+            }
+        }
+
+        #[cfg(feature = "jack")]
+        if let Some(jack) = self.jack.as_ref() {
+            self.jack_ports.clear();
+            for (name, kind) in jack.list()? {
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(&name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output: false,
+                    kind,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(&name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+                self.jack_ports.insert(name);
+            }
+        }
+
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        if let Some(winrt) = self.winrt.as_mut() {
+            self.winrt_ports.clear();
+            for (name, (kind, is_output)) in winrt.list()? {
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(&name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output,
+                    kind,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(&name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+                self.winrt_ports.insert(name);
+            }
+        }
+
+        #[cfg(feature = "ble")]
+        if let Some(ble) = self.ble.as_mut() {
+            self.ble_ports.clear();
+            for name in ble.refresh() {
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output: false,
+                    kind: Kind::Bluetooth,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+                self.ble_ports.insert(name.clone());
+            }
+        }
+
+        // Failing to list the serial ports must not hide the ports of the other transports
+        #[cfg(feature = "serial")]
+        {
+            self.serial_ports = self.serial.list().unwrap_or_else(|err| {
+                log::warn!("Couldn't list serial ports: {err}");
+                Default::default()
+            });
+            for name in self.serial_ports.iter() {
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output: false,
+                    kind: Kind::Serial,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+            }
+        }
+
+        #[cfg(feature = "agent")]
+        {
+            self.remote_ports = self.remote.list();
+            for name in self.remote_ports.iter() {
+                for (cur, prev) in self.cur.iter_mut().zip([&mut prev1, &mut prev2]) {
+                    if prev.as_ref() == Some(name) {
+                        *cur = prev.take();
+                    }
+                }
+
+                let caps = Capabilities {
+                    is_output: false,
+                    kind: Kind::Remote,
+                    is_ump: Some(false),
+                    is_refused: prev_caps.get(name).is_some_and(|caps| caps.is_refused),
+                };
+                self.caps.insert(name.clone(), caps);
+            }
+        }
+
+        let mut reconnectable = Vec::new();
+        for port_nb in [PortNb::One, PortNb::Two] {
+            if self.cur[port_nb.idx()].is_some() {
+                continue;
+            }
+
+            let desired = self.desired[port_nb.idx()]
+                .as_ref()
+                .filter(|desired| self.contains(desired))
+                .cloned();
+            let port_name = desired.or_else(|| self.matching_port(port_nb, &reconnectable));
+            if let Some(port_name) = port_name {
+                reconnectable.push((port_nb, port_name));
+            }
+        }
+
+        Ok(reconnectable)
+    }
+
+    /// Returns the first available port matching the pattern for `port_nb`.
+    fn matching_port(
+        &self,
+        port_nb: PortNb,
+        reconnectable: &[(PortNb, Arc<str>)],
+    ) -> Option<Arc<str>> {
+        let pattern = self.patterns[port_nb.idx()].as_ref()?;
+        self.list()
+            .find(|name| {
+                pattern.matches(name)
+                    && !self.caps.get(*name).is_some_and(|caps| caps.is_refused)
+                    && !self.cur.iter().flatten().any(|cur| cur == *name)
+                    && !reconnectable.iter().any(|(_, other)| other == *name)
+            })
+            .cloned()
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: super::PortNb,
+        port_name: Arc<str>,
+        callback: C,
+    ) -> Result<(), Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.desired[port_nb.idx()] = Some(port_name.clone());
+        self.disconnect_extra(port_nb);
+
+        #[cfg(feature = "jack")]
+        if self.jack_ports.contains(&port_name) {
+            if let Some(jack) = self.jack.as_mut() {
+                jack.connect(port_nb, &port_name, callback)?;
+            }
+
+            log::info!("Connected Input {} to {}", port_nb, port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        #[cfg(target_os = "macos")]
+        if self.virtual_ports.contains(&port_name) {
+            let app_port_name = format!("{} {}", self.client_name, port_nb);
+            self.midi_in_mut(port_nb)
+                .connect_virtual(&app_port_name, callback)
+                .map_err(|_| {
+                    if let Some(caps) = self.caps.get_mut(&port_name) {
+                        caps.is_refused = true;
+                    }
+                    Error::PortConnection
+                })?;
+
+            log::info!("Connected Input {} to {}", port_nb, app_port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        if self.winrt_ports.contains(&port_name) {
+            if let Some(winrt) = self.winrt.as_mut() {
+                winrt.connect(port_nb, &port_name, callback)?;
+            }
+
+            log::info!("Connected Input {} to {}", port_nb, port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        #[cfg(feature = "ble")]
+        if self.ble_ports.contains(&port_name) {
+            self.midi_in_mut(port_nb).disconnect();
+            if let Some(ble) = self.ble.as_mut() {
+                ble.connect(port_nb, port_name.clone(), callback)?;
+            }
+
+            log::info!("Connected Input {} to {}", port_nb, port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        #[cfg(feature = "serial")]
+        if self.serial_ports.contains(&port_name) {
+            self.midi_in_mut(port_nb).disconnect();
+            self.serial.connect(port_nb, &port_name, callback)?;
+
+            log::info!("Connected Input {} to {}", port_nb, port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        #[cfg(feature = "agent")]
+        if self.remote_ports.contains(&port_name) {
+            self.midi_in_mut(port_nb).disconnect();
+            self.remote.connect(port_nb, &port_name, callback)?;
+
+            log::info!("Connected Input {} to {}", port_nb, port_name);
+            self.cur[port_nb.idx()] = Some(port_name);
+            self.refresh()?;
+
+            return Ok(());
+        }
+
+        let port = self
+            .map
+            .get(&port_name)
+            .ok_or_else(|| Error::PortNotFound(port_name.clone()))?
+            .clone();
+
+        let app_port_name = format!("{} {}", self.client_name, port_nb);
+        self.midi_in_mut(port_nb)
+            .connect(port_name.clone(), &port, &app_port_name, callback)
+            .map_err(|_| {
+                self.cur[port_nb.idx()] = None;
+                // Don't retry automatically, the port is there but refuses the connection
+                self.desired[port_nb.idx()] = None;
+                if let Some(caps) = self.caps.get_mut(&port_name) {
+                    caps.is_refused = true;
+                }
+                Error::PortConnection
+            })?;
+
+        log::info!("Connected Input {} to {}", port_nb, port_name);
+        self.cur[port_nb.idx()] = Some(port_name);
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: super::PortNb) -> Result<(), Error> {
+        self.midi_in_mut(port_nb).disconnect();
+        self.disconnect_extra(port_nb);
+        self.desired[port_nb.idx()] = None;
+        // Don't connect to a matching port right away.
+        self.patterns[port_nb.idx()] = None;
+
+        if let Some(port_name) = self.cur[port_nb.idx()].take() {
+            log::info!("Disconnected Input {} from {}", port_nb, port_name);
+        }
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    /// Disconnects `port_nb` from the backends other than `midir`.
+    #[allow(unused_variables)]
+    fn disconnect_extra(&mut self, port_nb: super::PortNb) {
+        #[cfg(feature = "jack")]
+        if let Some(jack) = self.jack.as_mut() {
+            jack.disconnect(port_nb);
+        }
+        #[cfg(all(feature = "winrt", target_os = "windows"))]
+        if let Some(winrt) = self.winrt.as_mut() {
+            winrt.disconnect(port_nb);
+        }
+        #[cfg(feature = "ble")]
+        if let Some(ble) = self.ble.as_mut() {
+            ble.disconnect(port_nb);
+        }
+        #[cfg(feature = "serial")]
+        self.serial.disconnect(port_nb);
+        #[cfg(feature = "agent")]
+        self.remote.disconnect(port_nb);
+    }
+
+    /// Returns the remote agents whose ports are listed.
+    #[cfg(feature = "agent")]
+    pub fn agents(&self) -> &[std::net::SocketAddr] {
+        self.remote.agents()
+    }
+
+    #[cfg(feature = "agent")]
+    pub fn set_agents(&mut self, agents: Vec<std::net::SocketAddr>) {
+        self.remote.set_agents(agents);
+    }
+}
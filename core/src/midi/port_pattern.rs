@@ -0,0 +1,125 @@
+//! Patterns matching port names, e.g. to auto-connect a device whatever
+//! the port numbers assigned by the backend.
+
+use std::{fmt, sync::Arc};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid port pattern: {}", .0)]
+    Regex(#[from] regex::Error),
+}
+
+#[derive(Clone, Debug)]
+pub enum PortPattern {
+    /// `*` matches any sequence of characters, `?` matches any character.
+    Glob(Arc<str>),
+    Regex(regex::Regex),
+}
+
+impl PortPattern {
+    /// Parses `pattern`: a regex if enclosed between `/`, a glob otherwise.
+    pub fn parse(pattern: &str) -> Result<Self, Error> {
+        let pattern = pattern.trim();
+        match pattern
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'))
+        {
+            Some(regex) => Ok(PortPattern::Regex(regex::Regex::new(regex)?)),
+            None => Ok(PortPattern::Glob(pattern.into())),
+        }
+    }
+
+    pub fn matches(&self, port_name: &str) -> bool {
+        match self {
+            PortPattern::Glob(glob) => glob_matches(glob, port_name),
+            PortPattern::Regex(regex) => regex.is_match(port_name),
+        }
+    }
+}
+
+impl fmt::Display for PortPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortPattern::Glob(glob) => f.write_str(glob),
+            PortPattern::Regex(regex) => write!(f, "/{regex}/"),
+        }
+    }
+}
+
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut glob_idx, mut name_idx) = (0, 0);
+    // Position of the last `*` and the name index it was tried at.
+    let mut backtrack = None;
+    while name_idx < name.len() {
+        match glob.get(glob_idx) {
+            Some('*') => {
+                backtrack = Some((glob_idx, name_idx));
+                glob_idx += 1;
+            }
+            Some(&c) if c == '?' || c == name[name_idx] => {
+                glob_idx += 1;
+                name_idx += 1;
+            }
+            _ => match backtrack {
+                Some((star_idx, star_name_idx)) => {
+                    // Let the `*` consume one more character.
+                    glob_idx = star_idx + 1;
+                    name_idx = star_name_idx + 1;
+                    backtrack = Some((star_idx, name_idx));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[glob_idx..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, port_name: &str) -> bool {
+        PortPattern::parse(pattern).unwrap().matches(port_name)
+    }
+
+    #[test]
+    fn glob() {
+        assert!(matches("Launchkey MK3", "Launchkey MK3"));
+        assert!(!matches("Launchkey MK3", "Launchkey MK3 MIDI 1"));
+
+        assert!(matches("Launchkey*", "Launchkey MK3 MIDI 1"));
+        assert!(matches("*MIDI 1", "Launchkey MK3 MIDI 1"));
+        assert!(matches("*MK?*", "Launchkey MK3 MIDI 1"));
+        assert!(matches("*", ""));
+        assert!(!matches("*MIDI 2", "Launchkey MK3 MIDI 1"));
+
+        assert!(matches("MIDI ?", "MIDI 1"));
+        assert!(!matches("MIDI ?", "MIDI 10"));
+
+        // The first `*` must backtrack past the first ` 1`
+        assert!(matches("* 1*:* 1", "Port 1 client 14:0 1"));
+    }
+
+    #[test]
+    fn regex() {
+        assert!(matches("/^Launchkey MK[23]/", "Launchkey MK3 MIDI 1"));
+        assert!(!matches("/^Launchkey MK[23]/", "Launchkey Mini MK3"));
+        assert!(matches(" /MIDI \\d+$/ ", "Launchkey MK3 MIDI 1"));
+
+        assert!(matches!(
+            PortPattern::parse("/MIDI (/"),
+            Err(Error::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn display() {
+        for pattern in ["Launchkey*", "/^Launchkey MK[23]/"] {
+            assert_eq!(PortPattern::parse(pattern).unwrap().to_string(), pattern);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Device profiles mapping controller and NRPN numbers to names.
+//!
+//! Profiles are TOML or JSON files such as:
+//!
+//! ```toml
+//! name = "Moog Sub 37"
+//!
+//! [cc]
+//! 74 = "Filter Cutoff"
+//!
+//! [nrpn]
+//! # 14-bit parameter number: MSB * 128 + LSB
+//! 1025 = "Osc 1 Wave"
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Environment variable overriding the profiles directory.
+const PROFILES_DIR_ENV: &str = "MIDI_SNIFFER_PROFILES";
+const DEFAULT_PROFILES_DIR: &str = "profiles";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Couldn't read profile {}: {}", .0.display(), .1)]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("Invalid TOML profile {}: {}", .0.display(), .1)]
+    Toml(PathBuf, #[source] toml::de::Error),
+    #[error("Invalid JSON profile {}: {}", .0.display(), .1)]
+    Json(PathBuf, #[source] serde_json::Error),
+    #[error("Invalid parameter number {} in profile {}", .1, .0.display())]
+    InvalidNumber(PathBuf, String),
+    #[error("Unsupported profile format {}", .0.display())]
+    UnsupportedFormat(PathBuf),
+}
+
+/// Profile as found in the file.
+#[derive(serde::Deserialize)]
+struct RawProfile {
+    name: Option<String>,
+    #[serde(default)]
+    cc: BTreeMap<String, String>,
+    #[serde(default)]
+    nrpn: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    cc: BTreeMap<u8, String>,
+    nrpn: BTreeMap<u16, String>,
+}
+
+impl Profile {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(|err| Error::Io(path.into(), err))?;
+
+        let raw: RawProfile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => {
+                toml::from_str(&content).map_err(|err| Error::Toml(path.into(), err))?
+            }
+            Some("json") => {
+                serde_json::from_str(&content).map_err(|err| Error::Json(path.into(), err))?
+            }
+            _ => return Err(Error::UnsupportedFormat(path.into())),
+        };
+
+        fn parse_numbers<T: std::str::FromStr + Ord>(
+            path: &Path,
+            names: BTreeMap<String, String>,
+        ) -> Result<BTreeMap<T, String>, Error> {
+            names
+                .into_iter()
+                .map(|(nb, name)| match nb.trim().parse() {
+                    Ok(nb) => Ok((nb, name)),
+                    Err(_) => Err(Error::InvalidNumber(path.into(), nb)),
+                })
+                .collect()
+        }
+
+        let name = raw.name.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+
+        Ok(Self {
+            name,
+            cc: parse_numbers(path, raw.cc)?,
+            nrpn: parse_numbers(path, raw.nrpn)?,
+        })
+    }
+
+    pub fn cc_name(&self, cc: u8) -> Option<&str> {
+        self.cc.get(&cc).map(String::as_str)
+    }
+
+    pub fn nrpn_name(&self, nrpn: u16) -> Option<&str> {
+        self.nrpn.get(&nrpn).map(String::as_str)
+    }
+}
+
+/// Returns the directory the profiles are loaded from.
+pub fn dir() -> PathBuf {
+    std::env::var_os(PROFILES_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| DEFAULT_PROFILES_DIR.into())
+}
+
+/// Loads the profiles found in `dir`, sorted by name.
+///
+/// Profiles which can't be loaded are reported in the returned errors.
+pub fn load_dir(dir: &Path) -> (Vec<Arc<Profile>>, Vec<Error>) {
+    let mut profiles = Vec::new();
+    let mut errors = Vec::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // No profiles directory is fine
+        Err(_) => return (profiles, errors),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("toml" | "json")
+        ) {
+            continue;
+        }
+
+        match Profile::load(&path) {
+            Ok(profile) => profiles.push(Arc::new(profile)),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    (profiles, errors)
+}
@@ -0,0 +1,237 @@
+//! Ports captured by a remote `midi-sniffer --agent`.
+//!
+//! Protocol, over TCP:
+//!
+//! - The client sends `LIST\n`, the agent replies with one port name per line,
+//!   followed by an empty line.
+//! - The client sends `CAPTURE <port name>\n`, the agent replies with `OK\n`
+//!   or `ERR <reason>\n`. On success, the agent then streams frames
+//!   until the connection is closed.
+//!
+//! A frame is the backend timestamp (µs, `u64` BE), the buffer len (`u32` BE)
+//! and the Midi buffer.
+
+use crossbeam_channel as channel;
+use std::{
+    collections::BTreeSet,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::PortNb;
+
+/// Prefix of the remote port names, followed by the agent address.
+const PORT_NAME_PREFIX: &str = "Agent ";
+/// Prevents unreachable agents from blocking the port list polling.
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+/// Interval between two polls of the agents' ports.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Sanity limit for the frames.
+const MAX_FRAME_LEN: usize = 1024 * 1024;
+
+pub const LIST: &str = "LIST";
+pub const CAPTURE: &str = "CAPTURE";
+pub const OK: &str = "OK";
+pub const ERR: &str = "ERR";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Agent IO error: {}", .0)]
+    Io(#[from] io::Error),
+
+    #[error("Agent refused capture: {}", .0)]
+    Refused(String),
+
+    #[error("Invalid agent port name {}", .0)]
+    InvalidPortName(Arc<str>),
+}
+
+pub fn write_frame(mut w: impl Write, ts: u64, buf: &[u8]) -> io::Result<()> {
+    w.write_all(&ts.to_be_bytes())?;
+    w.write_all(&(buf.len() as u32).to_be_bytes())?;
+    w.write_all(buf)
+}
+
+pub fn read_frame(mut r: impl Read, buf: &mut Vec<u8>) -> io::Result<u64> {
+    let mut ts = [0u8; 8];
+    r.read_exact(&mut ts)?;
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame too large: {len}"),
+        ));
+    }
+
+    buf.resize(len, 0);
+    r.read_exact(buf)?;
+
+    Ok(u64::from_be_bytes(ts))
+}
+
+fn read_line(r: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+struct Connection {
+    stream: TcpStream,
+    reader: thread::JoinHandle<()>,
+}
+
+/// Lists and captures the ports of remote agents.
+#[derive(Default)]
+pub struct Backend {
+    agents: Vec<SocketAddr>,
+    /// Updated by the polling thread, so that refreshing the ports doesn't block.
+    ports: Arc<Mutex<BTreeSet<Arc<str>>>>,
+    /// Dropping the sender stops the polling thread.
+    poll_stop_tx: Option<channel::Sender<()>>,
+    connections: [Option<Connection>; 2],
+}
+
+impl Backend {
+    pub fn agents(&self) -> &[SocketAddr] {
+        &self.agents
+    }
+
+    /// Sets the agents and starts polling their ports in the background.
+    pub fn set_agents(&mut self, agents: Vec<SocketAddr>) {
+        self.poll_stop_tx = None;
+        // Don't let a terminating polling thread update the ports of the new agents
+        self.ports = Default::default();
+        self.agents = agents;
+        if self.agents.is_empty() {
+            return;
+        }
+
+        let (poll_stop_tx, poll_stop_rx) = channel::bounded::<()>(0);
+        let spawned = thread::Builder::new()
+            .name("agent-list".to_string())
+            .spawn({
+                let agents = self.agents.clone();
+                let ports = self.ports.clone();
+                move || loop {
+                    let listed = Self::list_agents(&agents);
+                    *ports.lock().unwrap() = listed;
+
+                    let res = poll_stop_rx.recv_timeout(POLL_INTERVAL);
+                    if let Err(channel::RecvTimeoutError::Disconnected) = res {
+                        break;
+                    }
+                }
+            });
+
+        match spawned {
+            Ok(_) => self.poll_stop_tx = Some(poll_stop_tx),
+            Err(err) => log::error!("Couldn't spawn the agent polling thread: {err}"),
+        }
+    }
+
+    /// Returns the ports of the agents which could be reached during the last poll.
+    pub fn list(&self) -> BTreeSet<Arc<str>> {
+        self.ports.lock().unwrap().clone()
+    }
+
+    fn list_agents(agents: &[SocketAddr]) -> BTreeSet<Arc<str>> {
+        let mut ports = BTreeSet::new();
+        for agent in agents.iter() {
+            match Self::list_agent(*agent) {
+                Ok(names) => ports.extend(
+                    names
+                        .into_iter()
+                        .map(|name| Arc::from(format!("{PORT_NAME_PREFIX}{agent} {name}"))),
+                ),
+                Err(err) => log::debug!("Couldn't list ports for agent {agent}: {err}"),
+            }
+        }
+
+        ports
+    }
+
+    fn list_agent(agent: SocketAddr) -> io::Result<Vec<String>> {
+        let mut stream = TcpStream::connect_timeout(&agent, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        writeln!(stream, "{LIST}")?;
+
+        let mut reader = BufReader::new(stream);
+        let mut names = Vec::new();
+        loop {
+            let name = read_line(&mut reader)?;
+            if name.is_empty() {
+                break;
+            }
+            names.push(name);
+        }
+
+        Ok(names)
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: PortNb,
+        port_name: &Arc<str>,
+        mut callback: C,
+    ) -> Result<(), Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.disconnect(port_nb);
+
+        let (agent, name) = port_name
+            .strip_prefix(PORT_NAME_PREFIX)
+            .and_then(|port_name| port_name.split_once(' '))
+            .and_then(|(agent, name)| Some((agent.parse::<SocketAddr>().ok()?, name)))
+            .ok_or_else(|| Error::InvalidPortName(port_name.clone()))?;
+
+        let mut stream = TcpStream::connect_timeout(&agent, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        writeln!(stream, "{CAPTURE} {name}")?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let reply = read_line(&mut reader)?;
+        if reply != OK {
+            let reason = reply.strip_prefix(ERR).unwrap_or(&reply).trim();
+            return Err(Error::Refused(reason.to_string()));
+        }
+
+        let reader = thread::Builder::new()
+            .name(format!("agent-midi-{}", port_nb.as_char()))
+            .spawn({
+                let port_name = port_name.clone();
+                move || {
+                    let mut buf = Vec::new();
+                    loop {
+                        match read_frame(&mut reader, &mut buf) {
+                            Ok(ts) => callback(ts, &buf),
+                            Err(err) => {
+                                log::info!("Agent capture of {port_name} ended: {err}");
+                                break;
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        self.connections[port_nb.idx()] = Some(Connection { stream, reader });
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: PortNb) {
+        if let Some(conn) = self.connections[port_nb.idx()].take() {
+            let _ = conn.stream.shutdown(Shutdown::Both);
+            let _ = conn.reader.join();
+        }
+    }
+}
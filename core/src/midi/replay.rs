@@ -0,0 +1,194 @@
+//! Plays captured messages back out of an output port with the original timing.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use super::{port::Error, MidiOut};
+use crate::time::{Duration, Instant};
+
+/// Maximum duration between two checks of the `Controls` while waiting.
+const CONTROLS_INTERVAL: Duration = Duration::from_millis(20);
+
+pub const MIN_SPEED: f32 = 0.1;
+pub const MAX_SPEED: f32 = 10.0;
+
+/// A message to replay.
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// Timestamp as provided by the Midi backend (µs).
+    pub ts: u64,
+    pub buffer: Arc<[u8]>,
+}
+
+/// Controls of a replay, shared between the UI & the `Player`.
+#[derive(Debug)]
+pub struct Controls {
+    speed: AtomicU32,
+    is_looping: AtomicBool,
+    must_stop: AtomicBool,
+    /// Keep on playing when the output rejects a message.
+    ignores_send_errors: AtomicBool,
+    send_errors: AtomicUsize,
+    /// Index of the next message to play.
+    position: AtomicUsize,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Controls {
+            speed: AtomicU32::new(1f32.to_bits()),
+            is_looping: AtomicBool::new(false),
+            must_stop: AtomicBool::new(false),
+            ignores_send_errors: AtomicBool::new(false),
+            send_errors: AtomicUsize::new(0),
+            position: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Controls {
+    pub fn speed(&self) -> f32 {
+        f32::from_bits(self.speed.load(Ordering::Relaxed))
+    }
+
+    /// Sets the speed factor, clamped to [`MIN_SPEED`, `MAX_SPEED`].
+    pub fn set_speed(&self, speed: f32) {
+        self.speed.store(
+            speed.clamp(MIN_SPEED, MAX_SPEED).to_bits(),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.is_looping.load(Ordering::Relaxed)
+    }
+
+    pub fn set_looping(&self, is_looping: bool) {
+        self.is_looping.store(is_looping, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.must_stop.store(true, Ordering::Relaxed);
+    }
+
+    fn must_stop(&self) -> bool {
+        self.must_stop.load(Ordering::Relaxed)
+    }
+
+    pub fn position(&self) -> usize {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    pub fn set_ignores_send_errors(&self, ignores_send_errors: bool) {
+        self.ignores_send_errors
+            .store(ignores_send_errors, Ordering::Relaxed);
+    }
+
+    /// Number of messages rejected by the output since the `Player` was spawned.
+    pub fn send_errors(&self) -> usize {
+        self.send_errors.load(Ordering::Relaxed)
+    }
+}
+
+/// Plays `Event`s on a dedicated thread.
+pub struct Player {
+    controls: Arc<Controls>,
+    handle: Option<thread::JoinHandle<Result<(), Error>>>,
+}
+
+impl Player {
+    pub fn spawn(mut midi_out: MidiOut, events: Arc<[Event]>, controls: Arc<Controls>) -> Self {
+        controls.must_stop.store(false, Ordering::Relaxed);
+        controls.position.store(0, Ordering::Relaxed);
+        controls.send_errors.store(0, Ordering::Relaxed);
+
+        let handle = thread::spawn({
+            let controls = controls.clone();
+            move || {
+                log::info!(
+                    "Replaying {} messages to {}",
+                    events.len(),
+                    midi_out.port_name()
+                );
+                loop {
+                    play(&mut midi_out, &events, &controls)?;
+                    if controls.must_stop() || !controls.is_looping() || events.is_empty() {
+                        break;
+                    }
+                }
+
+                Ok(())
+            }
+        });
+
+        Player {
+            controls,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn controls(&self) -> &Arc<Controls> {
+        &self.controls
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .map_or(true, thread::JoinHandle::is_finished)
+    }
+
+    /// Stops the replay and returns its result.
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.controls.stop();
+        match self.handle.take() {
+            Some(handle) => handle.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn play(midi_out: &mut MidiOut, events: &[Event], controls: &Controls) -> Result<(), Error> {
+    let mut prev_ts = None;
+    for (idx, event) in events.iter().enumerate() {
+        controls.position.store(idx, Ordering::Relaxed);
+
+        // Timestamps might not be monotonic, e.g. for messages from different backends.
+        let delta = prev_ts.map_or(0, |prev_ts| event.ts.saturating_sub(prev_ts));
+        prev_ts = Some(event.ts);
+
+        let target = Instant::now() + Duration::from_micros(delta).div_f32(controls.speed());
+        loop {
+            if controls.must_stop() {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= target {
+                break;
+            }
+            thread::sleep((target - now).min(CONTROLS_INTERVAL));
+        }
+
+        if let Err(err) = midi_out.send(&event.buffer) {
+            if !controls.ignores_send_errors.load(Ordering::Relaxed) {
+                return Err(err);
+            }
+            log::debug!("Output rejected {:02x?}: {err}", event.buffer);
+            controls.send_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    controls.position.store(events.len(), Ordering::Relaxed);
+
+    Ok(())
+}
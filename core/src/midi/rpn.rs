@@ -0,0 +1,106 @@
+//! Registered and Non-Registered Parameter Number tracking.
+
+use super::PortNb;
+
+const CC_DATA_ENTRY_MSB: u8 = 6;
+const CC_DATA_ENTRY_LSB: u8 = 38;
+const CC_NRPN_LSB: u8 = 98;
+const CC_NRPN_MSB: u8 = 99;
+const CC_RPN_LSB: u8 = 100;
+const CC_RPN_MSB: u8 = 101;
+
+/// Deselects the parameter.
+pub const RPN_NULL: u16 = 0x3fff;
+pub const RPN_PITCH_BEND_SENSITIVITY: u16 = 0x0000;
+pub const RPN_MPE_CONFIGURATION: u16 = 0x0006;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Param {
+    Registered(u16),
+    NonRegistered(u16),
+}
+
+/// A parameter value change, as received with a Data Entry Controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Channel in the range 0..=15.
+    pub channel: u8,
+    pub param: Param,
+    pub msb: u8,
+    /// Fine value, if received.
+    pub lsb: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Selection {
+    is_nrpn: bool,
+    msb: Option<u8>,
+    lsb: Option<u8>,
+    data_msb: u8,
+}
+
+impl Selection {
+    fn param(&self) -> Option<Param> {
+        let nb = (u16::from(self.msb?) << 7) | u16::from(self.lsb?);
+        if self.is_nrpn {
+            Some(Param::NonRegistered(nb))
+        } else if nb != RPN_NULL {
+            Some(Param::Registered(nb))
+        } else {
+            None
+        }
+    }
+}
+
+/// Keeps track of the selected parameters per port and channel.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    selections: [[Selection; 16]; 2],
+}
+
+impl Tracker {
+    /// Handles the message in `buf`, returning the parameter change it completes if any.
+    pub fn handle(&mut self, port_nb: PortNb, buf: &[u8]) -> Option<Change> {
+        let (status, controller, value) = match *buf {
+            [status, controller, value, ..] if status & 0xf0 == 0xb0 => (status, controller, value),
+            _ => return None,
+        };
+
+        let channel = status & 0x0f;
+        let selection = &mut self.selections[port_nb.idx()][channel as usize];
+        match controller {
+            CC_RPN_MSB | CC_RPN_LSB | CC_NRPN_MSB | CC_NRPN_LSB => {
+                let is_nrpn = controller == CC_NRPN_MSB || controller == CC_NRPN_LSB;
+                if selection.is_nrpn != is_nrpn {
+                    *selection = Selection {
+                        is_nrpn,
+                        ..Default::default()
+                    };
+                }
+                if controller == CC_RPN_MSB || controller == CC_NRPN_MSB {
+                    selection.msb = Some(value);
+                } else {
+                    selection.lsb = Some(value);
+                }
+
+                None
+            }
+            CC_DATA_ENTRY_MSB => {
+                selection.data_msb = value;
+                Some(Change {
+                    channel,
+                    param: selection.param()?,
+                    msb: value,
+                    lsb: None,
+                })
+            }
+            CC_DATA_ENTRY_LSB => Some(Change {
+                channel,
+                param: selection.param()?,
+                msb: selection.data_msb,
+                lsb: Some(value),
+            }),
+            _ => None,
+        }
+    }
+}
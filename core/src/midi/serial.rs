@@ -0,0 +1,171 @@
+//! Serial port (UART) MIDI input backend.
+//!
+//! Reads the raw MIDI byte stream, e.g. from an USB to UART adapter
+//! wired to a DIN-MIDI opto-coupler, and frames the messages.
+
+use std::{
+    collections::BTreeSet,
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{msg::data_len, PortNb};
+
+/// Standard MIDI baud rate.
+pub const MIDI_BAUD_RATE: u32 = 31_250;
+/// Prefix of the serial port names, so they can't be confused with the other backends.
+const PORT_NAME_PREFIX: &str = "Serial ";
+/// Read timeout, used to check whether the capture must stop.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Serial port error: {}", .0)]
+    Serial(#[from] serialport::Error),
+
+    #[error("Couldn't spawn serial port reader: {}", .0)]
+    Thread(#[from] io::Error),
+}
+
+/// Frames Midi messages from a byte stream.
+#[derive(Debug, Default)]
+pub struct StreamParser {
+    running_status: Option<u8>,
+    msg: Vec<u8>,
+    sysex: Option<Vec<u8>>,
+}
+
+impl StreamParser {
+    /// Pushes `byte`, returning the message it completes if any.
+    pub fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        match byte {
+            // Real time messages can show up anywhere, even inside a SysEx
+            0xf8..=0xff => Some(vec![byte]),
+            0xf0 => {
+                self.running_status = None;
+                self.msg.clear();
+                self.sysex = Some(vec![byte]);
+                None
+            }
+            0xf7 => {
+                let mut sysex = self.sysex.take()?;
+                sysex.push(byte);
+                Some(sysex)
+            }
+            0x80..=0xf6 => {
+                if self.sysex.take().is_some() {
+                    log::warn!("Serial MIDI: interrupted SysEx");
+                }
+                // System common messages cancel the running status
+                self.running_status = (byte < 0xf0).then_some(byte);
+                self.msg.clear();
+                self.msg.push(byte);
+                self.take_if_complete()
+            }
+            _ => {
+                if let Some(sysex) = self.sysex.as_mut() {
+                    sysex.push(byte);
+                    return None;
+                }
+
+                if self.msg.is_empty() {
+                    // Running status, or a stray data byte
+                    self.msg.push(self.running_status?);
+                }
+                self.msg.push(byte);
+                self.take_if_complete()
+            }
+        }
+    }
+
+    fn take_if_complete(&mut self) -> Option<Vec<u8>> {
+        let status = *self.msg.first()?;
+        (self.msg.len() > data_len(status)).then(|| std::mem::take(&mut self.msg))
+    }
+}
+
+struct Connection {
+    must_stop: Arc<AtomicBool>,
+    reader: thread::JoinHandle<()>,
+}
+
+/// Lists serial ports and captures their Midi byte stream.
+#[derive(Default)]
+pub struct Backend {
+    connections: [Option<Connection>; 2],
+}
+
+impl Backend {
+    /// Returns the port names of the available serial ports.
+    pub fn list(&self) -> Result<BTreeSet<Arc<str>>, Error> {
+        Ok(serialport::available_ports()?
+            .into_iter()
+            .map(|port| Arc::from(format!("{PORT_NAME_PREFIX}{}", port.port_name)))
+            .collect())
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: PortNb,
+        port_name: &str,
+        mut callback: C,
+    ) -> Result<(), Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.disconnect(port_nb);
+
+        let path = port_name
+            .strip_prefix(PORT_NAME_PREFIX)
+            .unwrap_or(port_name);
+        let mut port = serialport::new(path, MIDI_BAUD_RATE)
+            .timeout(READ_TIMEOUT)
+            .open()?;
+
+        let must_stop = Arc::new(AtomicBool::new(false));
+        let reader = thread::Builder::new()
+            .name(format!("serial-midi-{}", port_nb.as_char()))
+            .spawn({
+                let must_stop = must_stop.clone();
+                move || {
+                    let start = Instant::now();
+                    let mut parser = StreamParser::default();
+                    let mut buf = [0u8; 256];
+
+                    while !must_stop.load(Ordering::Relaxed) {
+                        let len = match port.read(&mut buf) {
+                            Ok(len) => len,
+                            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+                            Err(err) => {
+                                log::error!("Serial MIDI read error on {port_nb}: {err}");
+                                break;
+                            }
+                        };
+
+                        let ts = start.elapsed().as_micros() as u64;
+                        for byte in &buf[..len] {
+                            if let Some(msg) = parser.push(*byte) {
+                                callback(ts, &msg);
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        self.connections[port_nb.idx()] = Some(Connection { must_stop, reader });
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: PortNb) {
+        if let Some(conn) = self.connections[port_nb.idx()].take() {
+            conn.must_stop.store(true, Ordering::Relaxed);
+            let _ = conn.reader.join();
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Control surface protocols: Mackie Control (MCU) and HUI.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    None,
+    Mcu,
+    Hui,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 3] = [Protocol::None, Protocol::Mcu, Protocol::Hui];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::None => "No surface",
+            Protocol::Mcu => "Mackie Control",
+            Protocol::Hui => "HUI",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Protocol::None => "none",
+            Protocol::Mcu => "mcu",
+            Protocol::Hui => "hui",
+        }
+    }
+
+    pub fn from_storage_str(protocol: &str) -> Option<Self> {
+        Protocol::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == protocol)
+    }
+}
+
+const MCU_STRIP_BUTTONS: [&str; 5] = ["Rec", "Solo", "Mute", "Select", "V-Pot push"];
+
+const MCU_BUTTONS: [&str; 0x40] = [
+    "Assign Track",
+    "Assign Send",
+    "Assign Pan",
+    "Assign Plug-in",
+    "Assign EQ",
+    "Assign Instrument",
+    "Bank Left",
+    "Bank Right",
+    "Channel Left",
+    "Channel Right",
+    "Flip",
+    "Global View",
+    "Name/Value",
+    "SMPTE/Beats",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "MIDI Tracks",
+    "Inputs",
+    "Audio Tracks",
+    "Audio Instrument",
+    "Aux",
+    "Busses",
+    "Outputs",
+    "User",
+    "Shift",
+    "Option",
+    "Control",
+    "Alt",
+    "Read/Off",
+    "Write",
+    "Trim",
+    "Touch",
+    "Latch",
+    "Group",
+    "Save",
+    "Undo",
+    "Cancel",
+    "Enter",
+    "Marker",
+    "Nudge",
+    "Cycle",
+    "Drop",
+    "Replace",
+    "Click",
+    "Solo",
+    "Rewind",
+    "Fast Fwd",
+    "Stop",
+    "Play",
+    "Record",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "Zoom",
+    "Scrub",
+    "User A",
+    "User B",
+];
+
+const HUI_STRIP_SWITCHES: [&str; 8] = [
+    "Fader touch",
+    "Select",
+    "Mute",
+    "Solo",
+    "Auto",
+    "V-Sel",
+    "Insert",
+    "Rec",
+];
+
+/// Decodes the control surface messages of a port.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decoder {
+    pub protocol: Protocol,
+    /// Last HUI zone selected by the surface.
+    hui_zone: Option<u8>,
+}
+
+impl Decoder {
+    /// Returns the control surface meaning of the message in `buf`.
+    pub fn decode(&mut self, buf: &[u8]) -> Option<String> {
+        match self.protocol {
+            Protocol::None => None,
+            Protocol::Mcu => decode_mcu(buf),
+            Protocol::Hui => self.decode_hui(buf),
+        }
+    }
+
+    fn decode_hui(&mut self, buf: &[u8]) -> Option<String> {
+        match *buf {
+            [0x90, 0x00, 0x00, ..] => Some("Ping".to_string()),
+            [0xb0, 0x0f, zone, ..] => {
+                self.hui_zone = Some(zone);
+                Some(format!("Zone {zone} select"))
+            }
+            [0xb0, 0x2f, port, ..] => {
+                let action = if port & 0x40 != 0 { "press" } else { "release" };
+                let port = port & 0x0f;
+                let name = match self.hui_zone? {
+                    zone @ 0x00..=0x07 => match HUI_STRIP_SWITCHES.get(port as usize) {
+                        Some(switch) => format!("Strip {} {switch}", zone + 1),
+                        None => format!("Strip {} port {port}", zone + 1),
+                    },
+                    zone => format!("Zone {zone} port {port}"),
+                };
+                Some(format!("{name} {action}"))
+            }
+            [0xb0, cc @ 0x00..=0x07, _, ..] => Some(format!("Fader {} MSB", cc + 1)),
+            [0xb0, cc @ 0x20..=0x27, _, ..] => Some(format!("Fader {} LSB", cc - 0x20 + 1)),
+            [0xb0, cc @ 0x40..=0x47, value, ..] => {
+                Some(format!("V-Pot {} {:+}", cc - 0x40 + 1, hui_delta(value)))
+            }
+            [0xb0, 0x0d, value, ..] => Some(format!("Jog {:+}", hui_delta(value))),
+            _ => None,
+        }
+    }
+}
+
+fn decode_mcu(buf: &[u8]) -> Option<String> {
+    match *buf {
+        [status @ 0xe0..=0xe7, ..] => Some(format!("Fader {}", (status & 0x0f) + 1)),
+        [0xe8, ..] => Some("Master fader".to_string()),
+        [0xb0, cc @ 0x10..=0x17, value, ..] => {
+            Some(format!("V-Pot {} {:+}", cc - 0x10 + 1, mcu_delta(value)))
+        }
+        [0xb0, 0x3c, value, ..] => Some(format!("Jog {:+}", mcu_delta(value))),
+        [0x90, note, velocity, ..] => {
+            let action = if velocity > 0 { "press" } else { "release" };
+            let name = match note {
+                0x00..=0x27 => format!("{} {}", MCU_STRIP_BUTTONS[note as usize / 8], note % 8 + 1),
+                0x28..=0x67 => MCU_BUTTONS[note as usize - 0x28].to_string(),
+                0x68..=0x6f => format!("Fader {} touch", note - 0x68 + 1),
+                0x70 => "Master fader touch".to_string(),
+                _ => return None,
+            };
+            Some(format!("{name} {action}"))
+        }
+        [0xd0, value, ..] => Some(format!("Meter {} level {}", (value >> 4) + 1, value & 0x0f)),
+        _ => None,
+    }
+}
+
+/// Relative value with the sign in bit 6.
+fn mcu_delta(value: u8) -> i8 {
+    let ticks = (value & 0x3f) as i8;
+    if value & 0x40 != 0 {
+        -ticks
+    } else {
+        ticks
+    }
+}
+
+/// Relative value with the sign in bit 6, set for positive values.
+fn hui_delta(value: u8) -> i8 {
+    let ticks = (value & 0x3f) as i8;
+    if value & 0x40 != 0 {
+        ticks
+    } else {
+        -ticks
+    }
+}
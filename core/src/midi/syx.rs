@@ -0,0 +1,337 @@
+//! SysEx files (`.syx`), i.e. raw concatenated System Exclusive messages,
+//! and reassembly of the SysEx messages received in several buffers.
+
+use std::sync::Arc;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Unexpected byte {byte:02x} at offset {offset} outside of a SysEx message")]
+    UnexpectedByte { offset: usize, byte: u8 },
+
+    #[error("Unterminated SysEx message at offset {}", .0)]
+    Unterminated(usize),
+}
+
+/// Splits the content of a `.syx` file into SysEx messages.
+pub fn split(data: &[u8]) -> Result<Vec<Arc<[u8]>>, Error> {
+    let mut msgs = Vec::new();
+    let mut start = None;
+
+    for (offset, &byte) in data.iter().enumerate() {
+        match (start, byte) {
+            (None, 0xf0) => start = Some(offset),
+            (Some(first), 0xf7) => {
+                msgs.push(data[first..=offset].into());
+                start = None;
+            }
+            // A new SysEx can't start before the previous one is terminated.
+            (Some(_), 0xf0) => return Err(Error::UnexpectedByte { offset, byte }),
+            (Some(_), _) => (),
+            (None, byte) => return Err(Error::UnexpectedByte { offset, byte }),
+        }
+    }
+
+    match start {
+        Some(first) => Err(Error::Unterminated(first)),
+        None => Ok(msgs),
+    }
+}
+
+/// Returns whether `msg` is a complete SysEx message.
+pub fn is_sysex(msg: &[u8]) -> bool {
+    msg.len() >= 2 && msg[0] == 0xf0 && msg[msg.len() - 1] == 0xf7
+}
+
+/// Maximum length of a reassembled SysEx.
+///
+/// Beyond that, the message is forwarded as is, so that a missing End of
+/// Exclusive doesn't keep all the subsequent bytes.
+pub const MAX_REASSEMBLED_LEN: usize = 1 << 20;
+
+/// Maximum duration without new bytes for a pending SysEx (µs).
+///
+/// Beyond that, the SysEx is forwarded as is before the next buffer, so that
+/// a lost End of Exclusive doesn't hold it back indefinitely.
+pub const MAX_PENDING_AGE: u64 = 2_000_000;
+
+/// A SysEx being reassembled.
+#[derive(Debug)]
+struct Pending {
+    first_ts: u64,
+    /// Timestamp of the last buffer which contributed to the SysEx.
+    last_ts: u64,
+    sysex: Vec<u8>,
+}
+
+/// Reassembles the SysEx messages some backends split across several buffers.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: Option<Pending>,
+}
+
+impl Reassembler {
+    /// Pushes the buffer received at `ts`, calling `forward` with the buffers to handle.
+    ///
+    /// A SysEx which doesn't end in `buf` is held back until its End of Exclusive
+    /// shows up, it is then forwarded as a whole with the timestamp of its first buffer.
+    /// Real time messages interleaved in a SysEx are stripped & forwarded on their own.
+    pub fn push(&mut self, ts: u64, buf: &[u8], mut forward: impl FnMut(u64, &[u8])) {
+        let is_stale = self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| ts.saturating_sub(pending.last_ts) > MAX_PENDING_AGE);
+        if is_stale {
+            log::warn!("No End of Exclusive after {MAX_PENDING_AGE} µs");
+            self.flush(&mut forward);
+        }
+
+        let mut buf = buf;
+        while !buf.is_empty() {
+            buf = self.push_one(ts, buf, &mut forward);
+        }
+    }
+
+    /// Forwards the pending SysEx as is, if any, e.g. when the port is disconnected.
+    pub fn flush(&mut self, mut forward: impl FnMut(u64, &[u8])) {
+        if let Some(pending) = self.pending.take() {
+            forward(pending.first_ts, &pending.sysex);
+        }
+    }
+
+    /// Handles the beginning of `buf`, returning what's left to handle.
+    fn push_one<'a>(
+        &mut self,
+        ts: u64,
+        buf: &'a [u8],
+        forward: &mut impl FnMut(u64, &[u8]),
+    ) -> &'a [u8] {
+        let (first_ts, mut sysex, buf) = match self.pending.take() {
+            Some(pending) => (pending.first_ts, pending.sysex, buf),
+            None if buf[0] == 0xf0 => (ts, vec![0xf0], &buf[1..]),
+            None => {
+                // Forward the messages preceding the next SysEx, if any
+                return match buf.iter().position(|byte| *byte == 0xf0) {
+                    Some(pos) => {
+                        forward(ts, &buf[..pos]);
+                        &buf[pos..]
+                    }
+                    None => {
+                        forward(ts, buf);
+                        &[]
+                    }
+                };
+            }
+        };
+
+        match buf.iter().position(|byte| *byte >= 0x80) {
+            Some(pos) if buf[pos] >= 0xf8 => {
+                // Real time messages can show up anywhere, even inside a SysEx
+                sysex.extend_from_slice(&buf[..pos]);
+                forward(ts, &buf[pos..=pos]);
+                self.pending = Some(Pending {
+                    first_ts,
+                    last_ts: ts,
+                    sysex,
+                });
+
+                &buf[pos + 1..]
+            }
+            Some(pos) if buf[pos] == 0xf7 => {
+                sysex.extend_from_slice(&buf[..=pos]);
+                forward(first_ts, &sysex);
+
+                &buf[pos + 1..]
+            }
+            Some(pos) => {
+                // Interrupted by another message
+                log::warn!("SysEx interrupted by {:02x}", buf[pos]);
+                sysex.extend_from_slice(&buf[..pos]);
+                forward(first_ts, &sysex);
+
+                &buf[pos..]
+            }
+            None => {
+                sysex.extend_from_slice(buf);
+                if sysex.len() < MAX_REASSEMBLED_LEN {
+                    self.pending = Some(Pending {
+                        first_ts,
+                        last_ts: ts,
+                        sysex,
+                    });
+                } else {
+                    log::warn!("SysEx exceeds {MAX_REASSEMBLED_LEN} bytes");
+                    forward(first_ts, &sysex);
+                }
+
+                &[]
+            }
+        }
+    }
+}
+
+/// A `Reassembler` forwarding to `forward`, which flushes the pending SysEx when dropped.
+///
+/// Backends drop the callback of a port when it is disconnected.
+pub struct Reassembling<F: FnMut(u64, &[u8])> {
+    reassembler: Reassembler,
+    forward: F,
+}
+
+impl<F: FnMut(u64, &[u8])> Reassembling<F> {
+    pub fn new(forward: F) -> Self {
+        Self {
+            reassembler: Reassembler::default(),
+            forward,
+        }
+    }
+
+    /// See `Reassembler::push`.
+    pub fn push(&mut self, ts: u64, buf: &[u8]) {
+        self.reassembler.push(ts, buf, &mut self.forward);
+    }
+}
+
+impl<F: FnMut(u64, &[u8])> Drop for Reassembling<F> {
+    fn drop(&mut self) {
+        self.reassembler.flush(&mut self.forward);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reassemble(bufs: &[(u64, &[u8])]) -> (Vec<(u64, Vec<u8>)>, Reassembler) {
+        let mut reassembler = Reassembler::default();
+        let mut forwarded = Vec::new();
+        for (ts, buf) in bufs {
+            reassembler.push(*ts, buf, |ts, buf| forwarded.push((ts, buf.to_vec())));
+        }
+
+        (forwarded, reassembler)
+    }
+
+    #[test]
+    fn split_across_buffers() {
+        let (forwarded, reassembler) = reassemble(&[
+            (1, &[0xf0, 0x7e, 0x7f]),
+            (2, &[0x06, 0x01]),
+            (3, &[0x02, 0xf7]),
+        ]);
+        assert_eq!(
+            forwarded,
+            [(1, vec![0xf0, 0x7e, 0x7f, 0x06, 0x01, 0x02, 0xf7])]
+        );
+        assert!(reassembler.pending.is_none());
+    }
+
+    #[test]
+    fn realtime_inside_sysex() {
+        let (forwarded, _) = reassemble(&[(1, &[0xf0, 0x01, 0xf8, 0x02]), (2, &[0x03, 0xf7])]);
+        assert_eq!(
+            forwarded,
+            [(1, vec![0xf8]), (1, vec![0xf0, 0x01, 0x02, 0x03, 0xf7])]
+        );
+    }
+
+    #[test]
+    fn interrupted() {
+        let (forwarded, reassembler) =
+            reassemble(&[(1, &[0xf0, 0x01, 0x02]), (2, &[0x03, 0x90, 0x3c, 0x40])]);
+        assert_eq!(
+            forwarded,
+            [
+                (1, vec![0xf0, 0x01, 0x02, 0x03]),
+                (2, vec![0x90, 0x3c, 0x40])
+            ]
+        );
+        assert!(reassembler.pending.is_none());
+    }
+
+    #[test]
+    fn end_followed_by_msg() {
+        let (forwarded, _) =
+            reassemble(&[(1, &[0xf0, 0x01]), (2, &[0x02, 0xf7, 0x90, 0x3c, 0x40])]);
+        assert_eq!(
+            forwarded,
+            [
+                (1, vec![0xf0, 0x01, 0x02, 0xf7]),
+                (2, vec![0x90, 0x3c, 0x40])
+            ]
+        );
+    }
+
+    #[test]
+    fn starts_mid_buffer() {
+        let (forwarded, reassembler) = reassemble(&[(1, &[0xf8, 0xf0, 0x01]), (2, &[0x02, 0xf7])]);
+        assert_eq!(
+            forwarded,
+            [(1, vec![0xf8]), (1, vec![0xf0, 0x01, 0x02, 0xf7])]
+        );
+        assert!(reassembler.pending.is_none());
+    }
+
+    #[test]
+    fn too_long() {
+        let mut data = vec![0xf0];
+        data.resize(MAX_REASSEMBLED_LEN, 0x01);
+        let (forwarded, reassembler) = reassemble(&[(1, &data)]);
+        assert_eq!(forwarded, [(1, data)]);
+        assert!(reassembler.pending.is_none());
+    }
+
+    #[test]
+    fn flush() {
+        let (mut forwarded, mut reassembler) = reassemble(&[(1, &[0xf0, 0x01]), (2, &[0x02])]);
+        assert!(forwarded.is_empty());
+
+        reassembler.flush(|ts, buf| forwarded.push((ts, buf.to_vec())));
+        assert_eq!(forwarded, [(1, vec![0xf0, 0x01, 0x02])]);
+        assert!(reassembler.pending.is_none());
+    }
+
+    #[test]
+    fn stale() {
+        let late = 2 + MAX_PENDING_AGE + 1;
+        let (forwarded, reassembler) = reassemble(&[
+            (1, &[0xf0, 0x01]),
+            (2, &[0x02]),
+            (late, &[0x90, 0x3c, 0x40]),
+        ]);
+        assert_eq!(
+            forwarded,
+            [(1, vec![0xf0, 0x01, 0x02]), (late, vec![0x90, 0x3c, 0x40])]
+        );
+        assert!(reassembler.pending.is_none());
+
+        // Bytes keep the SysEx alive
+        let ts = 1 + MAX_PENDING_AGE;
+        let (forwarded, _) = reassemble(&[
+            (1, &[0xf0, 0x01]),
+            (ts, &[0x02]),
+            (ts + MAX_PENDING_AGE, &[0xf7]),
+        ]);
+        assert_eq!(forwarded, [(1, vec![0xf0, 0x01, 0x02, 0xf7])]);
+    }
+
+    #[test]
+    fn flush_on_drop() {
+        let mut forwarded = Vec::new();
+        let mut reassembling =
+            Reassembling::new(|ts, buf: &[u8]| forwarded.push((ts, buf.to_vec())));
+        reassembling.push(1, &[0xf0, 0x01]);
+        reassembling.push(2, &[0x02]);
+        drop(reassembling);
+
+        assert_eq!(forwarded, [(1, vec![0xf0, 0x01, 0x02])]);
+    }
+
+    #[test]
+    fn complete_sysex() {
+        let (forwarded, _) = reassemble(&[(1, &[0x90, 0x3c, 0x40]), (2, &[0xf0, 0x01, 0xf7])]);
+        assert_eq!(
+            forwarded,
+            [(1, vec![0x90, 0x3c, 0x40]), (2, vec![0xf0, 0x01, 0xf7])]
+        );
+    }
+}
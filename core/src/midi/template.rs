@@ -0,0 +1,163 @@
+//! Expected traffic templates.
+//!
+//! A `Template` declares the message kinds and channels a device is expected to send.
+//! Messages outside the template are considered anomalous.
+
+/// Kind of Midi message, as identified by its status byte.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    NoteOff,
+    NoteOn,
+    PolyPressure,
+    ControlChange,
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+    SysEx,
+    SysCommon,
+    SysRealTime,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 10] = [
+        Kind::NoteOff,
+        Kind::NoteOn,
+        Kind::PolyPressure,
+        Kind::ControlChange,
+        Kind::ProgramChange,
+        Kind::ChannelPressure,
+        Kind::PitchBend,
+        Kind::SysEx,
+        Kind::SysCommon,
+        Kind::SysRealTime,
+    ];
+
+    pub fn from_status(status: u8) -> Option<Self> {
+        Some(match status {
+            0x80..=0x8f => Kind::NoteOff,
+            0x90..=0x9f => Kind::NoteOn,
+            0xa0..=0xaf => Kind::PolyPressure,
+            0xb0..=0xbf => Kind::ControlChange,
+            0xc0..=0xcf => Kind::ProgramChange,
+            0xd0..=0xdf => Kind::ChannelPressure,
+            0xe0..=0xef => Kind::PitchBend,
+            0xf0 | 0xf7 => Kind::SysEx,
+            0xf1..=0xf6 => Kind::SysCommon,
+            0xf8..=0xff => Kind::SysRealTime,
+            _ => return None,
+        })
+    }
+
+    pub fn is_channel(self) -> bool {
+        !matches!(self, Kind::SysEx | Kind::SysCommon | Kind::SysRealTime)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::NoteOff => "Note Off",
+            Kind::NoteOn => "Note On",
+            Kind::PolyPressure => "Poly Pressure",
+            Kind::ControlChange => "Control Change",
+            Kind::ProgramChange => "Program Change",
+            Kind::ChannelPressure => "Channel Pressure",
+            Kind::PitchBend => "Pitch Bend",
+            Kind::SysEx => "SysEx",
+            Kind::SysCommon => "System Common",
+            Kind::SysRealTime => "System Real Time",
+        }
+    }
+
+    fn idx(self) -> usize {
+        Kind::ALL.iter().position(|kind| *kind == self).unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub is_enabled: bool,
+    kinds: [bool; Kind::ALL.len()],
+    /// Channels 1 to 16 at index 0 to 15.
+    channels: [bool; 16],
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            kinds: [true; Kind::ALL.len()],
+            channels: [true; 16],
+        }
+    }
+}
+
+impl Template {
+    pub fn kind_mut(&mut self, kind: Kind) -> &mut bool {
+        &mut self.kinds[kind.idx()]
+    }
+
+    /// `channel` in the range 0..=15.
+    pub fn channel_mut(&mut self, channel: u8) -> &mut bool {
+        &mut self.channels[channel as usize & 0x0f]
+    }
+
+    /// Returns whether the message in `buf` is expected.
+    pub fn accepts(&self, buf: &[u8]) -> bool {
+        if !self.is_enabled {
+            return true;
+        }
+
+        // Running status and empty buffers can't be checked
+        let status = match buf.first() {
+            Some(status) if *status >= 0x80 => *status,
+            _ => return true,
+        };
+
+        match Kind::from_status(status) {
+            Some(kind) if kind.is_channel() => {
+                self.kinds[kind.idx()] && self.channels[(status & 0x0f) as usize]
+            }
+            Some(kind) => self.kinds[kind.idx()],
+            None => true,
+        }
+    }
+
+    /// Serializes as `enabled;kind flags;channel flags`, using `0` and `1` for the flags.
+    pub fn to_storage_string(&self) -> String {
+        let flags = |flags: &[bool]| -> String {
+            flags
+                .iter()
+                .map(|flag| if *flag { '1' } else { '0' })
+                .collect()
+        };
+
+        format!(
+            "{};{};{}",
+            self.is_enabled,
+            flags(&self.kinds),
+            flags(&self.channels)
+        )
+    }
+
+    pub fn from_storage_str(template: &str) -> Option<Self> {
+        fn parse_flags<const N: usize>(flags: &str) -> Option<[bool; N]> {
+            if flags.len() != N {
+                return None;
+            }
+
+            let mut res = [false; N];
+            for (flag, char_) in res.iter_mut().zip(flags.chars()) {
+                *flag = char_ == '1';
+            }
+
+            Some(res)
+        }
+
+        let mut fields = template.split(';');
+
+        Some(Self {
+            is_enabled: fields.next()? == "true",
+            kinds: parse_flags(fields.next()?)?,
+            channels: parse_flags(fields.next()?)?,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+//! Per-port throughput measurement over a sliding window.
+
+use std::{collections::VecDeque, fmt};
+
+use super::PortNb;
+use crate::time::{Duration, Instant};
+
+/// Duration of the sliding window the rates are computed on.
+pub const WINDOW: Duration = Duration::from_secs(1);
+/// Granularity of the sliding window.
+const BUCKET_DURATION: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Counts {
+    msgs: u64,
+    bytes: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    start: Instant,
+    counts: [Counts; 2],
+}
+
+/// Throughput of a port.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rate {
+    pub msgs_per_sec: f64,
+    pub bytes_per_sec: f64,
+}
+
+impl Rate {
+    pub fn is_idle(&self) -> bool {
+        self.msgs_per_sec == 0.0
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} msg/s, {:.0} B/s",
+            self.msgs_per_sec, self.bytes_per_sec
+        )
+    }
+}
+
+/// Rates for each port, indexed by `PortNb::idx()`.
+pub type Rates = [Rate; 2];
+
+/// Accumulates the incoming messages and computes the rates over `WINDOW`.
+#[derive(Debug, Default)]
+pub struct Meter {
+    buckets: VecDeque<Bucket>,
+}
+
+impl Meter {
+    pub fn record(&mut self, port_nb: PortNb, len: usize) {
+        self.record_at(Instant::now(), port_nb, len);
+    }
+
+    fn record_at(&mut self, now: Instant, port_nb: PortNb, len: usize) {
+        let bucket = match self.buckets.back_mut() {
+            Some(bucket) if now.duration_since(bucket.start) < BUCKET_DURATION => bucket,
+            _ => {
+                self.buckets.push_back(Bucket {
+                    start: now,
+                    counts: Default::default(),
+                });
+                self.buckets.back_mut().unwrap()
+            }
+        };
+
+        let counts = &mut bucket.counts[port_nb.idx()];
+        counts.msgs += 1;
+        counts.bytes += len as u64;
+    }
+
+    /// Returns the current rates, discarding the buckets out of the window.
+    pub fn rates(&mut self) -> Rates {
+        let now = Instant::now();
+        while let Some(bucket) = self.buckets.front() {
+            if now.duration_since(bucket.start) < WINDOW {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+
+        let window_secs = WINDOW.as_secs_f64();
+        let mut rates = Rates::default();
+        for (idx, rate) in rates.iter_mut().enumerate() {
+            let (msgs, bytes) = self.buckets.iter().fold((0, 0), |(msgs, bytes), bucket| {
+                let counts = bucket.counts[idx];
+                (msgs + counts.msgs, bytes + counts.bytes)
+            });
+            rate.msgs_per_sec = msgs as f64 / window_secs;
+            rate.bytes_per_sec = bytes as f64 / window_secs;
+        }
+
+        rates
+    }
+}
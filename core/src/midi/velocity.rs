@@ -0,0 +1,111 @@
+//! Note On velocity statistics, e.g. to calibrate keyboard velocity curves.
+
+use std::collections::VecDeque;
+
+use super::PortNb;
+
+/// Number of velocities the running average is computed on.
+pub const RUNNING_AVERAGE_LEN: usize = 32;
+
+/// Note On velocities received on a channel.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    counts: [u64; 128],
+    total: u64,
+    sum: u64,
+    recent: VecDeque<u8>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            counts: [0; 128],
+            total: 0,
+            sum: 0,
+            recent: VecDeque::with_capacity(RUNNING_AVERAGE_LEN),
+        }
+    }
+}
+
+impl Histogram {
+    fn add(&mut self, velocity: u8) {
+        self.counts[velocity as usize] += 1;
+        self.total += 1;
+        self.sum += u64::from(velocity);
+
+        if self.recent.len() == RUNNING_AVERAGE_LEN {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(velocity);
+    }
+
+    /// Number of Note On by velocity.
+    pub fn counts(&self) -> &[u64; 128] {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.sum as f64 / self.total as f64)
+    }
+
+    /// Average of the last `RUNNING_AVERAGE_LEN` velocities.
+    pub fn running_average(&self) -> Option<f64> {
+        (!self.recent.is_empty()).then(|| {
+            self.recent.iter().map(|&v| f64::from(v)).sum::<f64>() / self.recent.len() as f64
+        })
+    }
+
+    pub fn min(&self) -> Option<u8> {
+        self.counts.iter().position(|&c| c > 0).map(|v| v as u8)
+    }
+
+    pub fn max(&self) -> Option<u8> {
+        self.counts.iter().rposition(|&c| c > 0).map(|v| v as u8)
+    }
+}
+
+/// Velocity histograms by port & channel.
+#[derive(Debug, Default)]
+pub struct Stats {
+    histograms: [[Histogram; 16]; 2],
+}
+
+impl Stats {
+    /// Handles the message in `buf`, returning the channel if it was a Note On.
+    pub fn handle(&mut self, port_nb: PortNb, buf: &[u8]) -> Option<u8> {
+        match *buf {
+            // Note On with velocity 0 is a Note Off.
+            [status, _, velocity] if status & 0xf0 == 0x90 && velocity > 0 => {
+                let channel = status & 0x0f;
+                self.histograms[port_nb.idx()][channel as usize].add(velocity);
+
+                Some(channel)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, port_nb: PortNb, channel: u8) -> &Histogram {
+        &self.histograms[port_nb.idx()][channel as usize]
+    }
+
+    /// Iterates over the ports & channels which received Note Ons.
+    pub fn active(&self) -> impl Iterator<Item = (PortNb, u8)> + '_ {
+        [PortNb::One, PortNb::Two]
+            .into_iter()
+            .flat_map(|port_nb| (0..16).map(move |channel| (port_nb, channel)))
+            .filter(|&(port_nb, channel)| !self.get(port_nb, channel).is_empty())
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
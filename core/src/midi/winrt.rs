@@ -0,0 +1,123 @@
+//! Windows Runtime Midi input backend.
+//!
+//! Unlike WinMM, WinRT lists the BLE-MIDI devices paired with Windows.
+//! Allows selecting WinRT at runtime, while `midir` keeps using WinMM.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use windows::{
+    core::HSTRING,
+    Devices::{
+        Enumeration::{DeviceInformation, DeviceInformationCollection},
+        Midi::{MidiInPort, MidiMessageReceivedEventArgs, MidiOutPort},
+    },
+    Foundation::{EventRegistrationToken, TypedEventHandler},
+    Storage::Streams::DataReader,
+};
+
+use super::{port::Kind, PortNb};
+
+pub type Error = windows::core::Error;
+
+struct Connection {
+    port: MidiInPort,
+    token: EventRegistrationToken,
+}
+
+/// Lists the WinRT Midi input ports and captures them.
+#[derive(Default)]
+pub struct Backend {
+    /// Device ids by port name.
+    ids: BTreeMap<Arc<str>, HSTRING>,
+    connections: [Option<Connection>; 2],
+}
+
+impl Backend {
+    /// Returns the available Midi sources, their `Kind` and whether they have an output.
+    pub fn list(&mut self) -> Result<BTreeMap<Arc<str>, (Kind, bool)>, Error> {
+        let outputs = find_devices(&MidiOutPort::GetDeviceSelector()?)?;
+        let outputs = (0..outputs.Size()?)
+            .map(|idx| outputs.GetAt(idx)?.Name())
+            .collect::<Result<Vec<HSTRING>, Error>>()?;
+
+        self.ids.clear();
+        let mut list = BTreeMap::new();
+        let inputs = find_devices(&MidiInPort::GetDeviceSelector()?)?;
+        for idx in 0..inputs.Size()? {
+            let device = inputs.GetAt(idx)?;
+            let (id, name) = (device.Id()?, device.Name()?);
+
+            let port_name: Arc<str> = name.to_string_lossy().into();
+            // E.g. `\\?\BTHLEDevice#{...}`
+            let kind = if id.to_string_lossy().to_uppercase().contains("BTHLE") {
+                Kind::Bluetooth
+            } else {
+                Kind::from_port_name(&port_name)
+            };
+
+            list.insert(port_name.clone(), (kind, outputs.contains(&name)));
+            self.ids.insert(port_name, id);
+        }
+
+        Ok(list)
+    }
+
+    pub fn connect<C>(
+        &mut self,
+        port_nb: PortNb,
+        port_name: &Arc<str>,
+        callback: C,
+    ) -> Result<(), super::port::Error>
+    where
+        C: FnMut(u64, &[u8]) + Send + 'static,
+    {
+        self.disconnect(port_nb);
+
+        let id = self
+            .ids
+            .get(port_name)
+            .ok_or_else(|| super::port::Error::PortNotFound(port_name.clone()))?;
+        let port = MidiInPort::FromIdAsync(id)?.get()?;
+
+        let callback = Mutex::new(callback);
+        let handler =
+            TypedEventHandler::new(move |_port, args: &Option<MidiMessageReceivedEventArgs>| {
+                if let Some(args) = args.as_ref() {
+                    let msg = args.Message()?;
+                    // 100 ns units since the port was opened
+                    let ts = msg.Timestamp()?.Duration as u64 / 10;
+
+                    let data = msg.RawData()?;
+                    let mut buf = vec![0; data.Length()? as usize];
+                    DataReader::FromBuffer(&data)?.ReadBytes(&mut buf)?;
+
+                    (*callback.lock().unwrap())(ts, &buf);
+                }
+
+                Ok(())
+            });
+        let token = port.MessageReceived(&handler)?;
+
+        self.connections[port_nb.idx()] = Some(Connection { port, token });
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, port_nb: PortNb) {
+        if let Some(conn) = self.connections[port_nb.idx()].take() {
+            let res = conn
+                .port
+                .RemoveMessageReceived(conn.token)
+                .and_then(|()| conn.port.Close());
+            if let Err(err) = res {
+                log::warn!("Failed to close WinRT port for {port_nb}: {err}");
+            }
+        }
+    }
+}
+
+fn find_devices(selector: &HSTRING) -> Result<DeviceInformationCollection, Error> {
+    DeviceInformation::FindAllAsyncAqsFilter(selector)?.get()
+}
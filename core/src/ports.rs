@@ -0,0 +1,118 @@
+//! Ports snapshots published by the Controller.
+
+use once_cell::sync::Lazy;
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::midi;
+
+/// Name used for a disconnected port.
+pub static DISCONNECTED: Lazy<Arc<str>> = Lazy::new(|| "Disconnected".into());
+
+/// Snapshot of the ports as seen by the Controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectionalPorts {
+    pub list: Vec<Arc<str>>,
+    caps: BTreeMap<Arc<str>, midi::port::Capabilities>,
+    descriptions: BTreeMap<Arc<str>, Arc<str>>,
+    cur: [Arc<str>; 2],
+    desired: [Option<Arc<str>>; 2],
+    pub backend: midi::port::Backend,
+}
+
+impl DirectionalPorts {
+    pub fn caps(&self) -> &BTreeMap<Arc<str>, midi::port::Capabilities> {
+        &self.caps
+    }
+
+    /// Returns the description of the ports, see `midi::Ports::descriptions`.
+    pub fn descriptions(&self) -> &BTreeMap<Arc<str>, Arc<str>> {
+        &self.descriptions
+    }
+
+    /// Returns the name of the port connected to `port_nb`.
+    pub fn cur(&self, port_nb: midi::PortNb) -> &Arc<str> {
+        &self.cur[port_nb.idx()]
+    }
+
+    /// Returns the name of the port connected to `port_nb`, `None` if disconnected.
+    pub fn connected(&self, port_nb: midi::PortNb) -> Option<&Arc<str>> {
+        Some(self.cur(port_nb)).filter(|cur| *cur != &*DISCONNECTED)
+    }
+
+    /// Updates from `ports` and returns whether something changed.
+    pub fn update_from(&mut self, ports: &midi::Ports) -> bool {
+        let mut changed = !self.list.iter().eq(ports.list());
+        if changed {
+            self.list.clear();
+            self.list.extend(ports.list().cloned());
+        }
+
+        if self.caps != ports.caps {
+            self.caps = ports.caps.clone();
+            changed = true;
+        }
+
+        if self.descriptions != ports.descriptions {
+            self.descriptions = ports.descriptions.clone();
+            changed = true;
+        }
+
+        if self.backend != ports.backend() {
+            self.backend = ports.backend();
+            changed = true;
+        }
+
+        changed |= self.update_cur(midi::PortNb::One, ports);
+        changed |= self.update_cur(midi::PortNb::Two, ports);
+
+        changed
+    }
+
+    fn update_cur(&mut self, port_nb: midi::PortNb, ports: &midi::Ports) -> bool {
+        let cur = ports
+            .cur(port_nb)
+            .cloned()
+            .unwrap_or_else(|| DISCONNECTED.clone());
+        let desired = ports.desired(port_nb).cloned();
+
+        let changed = cur != self.cur[port_nb.idx()] || desired != self.desired[port_nb.idx()];
+        self.cur[port_nb.idx()] = cur;
+        self.desired[port_nb.idx()] = desired;
+
+        changed
+    }
+
+    /// Returns the ports the user wants to be connected to.
+    pub fn desired_ports(&self) -> impl Iterator<Item = (midi::PortNb, Arc<str>)> + '_ {
+        [midi::PortNb::One, midi::PortNb::Two]
+            .into_iter()
+            .filter_map(|port_nb| {
+                self.desired[port_nb.idx()]
+                    .clone()
+                    .map(|port_name| (port_nb, port_name))
+            })
+    }
+
+    /// Returns the name of the port to persist for `port_nb`.
+    ///
+    /// This is the desired port, even if the device is currently unplugged,
+    /// so that the connection can be restored when it shows up.
+    pub fn persistent_name(&self, port_nb: midi::PortNb) -> &str {
+        self.desired[port_nb.idx()]
+            .as_deref()
+            .unwrap_or(DISCONNECTED.as_ref())
+    }
+}
+
+impl Default for DirectionalPorts {
+    fn default() -> Self {
+        Self {
+            list: Vec::new(),
+            caps: BTreeMap::new(),
+            descriptions: BTreeMap::new(),
+            cur: [DISCONNECTED.clone(), DISCONNECTED.clone()],
+            desired: [None, None],
+            backend: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Async subscription to the captured messages.
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use midi_sniffer_core::{bus, stream::MsgStream};
+//!
+//! # async fn capture(bus: bus::Bus) {
+//! let mut stream = MsgStream::new(&bus);
+//! while let Some(res) = stream.next().await {
+//!     match res {
+//!         Ok(msg) => println!("{:?}", msg.msg),
+//!         Err(err) => eprintln!("{err}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use futures::{channel::mpsc, ready, Stream, StreamExt};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use crate::{bus, midi};
+
+/// A `Stream` of the Midi messages captured by the Controller.
+///
+/// The `Event::MsgBatch`es published on the `bus::Bus` are flattened,
+/// so each item is a single message. The stream ends when the `bus::Bus`
+/// and its publishers are dropped.
+pub struct MsgStream {
+    rx: mpsc::UnboundedReceiver<bus::Event>,
+    batch: Option<Arc<[midi::msg::Result]>>,
+    idx: usize,
+}
+
+impl MsgStream {
+    pub fn new(bus: &bus::Bus) -> Self {
+        MsgStream {
+            rx: bus.subscribe_stream(bus::Event::is_msg_batch),
+            batch: None,
+            idx: 0,
+        }
+    }
+}
+
+impl Stream for MsgStream {
+    type Item = midi::msg::Result;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(batch) = self.batch.as_ref() {
+                if let Some(res) = batch.get(self.idx).cloned() {
+                    self.idx += 1;
+                    return Poll::Ready(Some(res));
+                }
+                self.batch = None;
+            }
+
+            match ready!(self.rx.poll_next_unpin(cx)) {
+                Some(bus::Event::MsgBatch(batch)) => {
+                    self.batch = Some(batch);
+                    self.idx = 0;
+                }
+                Some(_) => (),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Time types working on both native and web targets.
+//!
+//! `std::time::{Instant, SystemTime}::now()` panic on `wasm32-unknown-unknown`.
+
+pub use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
@@ -0,0 +1,103 @@
+#ifndef MIDI_SNIFFER_H
+#define MIDI_SNIFFER_H
+
+/* Generated by cbindgen from the midi-sniffer-ffi crate, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Handle to a running capture engine.
+typedef struct MidiSniffer MidiSniffer;
+
+// Called for each message captured on a port.
+//
+// - `user_data`: the pointer passed to `midi_sniffer_connect`.
+// - `port_nb`: the port number, 1 or 2.
+// - `ts`: the timestamp provided by the Midi backend (µs).
+// - `data` & `len`: the raw message.
+// - `desc`: the decoded message or the parse error, valid during the call.
+// - `is_err`: whether the message could not be parsed.
+typedef void (*MidiSnifferCallback)(void *user_data,
+                                    uint8_t port_nb,
+                                    uint64_t ts,
+                                    const uint8_t *data,
+                                    uintptr_t len,
+                                    const char *desc,
+                                    bool is_err);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Starts a capture engine.
+//
+// `client_name` is the name of the Midi client, e.g. the name of the test
+// harness. Returns `NULL` if `client_name` is not a valid UTF-8 string.
+//
+// # Safety
+//
+// `client_name` must be a valid NUL terminated string.
+// The result must be released with `midi_sniffer_shutdown`.
+MidiSniffer *midi_sniffer_init(const char *client_name);
+
+// Returns the names of the available ports, separated by `\n`.
+//
+// # Safety
+//
+// `sniffer` must have been returned by `midi_sniffer_init`.
+// The result must be released with `midi_sniffer_string_free`.
+char *midi_sniffer_list_ports(const MidiSniffer *sniffer);
+
+// Releases a string returned by the midi-sniffer API.
+//
+// # Safety
+//
+// `string` must have been returned by the midi-sniffer API or be `NULL`.
+void midi_sniffer_string_free(char *string);
+
+// Connects `port_nb` (1 or 2) to the port named `port_name`.
+//
+// `callback` is invoked with `user_data` for each message captured on
+// `port_nb`. Returns 0 if the connection was requested, -1 if the arguments
+// are invalid or the port is not listed. Connection failures are logged.
+//
+// # Safety
+//
+// `sniffer` must have been returned by `midi_sniffer_init` and `port_name`
+// must be a valid NUL terminated string. `user_data` must remain valid and
+// usable from another thread until the port is disconnected.
+int midi_sniffer_connect(MidiSniffer *sniffer,
+                         uint8_t port_nb,
+                         const char *port_name,
+                         MidiSnifferCallback callback,
+                         void *user_data);
+
+// Disconnects `port_nb` (1 or 2).
+//
+// Returns 0 on success, -1 if the arguments are invalid. If the callback of
+// `port_nb` is running, waits for it to return, so `user_data` can be released
+// after this call.
+//
+// # Safety
+//
+// `sniffer` must have been returned by `midi_sniffer_init`.
+// Must not be called from a callback.
+int midi_sniffer_disconnect(MidiSniffer *sniffer, uint8_t port_nb);
+
+// Stops the capture engine and releases `sniffer`.
+//
+// No callbacks are invoked after this function returns.
+//
+// # Safety
+//
+// `sniffer` must have been returned by `midi_sniffer_init` or be `NULL`.
+// It must not be used after this call.
+void midi_sniffer_shutdown(MidiSniffer *sniffer);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* MIDI_SNIFFER_H */
@@ -0,0 +1,432 @@
+//! C API of the `midi-sniffer` capture engine.
+//!
+//! The header `include/midi_sniffer.h` is generated when building this crate.
+//!
+//! All functions must be called from the same thread, except for the
+//! callbacks which are invoked from a dedicated thread.
+
+use crossbeam_channel as channel;
+use std::{
+    ffi::{c_void, CStr, CString},
+    os::raw::{c_char, c_int},
+    ptr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use midi_sniffer_core::{
+    bus,
+    controller::{self, Request},
+    format, midi,
+    ports::DirectionalPorts,
+};
+
+/// Maximum duration to wait for the initial port list.
+const INIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+const OK: c_int = 0;
+const ERR: c_int = -1;
+
+/// Called for each message captured on a port.
+///
+/// - `user_data`: the pointer passed to `midi_sniffer_connect`.
+/// - `port_nb`: the port number, 1 or 2.
+/// - `ts`: the timestamp provided by the Midi backend (µs).
+/// - `data` & `len`: the raw message.
+/// - `desc`: the decoded message or the parse error, valid during the call.
+/// - `is_err`: whether the message could not be parsed.
+pub type MidiSnifferCallback = Option<
+    unsafe extern "C" fn(
+        user_data: *mut c_void,
+        port_nb: u8,
+        ts: u64,
+        data: *const u8,
+        len: usize,
+        desc: *const c_char,
+        is_err: bool,
+    ),
+>;
+
+#[derive(Clone, Copy)]
+struct Callback {
+    func: unsafe extern "C" fn(*mut c_void, u8, u64, *const u8, usize, *const c_char, bool),
+    user_data: *mut c_void,
+}
+
+// Safety: the caller of `midi_sniffer_connect` guarantees that `user_data`
+// can be used from the dispatcher thread.
+unsafe impl Send for Callback {}
+
+impl Callback {
+    fn call(&self, res: &midi::msg::Result) {
+        let origin = midi::msg::origin(res);
+
+        let desc = CString::new(format::describe(res)).unwrap_or_default();
+
+        let port_nb = match origin.port_nb {
+            midi::PortNb::One => 1,
+            midi::PortNb::Two => 2,
+        };
+
+        // Safety: `func` & `user_data` were provided together by the caller.
+        unsafe {
+            (self.func)(
+                self.user_data,
+                port_nb,
+                origin.ts,
+                origin.buffer.as_ptr(),
+                origin.buffer.len(),
+                desc.as_ptr(),
+                res.is_err(),
+            );
+        }
+    }
+}
+
+/// The callback of each port.
+///
+/// The lock is held while a callback is invoked, so that its `user_data`
+/// is no longer in use once it is replaced or removed.
+#[derive(Default)]
+struct Callbacks(Mutex<[Option<Callback>; 2]>);
+
+impl Callbacks {
+    /// Sets the callback of `port_nb`, waiting for the current one to return.
+    fn set(&self, port_nb: midi::PortNb, callback: Option<Callback>) {
+        self.0.lock().unwrap()[port_nb.idx()] = callback;
+    }
+
+    fn dispatch(&self, batch: &[midi::msg::Result]) {
+        for res in batch {
+            // Locked for each message so that `set` doesn't wait for the whole batch
+            let callbacks = self.0.lock().unwrap();
+            if let Some(cb) = callbacks[midi::msg::origin(res).port_nb.idx()] {
+                cb.call(res);
+            }
+        }
+    }
+}
+
+/// Handle to a running capture engine.
+pub struct MidiSniffer {
+    req_tx: channel::Sender<Request>,
+    ports: Arc<Mutex<Arc<DirectionalPorts>>>,
+    callbacks: Arc<Callbacks>,
+    controller: Option<thread::JoinHandle<()>>,
+    dispatcher: Option<thread::JoinHandle<()>>,
+}
+
+impl MidiSniffer {
+    fn new(client_name: &str) -> Self {
+        let bus = bus::Bus::default();
+        let event_rx = bus.subscribe(|event| {
+            event.is_msg_batch()
+                || event.is_ports_changed()
+                || event.is_backend_unavailable()
+                || event.is_error()
+        });
+        let (req_tx, req_rx) = channel::unbounded();
+
+        let controller = controller::Spawner::new(req_rx, bus, client_name.into()).spawn();
+
+        // The Controller publishes the initial ports once it is started
+        let mut initial_ports = Arc::new(DirectionalPorts::default());
+        let deadline = Instant::now() + INIT_TIMEOUT;
+        while let Ok(event) = event_rx.recv_deadline(deadline) {
+            match event {
+                bus::Event::PortsChanged(ports) => {
+                    initial_ports = ports;
+                    break;
+                }
+                bus::Event::BackendUnavailable(Some(err)) => {
+                    log::error!("{err:#}");
+                    break;
+                }
+                bus::Event::Error(err) => log::error!("{err:#}"),
+                _ => (),
+            }
+        }
+        let ports = Arc::new(Mutex::new(initial_ports));
+
+        let callbacks = Arc::new(Callbacks::default());
+        let dispatcher = thread::spawn({
+            let ports = ports.clone();
+            let callbacks = callbacks.clone();
+            // Stops when the Controller is gone, along with the bus.
+            move || {
+                for event in event_rx.iter() {
+                    match event {
+                        bus::Event::MsgBatch(batch) => callbacks.dispatch(&batch),
+                        bus::Event::PortsChanged(new_ports) => {
+                            *ports.lock().unwrap() = new_ports;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        });
+
+        MidiSniffer {
+            req_tx,
+            ports,
+            callbacks,
+            controller: Some(controller),
+            dispatcher: Some(dispatcher),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.req_tx.send(Request::Shutdown);
+        for handle in [self.controller.take(), self.dispatcher.take()]
+            .into_iter()
+            .flatten()
+        {
+            if handle.join().is_err() {
+                log::error!("midi-sniffer thread panicked");
+            }
+        }
+    }
+}
+
+fn port_nb(port_nb: u8) -> Option<midi::PortNb> {
+    match port_nb {
+        1 => Some(midi::PortNb::One),
+        2 => Some(midi::PortNb::Two),
+        _ => None,
+    }
+}
+
+/// Starts a capture engine.
+///
+/// `client_name` is the name of the Midi client, e.g. the name of the test
+/// harness. Returns `NULL` if `client_name` is not a valid UTF-8 string.
+///
+/// # Safety
+///
+/// `client_name` must be a valid NUL terminated string.
+/// The result must be released with `midi_sniffer_shutdown`.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_init(client_name: *const c_char) -> *mut MidiSniffer {
+    if client_name.is_null() {
+        return ptr::null_mut();
+    }
+
+    match CStr::from_ptr(client_name).to_str() {
+        Ok(client_name) => Box::into_raw(Box::new(MidiSniffer::new(client_name))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Returns the names of the available ports, separated by `\n`.
+///
+/// # Safety
+///
+/// `sniffer` must have been returned by `midi_sniffer_init`.
+/// The result must be released with `midi_sniffer_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_list_ports(sniffer: *const MidiSniffer) -> *mut c_char {
+    let sniffer = match sniffer.as_ref() {
+        Some(sniffer) => sniffer,
+        None => return ptr::null_mut(),
+    };
+
+    let ports = sniffer.ports.lock().unwrap().clone();
+    let list = ports.list.join("\n");
+
+    CString::new(list).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Releases a string returned by the midi-sniffer API.
+///
+/// # Safety
+///
+/// `string` must have been returned by the midi-sniffer API or be `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Connects `port_nb` (1 or 2) to the port named `port_name`.
+///
+/// `callback` is invoked with `user_data` for each message captured on
+/// `port_nb`. Returns 0 if the connection was requested, -1 if the arguments
+/// are invalid or the port is not listed. Connection failures are logged.
+///
+/// # Safety
+///
+/// `sniffer` must have been returned by `midi_sniffer_init` and `port_name`
+/// must be a valid NUL terminated string. `user_data` must remain valid and
+/// usable from another thread until the port is disconnected.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_connect(
+    sniffer: *mut MidiSniffer,
+    port_nb: u8,
+    port_name: *const c_char,
+    callback: MidiSnifferCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let (sniffer, port_nb, func) = match (sniffer.as_ref(), self::port_nb(port_nb), callback) {
+        (Some(sniffer), Some(port_nb), Some(func)) => (sniffer, port_nb, func),
+        _ => return ERR,
+    };
+    if port_name.is_null() {
+        return ERR;
+    }
+    let port_name = match CStr::from_ptr(port_name).to_str() {
+        Ok(port_name) => port_name,
+        Err(_) => return ERR,
+    };
+
+    if !sniffer
+        .ports
+        .lock()
+        .unwrap()
+        .list
+        .iter()
+        .any(|name| name.as_ref() == port_name)
+    {
+        return ERR;
+    }
+
+    sniffer
+        .callbacks
+        .set(port_nb, Some(Callback { func, user_data }));
+
+    if sniffer
+        .req_tx
+        .send(Request::Connect((port_nb, port_name.into())))
+        .is_err()
+    {
+        return ERR;
+    }
+
+    OK
+}
+
+/// Disconnects `port_nb` (1 or 2).
+///
+/// Returns 0 on success, -1 if the arguments are invalid. If the callback of
+/// `port_nb` is running, waits for it to return, so `user_data` can be released
+/// after this call.
+///
+/// # Safety
+///
+/// `sniffer` must have been returned by `midi_sniffer_init`.
+/// Must not be called from a callback.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_disconnect(sniffer: *mut MidiSniffer, port_nb: u8) -> c_int {
+    let (sniffer, port_nb) = match (sniffer.as_ref(), self::port_nb(port_nb)) {
+        (Some(sniffer), Some(port_nb)) => (sniffer, port_nb),
+        _ => return ERR,
+    };
+
+    // Even if the Controller is gone, the callback must no longer be used
+    sniffer.callbacks.set(port_nb, None);
+    if sniffer.req_tx.send(Request::Disconnect(port_nb)).is_err() {
+        return ERR;
+    }
+
+    OK
+}
+
+/// Stops the capture engine and releases `sniffer`.
+///
+/// No callbacks are invoked after this function returns.
+///
+/// # Safety
+///
+/// `sniffer` must have been returned by `midi_sniffer_init` or be `NULL`.
+/// It must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn midi_sniffer_shutdown(sniffer: *mut MidiSniffer) {
+    if !sniffer.is_null() {
+        Box::from_raw(sniffer).shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Probe {
+        is_in_call: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    unsafe extern "C" fn slow_callback(
+        user_data: *mut c_void,
+        _port_nb: u8,
+        _ts: u64,
+        _data: *const u8,
+        _len: usize,
+        _desc: *const c_char,
+        _is_err: bool,
+    ) {
+        let probe = &*(user_data as *const Probe);
+        probe.is_in_call.store(true, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        probe.calls.fetch_add(1, Ordering::SeqCst);
+        probe.is_in_call.store(false, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn disconnect_waits_for_callback() {
+        let probe = Arc::new(Probe::default());
+        let callbacks = Arc::new(Callbacks::default());
+        callbacks.set(
+            midi::PortNb::One,
+            Some(Callback {
+                func: slow_callback,
+                user_data: Arc::as_ptr(&probe) as *mut c_void,
+            }),
+        );
+
+        let batch: Vec<_> = (0..3)
+            .map(|ts| {
+                let origin = midi::msg::Origin::new(ts, midi::PortNb::One, &[0x90, 0x3c, 0x40]);
+                midi::msg::parse(origin)
+            })
+            .collect();
+        let dispatcher = thread::spawn({
+            let callbacks = callbacks.clone();
+            move || callbacks.dispatch(&batch)
+        });
+
+        while !probe.is_in_call.load(Ordering::SeqCst) {
+            thread::yield_now();
+        }
+        callbacks.set(midi::PortNb::One, None);
+
+        // The call in progress returned and no other call is started
+        assert!(!probe.is_in_call.load(Ordering::SeqCst));
+        let calls = probe.calls.load(Ordering::SeqCst);
+        assert!(calls > 0);
+        dispatcher.join().unwrap();
+        assert_eq!(probe.calls.load(Ordering::SeqCst), calls);
+    }
+
+    #[test]
+    fn dispatch_to_port() {
+        let probe = Arc::new(Probe::default());
+        let callbacks = Callbacks::default();
+        callbacks.set(
+            midi::PortNb::Two,
+            Some(Callback {
+                func: slow_callback,
+                user_data: Arc::as_ptr(&probe) as *mut c_void,
+            }),
+        );
+
+        let batch = [
+            midi::msg::parse(midi::msg::Origin::new(0, midi::PortNb::One, &[0xf8])),
+            midi::msg::parse(midi::msg::Origin::new(1, midi::PortNb::Two, &[0xf8])),
+        ];
+        callbacks.dispatch(&batch);
+        assert_eq!(probe.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,271 @@
+//! Python bindings of the `midi-sniffer` capture engine.
+//!
+//! ```python
+//! import midi_sniffer
+//!
+//! sniffer = midi_sniffer.Sniffer("qa")
+//! sniffer.connect(1, sniffer.ports()[0])
+//! for msg in sniffer:
+//!     assert not msg.is_err, msg.desc
+//!     print(msg.port, msg.ts, msg.raw.hex(), msg.desc)
+//! ```
+
+use crossbeam_channel as channel;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use std::{collections::VecDeque, sync::Arc, thread, time::Duration};
+
+use midi_sniffer_core::{
+    bus,
+    controller::{self, Request},
+    format, midi,
+    ports::DirectionalPorts,
+};
+
+/// Maximum duration to wait for the initial port list.
+const INIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval between two checks for Python signals, e.g. Ctrl+C, while waiting.
+const SIGNALS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A message captured on a port.
+#[pyclass(module = "midi_sniffer")]
+pub struct Message {
+    /// The port number, 1 or 2.
+    #[pyo3(get)]
+    port: u8,
+    /// The timestamp provided by the Midi backend (µs).
+    #[pyo3(get)]
+    ts: u64,
+    raw: Arc<[u8]>,
+    /// The decoded message or the parse error.
+    #[pyo3(get)]
+    desc: String,
+    /// Whether the message could not be parsed.
+    #[pyo3(get)]
+    is_err: bool,
+}
+
+#[pymethods]
+impl Message {
+    /// The raw message.
+    #[getter]
+    fn raw<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.raw)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Message(port={}, ts={}, desc={:?}, is_err={})",
+            self.port,
+            self.ts,
+            self.desc,
+            if self.is_err { "True" } else { "False" },
+        )
+    }
+}
+
+impl From<&midi::msg::Result> for Message {
+    fn from(res: &midi::msg::Result) -> Self {
+        let origin = midi::msg::origin(res);
+        Message {
+            port: match origin.port_nb {
+                midi::PortNb::One => 1,
+                midi::PortNb::Two => 2,
+            },
+            ts: origin.ts,
+            raw: origin.buffer.clone(),
+            desc: format::describe(res),
+            is_err: res.is_err(),
+        }
+    }
+}
+
+/// A capture engine.
+///
+/// Iterating over the `Sniffer` blocks until the next message is captured.
+#[pyclass(module = "midi_sniffer")]
+pub struct Sniffer {
+    req_tx: channel::Sender<Request>,
+    event_rx: channel::Receiver<bus::Event>,
+    ports: Arc<DirectionalPorts>,
+    pending: VecDeque<Message>,
+    controller: Option<thread::JoinHandle<()>>,
+}
+
+impl Sniffer {
+    fn handle_event(&mut self, event: bus::Event) {
+        match event {
+            bus::Event::MsgBatch(batch) => self.pending.extend(batch.iter().map(Message::from)),
+            bus::Event::PortsChanged(ports) => self.ports = ports,
+            _ => (),
+        }
+    }
+
+    fn pop_events(&mut self) {
+        while let Ok(event) = self.event_rx.try_recv() {
+            self.handle_event(event);
+        }
+    }
+}
+
+fn port_nb(port_nb: u8) -> PyResult<midi::PortNb> {
+    match port_nb {
+        1 => Ok(midi::PortNb::One),
+        2 => Ok(midi::PortNb::Two),
+        other => Err(PyValueError::new_err(format!(
+            "Invalid port number {other}, expected 1 or 2"
+        ))),
+    }
+}
+
+#[pymethods]
+impl Sniffer {
+    #[new]
+    fn new(py: Python<'_>, client_name: &str) -> Self {
+        let bus = bus::Bus::default();
+        let event_rx = bus.subscribe(|event| event.is_msg_batch() || event.is_ports_changed());
+        let (req_tx, req_rx) = channel::unbounded();
+
+        let controller = controller::Spawner::new(req_rx, bus, client_name.into()).spawn();
+
+        let ports = py
+            .allow_threads({
+                let event_rx = event_rx.clone();
+                move || event_rx.recv_timeout(INIT_TIMEOUT)
+            })
+            .ok()
+            .and_then(|event| match event {
+                bus::Event::PortsChanged(ports) => Some(ports),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        Sniffer {
+            req_tx,
+            event_rx,
+            ports,
+            pending: VecDeque::new(),
+            controller: Some(controller),
+        }
+    }
+
+    /// Returns the names of the available ports.
+    fn ports(&mut self) -> Vec<String> {
+        self.pop_events();
+        self.ports.list.iter().map(ToString::to_string).collect()
+    }
+
+    /// Returns the name of the port connected to `port_nb`, `None` if disconnected.
+    fn connected(&mut self, port_nb: u8) -> PyResult<Option<String>> {
+        let port_nb = self::port_nb(port_nb)?;
+        self.pop_events();
+        Ok(self.ports.connected(port_nb).map(ToString::to_string))
+    }
+
+    /// Connects `port_nb` (1 or 2) to the port named `port_name`.
+    fn connect(&mut self, port_nb: u8, port_name: &str) -> PyResult<()> {
+        let port_nb = self::port_nb(port_nb)?;
+        self.pop_events();
+        if !self
+            .ports
+            .list
+            .iter()
+            .any(|name| name.as_ref() == port_name)
+        {
+            return Err(PyValueError::new_err(format!(
+                "Port {port_name:?} is not available"
+            )));
+        }
+
+        self.req_tx
+            .send(Request::Connect((port_nb, port_name.into())))
+            .map_err(|_| PyValueError::new_err("Sniffer is closed"))
+    }
+
+    /// Disconnects `port_nb` (1 or 2).
+    fn disconnect(&mut self, port_nb: u8) -> PyResult<()> {
+        let port_nb = self::port_nb(port_nb)?;
+        self.req_tx
+            .send(Request::Disconnect(port_nb))
+            .map_err(|_| PyValueError::new_err("Sniffer is closed"))
+    }
+
+    /// Returns the next message or `None` if none is captured within `timeout` seconds.
+    ///
+    /// Waits indefinitely if `timeout` is `None`.
+    #[args(timeout = "None")]
+    fn next(&mut self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<Message>> {
+        let deadline = timeout
+            .map(|timeout| std::time::Instant::now() + Duration::from_secs_f64(timeout.max(0.0)));
+
+        loop {
+            self.pop_events();
+            if let Some(msg) = self.pending.pop_front() {
+                return Ok(Some(msg));
+            }
+
+            let wait = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(None);
+                    }
+                    remaining.min(SIGNALS_INTERVAL)
+                }
+                None => SIGNALS_INTERVAL,
+            };
+
+            let event_rx = self.event_rx.clone();
+            match py.allow_threads(move || event_rx.recv_timeout(wait)) {
+                Ok(event) => self.handle_event(event),
+                Err(channel::RecvTimeoutError::Timeout) => py.check_signals()?,
+                Err(channel::RecvTimeoutError::Disconnected) => return Ok(None),
+            }
+        }
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Message>> {
+        self.next(py, None)
+    }
+
+    /// Stops the capture engine.
+    fn close(&mut self, py: Python<'_>) {
+        let _ = self.req_tx.send(Request::Shutdown);
+        if let Some(controller) = self.controller.take() {
+            let _ = py.allow_threads(move || controller.join());
+        }
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> bool {
+        self.close(py);
+        false
+    }
+}
+
+impl Drop for Sniffer {
+    fn drop(&mut self) {
+        let _ = self.req_tx.send(Request::Shutdown);
+    }
+}
+
+#[pymodule]
+fn midi_sniffer(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Message>()?;
+    m.add_class::<Sniffer>()?;
+
+    Ok(())
+}
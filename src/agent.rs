@@ -0,0 +1,165 @@
+//! Headless agent capturing local ports on behalf of remote sniffers.
+//!
+//! See `midi::remote` for the protocol.
+
+use anyhow::Context;
+use crossbeam_channel as channel;
+use std::{
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::midi::{self, remote, PortNb};
+
+/// Maximum number of frames buffered for a slow client.
+const FRAME_CHANNEL_CAPACITY: usize = 8192;
+
+struct State {
+    ports: midi::Ports,
+    is_busy: [bool; 2],
+}
+
+/// Runs the agent, listening on `addr`.
+pub fn run(client_name: &str, addr: SocketAddr) -> anyhow::Result<()> {
+    let ports = midi::Ports::try_new(client_name.into(), midi::port::Backend::default())
+        .context("Failed to create agent ports")?;
+    let state = Arc::new(Mutex::new(State {
+        ports,
+        is_busy: [false; 2],
+    }));
+
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    log::info!("Agent listening on {addr}");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("Agent failed to accept a connection: {err}");
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map_or_else(|_| "?".into(), |peer| peer.to_string());
+            if let Err(err) = handle_client(stream, &state) {
+                log::debug!("Agent client {peer}: {err}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, state: &Mutex<State>) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if line == remote::LIST {
+            let mut state = state.lock().unwrap();
+            state.ports.refresh()?;
+            let mut names = String::new();
+            for name in state.ports.list() {
+                names.push_str(name);
+                names.push('\n');
+            }
+            names.push('\n');
+            writer.write_all(names.as_bytes())?;
+        } else if let Some(port_name) = line.strip_prefix(remote::CAPTURE) {
+            return capture(port_name.trim(), reader, writer, state);
+        } else {
+            writeln!(writer, "{} unknown command", remote::ERR)?;
+        }
+    }
+}
+
+fn capture(
+    port_name: &str,
+    mut reader: BufReader<TcpStream>,
+    mut writer: TcpStream,
+    state: &Mutex<State>,
+) -> anyhow::Result<()> {
+    let (frame_tx, frame_rx) = channel::bounded::<(u64, Box<[u8]>)>(FRAME_CHANNEL_CAPACITY);
+
+    let port_nb = {
+        let mut state = state.lock().unwrap();
+        let port_nb = match [PortNb::One, PortNb::Two]
+            .into_iter()
+            .find(|port_nb| !state.is_busy[port_nb.idx()])
+        {
+            Some(port_nb) => port_nb,
+            None => {
+                writeln!(writer, "{} no free capture slot", remote::ERR)?;
+                return Ok(());
+            }
+        };
+
+        let callback = move |ts, buf: &[u8]| {
+            if frame_tx.try_send((ts, buf.into())).is_err() {
+                log::warn!("Agent client can't keep up, dropping message");
+            }
+        };
+        if let Err(err) = state.ports.connect(port_nb, port_name.into(), callback) {
+            writeln!(writer, "{} {err}", remote::ERR)?;
+            return Ok(());
+        }
+
+        state.is_busy[port_nb.idx()] = true;
+        port_nb
+    };
+
+    log::info!("Agent capturing {port_name}");
+    writeln!(writer, "{}", remote::OK)?;
+    writer.set_nodelay(true)?;
+
+    // Detects the client disconnection
+    let (stop_tx, stop_rx) = channel::bounded::<()>(1);
+    let watcher = thread::spawn(move || {
+        let _ = std::io::copy(&mut reader, &mut std::io::sink());
+        drop(stop_tx);
+    });
+
+    let stream = writer.try_clone()?;
+    let mut writer = BufWriter::new(writer);
+    'frames: loop {
+        channel::select! {
+            recv(frame_rx) -> frame => {
+                let Ok(frame) = frame else { break };
+                for (ts, buf) in std::iter::once(frame).chain(frame_rx.try_iter()) {
+                    if remote::write_frame(&mut writer, ts, &buf).is_err() {
+                        break 'frames;
+                    }
+                }
+                if writer.flush().is_err() {
+                    break;
+                }
+            }
+            recv(stop_rx) -> _ => break,
+        }
+    }
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+
+    log::info!("Agent stopped capturing {port_name}");
+    {
+        let mut state = state.lock().unwrap();
+        let _ = state.ports.disconnect(port_nb);
+        state.is_busy[port_nb.idx()] = false;
+    }
+    let _ = watcher.join();
+
+    Ok(())
+}
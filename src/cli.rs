@@ -0,0 +1,126 @@
+//! Command line arguments.
+
+use crate::midi::port::Backend;
+
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Runs as a headless agent listening on this address.
+    #[cfg(feature = "agent")]
+    pub agent_addr: Option<std::net::SocketAddr>,
+    /// Agents whose ports are listed along with the local ports.
+    #[cfg(feature = "agent")]
+    pub remotes: Vec<std::net::SocketAddr>,
+    /// Overrides the `Backend` from the settings.
+    pub backend: Option<Backend>,
+    /// Configuration profile to apply instead of the default one.
+    #[cfg(feature = "config")]
+    pub profile: Option<String>,
+    /// Address the HTTP remote control server listens on.
+    #[cfg(feature = "http")]
+    pub http_addr: Option<std::net::SocketAddr>,
+    /// Address the WebSocket server listens on.
+    #[cfg(feature = "ws")]
+    pub ws_addr: Option<std::net::SocketAddr>,
+    /// Prints the `usage` instead of starting.
+    pub help: bool,
+}
+
+/// Returns the arguments available with the enabled features.
+pub fn usage() -> String {
+    let mut usage = format!("Usage: {} [OPTIONS]\n\nOptions:\n", env!("CARGO_PKG_NAME"));
+
+    #[cfg(feature = "agent")]
+    usage.push_str("  --agent <ADDR>       Run as a headless agent listening on ADDR\n");
+    usage.push_str(&format!(
+        "  --backend <BACKEND>  Midi backend, one of: {}\n",
+        backends()
+    ));
+    #[cfg(feature = "http")]
+    usage.push_str("  --http <ADDR>        Serve the HTTP remote control API on ADDR\n");
+    #[cfg(feature = "config")]
+    usage.push_str("  --profile <NAME>     Apply the configuration profile NAME\n");
+    #[cfg(feature = "agent")]
+    usage.push_str("  --remote <ADDR>      List the ports of the agent at ADDR, can be repeated\n");
+    #[cfg(feature = "ws")]
+    usage.push_str("  --ws <ADDR>          Stream the messages over WebSocket on ADDR\n");
+    usage.push_str("  -h, --help           Print this help\n");
+
+    usage
+}
+
+fn backends() -> String {
+    Backend::ALL
+        .iter()
+        .map(|backend| backend.storage_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Parses `--name value` or `--name=value` arguments.
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut this = Self::default();
+
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-h" || arg == "--help" {
+                this.help = true;
+                continue;
+            }
+
+            let (name, inline_val) = match arg.split_once('=') {
+                Some((name, val)) => (name.to_string(), Some(val.to_string())),
+                None => (arg, None),
+            };
+            let val = inline_val
+                .or_else(|| iter.next())
+                .ok_or_else(|| format!("Missing value for {name}"))?;
+
+            match name.as_str() {
+                #[cfg(feature = "agent")]
+                "--agent" => {
+                    let addr = val
+                        .parse()
+                        .map_err(|err| format!("Invalid agent address {val}: {err}"))?;
+                    this.agent_addr = Some(addr);
+                }
+                "--backend" => {
+                    let backend = Backend::from_storage_str(&val).ok_or_else(|| {
+                        format!("Unsupported backend {val}, expected one of: {}", backends())
+                    })?;
+                    this.backend = Some(backend);
+                }
+                #[cfg(feature = "http")]
+                "--http" => {
+                    let addr = val
+                        .parse()
+                        .map_err(|err| format!("Invalid HTTP address {val}: {err}"))?;
+                    this.http_addr = Some(addr);
+                }
+                #[cfg(feature = "config")]
+                "--profile" => this.profile = Some(val),
+                #[cfg(feature = "agent")]
+                "--remote" => {
+                    let addr = val
+                        .parse()
+                        .map_err(|err| format!("Invalid remote agent address {val}: {err}"))?;
+                    this.remotes.push(addr);
+                }
+                #[cfg(feature = "ws")]
+                "--ws" => {
+                    let addr = val
+                        .parse()
+                        .map_err(|err| format!("Invalid WebSocket address {val}: {err}"))?;
+                    this.ws_addr = Some(addr);
+                }
+                _ => return Err(format!("Unknown argument {name}")),
+            }
+        }
+
+        Ok(this)
+    }
+}
@@ -0,0 +1,129 @@
+//! Configuration file holding named profiles, e.g. one per studio setup.
+//!
+//! The file is loaded from the user's configuration directory, e.g.
+//! `~/.config/midi-sniffer/config.toml` on Linux, or from the path set in
+//! the `MIDI_SNIFFER_CONFIG` environment variable:
+//!
+//! ```toml
+//! default_profile = "studio"
+//!
+//! [profiles.studio]
+//! filter = "note"
+//!
+//! [profiles.studio.port_1]
+//! pattern = "*Sub 37*"
+//! color = "#000064"
+//! device_profile = "Moog Sub 37"
+//!
+//! [profiles.studio.columns]
+//! delta = true
+//! raw = false
+//! ```
+//!
+//! The settings left out of a profile are kept as they are.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::midi::PortNb;
+
+/// Environment variable overriding the configuration file path.
+const CONFIG_PATH_ENV: &str = "MIDI_SNIFFER_CONFIG";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Couldn't read configuration {}: {}", .0.display(), .1)]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("Invalid configuration {}: {}", .0.display(), .1)]
+    Toml(PathBuf, #[source] toml::de::Error),
+    #[error("Unknown configuration profile {}", .0)]
+    UnknownProfile(String),
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile applied on startup when none is selected on the command line.
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Loads the configuration file, if any.
+    pub fn load() -> Result<Self, Error> {
+        match path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(|err| Error::Io(path.into(), err))?;
+        toml::from_str(&content).map_err(|err| Error::Toml(path.into(), err))
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, Error> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| Error::UnknownProfile(name.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub port_1: PortSetup,
+    pub port_2: PortSetup,
+    /// Text of the active filter set.
+    pub filter: Option<String>,
+    pub columns: Columns,
+}
+
+impl Profile {
+    pub fn port(&self, port_nb: PortNb) -> &PortSetup {
+        match port_nb {
+            PortNb::One => &self.port_1,
+            PortNb::Two => &self.port_2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PortSetup {
+    /// Auto-connect pattern, see `midi::PortPattern`.
+    pub pattern: Option<String>,
+    /// Color as `#rrggbb`.
+    pub color: Option<String>,
+    /// Name of a device profile.
+    pub device_profile: Option<String>,
+}
+
+/// Columns of the message list.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Columns {
+    pub timestamp: Option<bool>,
+    pub delta: Option<bool>,
+    pub port: Option<bool>,
+    pub repetitions: Option<bool>,
+    pub channel: Option<bool>,
+    pub duration: Option<bool>,
+    pub parsed: Option<bool>,
+    pub raw: Option<bool>,
+}
+
+/// Returns the path of the configuration file.
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Some(path.into());
+    }
+
+    directories_next::ProjectDirs::from("", "", crate::ui::APP_ID)
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+}
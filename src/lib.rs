@@ -0,0 +1,17 @@
+#[cfg(feature = "agent")]
+pub mod agent;
+
+pub mod cli;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+pub use midi_sniffer_core::{bus, bytes, controller, format, intern, midi, ports, time, MidiIn};
+
+#[cfg(feature = "net")]
+pub mod net;
+
+pub mod ui;
+
+#[cfg(target_arch = "wasm32")]
+pub mod web;
@@ -1,21 +1,41 @@
-pub mod bytes;
-
-pub mod midi;
-pub use midi::MidiIn;
-
-mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+use midi_sniffer::{cli, ui};
 
+#[cfg(not(target_arch = "wasm32"))]
 const APP_NAME: &str = "MIDI sniffer";
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     env_logger::Builder::new()
         .filter_level(log::LevelFilter::Debug)
         .init();
 
+    let args = cli::Args::parse().unwrap_or_else(|err| {
+        eprintln!("{err}\n\n{}", cli::usage());
+        std::process::exit(1);
+    });
+    if args.help {
+        print!("{}", cli::usage());
+        return;
+    }
+
+    #[cfg(feature = "agent")]
+    if let Some(addr) = args.agent_addr {
+        if let Err(err) = midi_sniffer::agent::run(APP_NAME, addr) {
+            log::error!("{err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let options = eframe::NativeOptions::default();
     eframe::run_native(
-        "midi-sniffer",
+        ui::APP_ID,
         options,
-        Box::new(|cc| Box::new(ui::App::new(APP_NAME, cc))),
+        Box::new(move |cc| Box::new(ui::App::new(APP_NAME, args, cc))),
     );
 }
+
+/// See `midi_sniffer::web` for the web entry point.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
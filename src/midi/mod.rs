@@ -1,8 +0,0 @@
-pub mod io;
-pub use io::MidiIn;
-
-pub mod msg;
-pub use msg::Msg;
-
-pub mod port;
-pub use port::{PortNb, Ports};
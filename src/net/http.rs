@@ -0,0 +1,195 @@
+//! HTTP remote control API.
+//!
+//! - `GET /ports`: lists the ports and the current connections as JSON.
+//! - `POST /ports/{1,2}/connect`: connects to the port named in the request body.
+//! - `POST /ports/{1,2}/disconnect`.
+//! - `GET /stats`: statistics about the captured messages as Markdown.
+//! - `GET /capture`: downloads the captured messages in RON format.
+
+use crossbeam_channel as channel;
+use eframe::egui;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+
+use crate::{
+    midi::PortNb,
+    ui::{app, bus, port::DirectionalPorts},
+};
+
+/// Maximum duration to wait for the UI to answer a `Query`.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Requests for data owned by the UI.
+pub enum Query {
+    /// Statistics as Markdown.
+    Stats(oneshot::Sender<String>),
+    /// The captured messages in RON format.
+    Capture(oneshot::Sender<Vec<u8>>),
+}
+
+struct State {
+    ports: Mutex<Arc<DirectionalPorts>>,
+    req_tx: channel::Sender<app::Request>,
+    query_tx: channel::Sender<Query>,
+    egui_ctx: egui::Context,
+}
+
+impl State {
+    fn ports_json(&self) -> Response<Body> {
+        let ports = self.ports.lock().unwrap().clone();
+        let json = serde_json::json!({
+            "backend": ports.backend.as_str(),
+            "list": ports.list.iter().map(AsRef::as_ref).collect::<Vec<&str>>(),
+            "connected": [PortNb::One, PortNb::Two]
+                .map(|port_nb| ports.connected(port_nb).map(AsRef::as_ref)),
+        });
+
+        Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(json.to_string()))
+            .unwrap()
+    }
+
+    fn send_req(&self, req: app::Request) -> Response<Body> {
+        if self.req_tx.send(req).is_err() {
+            return status(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        status(StatusCode::ACCEPTED)
+    }
+
+    async fn query<T>(
+        &self,
+        query: impl FnOnce(oneshot::Sender<T>) -> Query,
+    ) -> Result<T, StatusCode> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.query_tx
+            .send(query(reply_tx))
+            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+        // Queries are handled on next UI update
+        self.egui_ctx.request_repaint();
+
+        match tokio::time::timeout(QUERY_TIMEOUT, reply_rx).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+            Err(_) => Err(StatusCode::GATEWAY_TIMEOUT),
+        }
+    }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(code)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn text(content_type: &str, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .header("Content-Type", content_type)
+        .body(body.into())
+        .unwrap()
+}
+
+async fn handle(state: Arc<State>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let resp = match (method, path.as_slice()) {
+        (Method::GET, ["ports"]) => state.ports_json(),
+        (Method::POST, ["ports", port, action]) => {
+            let port_nb = match *port {
+                "1" => PortNb::One,
+                "2" => PortNb::Two,
+                _ => return Ok(status(StatusCode::NOT_FOUND)),
+            };
+
+            match *action {
+                "connect" => match hyper::body::to_bytes(req.into_body()).await {
+                    Ok(body) => match std::str::from_utf8(&body).map(str::trim) {
+                        Ok(port_name) if !port_name.is_empty() => {
+                            state.send_req(app::Request::Connect((port_nb, port_name.into())))
+                        }
+                        _ => status(StatusCode::BAD_REQUEST),
+                    },
+                    Err(_) => status(StatusCode::BAD_REQUEST),
+                },
+                "disconnect" => state.send_req(app::Request::Disconnect(port_nb)),
+                _ => status(StatusCode::NOT_FOUND),
+            }
+        }
+        (Method::GET, ["stats"]) => match state.query(Query::Stats).await {
+            Ok(md) => text("text/markdown; charset=utf-8", md),
+            Err(code) => status(code),
+        },
+        (Method::GET, ["capture"]) => match state.query(Query::Capture).await {
+            Ok(ron) => text("text/plain; charset=utf-8", ron),
+            Err(code) => status(code),
+        },
+        _ => status(StatusCode::NOT_FOUND),
+    };
+
+    Ok(resp)
+}
+
+/// Spawns the HTTP server on `rt`, listening on `addr`.
+pub fn spawn(
+    rt: &super::Runtime,
+    addr: SocketAddr,
+    req_tx: channel::Sender<app::Request>,
+    query_tx: channel::Sender<Query>,
+    egui_ctx: egui::Context,
+) -> JoinHandle<()> {
+    let state = Arc::new(State {
+        ports: Default::default(),
+        req_tx,
+        query_tx,
+        egui_ctx,
+    });
+
+    let mut ports_rx = rt.subscribe(bus::Event::is_ports_changed);
+    rt.spawn({
+        let state = state.clone();
+        async move {
+            while let Some(event) = ports_rx.recv().await {
+                if let bus::Event::PortsChanged(ports) = event {
+                    *state.ports.lock().unwrap() = ports;
+                }
+            }
+        }
+    });
+
+    rt.spawn(serve(addr, state, rt.bus().clone()))
+}
+
+async fn serve(addr: SocketAddr, state: Arc<State>, bus: bus::Bus) {
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+    });
+
+    let server = match hyper::Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(make_svc),
+        Err(err) => {
+            bus.publish_err(anyhow::anyhow!(
+                "HTTP server failed to listen on {addr}: {err}"
+            ));
+            return;
+        }
+    };
+    log::info!("HTTP server listening on {addr}");
+
+    if let Err(err) = server.await {
+        bus.publish_err(anyhow::anyhow!("HTTP server error: {err}"));
+    }
+}
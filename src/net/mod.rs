@@ -0,0 +1,64 @@
+//! Asynchronous IO layer shared by the network transports.
+//!
+//! Network transports run as tasks on a single shared `Runtime`
+//! and interact with the rest of the application through the `bus::Bus`.
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "ws")]
+pub mod ws;
+
+use std::future::Future;
+use tokio::{runtime, sync::mpsc, task::JoinHandle};
+
+use crate::ui::bus;
+
+const WORKER_THREADS: usize = 2;
+
+pub struct Runtime {
+    rt: Option<runtime::Runtime>,
+    bus: bus::Bus,
+}
+
+impl Runtime {
+    pub fn try_new(bus: bus::Bus) -> std::io::Result<Self> {
+        let rt = runtime::Builder::new_multi_thread()
+            .worker_threads(WORKER_THREADS)
+            .thread_name("midi-sniffer-net")
+            .enable_all()
+            .build()?;
+
+        Ok(Self { rt: Some(rt), bus })
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.rt.as_ref().expect("runtime alive").spawn(future)
+    }
+
+    pub fn handle(&self) -> &runtime::Handle {
+        self.rt.as_ref().expect("runtime alive").handle()
+    }
+
+    pub fn subscribe(&self, filter: bus::Filter) -> mpsc::UnboundedReceiver<bus::Event> {
+        self.bus.subscribe_async(filter)
+    }
+
+    pub fn bus(&self) -> &bus::Bus {
+        &self.bus
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        if let Some(rt) = self.rt.take() {
+            log::debug!("Shutting down network runtime");
+            // Don't wait for transports blocked on IO.
+            rt.shutdown_background();
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! WebSocket server broadcasting the decoded Midi messages as JSON.
+//!
+//! Each message is sent as a text frame, e.g.:
+//!
+//! ```json
+//! {"ts":123456,"wall_ts":1650000000000,"port":1,"raw":"90, 3c, 64","parsed":"...","is_err":false}
+//! ```
+
+use futures::{SinkExt, StreamExt};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    midi::{self, PortNb},
+    time::UNIX_EPOCH,
+    ui::{bus, msg_list::MsgParseResult},
+};
+
+/// Number of messages a client can lag behind before it misses some.
+const CLIENT_CHANNEL_CAPACITY: usize = 4096;
+
+#[derive(serde::Serialize)]
+struct JsonMsg<'a> {
+    /// Timestamp as provided by the Midi backend (µs).
+    ts: u64,
+    /// Wall-clock arrival time (ms since the Unix epoch).
+    wall_ts: u64,
+    port: u8,
+    raw: &'a str,
+    parsed: &'a str,
+    is_err: bool,
+}
+
+fn to_json(res: &midi::msg::Result) -> Option<Arc<str>> {
+    let origin = midi::msg::origin(res);
+    let parsed = MsgParseResult::from(res);
+
+    let msg = JsonMsg {
+        ts: origin.ts,
+        wall_ts: origin
+            .wall_ts
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |wall_ts| wall_ts.as_millis() as u64),
+        port: match origin.port_nb {
+            PortNb::One => 1,
+            PortNb::Two => 2,
+        },
+        raw: parsed.raw_str(),
+        parsed: parsed.parsed_str(),
+        is_err: parsed.is_err(),
+    };
+
+    serde_json::to_string(&msg)
+        .map_err(|err| log::error!("Failed to serialize Midi message: {err}"))
+        .ok()
+        .map(Arc::from)
+}
+
+/// Spawns the WebSocket server on `rt`, listening on `addr`.
+pub fn spawn(rt: &super::Runtime, addr: SocketAddr) -> JoinHandle<()> {
+    let events = rt.subscribe(bus::Event::is_msg_batch);
+    rt.spawn(serve(addr, events, rt.bus().clone()))
+}
+
+async fn serve(addr: SocketAddr, mut events: mpsc::UnboundedReceiver<bus::Event>, bus: bus::Bus) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            bus.publish_err(anyhow::anyhow!(
+                "WebSocket server failed to listen on {addr}: {err}"
+            ));
+            return;
+        }
+    };
+    log::info!("WebSocket server listening on {addr}");
+
+    let (msg_tx, _) = broadcast::channel(CLIENT_CHANNEL_CAPACITY);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    tokio::spawn(handle_client(stream, peer, msg_tx.subscribe()));
+                }
+                Err(err) => log::warn!("WebSocket server failed to accept a connection: {err}"),
+            },
+            event = events.recv() => match event {
+                Some(bus::Event::MsgBatch(batch)) => {
+                    if msg_tx.receiver_count() == 0 {
+                        continue;
+                    }
+
+                    for json in batch.iter().filter_map(to_json) {
+                        let _ = msg_tx.send(json);
+                    }
+                }
+                Some(_) => (),
+                None => break,
+            },
+        }
+    }
+
+    log::debug!("Shutting down WebSocket server");
+}
+
+async fn handle_client(
+    stream: TcpStream,
+    peer: SocketAddr,
+    mut msg_rx: broadcast::Receiver<Arc<str>>,
+) {
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            log::warn!("WebSocket handshake with {peer} failed: {err}");
+            return;
+        }
+    };
+    log::info!("WebSocket client {peer} connected");
+
+    let (mut sink, mut source) = ws.split();
+    loop {
+        tokio::select! {
+            json = msg_rx.recv() => match json {
+                Ok(json) => {
+                    if sink.send(Message::Text(json.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    log::warn!("WebSocket client {peer} lagging, skipped {count} messages");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = source.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Incoming messages are ignored, pings are handled by tungstenite
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+
+    log::info!("WebSocket client {peer} disconnected");
+}
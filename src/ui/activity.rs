@@ -0,0 +1,130 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+
+use super::bus;
+use crate::{
+    midi::{
+        activity::{Class, Grid},
+        PortNb,
+    },
+    time::{Duration, Instant},
+};
+
+/// Duration for a cell to fade out after some activity.
+const FADE: Duration = Duration::from_millis(600);
+const CELL_WIDTH: f32 = 28.0;
+const CELL_HEIGHT: f32 = 14.0;
+
+const ACTIVE_COLOR: egui::Color32 = egui::Color32::from_rgb(0x40, 0xd0, 0x40);
+
+/// Compact overview of the channel messages per channel & class.
+pub struct ActivityPanel {
+    pub is_open: bool,
+    port_nb: PortNb,
+    grid: Grid,
+    msg_rx: channel::Receiver<bus::Event>,
+}
+
+impl ActivityPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        Self {
+            is_open: false,
+            port_nb: PortNb::One,
+            grid: Grid::default(),
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        bus::drain_msgs(&self.msg_rx, |origin| {
+            self.grid.handle(origin.port_nb, now, &origin.buffer)
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.is_open;
+        egui::Window::new("Activity")
+            .open(&mut is_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for port_nb in [PortNb::One, PortNb::Two] {
+                        ui.selectable_value(&mut self.port_nb, port_nb, port_nb.as_str());
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.grid.clear();
+                    }
+                });
+                ui.separator();
+
+                self.show_grid(ui);
+            });
+        self.is_open = is_open;
+    }
+}
+
+impl ActivityPanel {
+    fn show_grid(&self, ui: &mut egui::Ui) {
+        let now = Instant::now();
+        let mut is_fading = false;
+
+        egui::Grid::new("activity-grid")
+            .spacing(egui::vec2(2.0, 2.0))
+            .show(ui, |ui| {
+                ui.label("");
+                for class in Class::ALL {
+                    ui.small(class.as_str()).on_hover_text(class.description());
+                }
+                ui.end_row();
+
+                for channel in 0..16 {
+                    ui.small(format!("ch{}", channel + 1));
+                    for class in Class::ALL {
+                        let cell = self.grid.get(self.port_nb, channel, class);
+
+                        let fade = match cell.last {
+                            Some(last) if now.duration_since(last) < FADE => {
+                                is_fading = true;
+                                1.0 - now.duration_since(last).as_secs_f32() / FADE.as_secs_f32()
+                            }
+                            _ => 0.0,
+                        };
+                        let idle = if cell.count > 0 {
+                            ui.visuals().widgets.inactive.bg_fill
+                        } else {
+                            ui.visuals().extreme_bg_color
+                        };
+                        let color = lerp_color(idle, ACTIVE_COLOR, fade);
+
+                        let (rect, response) = ui.allocate_exact_size(
+                            egui::vec2(CELL_WIDTH, CELL_HEIGHT),
+                            egui::Sense::hover(),
+                        );
+                        ui.painter().rect_filled(rect, 2.0, color);
+                        response.on_hover_text(format!(
+                            "{} ch{}: {} × {}",
+                            self.port_nb.as_str(),
+                            channel + 1,
+                            cell.count,
+                            class.description(),
+                        ));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if is_fading {
+            ui.ctx().request_repaint();
+        }
+    }
+}
+
+fn lerp_color(from: egui::Color32, to: egui::Color32, t: f32) -> egui::Color32 {
+    let lerp = |from: u8, to: u8| (f32::from(from) + (f32::from(to) - f32::from(from)) * t) as u8;
+    egui::Color32::from_rgb(
+        lerp(from.r(), to.r()),
+        lerp(from.g(), to.g()),
+        lerp(from.b(), to.b()),
+    )
+}
@@ -0,0 +1,317 @@
+use crossbeam_channel as channel;
+use eframe::{self, egui};
+use std::collections::VecDeque;
+
+use super::{bus, filter::Filter, msg_list::MsgParseResult};
+use crate::time::{Duration, Instant, SystemTime};
+
+/// Minimum interval between two triggers of the same rule.
+const ALERT_COOLDOWN: Duration = Duration::from_secs(1);
+const FLASH_DURATION: Duration = Duration::from_millis(600);
+const STORAGE_ALERT_RULES: &str = "alert_rules";
+
+/// What happens when a message matches a `Rule`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Flash the window.
+    Flash,
+    /// Ring the terminal bell.
+    Sound,
+    /// Show a desktop notification.
+    #[cfg(feature = "notify")]
+    Notify,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Flash,
+        Action::Sound,
+        #[cfg(feature = "notify")]
+        Action::Notify,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Flash => "Flash",
+            Action::Sound => "Sound",
+            #[cfg(feature = "notify")]
+            Action::Notify => "Notify",
+        }
+    }
+
+    fn storage_str(self) -> &'static str {
+        match self {
+            Action::Flash => "flash",
+            Action::Sound => "sound",
+            #[cfg(feature = "notify")]
+            Action::Notify => "notify",
+        }
+    }
+
+    fn from_storage_str(action: &str) -> Option<Self> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.storage_str() == action)
+    }
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub is_enabled: bool,
+    pub filter: Filter,
+    /// Number of matching messages within `window` which trigger the rule.
+    pub count: u32,
+    pub window: Duration,
+    pub action: Action,
+    occurrences: VecDeque<SystemTime>,
+    last_triggered: Option<Instant>,
+}
+
+impl Rule {
+    fn new(filter: Filter, action: Action) -> Self {
+        Self {
+            is_enabled: true,
+            filter,
+            count: 1,
+            window: Duration::from_secs(1),
+            action,
+            occurrences: VecDeque::new(),
+            last_triggered: None,
+        }
+    }
+
+    /// Returns `true` if `msg` triggers this rule.
+    fn check(&mut self, msg: &MsgParseResult, now: Instant) -> bool {
+        if !self.is_enabled || !self.filter.is_active() || !self.filter.matches(msg) {
+            return false;
+        }
+
+        if self.count > 1 {
+            let wall_ts = msg.ts().wall_ts;
+            self.occurrences.push_back(wall_ts);
+            while let Some(first) = self.occurrences.front() {
+                match wall_ts.duration_since(*first) {
+                    Ok(elapsed) if elapsed > self.window => {
+                        self.occurrences.pop_front();
+                    }
+                    _ => break,
+                }
+            }
+
+            if self.occurrences.len() < self.count as usize {
+                return false;
+            }
+
+            self.occurrences.clear();
+        }
+
+        if let Some(last) = self.last_triggered {
+            if now.duration_since(last) < ALERT_COOLDOWN {
+                return false;
+            }
+        }
+
+        self.last_triggered = Some(now);
+
+        true
+    }
+}
+
+/// Triggers actions when incoming messages match the alert rules.
+pub struct AlertsPanel {
+    pub is_open: bool,
+    rules: Vec<Rule>,
+    flash_start: Option<Instant>,
+    msg_rx: channel::Receiver<bus::Event>,
+}
+
+impl AlertsPanel {
+    pub fn new(bus: &bus::Bus, storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut rules = Vec::new();
+
+        if let Some(rules_str) = storage.and_then(|storage| storage.get_string(STORAGE_ALERT_RULES))
+        {
+            // One rule per line: `action\tcount\twindow_ms\tpattern`
+            for line in rules_str.lines() {
+                let fields: Vec<&str> = line.splitn(4, '\t').collect();
+                if let [action, count, window_ms, pattern] = fields[..] {
+                    if let Some(action) = Action::from_storage_str(action) {
+                        let mut rule = Rule::new(Filter::new(pattern), action);
+                        rule.count = count.parse().unwrap_or(1).max(1);
+                        rule.window = Duration::from_millis(window_ms.parse().unwrap_or(1_000));
+                        rules.push(rule);
+                    }
+                }
+            }
+        }
+
+        Self {
+            is_open: false,
+            rules,
+            flash_start: None,
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+        }
+    }
+
+    /// Checks incoming messages against the rules and renders the pending flash.
+    pub fn check(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+
+        for event in self.msg_rx.try_iter() {
+            let batch = match event {
+                bus::Event::MsgBatch(batch) => batch,
+                _ => continue,
+            };
+
+            if !self.rules.iter().any(|rule| rule.is_enabled) {
+                continue;
+            }
+
+            for res in batch.iter() {
+                let msg = MsgParseResult::from(res);
+                for rule in self.rules.iter_mut() {
+                    if rule.check(&msg, now) {
+                        log::info!("Alert triggered by {}", msg.parsed_str());
+                        match rule.action {
+                            Action::Flash => self.flash_start = Some(now),
+                            Action::Sound => {
+                                use std::io::Write;
+                                let mut stderr = std::io::stderr();
+                                let _ = stderr.write_all(b"\x07");
+                                let _ = stderr.flush();
+                            }
+                            #[cfg(feature = "notify")]
+                            Action::Notify => notify(&msg),
+                        }
+                    }
+                }
+            }
+        }
+
+        self.show_flash(ctx, now);
+    }
+
+    fn show_flash(&mut self, ctx: &egui::Context, now: Instant) {
+        let elapsed = match self.flash_start {
+            Some(start) => now.duration_since(start),
+            None => return,
+        };
+
+        if elapsed >= FLASH_DURATION {
+            self.flash_start = None;
+            return;
+        }
+
+        let alpha = 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32();
+        let screen_rect = ctx.input().screen_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("alert-flash"),
+        ));
+        painter.rect_stroke(
+            screen_rect,
+            0.0,
+            egui::Stroke::new(8.0, egui::Color32::YELLOW.linear_multiply(alpha)),
+        );
+
+        ctx.request_repaint();
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Alerts")
+            .open(&mut self.is_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let mut removed = None;
+
+                egui::Grid::new("alert-rules")
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        for (idx, rule) in self.rules.iter_mut().enumerate() {
+                            ui.checkbox(&mut rule.is_enabled, "");
+                            rule.filter.show(ui);
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut rule.count)
+                                        .clamp_range(1..=1_000)
+                                        .prefix("×"),
+                                )
+                                .on_hover_text("Number of occurrences triggering the rule");
+                                ui.add_enabled_ui(rule.count > 1, |ui| {
+                                    let mut secs = rule.window.as_secs_f32();
+                                    let resp = ui.add(
+                                        egui::DragValue::new(&mut secs)
+                                            .clamp_range(0.01..=3_600.0)
+                                            .speed(0.1)
+                                            .prefix("in ")
+                                            .suffix(" s"),
+                                    );
+                                    if resp.changed() {
+                                        rule.window = Duration::from_secs_f32(secs);
+                                        rule.occurrences.clear();
+                                    }
+                                });
+                            });
+
+                            egui::ComboBox::from_id_source(("alert-action", idx))
+                                .selected_text(rule.action.as_str())
+                                .show_ui(ui, |ui| {
+                                    for action in Action::ALL {
+                                        ui.selectable_value(
+                                            &mut rule.action,
+                                            *action,
+                                            action.as_str(),
+                                        );
+                                    }
+                                });
+                            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                removed = Some(idx);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(idx) = removed {
+                    self.rules.remove(idx);
+                }
+
+                if ui.button("Add rule").clicked() {
+                    self.rules.push(Rule::new(Filter::default(), Action::Flash));
+                }
+            });
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        let rules_str = self
+            .rules
+            .iter()
+            .filter(|rule| rule.filter.is_active())
+            .map(|rule| {
+                format!(
+                    "{}\t{}\t{}\t{}",
+                    rule.action.storage_str(),
+                    rule.count,
+                    rule.window.as_millis(),
+                    rule.filter.text(),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        storage.set_string(STORAGE_ALERT_RULES, rules_str);
+    }
+}
+
+#[cfg(feature = "notify")]
+fn notify(msg: &MsgParseResult) {
+    let res = notify_rust::Notification::new()
+        .summary("MIDI Sniffer alert")
+        .body(&format!("{}: {}", msg.port_nb(), msg.parsed_str()))
+        .show();
+
+    if let Err(err) = res {
+        log::error!("Couldn't show notification: {err}");
+    }
+}
@@ -0,0 +1,99 @@
+use eframe::egui;
+
+use super::msg_list::MsgParseResult;
+use crate::midi::anatomy;
+
+#[derive(Default)]
+pub struct AnatomyPanel {
+    pub is_open: bool,
+    is_teaching: bool,
+    step: usize,
+    /// Bytes of the message being taught, used to restart on selection change.
+    taught: Vec<u8>,
+}
+
+impl AnatomyPanel {
+    pub fn show(&mut self, ctx: &egui::Context, selected: Option<&MsgParseResult>) {
+        let mut is_open = self.is_open;
+        egui::Window::new("Message anatomy")
+            .open(&mut is_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let msg = match selected {
+                    Some(msg) if !msg.raw().is_empty() => msg,
+                    _ => {
+                        ui.label("Select a message in the list");
+                        return;
+                    }
+                };
+
+                ui.label(msg.parsed_str());
+                ui.checkbox(&mut self.is_teaching, "Teaching mode");
+                ui.separator();
+
+                let cur_byte_idx = if self.is_teaching {
+                    self.show_step(ui, msg.raw())
+                } else {
+                    None
+                };
+
+                egui::Grid::new("anatomy")
+                    .striped(true)
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        for header in ["Hex", "Bits", "Layout", "Field", "Meaning"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+
+                        for (idx, field) in anatomy::describe(msg.raw()).into_iter().enumerate() {
+                            let color = if Some(idx) == cur_byte_idx {
+                                ui.visuals().strong_text_color()
+                            } else {
+                                ui.visuals().text_color()
+                            };
+
+                            ui.colored_label(
+                                color,
+                                egui::RichText::new(format!("{:02x}", field.byte)).monospace(),
+                            );
+                            ui.colored_label(color, egui::RichText::new(field.bits()).monospace());
+                            ui.colored_label(color, egui::RichText::new(field.layout).monospace());
+                            ui.colored_label(color, field.name);
+                            ui.colored_label(color, field.meaning);
+                            ui.end_row();
+                        }
+                    });
+            });
+        self.is_open = is_open;
+    }
+
+    /// Shows the current teaching step and returns the index of the byte it applies to.
+    fn show_step(&mut self, ui: &mut egui::Ui, raw: &[u8]) -> Option<usize> {
+        if self.taught != raw {
+            self.taught = raw.to_vec();
+            self.step = 0;
+        }
+
+        let steps = anatomy::steps(raw);
+        let step = steps.get(self.step)?;
+
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.step > 0, |ui| {
+                if ui.button("◀ Previous").clicked() {
+                    self.step -= 1;
+                }
+            });
+            ui.label(format!("{} / {}", self.step + 1, steps.len()));
+            ui.add_enabled_ui(self.step + 1 < steps.len(), |ui| {
+                if ui.button("Next ▶").clicked() {
+                    self.step += 1;
+                }
+            });
+        });
+        ui.label(step.explanation.as_str());
+        ui.separator();
+
+        Some(step.byte_idx)
+    }
+}
@@ -1,106 +1,487 @@
 use crossbeam_channel as channel;
 use eframe::{self, egui};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use super::{controller, Dispatcher};
-use crate::midi;
+use super::{bus, controller, Dispatch, Dispatcher};
+#[cfg(feature = "net")]
+use crate::net;
+use crate::{cli, midi, time::Instant};
 
-pub enum Request {
-    Connect((midi::PortNb, Arc<str>)),
-    Disconnect(midi::PortNb),
-    RefreshPorts,
-    Shutdown,
-}
+pub use crate::controller::Request;
+
+const STORAGE_MSG_LIST_DETACHED: &str = "msg_list_detached";
 
 pub struct App {
-    msg_list_panel: Arc<Mutex<super::MsgListPanel>>,
+    msg_list_panel: super::MsgListPanel,
+    /// Shows the message list in a floating panel instead of the central panel.
+    msg_list_detached: bool,
+    activity_panel: super::ActivityPanel,
+    alerts_panel: super::AlertsPanel,
+    anatomy_panel: super::AnatomyPanel,
+    #[cfg(feature = "save")]
+    autolog_panel: super::AutoLogPanel,
+    bend_panel: super::BendPanel,
+    bookmarks_panel: super::BookmarksPanel,
+    #[cfg(feature = "save")]
+    diff_panel: super::DiffPanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    fuzz_panel: super::FuzzPanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    generator_panel: super::GeneratorPanel,
+    inspector_panel: super::InspectorPanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    latency_panel: super::LatencyPanel,
+    learn_panel: super::LearnPanel,
     req_tx: channel::Sender<Request>,
-    err_rx: channel::Receiver<anyhow::Error>,
-    ports_panel: Arc<Mutex<super::PortsPanel>>,
-    last_err: Option<anyhow::Error>,
+    err_rx: channel::Receiver<bus::Event>,
+    backend_rx: channel::Receiver<bus::Event>,
+    /// Why the Midi backend is unavailable, if it is.
+    backend_err: Option<Arc<anyhow::Error>>,
+    throughput_rx: channel::Receiver<bus::Event>,
+    throughput: midi::throughput::Rates,
+    piano_panel: super::PianoPanel,
+    ports_panel: super::PortsPanel,
+    #[cfg(feature = "save")]
+    replay_panel: super::ReplayPanel,
+    settings_panel: super::SettingsPanel,
+    #[cfg(feature = "config")]
+    config: crate::config::Config,
+    /// Name of the last applied configuration profile.
+    #[cfg(feature = "config")]
+    cur_profile: Option<String>,
+    stuck_notes_panel: super::StuckNotesPanel,
+    templates_panel: super::TemplatesPanel,
+    velocity_panel: super::VelocityPanel,
+    toasts: super::toast::Toasts,
+    dispatcher: Dispatcher,
+    app_name: Arc<str>,
+    session_start: Instant,
+    title: String,
+    #[cfg(not(target_arch = "wasm32"))]
     controller_thread: Option<std::thread::JoinHandle<()>>,
+    #[cfg(target_arch = "wasm32")]
+    controller: Option<controller::Local>,
+    #[cfg(feature = "net")]
+    _net: Option<net::Runtime>,
+    #[cfg(feature = "http")]
+    http_query_rx: channel::Receiver<net::http::Query>,
 }
 
 impl App {
-    pub fn new(client_name: &str, cc: &eframe::CreationContext) -> Self {
-        cc.egui_ctx.set_visuals(egui::Visuals::dark());
-
-        let (err_tx, err_rx) = channel::unbounded();
+    pub fn new(client_name: &str, args: cli::Args, cc: &eframe::CreationContext) -> Self {
+        let bus = bus::Bus::default();
         let (req_tx, req_rx) = channel::unbounded();
 
-        let ports_panel = Arc::new(Mutex::new(super::PortsPanel::default()));
-        let msg_list_panel = Arc::new(Mutex::new(super::MsgListPanel::new(err_tx.clone(), cc)));
+        // Subscribe before spawning the Controller so as to get all the events.
+        let err_rx = bus.subscribe(bus::Event::is_error);
+        let backend_rx = bus.subscribe(bus::Event::is_backend_unavailable);
+        let throughput_rx = bus.subscribe(bus::Event::is_throughput);
+        #[cfg(feature = "config")]
+        let config = crate::config::Config::load().unwrap_or_else(|err| {
+            bus.publish_err(err.into());
+            Default::default()
+        });
+        let ports_panel = super::PortsPanel::new(&bus, cc.storage);
+        let mut settings_panel = super::SettingsPanel::new(cc.storage);
+        if let Some(backend) = args.backend {
+            settings_panel.backend = backend;
+        }
+        cc.egui_ctx.set_visuals(settings_panel.visuals());
+        let msg_list_panel = super::MsgListPanel::new(
+            &bus,
+            settings_panel.batching.clone(),
+            settings_panel.backpressure.clone(),
+            cc.storage,
+        );
+        let msg_list_detached = cc
+            .storage
+            .and_then(|storage| storage.get_string(STORAGE_MSG_LIST_DETACHED))
+            .map_or(false, |detached| detached == "true");
+        let alerts_panel = super::AlertsPanel::new(&bus, cc.storage);
+        let stuck_notes_panel = super::StuckNotesPanel::new(&bus, cc.storage);
+        let learn_panel = super::LearnPanel::new(&bus);
+        let bend_panel = super::BendPanel::new(&bus);
+        let velocity_panel = super::VelocityPanel::new(&bus);
+        let piano_panel = super::PianoPanel::new(&bus);
+        let activity_panel = super::ActivityPanel::new(&bus);
+        #[cfg(not(target_arch = "wasm32"))]
+        let fuzz_panel = super::FuzzPanel::new(&bus, Arc::from(client_name), cc.storage);
+        #[cfg(not(target_arch = "wasm32"))]
+        let generator_panel = super::GeneratorPanel::new(&bus, Arc::from(client_name), cc.storage);
+        #[cfg(not(target_arch = "wasm32"))]
+        let latency_panel = super::LatencyPanel::new(&bus, Arc::from(client_name), cc.storage);
+        #[cfg(feature = "save")]
+        let replay_panel = super::ReplayPanel::new(&bus, Arc::from(client_name), cc.storage);
+        #[cfg(feature = "save")]
+        let autolog_panel = super::AutoLogPanel::new(&bus, cc.storage);
+        #[cfg(feature = "save")]
+        let diff_panel = super::DiffPanel::new(&bus);
+        #[cfg(feature = "http")]
+        let (http_query_tx, http_query_rx) = channel::unbounded();
+        #[cfg(feature = "net")]
+        let net = spawn_net(
+            &args,
+            &bus,
+            &req_tx,
+            &cc.egui_ctx,
+            #[cfg(feature = "http")]
+            http_query_tx,
+        );
 
-        let controller_thread = controller::Spawner {
+        let spawner = controller::Spawner {
             req_rx,
-            err_tx,
-            msg_list_panel: msg_list_panel.clone(),
+            bus,
             client_name: Arc::from(client_name),
-            ports_panel: ports_panel.clone(),
-            egui_ctx: cc.egui_ctx.clone(),
-        }
-        .spawn();
+            backend: settings_panel.backend,
+            #[cfg(feature = "agent")]
+            agents: args.remotes.clone(),
+            backpressure: settings_panel.backpressure.clone(),
+            batching: settings_panel.batching.clone(),
+            notifier: {
+                let egui_ctx = cc.egui_ctx.clone();
+                Arc::new(move || egui_ctx.request_repaint())
+            },
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let controller_thread = spawner.spawn();
+        #[cfg(target_arch = "wasm32")]
+        let controller = spawner.start_local();
 
         let mut this = Self {
             msg_list_panel,
+            msg_list_detached,
+            alerts_panel,
+            anatomy_panel: super::AnatomyPanel::default(),
+            activity_panel,
+            #[cfg(feature = "save")]
+            autolog_panel,
+            bend_panel,
+            bookmarks_panel: super::BookmarksPanel::default(),
+            #[cfg(feature = "save")]
+            diff_panel,
+            #[cfg(not(target_arch = "wasm32"))]
+            fuzz_panel,
+            #[cfg(not(target_arch = "wasm32"))]
+            generator_panel,
+            inspector_panel: super::InspectorPanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            latency_panel,
+            learn_panel,
             req_tx,
             err_rx,
+            backend_rx,
+            backend_err: None,
+            throughput_rx,
+            throughput: Default::default(),
+            piano_panel,
             ports_panel,
-            last_err: None,
+            #[cfg(feature = "save")]
+            replay_panel,
+            settings_panel,
+            #[cfg(feature = "config")]
+            config,
+            #[cfg(feature = "config")]
+            cur_profile: None,
+            stuck_notes_panel,
+            templates_panel: super::TemplatesPanel::default(),
+            velocity_panel,
+            toasts: super::toast::Toasts::default(),
+            dispatcher: Dispatcher::default(),
+            app_name: Arc::from(client_name),
+            session_start: Instant::now(),
+            title: client_name.to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
             controller_thread: Some(controller_thread),
+            #[cfg(target_arch = "wasm32")]
+            controller,
+            #[cfg(feature = "net")]
+            _net: net,
+            #[cfg(feature = "http")]
+            http_query_rx,
         };
 
-        for evt in super::PortsPanel::setup(cc.storage) {
-            Dispatcher::<super::PortsPanel>::handle(&mut this, Some(evt));
+        for resp in super::PortsPanel::setup(cc.storage) {
+            super::PortsPanel::dispatch(&mut this, resp);
+        }
+
+        for port_nb in [midi::PortNb::One, midi::PortNb::Two] {
+            let protocol = this.ports_panel.surface(port_nb);
+            this.set_surface(port_nb, protocol);
+
+            #[cfg(feature = "profile")]
+            {
+                let profile = this.ports_panel.profile(port_nb).cloned();
+                this.set_profile(port_nb, profile);
+            }
+        }
+
+        #[cfg(feature = "config")]
+        if let Some(name) = args
+            .profile
+            .clone()
+            .or_else(|| this.config.default_profile.clone())
+        {
+            this.apply_profile(&name);
         }
 
         this
     }
 }
 
+/// Spawns the network transports requested by `args`, if any.
+#[cfg(feature = "net")]
+#[allow(unused_variables)]
+fn spawn_net(
+    args: &cli::Args,
+    bus: &bus::Bus,
+    req_tx: &channel::Sender<Request>,
+    egui_ctx: &egui::Context,
+    #[cfg(feature = "http")] http_query_tx: channel::Sender<net::http::Query>,
+) -> Option<net::Runtime> {
+    let is_needed = [
+        #[cfg(feature = "http")]
+        args.http_addr.is_some(),
+        #[cfg(feature = "ws")]
+        args.ws_addr.is_some(),
+    ]
+    .into_iter()
+    .any(|is_needed| is_needed);
+
+    if !is_needed {
+        return None;
+    }
+
+    let rt = net::Runtime::try_new(bus.clone())
+        .map_err(|err| bus.publish_err(anyhow::anyhow!("Failed to start network runtime: {err}")))
+        .ok()?;
+
+    #[cfg(feature = "http")]
+    if let Some(addr) = args.http_addr {
+        net::http::spawn(&rt, addr, req_tx.clone(), http_query_tx, egui_ctx.clone());
+    }
+
+    #[cfg(feature = "ws")]
+    if let Some(addr) = args.ws_addr {
+        net::ws::spawn(&rt, addr);
+    }
+
+    Some(rt)
+}
+
 impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(controller) = self.controller.as_mut() {
+            if controller.poll() {
+                // No threads on the web target: keep polling the Controller
+                ctx.request_repaint();
+            } else {
+                self.controller = None;
+            }
+        }
+
+        self.update_title(frame);
+        self.stuck_notes_panel.update();
+        self.learn_panel.update();
+        self.bend_panel.update();
+        self.velocity_panel.update();
+        self.piano_panel.update();
+        self.activity_panel.update();
+
+        // Don't repaint for the incoming messages if nothing displays them
+        self.settings_panel.batching.set_repaints_on_msgs(
+            !self.msg_list_panel.is_paused()
+                || self.bend_panel.is_open
+                || self.velocity_panel.is_open
+                || self.piano_panel.is_open
+                || self.activity_panel.is_open
+                || self.learn_panel.is_open,
+        );
+        #[cfg(feature = "http")]
+        self.handle_http_queries();
+
         egui::TopBottomPanel::top("top-area").show(ctx, |ui| {
             ui.add_space(10f32);
             ui.heading("MIDI Sniffer");
             ui.add_space(10f32);
+            self.show_backend_banner(ui);
             ui.horizontal(|ui| {
                 use crate::midi::PortNb;
 
-                let resp1 = self.ports_panel.lock().unwrap().show(PortNb::One, ui);
-                let resp2 = self.ports_panel.lock().unwrap().show(PortNb::Two, ui);
+                let resp1 = self.ports_panel.show(PortNb::One, ui);
+                let resp2 = self.ports_panel.show(PortNb::Two, ui);
+
+                self.dispatcher.push::<super::PortsPanel>(resp1.or(resp2));
+                for port_nb in [PortNb::One, PortNb::Two] {
+                    self.msg_list_panel
+                        .set_port_alias(port_nb, self.ports_panel.alias(port_nb));
+                }
 
-                Dispatcher::<super::PortsPanel>::handle(self, resp1.or(resp2));
+                #[cfg(feature = "config")]
+                self.show_config_profiles(ui);
+
+                ui.separator();
+                if ui
+                    .button("Copy summary")
+                    .on_hover_text("Copy statistics as Markdown")
+                    .clicked()
+                {
+                    ui.output().copied_text = super::stats::Summary::new(
+                        self.msg_list_panel.list.iter(),
+                        &self.ports_panel.ports,
+                        self.settings_panel.backpressure.metrics(),
+                    )
+                    .to_markdown();
+                }
+                #[cfg(feature = "save")]
+                if ui
+                    .button("Export report")
+                    .on_hover_text("Save the statistics & the visible messages as an HTML page")
+                    .clicked()
+                {
+                    self.msg_list_panel.export_html(
+                        self.ports_panel.ports.clone(),
+                        self.settings_panel.backpressure.metrics(),
+                        self.settings_panel.port_colors,
+                    );
+                }
+                ui.toggle_value(&mut self.msg_list_panel.trigger.is_open, "Trigger");
+                ui.toggle_value(&mut self.alerts_panel.is_open, "Alerts");
+                ui.toggle_value(&mut self.bookmarks_panel.is_open, "Bookmarks");
+                ui.toggle_value(&mut self.inspector_panel.is_open, "Inspector");
+                ui.toggle_value(&mut self.anatomy_panel.is_open, "Anatomy");
+                ui.toggle_value(&mut self.templates_panel.is_open, "Templates");
+                ui.toggle_value(&mut self.stuck_notes_panel.is_open, "Stuck notes");
+                ui.toggle_value(&mut self.learn_panel.is_open, "MIDI learn");
+                ui.toggle_value(&mut self.bend_panel.is_open, "Pitch bend");
+                ui.toggle_value(&mut self.velocity_panel.is_open, "Velocity");
+                ui.toggle_value(&mut self.piano_panel.is_open, "Keyboard");
+                ui.toggle_value(&mut self.activity_panel.is_open, "Activity");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.toggle_value(&mut self.generator_panel.is_open, "Generator");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.toggle_value(&mut self.fuzz_panel.is_open, "Fuzz");
+                #[cfg(feature = "save")]
+                ui.toggle_value(&mut self.replay_panel.is_open, "Replay");
+                #[cfg(feature = "save")]
+                {
+                    let text = if self.autolog_panel.is_logging() {
+                        egui::RichText::new("⏺ Auto-log").color(egui::Color32::RED)
+                    } else {
+                        egui::RichText::new("Auto-log")
+                    };
+                    ui.toggle_value(&mut self.autolog_panel.is_open, text);
+                }
+                #[cfg(feature = "save")]
+                ui.toggle_value(&mut self.diff_panel.is_open, "Compare");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.toggle_value(&mut self.latency_panel.is_open, "Latency");
+                ui.toggle_value(&mut self.msg_list_detached, "Float list")
+                    .on_hover_text("Show the message list in a floating panel of the main window");
+                ui.toggle_value(&mut self.settings_panel.is_open, "Settings");
+                self.stuck_notes_panel.show_indicator(ui);
             });
             ui.add_space(5f32);
         });
 
         egui::TopBottomPanel::bottom("status-area").show(ctx, |ui| {
-            self.pop_err();
-            if let Some(ref err) = self.last_err {
-                ui.add_space(5f32);
-                let text = egui::RichText::new(err.to_string())
-                    .color(egui::Color32::WHITE)
-                    .background_color(egui::Color32::DARK_RED);
-                ui.group(|ui| {
-                    use egui::Widget;
-                    let label = egui::Label::new(text).sense(egui::Sense::click());
-                    if label.ui(ui).clicked() {
-                        self.clear_last_err();
+            self.show_status_bar(ui);
+        });
+
+        self.pop_errs();
+        self.toasts.show(ctx, self.settings_panel.error_lifetime);
+
+        self.inspector_panel
+            .show(ctx, self.msg_list_panel.selected());
+        self.bend_panel.show(ctx);
+
+        if self.msg_list_detached {
+            let mut is_detached = true;
+            egui::Window::new("Messages")
+                .open(&mut is_detached)
+                .default_size([800.0, 600.0])
+                .resizable(true)
+                .show(ctx, |ui| {
+                    self.msg_list_panel
+                        .show(ui, &self.settings_panel.port_colors);
+                });
+            self.msg_list_detached = is_detached;
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    if ui.button("Dock the message list").clicked() {
+                        self.msg_list_detached = false;
                     }
                 });
-            }
-        });
+            });
+        } else {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                self.msg_list_panel
+                    .show(ui, &self.settings_panel.port_colors);
+            });
+        }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.msg_list_panel.lock().unwrap().show(ui);
-        });
+        self.msg_list_panel.trigger.show(ctx);
+        self.alerts_panel.show(ctx);
+        self.alerts_panel.check(ctx);
+        self.anatomy_panel.show(ctx, self.msg_list_panel.selected());
+        self.bookmarks_panel.show(ctx, &mut self.msg_list_panel);
+        if self
+            .templates_panel
+            .show(ctx, &mut self.msg_list_panel.templates)
+        {
+            self.msg_list_panel.refresh_anomalies();
+        }
+        self.stuck_notes_panel.show(ctx);
+        self.learn_panel.show(ctx);
+        self.velocity_panel.show(ctx);
+        self.piano_panel.show(ctx);
+        self.activity_panel.show(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.generator_panel.show(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.fuzz_panel.show(ctx);
+        #[cfg(feature = "save")]
+        self.replay_panel.show(ctx);
+        #[cfg(feature = "save")]
+        self.autolog_panel.show(ctx);
+        #[cfg(feature = "save")]
+        self.diff_panel.show(ctx, &self.msg_list_panel.list);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.latency_panel.show(ctx);
+        // Also reverts the selection when switching backend failed
+        if let Some(backend) = self.ports_panel.take_backend() {
+            self.settings_panel.backend = backend;
+        }
+        if let Some(backend) = self.settings_panel.show(ctx) {
+            self.send_req(Request::SetBackend(backend));
+        }
+
+        for pending in self.dispatcher.take_pending() {
+            pending(self);
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        self.ports_panel.lock().unwrap().save(storage);
-        self.msg_list_panel.lock().unwrap().save(storage);
-        self.clear_last_err();
+        storage.set_string(
+            STORAGE_MSG_LIST_DETACHED,
+            self.msg_list_detached.to_string(),
+        );
+        self.ports_panel.save(storage);
+        self.msg_list_panel.save(storage);
+        self.alerts_panel.save(storage);
+        self.stuck_notes_panel.save(storage);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.generator_panel.save(storage);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.fuzz_panel.save(storage);
+        #[cfg(feature = "save")]
+        self.replay_panel.save(storage);
+        #[cfg(feature = "save")]
+        self.autolog_panel.save(storage);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.latency_panel.save(storage);
+        self.settings_panel.save(storage);
     }
 
     fn persist_egui_memory(&self) -> bool {
@@ -110,12 +491,21 @@ impl eframe::App for App {
 
     fn on_exit(&mut self, _gl: &eframe::glow::Context) {
         log::info!("Shutting down");
+        #[cfg(feature = "save")]
+        self.msg_list_panel.save_session();
         self.shutdown();
     }
 }
 
 impl App {
     pub fn shutdown(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(mut controller) = self.controller.take() {
+            let _ = self.req_tx.send(Request::Shutdown);
+            controller.poll();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
         if let Some(controller_thread) = self.controller_thread.take() {
             if let Err(err) = self.req_tx.send(Request::Shutdown) {
                 log::error!("Sniffer couldn't request shutdown: {}", err);
@@ -126,18 +516,289 @@ impl App {
     }
 
     pub fn send_req(&mut self, req: Request) {
-        self.req_tx.send(req).unwrap();
+        if let Err(err) = self.req_tx.send(req) {
+            log::error!("Couldn't send request to Controller: {err}");
+            self.toasts
+                .push(Arc::new(anyhow::anyhow!("Controller is not running")));
+        }
+    }
+
+    /// Answers the HTTP API queries for data owned by the UI.
+    #[cfg(feature = "http")]
+    fn handle_http_queries(&self) {
+        use net::http::Query;
+
+        for query in self.http_query_rx.try_iter() {
+            match query {
+                Query::Stats(reply_tx) => {
+                    let summary = super::stats::Summary::new(
+                        self.msg_list_panel.list.iter(),
+                        &self.ports_panel.ports,
+                        self.settings_panel.backpressure.metrics(),
+                    );
+                    let _ = reply_tx.send(summary.to_markdown());
+                }
+                Query::Capture(reply_tx) => {
+                    let mut ron = Vec::new();
+                    match super::msg_list::write_ron(&mut ron, self.msg_list_panel.list.iter()) {
+                        Ok(()) => {
+                            let _ = reply_tx.send(ron);
+                        }
+                        Err(err) => log::error!("Couldn't serialize the capture: {err}"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Displays the session duration and the connected ports in the window title.
+    fn update_title(&mut self, frame: &mut eframe::Frame) {
+        use crate::midi::PortNb;
+        use std::fmt::Write;
+
+        let secs = self.session_start.elapsed().as_secs();
+        let mut title = format!(
+            "{} - {:02}:{:02}:{:02}",
+            self.app_name,
+            secs / 3_600,
+            secs / 60 % 60,
+            secs % 60,
+        );
+        for port_nb in [PortNb::One, PortNb::Two] {
+            let _ = write!(
+                title,
+                " - {}: {}",
+                port_nb.as_char(),
+                self.ports_panel.ports.cur(port_nb)
+            );
+        }
+
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
+        }
+    }
+
+    fn show_backend_banner(&mut self, ui: &mut egui::Ui) {
+        if let Some(bus::Event::BackendUnavailable(err)) = self.backend_rx.try_iter().last() {
+            self.backend_err = err;
+        }
+
+        if let Some(ref err) = self.backend_err {
+            let text = egui::RichText::new(
+                "No Midi backend available, e.g. the ALSA sequencer or the JACK server \
+                 isn't running. Waiting for it to show up…",
+            )
+            .color(egui::Color32::WHITE)
+            .background_color(egui::Color32::DARK_RED);
+            ui.label(text).on_hover_text(format!("{err:#}"));
+            ui.add_space(5f32);
+        }
+    }
+
+    /// Shows the capture summary, the connection state & the throughput of the ports.
+    fn show_status_bar(&mut self, ui: &mut egui::Ui) {
+        use super::msg_list::thousands;
+        use crate::midi::PortNb;
+
+        if let Some(bus::Event::Throughput(rates)) = self.throughput_rx.try_iter().last() {
+            self.throughput = rates;
+        }
+
+        ui.horizontal(|ui| {
+            let list = &self.msg_list_panel;
+            ui.label(format!("{} msgs", thousands(list.msg_count())))
+                .on_hover_text("Messages in the list, including the repetitions");
+            ui.separator();
+
+            let metrics = self.settings_panel.backpressure.metrics();
+            let dropped = format!("{} dropped", thousands(metrics.dropped()));
+            let dropped = if metrics.dropped() > 0 {
+                ui.colored_label(egui::Color32::RED, dropped)
+            } else {
+                ui.label(dropped)
+            };
+            dropped.on_hover_text(format!(
+                "Messages dropped because the capture couldn't keep up\n\
+                 timed out: {}, oldest: {}, newest: {}, summarized: {}, paused overflow: {}",
+                metrics.timed_out,
+                metrics.dropped_oldest,
+                metrics.dropped_newest,
+                metrics.summarized,
+                metrics.paused_overflow,
+            ));
+            ui.separator();
+
+            ui.label(format!("{} hidden", thousands(list.hidden_noise_count())))
+                .on_hover_text("Messages hidden by the Clock, Active sensing & MTC toggles");
+            ui.separator();
+
+            let secs = list.capture_duration().as_secs();
+            ui.label(format!(
+                "{:02}:{:02}:{:02}",
+                secs / 3_600,
+                secs / 60 % 60,
+                secs % 60,
+            ))
+            .on_hover_text(
+                "Wall-clock time between the arrival of the first & the last messages of the list",
+            );
+            ui.separator();
+
+            for port_nb in [PortNb::One, PortNb::Two] {
+                let ports = &self.ports_panel.ports;
+                let color = if ports.connected(port_nb).is_some() {
+                    egui::Color32::GREEN
+                } else {
+                    egui::Color32::GRAY
+                };
+                ui.colored_label(color, "⏺");
+                ui.label(format!("{}: {}", port_nb.as_char(), ports.cur(port_nb)));
+                ui.label(self.throughput[port_nb.idx()].to_string())
+                    .on_hover_text(format!(
+                        "Average over the last {} s",
+                        midi::throughput::WINDOW.as_secs()
+                    ));
+                ui.separator();
+            }
+
+            let msgs_per_sec: f64 = self.throughput.iter().map(|rate| rate.msgs_per_sec).sum();
+            ui.label(format!("{msgs_per_sec:.0} msg/s"))
+                .on_hover_text("Messages per second on both ports");
+        });
+    }
+
+    /// Sends an Identity Request to the output port of the device connected to `port_nb`.
+    ///
+    /// The reply is captured & decoded on `port_nb`.
+    pub fn identify(&mut self, port_nb: midi::PortNb) {
+        let port_name = match self.ports_panel.ports.connected(port_nb) {
+            Some(port_name) => port_name.clone(),
+            None => return,
+        };
+
+        // Devices usually use the same name for their input & output ports.
+        let res = midi::MidiOut::connect(&self.app_name, port_name.clone())
+            .and_then(|mut midi_out| midi_out.send(&midi::identity::REQUEST));
+        if let Err(err) = res {
+            self.toasts.push(Arc::new(anyhow::anyhow!(
+                "Couldn't send Identity Request to {port_name}: {err}"
+            )));
+        }
+    }
+
+    pub fn set_port_pattern(&mut self, port_nb: midi::PortNb, pattern: &str) {
+        if pattern.trim().is_empty() {
+            self.send_req(Request::SetPortPattern((port_nb, None)));
+            return;
+        }
+
+        match midi::PortPattern::parse(pattern) {
+            Ok(pattern) => self.send_req(Request::SetPortPattern((port_nb, Some(pattern)))),
+            Err(err) => self.toasts.push(Arc::new(err.into())),
+        }
+    }
+
+    pub fn set_surface(&mut self, port_nb: midi::PortNb, protocol: midi::surface::Protocol) {
+        self.msg_list_panel.set_surface(port_nb, protocol);
+    }
+
+    #[cfg(feature = "profile")]
+    pub fn set_profile(
+        &mut self,
+        port_nb: midi::PortNb,
+        profile: Option<Arc<midi::profile::Profile>>,
+    ) {
+        self.msg_list_panel.set_profile(port_nb, profile);
+    }
+
+    /// Applies the settings of the configuration profile `name`.
+    ///
+    /// The settings left out of the profile are kept as they are.
+    #[cfg(feature = "config")]
+    pub fn apply_profile(&mut self, name: &str) {
+        let profile = match self.config.profile(name) {
+            Ok(profile) => profile.clone(),
+            Err(err) => {
+                self.toasts.push(Arc::new(err.into()));
+                return;
+            }
+        };
+
+        for port_nb in [midi::PortNb::One, midi::PortNb::Two] {
+            let setup = profile.port(port_nb);
+            if let Some(pattern) = setup.pattern.as_ref() {
+                self.ports_panel.set_pattern(port_nb, pattern.clone());
+                self.set_port_pattern(port_nb, pattern);
+            }
+
+            if let Some(color) = setup.color.as_ref() {
+                match super::settings::color_from_hex(color) {
+                    Some(color) => self.settings_panel.port_colors.set(port_nb, color),
+                    None => {
+                        self.toasts.push(Arc::new(anyhow::anyhow!(
+                            "Invalid color {color} in profile {name}"
+                        )));
+                    }
+                }
+            }
+
+            #[cfg(feature = "profile")]
+            if let Some(device_profile) = setup.device_profile.as_ref() {
+                match self.ports_panel.select_profile(port_nb, device_profile) {
+                    Some(device_profile) => self.set_profile(port_nb, Some(device_profile)),
+                    None => {
+                        self.toasts.push(Arc::new(anyhow::anyhow!(
+                            "Unknown device profile {device_profile} in profile {name}"
+                        )));
+                    }
+                }
+            }
+        }
+
+        if let Some(filter) = profile.filter.as_ref() {
+            self.msg_list_panel.set_filter(filter);
+        }
+        self.msg_list_panel.set_columns(&profile.columns);
+
+        self.cur_profile = Some(name.to_string());
     }
 
-    pub fn clear_last_err(&mut self) {
-        self.last_err = None;
+    #[cfg(feature = "config")]
+    fn show_config_profiles(&mut self, ui: &mut egui::Ui) {
+        if self.config.profiles.is_empty() {
+            return;
+        }
+
+        ui.separator();
+        let mut selected = self.cur_profile.clone();
+        egui::ComboBox::from_id_source("config-profile")
+            .selected_text(selected.as_deref().unwrap_or("No profile"))
+            .show_ui(ui, |ui| {
+                for name in self.config.profiles.keys() {
+                    ui.selectable_value(&mut selected, Some(name.clone()), name);
+                }
+            })
+            .response
+            .on_hover_text("Configuration profile");
+
+        if selected != self.cur_profile {
+            if let Some(name) = selected {
+                self.apply_profile(&name);
+            }
+        }
     }
 
-    fn pop_err(&mut self) {
-        match self.err_rx.try_recv() {
-            Err(channel::TryRecvError::Empty) => (),
-            Ok(err) => self.last_err = Some(err),
-            Err(err) => panic!("{}", err),
+    /// Dismisses the errors which are not pinned.
+    pub fn clear_errs(&mut self) {
+        self.toasts.clear();
+    }
+
+    fn pop_errs(&mut self) {
+        for event in self.err_rx.try_iter() {
+            if let bus::Event::Error(err) = event {
+                self.toasts.push(err);
+            }
         }
     }
 }
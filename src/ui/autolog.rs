@@ -0,0 +1,482 @@
+use anyhow::Context;
+use crossbeam_channel as channel;
+use eframe::{self, egui};
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use super::{bus, msg_list::MsgParseResult};
+use crate::{
+    midi,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+const STORAGE_AUTOLOG_ENABLED: &str = "autolog_enabled";
+const STORAGE_AUTOLOG_DIR: &str = "autolog_dir";
+const STORAGE_AUTOLOG_FORMAT: &str = "autolog_format";
+const STORAGE_AUTOLOG_MAX_SIZE: &str = "autolog_max_size_mb";
+const STORAGE_AUTOLOG_MAX_MINUTES: &str = "autolog_max_minutes";
+
+const DEFAULT_MAX_SIZE_MB: u32 = 100;
+const DEFAULT_MAX_MINUTES: u32 = 60;
+
+const CSV_HEADER: &str = "timestamp_us,wall_time_s,port,raw,parsed,error\n";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Ron,
+    Csv,
+    JsonLines,
+    /// Compressed binary format, see `midi::capture`.
+    Capture,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Ron, Format::Csv, Format::JsonLines, Format::Capture];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Format::Ron => "RON",
+            Format::Csv => "CSV",
+            Format::JsonLines => "JSON Lines",
+            Format::Capture => "Compressed capture",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ron => "ron",
+            Format::Csv => "csv",
+            Format::JsonLines => "jsonl",
+            Format::Capture => midi::capture::EXTENSION,
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        self.extension()
+    }
+
+    pub fn from_storage_str(format: &str) -> Option<Self> {
+        Format::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == format)
+    }
+}
+
+/// The `Format`s which log one line per message.
+#[derive(Clone, Copy, Debug)]
+enum TextFormat {
+    Ron,
+    Csv,
+    JsonLines,
+}
+
+impl TextFormat {
+    /// Formats `msg` as a line of the log.
+    fn line(self, msg: MsgParseResult) -> anyhow::Result<String> {
+        let mut line = match self {
+            TextFormat::Ron => {
+                let mut buf = Vec::new();
+                super::msg_list::write_ron(&mut buf, std::iter::once(&Arc::new(msg)))?;
+                return Ok(String::from_utf8(buf)?);
+            }
+            TextFormat::Csv => {
+                let wall_ts = msg
+                    .ts()
+                    .wall_ts
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                format!(
+                    "{},{}.{:06},{},{},{},{}",
+                    msg.ts().ts,
+                    wall_ts.as_secs(),
+                    wall_ts.subsec_micros(),
+                    msg.port_nb().as_char(),
+                    msg.raw_str(),
+                    super::msg_list::csv_field(msg.parsed_str()),
+                    msg.is_err(),
+                )
+            }
+            TextFormat::JsonLines => serde_json::to_string(&msg)?,
+        };
+        line.push('\n');
+
+        Ok(line)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Settings {
+    dir: PathBuf,
+    format: Format,
+    /// Rotates the file past this size, 0 for never.
+    max_size_mb: u32,
+    /// Rotates the file after this duration, 0 for never.
+    max_minutes: u32,
+}
+
+#[derive(Debug, Default)]
+struct Status {
+    is_running: bool,
+    file_path: Option<PathBuf>,
+    /// Bytes written to the current file.
+    written: u64,
+    files: usize,
+}
+
+enum Output {
+    Text(BufWriter<fs::File>, TextFormat),
+    Capture(midi::capture::Writer<BufWriter<fs::File>>),
+}
+
+impl Output {
+    /// Terminates the log file.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Text(mut file, _) => file.flush(),
+            Output::Capture(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+/// Appends the captured messages to the current log file.
+struct Logger {
+    settings: Settings,
+    status: Arc<Mutex<Status>>,
+    file: Option<Output>,
+    opened_at: Instant,
+}
+
+impl Logger {
+    fn log(&mut self, batch: &[midi::msg::Result]) -> anyhow::Result<()> {
+        if self.must_rotate() {
+            self.open()?;
+        }
+
+        match self.file.as_mut().expect("opened above") {
+            Output::Text(file, format) => {
+                let mut written = 0;
+                for res in batch {
+                    let line = format.line(MsgParseResult::from(res))?;
+                    file.write_all(line.as_bytes())?;
+                    written += line.len() as u64;
+                }
+                // Flush each batch so the log survives a crash.
+                file.flush()?;
+
+                self.status.lock().unwrap().written += written;
+            }
+            Output::Capture(writer) => {
+                for res in batch {
+                    writer.write(midi::msg::origin(res))?;
+                }
+                writer.flush()?;
+
+                // Rotate on the compressed size
+                let written = writer.get_ref().get_ref().metadata()?.len();
+                self.status.lock().unwrap().written = written;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn must_rotate(&self) -> bool {
+        if self.file.is_none() {
+            return true;
+        }
+
+        let max_size = u64::from(self.settings.max_size_mb) * 1_000_000;
+        let max_duration = Duration::from_secs(u64::from(self.settings.max_minutes) * 60);
+
+        (max_size > 0 && self.status.lock().unwrap().written >= max_size)
+            || (!max_duration.is_zero() && self.opened_at.elapsed() >= max_duration)
+    }
+
+    fn open(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.finish()?;
+        }
+
+        let files = self.status.lock().unwrap().files;
+        let file_name = format!(
+            "midi_log_{}_{:03}.{}",
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            files + 1,
+            self.settings.format.extension(),
+        );
+        let file_path = self.settings.dir.join(file_name);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .with_context(|| format!("Couldn't create log file {}", file_path.display()))?;
+        let mut file = BufWriter::new(file);
+
+        let mut written = 0;
+        let file = match self.settings.format {
+            Format::Capture => Output::Capture(midi::capture::Writer::new(file)?),
+            Format::Ron => Output::Text(file, TextFormat::Ron),
+            Format::Csv => {
+                file.write_all(CSV_HEADER.as_bytes())?;
+                written = CSV_HEADER.len() as u64;
+                Output::Text(file, TextFormat::Csv)
+            }
+            Format::JsonLines => Output::Text(file, TextFormat::JsonLines),
+        };
+
+        log::info!("Logging to {}", file_path.display());
+        self.file = Some(file);
+        self.opened_at = Instant::now();
+
+        let mut status = self.status.lock().unwrap();
+        status.file_path = Some(file_path);
+        status.written = written;
+        status.files += 1;
+
+        Ok(())
+    }
+
+    /// Logs the captured messages until `stop_rx` is dropped.
+    fn run(
+        mut self,
+        msg_rx: channel::Receiver<bus::Event>,
+        stop_rx: channel::Receiver<()>,
+        bus: bus::Bus,
+    ) {
+        loop {
+            channel::select! {
+                recv(msg_rx) -> event => match event {
+                    Ok(bus::Event::MsgBatch(batch)) => {
+                        if let Err(err) = self.log(&batch) {
+                            bus.publish_err(err.context("Auto-log stopped"));
+                            break;
+                        }
+                    }
+                    Ok(_) => (),
+                    Err(_) => break,
+                },
+                recv(stop_rx) -> _ => break,
+            }
+        }
+
+        if let Some(file) = self.file.take() {
+            let _ = file.finish();
+        }
+        self.status.lock().unwrap().is_running = false;
+    }
+}
+
+/// Continuously logs the captured messages to rotating files.
+pub struct AutoLogPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    settings: Settings,
+    status: Arc<Mutex<Status>>,
+    /// Stops the logger thread when dropped.
+    stop_tx: Option<channel::Sender<()>>,
+    dir_rx: channel::Receiver<PathBuf>,
+    dir_tx: channel::Sender<PathBuf>,
+}
+
+impl AutoLogPanel {
+    pub fn new(bus: &bus::Bus, storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut settings = Settings {
+            dir: std::env::current_dir().unwrap_or_else(|_| ".".into()),
+            format: Format::Ron,
+            max_size_mb: DEFAULT_MAX_SIZE_MB,
+            max_minutes: DEFAULT_MAX_MINUTES,
+        };
+
+        if let Some(storage) = storage {
+            if let Some(dir) = storage.get_string(STORAGE_AUTOLOG_DIR) {
+                settings.dir = dir.into();
+            }
+            if let Some(format) = storage
+                .get_string(STORAGE_AUTOLOG_FORMAT)
+                .as_deref()
+                .and_then(Format::from_storage_str)
+            {
+                settings.format = format;
+            }
+            if let Some(max_size_mb) = storage
+                .get_string(STORAGE_AUTOLOG_MAX_SIZE)
+                .and_then(|max_size_mb| max_size_mb.parse().ok())
+            {
+                settings.max_size_mb = max_size_mb;
+            }
+            if let Some(max_minutes) = storage
+                .get_string(STORAGE_AUTOLOG_MAX_MINUTES)
+                .and_then(|max_minutes| max_minutes.parse().ok())
+            {
+                settings.max_minutes = max_minutes;
+            }
+        }
+
+        let (dir_tx, dir_rx) = channel::unbounded();
+
+        let mut this = Self {
+            is_open: false,
+            bus: bus.clone(),
+            settings,
+            status: Default::default(),
+            stop_tx: None,
+            dir_rx,
+            dir_tx,
+        };
+
+        // Resume logging so captures don't depend on remembering to start it
+        if storage
+            .and_then(|storage| storage.get_string(STORAGE_AUTOLOG_ENABLED))
+            .map_or(false, |is_enabled| is_enabled == "true")
+        {
+            this.start();
+        }
+
+        this
+    }
+
+    pub fn is_logging(&self) -> bool {
+        self.stop_tx.is_some()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if let Some(dir) = self.dir_rx.try_iter().last() {
+            self.settings.dir = dir;
+        }
+
+        if self.is_logging() && !self.status.lock().unwrap().is_running {
+            // Stopped on error
+            self.stop_tx = None;
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Auto-log")
+            .open(&mut is_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!self.is_logging(), |ui| self.show_settings(ui));
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if self.is_logging() {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.stop();
+                        }
+                    } else if ui.button("⏺ Start").clicked() {
+                        self.start();
+                    }
+                });
+
+                let status = self.status.lock().unwrap();
+                if let Some(ref file_path) = status.file_path {
+                    ui.label(format!("File: {}", file_path.display()));
+                    ui.label(format!(
+                        "{:.1} MB written, {} file(s)",
+                        status.written as f64 / 1_000_000.0,
+                        status.files,
+                    ));
+                }
+            });
+        self.is_open = is_open;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_AUTOLOG_ENABLED, self.is_logging().to_string());
+        storage.set_string(STORAGE_AUTOLOG_DIR, self.settings.dir.display().to_string());
+        storage.set_string(
+            STORAGE_AUTOLOG_FORMAT,
+            self.settings.format.storage_str().to_string(),
+        );
+        storage.set_string(
+            STORAGE_AUTOLOG_MAX_SIZE,
+            self.settings.max_size_mb.to_string(),
+        );
+        storage.set_string(
+            STORAGE_AUTOLOG_MAX_MINUTES,
+            self.settings.max_minutes.to_string(),
+        );
+    }
+}
+
+impl AutoLogPanel {
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("autolog-settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Directory");
+                ui.horizontal(|ui| {
+                    ui.label(self.settings.dir.display().to_string());
+                    if ui.button("…").clicked() {
+                        let dir_tx = self.dir_tx.clone();
+                        let dir = self.settings.dir.clone();
+                        std::thread::spawn(move || {
+                            if let Some(dir) =
+                                rfd::FileDialog::new().set_directory(dir).pick_folder()
+                            {
+                                let _ = dir_tx.send(dir);
+                            }
+                        });
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Format");
+                egui::ComboBox::from_id_source("autolog-format")
+                    .selected_text(self.settings.format.as_str())
+                    .show_ui(ui, |ui| {
+                        for format in Format::ALL {
+                            ui.selectable_value(&mut self.settings.format, format, format.as_str());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Rotate after");
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.max_size_mb)
+                        .clamp_range(0..=100_000)
+                        .suffix(" MB"),
+                )
+                .on_hover_text("0 for no size limit");
+                ui.end_row();
+
+                ui.label("or after");
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.max_minutes)
+                        .clamp_range(0..=7 * 24 * 60)
+                        .suffix(" min"),
+                )
+                .on_hover_text("0 for no time limit");
+                ui.end_row();
+            });
+    }
+
+    fn start(&mut self) {
+        let (stop_tx, stop_rx) = channel::bounded(1);
+        // A previous logger might still be terminating: don't let it update the new status
+        self.status = Arc::new(Mutex::new(Status {
+            is_running: true,
+            ..Default::default()
+        }));
+
+        let logger = Logger {
+            settings: self.settings.clone(),
+            status: self.status.clone(),
+            file: None,
+            opened_at: Instant::now(),
+        };
+        let msg_rx = self.bus.subscribe(bus::Event::is_msg_batch);
+        let bus = self.bus.clone();
+        std::thread::spawn(move || logger.run(msg_rx, stop_rx, bus));
+
+        self.stop_tx = Some(stop_tx);
+    }
+
+    fn stop(&mut self) {
+        // Dropping the sender stops the logger
+        self.stop_tx = None;
+    }
+}
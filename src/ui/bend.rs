@@ -0,0 +1,174 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+use std::collections::{BTreeMap, VecDeque};
+
+use super::bus;
+use crate::{
+    midi::{self, rpn, PortNb},
+    time::SystemTime,
+};
+
+const DEFAULT_WINDOW_SECS: f64 = 10.0;
+const MAX_WINDOW_SECS: f64 = 600.0;
+/// Maximum number of points per curve.
+const MAX_POINTS: usize = 10_000;
+const PLOT_HEIGHT: f32 = 200.0;
+
+/// Points (seconds, value) by port index & channel.
+type Curves = BTreeMap<(usize, u8), VecDeque<[f64; 2]>>;
+
+/// Side panel plotting the pitch bend & channel pressure per channel.
+pub struct BendPanel {
+    pub is_open: bool,
+    msg_rx: channel::Receiver<bus::Event>,
+    rpns: rpn::Tracker,
+    /// Pitch bend sensitivity by port & channel, in cents.
+    bend_ranges: [[u32; 16]; 2],
+    /// Reference for the time axis.
+    start: Option<SystemTime>,
+    /// Pitch bend in semitones.
+    bends: Curves,
+    pressures: Curves,
+    window_secs: f64,
+}
+
+impl BendPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        Self {
+            is_open: false,
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+            rpns: rpn::Tracker::default(),
+            bend_ranges: [[midi::annotator::DEFAULT_BEND_RANGE; 16]; 2],
+            start: None,
+            bends: Curves::new(),
+            pressures: Curves::new(),
+            window_secs: DEFAULT_WINDOW_SECS,
+        }
+    }
+
+    /// Tracks the bend ranges & records the curves.
+    pub fn update(&mut self) {
+        // Cloned so `handle` can borrow `self` mutably
+        let msg_rx = self.msg_rx.clone();
+        bus::drain_msgs(&msg_rx, |origin| {
+            self.handle(origin.port_nb, origin.wall_ts, &origin.buffer)
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.is_open {
+            return;
+        }
+
+        egui::SidePanel::right("bend-plot")
+            .resizable(true)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.heading("Pitch bend");
+                ui.horizontal(|ui| {
+                    ui.label("Window");
+                    ui.add(
+                        egui::DragValue::new(&mut self.window_secs)
+                            .clamp_range(1.0..=MAX_WINDOW_SECS)
+                            .suffix(" s"),
+                    );
+                    if ui.button("Clear").clicked() {
+                        self.clear();
+                    }
+                });
+                ui.separator();
+
+                use egui::plot::{Legend, Line, Plot, Value, Values};
+
+                let line = |(port_idx, channel): &(usize, u8), points: &VecDeque<[f64; 2]>| {
+                    let values = points.iter().map(|&[x, y]| Value::new(x, y)).collect();
+                    Line::new(Values::from_values(values)).name(format!(
+                        "Port {} ch{}",
+                        port_idx + 1,
+                        channel + 1
+                    ))
+                };
+
+                // Show the whole sensitivity range of the active channels.
+                let max_range = self
+                    .bends
+                    .keys()
+                    .map(|&(port_idx, channel)| self.bend_ranges[port_idx][channel as usize])
+                    .max()
+                    .unwrap_or(midi::annotator::DEFAULT_BEND_RANGE);
+                let max_st = f64::from(max_range) / 100.0;
+
+                ui.label(format!("Semitones (±{max_st:.2} st)"));
+                Plot::new("bend-plot")
+                    .height(PLOT_HEIGHT)
+                    .legend(Legend::default())
+                    .include_y(-max_st)
+                    .include_y(max_st)
+                    .show(ui, |plot_ui| {
+                        for (key, points) in self.bends.iter() {
+                            plot_ui.line(line(key, points));
+                        }
+                    });
+
+                ui.label("Channel pressure");
+                Plot::new("pressure-plot")
+                    .height(PLOT_HEIGHT)
+                    .legend(Legend::default())
+                    .include_y(0.0)
+                    .include_y(127.0)
+                    .show(ui, |plot_ui| {
+                        for (key, points) in self.pressures.iter() {
+                            plot_ui.line(line(key, points));
+                        }
+                    });
+            });
+    }
+}
+
+impl BendPanel {
+    fn handle(&mut self, port_nb: PortNb, wall_ts: SystemTime, buf: &[u8]) {
+        if let Some(change) = self.rpns.handle(port_nb, buf) {
+            if change.param == rpn::Param::Registered(rpn::RPN_PITCH_BEND_SENSITIVITY) {
+                self.bend_ranges[port_nb.idx()][change.channel as usize] =
+                    u32::from(change.msb) * 100 + u32::from(change.lsb.unwrap_or(0));
+            }
+        }
+
+        let (curves, channel, value) = match *buf {
+            [status, lsb, msb] if status & 0xf0 == 0xe0 => {
+                let channel = status & 0x0f;
+                let value = (i32::from(msb) << 7 | i32::from(lsb)) - 0x2000;
+                let range = self.bend_ranges[port_nb.idx()][channel as usize];
+                let semitones = f64::from(value) * f64::from(range) / 100.0 / 8192.0;
+
+                (&mut self.bends, channel, semitones)
+            }
+            [status, pressure] if status & 0xf0 == 0xd0 => {
+                (&mut self.pressures, status & 0x0f, f64::from(pressure))
+            }
+            _ => return,
+        };
+
+        let start = *self.start.get_or_insert(wall_ts);
+        let secs = wall_ts
+            .duration_since(start)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        let points = curves.entry((port_nb.idx(), channel)).or_default();
+        points.push_back([secs, value]);
+        while points.len() > MAX_POINTS
+            || points
+                .front()
+                .map_or(false, |&[first, _]| secs - first > self.window_secs)
+        {
+            points.pop_front();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.start = None;
+        self.bends.clear();
+        self.pressures.clear();
+    }
+}
@@ -0,0 +1,63 @@
+use eframe::egui;
+
+use super::MsgListPanel;
+
+/// Lists the bookmarked messages and their notes.
+#[derive(Default)]
+pub struct BookmarksPanel {
+    pub is_open: bool,
+}
+
+impl BookmarksPanel {
+    pub fn show(&mut self, ctx: &egui::Context, msg_list_panel: &mut MsgListPanel) {
+        let bookmarks: Vec<(usize, String, String, String)> = msg_list_panel
+            .bookmarks()
+            .map(|(idx, msg)| {
+                (
+                    idx,
+                    msg.ts_str().to_string(),
+                    msg.parsed_str().to_string(),
+                    msg.note().to_string(),
+                )
+            })
+            .collect();
+
+        egui::Window::new("Bookmarks")
+            .open(&mut self.is_open)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                if bookmarks.is_empty() {
+                    ui.label("Right click a timestamp to bookmark a message");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("bookmarks")
+                        .striped(true)
+                        .num_columns(4)
+                        .show(ui, |ui| {
+                            for (idx, ts_str, parsed_str, mut note) in bookmarks {
+                                if ui.button(ts_str).on_hover_text("Go to message").clicked() {
+                                    msg_list_panel.focus(idx);
+                                }
+                                ui.label(parsed_str);
+                                if ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut note)
+                                            .hint_text("Note")
+                                            .desired_width(160.0),
+                                    )
+                                    .changed()
+                                {
+                                    msg_list_panel.set_note(idx, note);
+                                }
+                                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                    msg_list_panel.toggle_bookmark(idx);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+    }
+}
@@ -0,0 +1,188 @@
+use eframe::egui;
+
+/// Columns of the message list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Timestamp,
+    Delta,
+    Port,
+    /// Only displayed when the list holds merged captures.
+    Source,
+    Repetitions,
+    Channel,
+    Duration,
+    Parsed,
+    Raw,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Timestamp,
+        Column::Delta,
+        Column::Port,
+        Column::Source,
+        Column::Repetitions,
+        Column::Channel,
+        Column::Duration,
+        Column::Parsed,
+        Column::Raw,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Column::Timestamp => "Timestamp",
+            Column::Delta => "Δt",
+            Column::Port => "Port",
+            Column::Source => "Source",
+            Column::Repetitions => "Rep.",
+            Column::Channel => "Ch.",
+            Column::Duration => "Duration",
+            Column::Parsed => "Parsed msg",
+            Column::Raw => "Raw msg",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Column::Timestamp => "timestamp",
+            Column::Delta => "delta",
+            Column::Port => "port",
+            Column::Source => "source",
+            Column::Repetitions => "repetitions",
+            Column::Channel => "channel",
+            Column::Duration => "duration",
+            Column::Parsed => "parsed",
+            Column::Raw => "raw",
+        }
+    }
+
+    pub fn from_storage_str(column: &str) -> Option<Self> {
+        Column::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == column)
+    }
+
+    fn is_visible_by_default(self) -> bool {
+        !matches!(
+            self,
+            Column::Delta | Column::Channel | Column::Duration | Column::Raw
+        )
+    }
+}
+
+/// Order & visibility of the message list columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    columns: Vec<(Column, bool)>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            columns: Column::ALL
+                .into_iter()
+                .map(|column| (column, column.is_visible_by_default()))
+                .collect(),
+        }
+    }
+}
+
+impl Layout {
+    /// Parses a comma separated list of columns, hidden columns start with `-`.
+    ///
+    /// Missing columns are appended with their default visibility.
+    pub fn from_storage_str(layout: &str) -> Self {
+        let mut columns = Vec::<(Column, bool)>::new();
+        for item in layout.split(',') {
+            let item = item.trim();
+            let (name, is_visible) = match item.strip_prefix('-') {
+                Some(name) => (name, false),
+                None => (item, true),
+            };
+            if let Some(column) = Column::from_storage_str(name) {
+                if !columns.iter().any(|(cur, _)| *cur == column) {
+                    columns.push((column, is_visible));
+                }
+            }
+        }
+
+        for column in Column::ALL {
+            if !columns.iter().any(|(cur, _)| *cur == column) {
+                columns.push((column, column.is_visible_by_default()));
+            }
+        }
+
+        Self { columns }
+    }
+
+    pub fn storage_string(&self) -> String {
+        self.columns
+            .iter()
+            .map(|(column, is_visible)| {
+                if *is_visible {
+                    column.storage_str().to_string()
+                } else {
+                    format!("-{}", column.storage_str())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn is_visible(&self, column: Column) -> bool {
+        self.columns
+            .iter()
+            .any(|(cur, is_visible)| *cur == column && *is_visible)
+    }
+
+    pub fn visible_mut(&mut self, column: Column) -> &mut bool {
+        self.columns
+            .iter_mut()
+            .find_map(|(cur, is_visible)| (*cur == column).then_some(is_visible))
+            .expect("all columns in layout")
+    }
+
+    /// Returns the visible columns in display order.
+    pub fn visible(&self) -> impl Iterator<Item = Column> + '_ {
+        self.columns
+            .iter()
+            .filter(|(_, is_visible)| *is_visible)
+            .map(|(column, _)| *column)
+    }
+
+    /// Shows the columns with a checkbox and a handle to drag them around.
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let mut dragged = None;
+        let mut row_rects = Vec::with_capacity(self.columns.len());
+        for (idx, (column, is_visible)) in self.columns.iter_mut().enumerate() {
+            let row = ui.horizontal(|ui| {
+                let handle = ui.label("☰");
+                let handle = ui
+                    .interact(
+                        handle.rect,
+                        egui::Id::new(("column-handle", *column)),
+                        egui::Sense::drag(),
+                    )
+                    .on_hover_text("Drag to reorder");
+                if handle.dragged() {
+                    dragged = Some(idx);
+                }
+                ui.checkbox(is_visible, column.as_str());
+            });
+            row_rects.push(row.response.rect);
+        }
+
+        let (from, pointer) = match (dragged, ui.ctx().pointer_interact_pos()) {
+            (Some(from), Some(pointer)) => (from, pointer),
+            _ => return,
+        };
+        let to = row_rects
+            .iter()
+            .position(|rect| pointer.y < rect.bottom())
+            .unwrap_or(self.columns.len() - 1);
+        if to != from {
+            let column = self.columns.remove(from);
+            self.columns.insert(to, column);
+        }
+    }
+}
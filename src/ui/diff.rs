@@ -0,0 +1,373 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+use egui_extras::{Size, TableBuilder};
+use std::{path::PathBuf, sync::Arc};
+
+use super::{bus, msg_list::MsgParseResult};
+use crate::midi::{
+    self,
+    diff::{self, Kind},
+};
+
+const MISSING_COLOR: egui::Color32 = egui::Color32::from_rgb(0xe0, 0x50, 0x50);
+const EXTRA_COLOR: egui::Color32 = egui::Color32::from_rgb(0x50, 0xc0, 0x50);
+const CHANGED_COLOR: egui::Color32 = egui::Color32::from_rgb(0xe0, 0xb0, 0x30);
+
+const SIDE_NAMES: [&str; 2] = ["Reference", "Compared"];
+
+#[derive(Clone)]
+struct Msg {
+    raw: Arc<[u8]>,
+    raw_str: String,
+    parsed_str: String,
+}
+
+impl Msg {
+    fn is_realtime(&self) -> bool {
+        self.raw.first().map_or(false, |status| *status >= 0xf8)
+    }
+}
+
+impl AsRef<[u8]> for Msg {
+    fn as_ref(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+impl From<&MsgParseResult> for Msg {
+    fn from(msg: &MsgParseResult) -> Self {
+        Msg {
+            raw: msg.raw().into(),
+            raw_str: msg.raw_str().to_string(),
+            parsed_str: msg.parsed_str().to_string(),
+        }
+    }
+}
+
+/// A sequence of messages to compare.
+struct Side {
+    name: String,
+    msgs: Arc<[Msg]>,
+}
+
+impl Side {
+    /// Expands the repetitions of the message list, as when it is saved.
+    fn from_msg_list(list: &[Arc<MsgParseResult>]) -> Self {
+        let mut msgs = Vec::new();
+        for msg in list {
+            // Skip backpressure summaries
+            if msg.raw().is_empty() {
+                continue;
+            }
+
+            let repetitions = msg.repetitions().max(1);
+            msgs.extend(std::iter::repeat(Msg::from(msg.as_ref())).take(repetitions as usize));
+        }
+
+        Side {
+            name: "Message list".to_string(),
+            msgs: msgs.into(),
+        }
+    }
+}
+
+/// Result of the comparison of two sides.
+struct Comparison {
+    left: Vec<Msg>,
+    right: Vec<Msg>,
+    entries: Vec<diff::Entry>,
+    /// Indices of the entries which are not `Kind::Same`.
+    diffs: Vec<usize>,
+    /// Number of entries by `Kind`.
+    counts: [usize; 4],
+}
+
+fn kind_idx(kind: Kind) -> usize {
+    match kind {
+        Kind::Same => 0,
+        Kind::Missing => 1,
+        Kind::Extra => 2,
+        Kind::Changed => 3,
+    }
+}
+
+fn kind_color(kind: Kind) -> Option<egui::Color32> {
+    match kind {
+        Kind::Same => None,
+        Kind::Missing => Some(MISSING_COLOR),
+        Kind::Extra => Some(EXTRA_COLOR),
+        Kind::Changed => Some(CHANGED_COLOR),
+    }
+}
+
+/// Compares two captures or a capture with the message list.
+pub struct DiffPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    sides: [Option<Side>; 2],
+    load_tx: channel::Sender<(usize, Side)>,
+    load_rx: channel::Receiver<(usize, Side)>,
+    /// Compares the messages regardless of the interleaved Timing Clock & co.
+    ignores_realtime: bool,
+    only_diffs: bool,
+    comparison: Option<Comparison>,
+    comparison_tx: channel::Sender<Result<Comparison, diff::TooDifferent>>,
+    comparison_rx: channel::Receiver<Result<Comparison, diff::TooDifferent>>,
+    is_comparing: bool,
+}
+
+impl DiffPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        let (load_tx, load_rx) = channel::unbounded();
+        let (comparison_tx, comparison_rx) = channel::unbounded();
+
+        Self {
+            is_open: false,
+            bus: bus.clone(),
+            sides: [None, None],
+            load_tx,
+            load_rx,
+            ignores_realtime: true,
+            only_diffs: false,
+            comparison: None,
+            comparison_tx,
+            comparison_rx,
+            is_comparing: false,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, msg_list: &[Arc<MsgParseResult>]) {
+        for (side_idx, side) in self.load_rx.try_iter() {
+            self.sides[side_idx] = Some(side);
+            self.comparison = None;
+        }
+        if let Some(res) = self.comparison_rx.try_iter().last() {
+            self.is_comparing = false;
+            match res {
+                Ok(comparison) => self.comparison = Some(comparison),
+                Err(err) => self.bus.publish_err(err.into()),
+            }
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Compare")
+            .open(&mut is_open)
+            .default_width(720.0)
+            .default_height(480.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("diff-sides").show(ui, |ui| {
+                    for side_idx in 0..2 {
+                        ui.label(SIDE_NAMES[side_idx]);
+                        if ui.button("Load…").clicked() {
+                            self.load(side_idx);
+                        }
+                        if ui
+                            .button("Message list")
+                            .on_hover_text("Use the messages currently in the list")
+                            .clicked()
+                        {
+                            self.sides[side_idx] = Some(Side::from_msg_list(msg_list));
+                            self.comparison = None;
+                        }
+                        match &self.sides[side_idx] {
+                            Some(side) => {
+                                ui.label(format!("{} ({} msgs)", side.name, side.msgs.len()))
+                            }
+                            None => ui.weak("None"),
+                        };
+                        ui.end_row();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .checkbox(&mut self.ignores_realtime, "Ignore realtime")
+                        .on_hover_text("Ignore Timing Clock, Active Sensing, ...")
+                        .changed()
+                    {
+                        self.comparison = None;
+                    }
+                    let can_compare = !self.is_comparing && self.sides.iter().all(Option::is_some);
+                    if ui
+                        .add_enabled(can_compare, egui::Button::new("Compare"))
+                        .clicked()
+                    {
+                        self.compare();
+                    }
+                    if self.is_comparing {
+                        ui.label("Comparing…");
+                        ctx.request_repaint();
+                    }
+                });
+                ui.separator();
+
+                if let Some(comparison) = self.comparison.as_ref() {
+                    show_summary(ui, comparison, &mut self.only_diffs);
+                    ui.separator();
+                    show_entries(ui, comparison, self.only_diffs);
+                }
+            });
+        self.is_open = is_open;
+    }
+}
+
+impl DiffPanel {
+    fn load(&self, side_idx: usize) {
+        let bus = self.bus.clone();
+        let load_tx = self.load_tx.clone();
+        std::thread::spawn(move || {
+            let file_path: Option<PathBuf> =
+                super::msg_list::add_open_filters(rfd::FileDialog::new()).pick_file();
+
+            if let Some(file_path) = file_path {
+                match super::msg_list::read_msgs(&file_path) {
+                    Ok(super::msg_list::ReadMsgs { origins, err, .. }) => {
+                        if let Some(err) = err {
+                            bus.publish_err(err);
+                        }
+
+                        let mut parser = midi::msg::Parser::default();
+                        let msgs = origins
+                            .into_iter()
+                            .map(|origin| {
+                                let msg = MsgParseResult::from(&parser.parse(origin));
+                                Msg::from(&msg)
+                            })
+                            .collect();
+                        let name = file_path
+                            .file_name()
+                            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+                        let _ = load_tx.send((side_idx, Side { name, msgs }));
+                    }
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+        });
+    }
+
+    fn compare(&mut self) {
+        let (left, right) = match &self.sides {
+            [Some(left), Some(right)] => (left.msgs.clone(), right.msgs.clone()),
+            _ => return,
+        };
+
+        self.is_comparing = true;
+        self.comparison = None;
+        let ignores_realtime = self.ignores_realtime;
+        let comparison_tx = self.comparison_tx.clone();
+        std::thread::spawn(move || {
+            let keep = |msg: &&Msg| !(ignores_realtime && msg.is_realtime());
+            let left: Vec<Msg> = left.iter().filter(keep).cloned().collect();
+            let right: Vec<Msg> = right.iter().filter(keep).cloned().collect();
+
+            let res = diff::diff(&left, &right).map(|entries| {
+                let mut counts = [0; 4];
+                let mut diffs = Vec::new();
+                for (idx, entry) in entries.iter().enumerate() {
+                    counts[kind_idx(entry.kind)] += 1;
+                    if entry.kind != Kind::Same {
+                        diffs.push(idx);
+                    }
+                }
+
+                Comparison {
+                    left,
+                    right,
+                    entries,
+                    diffs,
+                    counts,
+                }
+            });
+            let _ = comparison_tx.send(res);
+        });
+    }
+}
+
+fn show_summary(ui: &mut egui::Ui, comparison: &Comparison, only_diffs: &mut bool) {
+    ui.horizontal(|ui| {
+        if comparison.diffs.is_empty() {
+            ui.label(format!("Identical: {} msgs", comparison.counts[0]));
+        } else {
+            ui.label(format!(
+                "{} identical",
+                comparison.counts[kind_idx(Kind::Same)]
+            ));
+            for (kind, label) in [
+                (Kind::Missing, "missing"),
+                (Kind::Extra, "extra"),
+                (Kind::Changed, "changed"),
+            ] {
+                ui.colored_label(
+                    kind_color(kind).expect("not Same"),
+                    format!(
+                        "{} {} {}",
+                        kind.as_char(),
+                        comparison.counts[kind_idx(kind)],
+                        label
+                    ),
+                );
+            }
+        }
+        ui.checkbox(only_diffs, "Only differences");
+    });
+}
+
+fn show_entries(ui: &mut egui::Ui, comparison: &Comparison, only_diffs: bool) {
+    let rows = if only_diffs {
+        comparison.diffs.len()
+    } else {
+        comparison.entries.len()
+    };
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .column(Size::exact(16.0))
+        .column(Size::relative(0.5))
+        .column(Size::remainder())
+        .header(20.0, |mut header| {
+            header.col(|_| ());
+            for name in SIDE_NAMES {
+                header.col(|ui| {
+                    ui.label(name);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(18.0, rows, |row_idx, mut row| {
+                let entry_idx = if only_diffs {
+                    comparison.diffs[row_idx]
+                } else {
+                    row_idx
+                };
+                let entry = comparison.entries[entry_idx];
+                let color = kind_color(entry.kind);
+
+                row.col(|ui| {
+                    let text = egui::RichText::new(entry.kind.as_char().to_string()).monospace();
+                    match color {
+                        Some(color) => ui.label(text.color(color)),
+                        None => ui.label(text),
+                    };
+                });
+                row.col(|ui| {
+                    let left = entry.left.map(|idx| (idx, &comparison.left[idx]));
+                    show_msg(ui, left, color);
+                });
+                row.col(|ui| {
+                    let right = entry.right.map(|idx| (idx, &comparison.right[idx]));
+                    show_msg(ui, right, color);
+                });
+            });
+        });
+}
+
+/// Shows the message number & description, along with its raw bytes on hover.
+fn show_msg(ui: &mut egui::Ui, msg: Option<(usize, &Msg)>, color: Option<egui::Color32>) {
+    if let Some((idx, msg)) = msg {
+        let mut text = egui::RichText::new(format!("{:>6} {}", idx + 1, msg.parsed_str));
+        if let Some(color) = color {
+            text = text.color(color);
+        }
+        ui.label(text).on_hover_text(msg.raw_str.as_str());
+    }
+}
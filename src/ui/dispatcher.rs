@@ -1,24 +1,59 @@
 use super::{app, App};
 
-pub struct Dispatcher<T>(std::marker::PhantomData<*const T>);
+/// Routes the `Response`s of a panel to `App` requests.
+///
+/// Implement this trait to register a new panel type with the `Dispatcher`.
+pub trait Dispatch {
+    type Response: 'static;
 
-impl Dispatcher<super::PortsPanel> {
-    pub fn handle(app: &mut App, resp: Option<super::port::Response>) {
+    fn dispatch(app: &mut App, resp: Self::Response);
+}
+
+pub type Pending = Box<dyn FnOnce(&mut App)>;
+
+/// Collects panel `Response`s while the UI is drawn
+/// so that they can be dispatched once the panels are released.
+#[derive(Default)]
+pub struct Dispatcher {
+    pending: Vec<Pending>,
+}
+
+impl Dispatcher {
+    pub fn push<P: Dispatch + 'static>(&mut self, resp: Option<P::Response>) {
         if let Some(resp) = resp {
-            use super::port::Response::*;
-
-            app.clear_last_err();
-            app.send_req(app::Request::RefreshPorts);
-
-            match resp {
-                Connect((port_nb, port_name)) => {
-                    app.send_req(app::Request::Connect((port_nb, port_name)));
-                }
-                Disconnect(port_nb) => {
-                    app.send_req(app::Request::Disconnect(port_nb));
-                }
-                CheckingList => (), // only refresh ports & clear last_err
+            self.pending
+                .push(Box::new(move |app: &mut App| P::dispatch(app, resp)));
+        }
+    }
+
+    #[must_use]
+    pub fn take_pending(&mut self) -> Vec<Pending> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+impl Dispatch for super::PortsPanel {
+    type Response = super::port::Response;
+
+    fn dispatch(app: &mut App, resp: Self::Response) {
+        use super::port::Response::*;
+
+        app.clear_errs();
+        app.send_req(app::Request::RefreshPorts);
+
+        match resp {
+            Connect((port_nb, port_name)) => {
+                app.send_req(app::Request::Connect((port_nb, port_name)));
+            }
+            Disconnect(port_nb) => {
+                app.send_req(app::Request::Disconnect(port_nb));
             }
+            CheckingList => (), // only refresh ports & clear the errors
+            Identify(port_nb) => app.identify(port_nb),
+            Pattern((port_nb, pattern)) => app.set_port_pattern(port_nb, &pattern),
+            Surface((port_nb, protocol)) => app.set_surface(port_nb, protocol),
+            #[cfg(feature = "profile")]
+            Profile((port_nb, profile)) => app.set_profile(port_nb, profile),
         }
     }
 }
@@ -0,0 +1,67 @@
+use eframe::egui;
+
+use super::{
+    msg_list::MsgParseResult,
+    query::{self, Query},
+};
+
+/// Filters the messages displayed in the list.
+#[derive(Debug)]
+pub struct Filter {
+    text: String,
+    /// The compiled `text`, `None` if it holds no terms.
+    query: Result<Option<Query>, query::Error>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Filter {
+    pub fn new(text: impl ToString) -> Self {
+        let text = text.to_string();
+        let query = Query::parse(&text);
+
+        Self { text, query }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the filter holds a valid expression.
+    pub fn is_active(&self) -> bool {
+        matches!(self.query, Ok(Some(_)))
+    }
+
+    /// Matches `msg` against the filter expression, see `query`.
+    pub fn matches(&self, msg: &MsgParseResult) -> bool {
+        match self.query {
+            Ok(Some(ref query)) => query.matches(msg),
+            _ => true,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let mut text_edit = egui::TextEdit::singleline(&mut self.text)
+            .hint_text("Filter")
+            .desired_width(120.0);
+        if self.query.is_err() {
+            text_edit = text_edit.text_color(egui::Color32::RED);
+        }
+
+        let resp = ui.add(text_edit);
+        let resp = match self.query {
+            Err(ref err) => resp.on_hover_text(err.to_string()),
+            Ok(_) => resp.on_hover_text(
+                "Text or terms such as port:1 chan:10 type:cc cc>=70 note:60-72, \
+                 combined with AND, OR, NOT & parentheses",
+            ),
+        };
+        if resp.changed() {
+            self.query = Query::parse(&self.text);
+        }
+    }
+}
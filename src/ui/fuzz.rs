@@ -0,0 +1,186 @@
+use eframe::{self, egui};
+use std::sync::Arc;
+
+use super::{bus, output::OutputSelector};
+use crate::midi::{
+    fuzz::{Fuzzer, Pattern},
+    replay::{self, Controls, Player},
+};
+
+const STORAGE_FUZZ_OUTPUT: &str = "fuzz_output";
+const STORAGE_FUZZ_RATE: &str = "fuzz_rate";
+
+const MAX_COUNT: u32 = 1_000_000;
+const MAX_RATE: u32 = 10_000;
+
+/// Sends randomized & pathological byte sequences to an output port.
+pub struct FuzzPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    output: OutputSelector,
+    pattern: Pattern,
+    /// Sequences per second.
+    rate: u32,
+    count: u32,
+    seed: u64,
+    controls: Arc<Controls>,
+    player: Option<Player>,
+    /// Number of sequences sent by the current `Player`.
+    len: usize,
+}
+
+impl FuzzPanel {
+    pub fn new(
+        bus: &bus::Bus,
+        client_name: Arc<str>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> Self {
+        let mut output = None;
+        let mut rate = 100;
+        if let Some(storage) = storage {
+            output = storage.get_string(STORAGE_FUZZ_OUTPUT);
+            if let Some(stored) = storage
+                .get_string(STORAGE_FUZZ_RATE)
+                .and_then(|rate| rate.parse().ok())
+                .filter(|rate| (1..=MAX_RATE).contains(rate))
+            {
+                rate = stored;
+            }
+        }
+
+        let controls = Arc::new(Controls::default());
+        // Pathological sequences are likely to be rejected by some backends.
+        controls.set_ignores_send_errors(true);
+
+        Self {
+            is_open: false,
+            bus: bus.clone(),
+            output: OutputSelector::new("fuzz-output", client_name, output),
+            pattern: Pattern::default(),
+            rate,
+            count: 1_000,
+            seed: 1,
+            controls,
+            player: None,
+            len: 0,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.player.as_ref().map_or(false, Player::is_finished) {
+            self.stop();
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Fuzz")
+            .open(&mut is_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(self.player.is_none(), |ui| self.show_settings(ui));
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if self.player.is_some() {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.stop();
+                            return;
+                        }
+
+                        let pos = self.controls.position();
+                        ui.add(
+                            egui::ProgressBar::new(pos as f32 / self.len.max(1) as f32)
+                                .text(format!("{pos} / {}", self.len)),
+                        );
+                        // Keep repainting to update the progress
+                        ui.ctx().request_repaint();
+                    } else if ui
+                        .add_enabled(self.output.selected.is_some(), egui::Button::new("Start"))
+                        .on_hover_text(
+                            "The responses of the device are captured on the connected ports",
+                        )
+                        .clicked()
+                    {
+                        self.start();
+                    }
+                });
+
+                let send_errors = self.controls.send_errors();
+                if send_errors > 0 {
+                    ui.label(format!("{send_errors} sequence(s) rejected by the output"));
+                }
+            });
+        self.is_open = is_open;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_FUZZ_OUTPUT, self.output.storage_string());
+        storage.set_string(STORAGE_FUZZ_RATE, self.rate.to_string());
+    }
+}
+
+impl FuzzPanel {
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("fuzz-settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Output");
+                self.output.show(ui, &self.bus);
+                ui.end_row();
+
+                ui.label("Pattern");
+                egui::ComboBox::from_id_source("fuzz-pattern")
+                    .selected_text(self.pattern.as_str())
+                    .show_ui(ui, |ui| {
+                        for pattern in Pattern::ALL {
+                            ui.selectable_value(&mut self.pattern, pattern, pattern.as_str());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Rate");
+                ui.add(
+                    egui::DragValue::new(&mut self.rate)
+                        .clamp_range(1..=MAX_RATE)
+                        .suffix(" /s"),
+                );
+                ui.end_row();
+
+                ui.label("Count");
+                ui.add(egui::DragValue::new(&mut self.count).clamp_range(1..=MAX_COUNT));
+                ui.end_row();
+
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut self.seed))
+                    .on_hover_text("The same seed produces the same sequences");
+                ui.end_row();
+            });
+    }
+
+    fn start(&mut self) {
+        let midi_out = match self.output.connect(&self.bus) {
+            Some(midi_out) => midi_out,
+            None => return,
+        };
+
+        let interval_us = 1_000_000 / u64::from(self.rate.max(1));
+        let mut fuzzer = Fuzzer::new(self.pattern, self.seed);
+        let events: Arc<[replay::Event]> = (0..u64::from(self.count))
+            .map(|idx| replay::Event {
+                ts: idx * interval_us,
+                buffer: fuzzer.next_chunk().into(),
+            })
+            .collect();
+
+        self.len = events.len();
+        self.player = Some(Player::spawn(midi_out, events, self.controls.clone()));
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            if let Err(err) = player.stop() {
+                self.bus
+                    .publish_err(anyhow::anyhow!("Fuzzing failed: {err}"));
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+#[cfg(feature = "save")]
+use crossbeam_channel as channel;
+use eframe::{self, egui};
+use std::sync::Arc;
+
+use super::{bus, output::OutputSelector};
+use crate::{
+    bytes,
+    midi::{
+        generator::Kind,
+        replay::{self, Controls, Player},
+    },
+};
+
+const STORAGE_GENERATOR_OUTPUT: &str = "generator_output";
+const STORAGE_GENERATOR_KIND: &str = "generator_kind";
+const STORAGE_GENERATOR_CHANNEL: &str = "generator_channel";
+#[cfg(feature = "save")]
+const STORAGE_GENERATOR_SYX_DELAY: &str = "generator_syx_delay";
+
+const MAX_COUNT: u32 = 100_000;
+const MAX_INTERVAL_MS: u32 = 60_000;
+#[cfg(feature = "save")]
+const DEFAULT_SYX_DELAY_MS: u32 = 20;
+
+/// SysEx messages loaded from a `.syx` file.
+#[cfg(feature = "save")]
+struct SyxFile {
+    name: String,
+    msgs: Vec<Arc<[u8]>>,
+}
+
+/// Builds test messages and sends them to an output port.
+pub struct GeneratorPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    output: OutputSelector,
+    kind: Kind,
+    /// 1 based.
+    channel: u8,
+    data1: i16,
+    data2: u8,
+    count: u32,
+    interval_ms: u32,
+    #[cfg(feature = "save")]
+    syx: Option<SyxFile>,
+    /// Delay between two SysEx messages.
+    #[cfg(feature = "save")]
+    syx_delay_ms: u32,
+    #[cfg(feature = "save")]
+    syx_tx: channel::Sender<SyxFile>,
+    #[cfg(feature = "save")]
+    syx_rx: channel::Receiver<SyxFile>,
+    controls: Arc<Controls>,
+    player: Option<Player>,
+    /// Number of messages sent by the current `Player`.
+    len: usize,
+}
+
+impl GeneratorPanel {
+    pub fn new(
+        bus: &bus::Bus,
+        client_name: Arc<str>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> Self {
+        let mut output = None;
+        let mut kind = Kind::default();
+        let mut channel = 1;
+        #[cfg(feature = "save")]
+        let mut syx_delay_ms = DEFAULT_SYX_DELAY_MS;
+        if let Some(storage) = storage {
+            output = storage.get_string(STORAGE_GENERATOR_OUTPUT);
+            if let Some(stored) = storage
+                .get_string(STORAGE_GENERATOR_KIND)
+                .as_deref()
+                .and_then(Kind::from_storage_str)
+            {
+                kind = stored;
+            }
+            if let Some(stored) = storage
+                .get_string(STORAGE_GENERATOR_CHANNEL)
+                .and_then(|channel| channel.parse().ok())
+                .filter(|channel| (1..=16).contains(channel))
+            {
+                channel = stored;
+            }
+            #[cfg(feature = "save")]
+            if let Some(stored) = storage
+                .get_string(STORAGE_GENERATOR_SYX_DELAY)
+                .and_then(|delay| delay.parse().ok())
+            {
+                syx_delay_ms = stored;
+            }
+        }
+
+        #[cfg(feature = "save")]
+        let (syx_tx, syx_rx) = channel::unbounded();
+
+        Self {
+            is_open: false,
+            bus: bus.clone(),
+            output: OutputSelector::new("generator-output", client_name, output),
+            kind,
+            channel,
+            data1: 60,
+            data2: 100,
+            count: 1,
+            interval_ms: 100,
+            #[cfg(feature = "save")]
+            syx: None,
+            #[cfg(feature = "save")]
+            syx_delay_ms,
+            #[cfg(feature = "save")]
+            syx_tx,
+            #[cfg(feature = "save")]
+            syx_rx,
+            controls: Arc::new(Controls::default()),
+            player: None,
+            len: 0,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.player.as_ref().map_or(false, Player::is_finished) {
+            self.stop();
+        }
+        #[cfg(feature = "save")]
+        if let Some(syx) = self.syx_rx.try_iter().last() {
+            self.syx = Some(syx);
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Generator")
+            .open(&mut is_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("generator-settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Output");
+                        self.output.show(ui, &self.bus);
+                        ui.end_row();
+
+                        ui.label("Message");
+                        egui::ComboBox::from_id_source("generator-kind")
+                            .selected_text(self.kind.as_str())
+                            .show_ui(ui, |ui| {
+                                for kind in Kind::ALL {
+                                    ui.selectable_value(&mut self.kind, kind, kind.as_str());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Channel");
+                        ui.add(egui::DragValue::new(&mut self.channel).clamp_range(1..=16));
+                        ui.end_row();
+
+                        let (data1_name, data2_name) = self.kind.data_names();
+                        ui.label(data1_name);
+                        ui.add(
+                            egui::DragValue::new(&mut self.data1)
+                                .clamp_range(self.kind.data1_range()),
+                        );
+                        ui.end_row();
+
+                        if let Some(data2_name) = data2_name {
+                            ui.label(data2_name);
+                            ui.add(egui::DragValue::new(&mut self.data2).clamp_range(0..=127));
+                            ui.end_row();
+                        }
+
+                        ui.label("Count");
+                        ui.add(egui::DragValue::new(&mut self.count).clamp_range(1..=MAX_COUNT));
+                        ui.end_row();
+
+                        ui.label("Interval");
+                        ui.add_enabled(
+                            self.count > 1,
+                            egui::DragValue::new(&mut self.interval_ms)
+                                .clamp_range(0..=MAX_INTERVAL_MS)
+                                .suffix(" ms"),
+                        );
+                        ui.end_row();
+                    });
+
+                let msg = self.msg();
+                ui.label(format!("Raw: {}", bytes::Displayable::from(msg.as_slice())));
+
+                let can_send = self.player.is_none() && self.output.selected.is_some();
+                if ui
+                    .add_enabled(can_send, egui::Button::new("Send"))
+                    .clicked()
+                {
+                    let buffer: Arc<[u8]> = msg.into();
+                    self.send(vec![buffer; self.count as usize], self.interval_ms);
+                }
+
+                #[cfg(feature = "save")]
+                self.show_syx(ui, can_send);
+
+                if self.player.is_some() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.stop();
+                        }
+
+                        let pos = self.controls.position();
+                        ui.add(
+                            egui::ProgressBar::new(pos as f32 / self.len.max(1) as f32)
+                                .text(format!("{pos} / {}", self.len)),
+                        );
+                        // Keep repainting to update the progress
+                        ui.ctx().request_repaint();
+                    });
+                }
+            });
+        self.is_open = is_open;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_GENERATOR_OUTPUT, self.output.storage_string());
+        storage.set_string(STORAGE_GENERATOR_KIND, self.kind.storage_str().to_string());
+        storage.set_string(STORAGE_GENERATOR_CHANNEL, self.channel.to_string());
+        #[cfg(feature = "save")]
+        storage.set_string(STORAGE_GENERATOR_SYX_DELAY, self.syx_delay_ms.to_string());
+    }
+}
+
+impl GeneratorPanel {
+    fn msg(&self) -> Vec<u8> {
+        self.kind.build(self.channel - 1, self.data1, self.data2)
+    }
+
+    /// Sends `msgs` to the selected output, waiting `interval_ms` between two messages.
+    fn send(&mut self, msgs: Vec<Arc<[u8]>>, interval_ms: u32) {
+        let midi_out = match self.output.connect(&self.bus) {
+            Some(midi_out) => midi_out,
+            None => return,
+        };
+
+        let interval_us = u64::from(interval_ms) * 1_000;
+        let events: Arc<[replay::Event]> = msgs
+            .into_iter()
+            .zip(0..)
+            .map(|(buffer, idx)| replay::Event {
+                ts: idx * interval_us,
+                buffer,
+            })
+            .collect();
+
+        self.len = events.len();
+        self.player = Some(Player::spawn(midi_out, events, self.controls.clone()));
+    }
+
+    #[cfg(feature = "save")]
+    fn show_syx(&mut self, ui: &mut egui::Ui, can_send: bool) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.player.is_none(), egui::Button::new("Load SysEx file…"))
+                .clicked()
+            {
+                self.load_syx();
+            }
+            match self.syx {
+                Some(ref syx) => ui.label(format!("{}: {} SysEx", syx.name, syx.msgs.len())),
+                None => ui.label("No SysEx file loaded"),
+            };
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Delay between messages");
+            ui.add(
+                egui::DragValue::new(&mut self.syx_delay_ms)
+                    .clamp_range(0..=MAX_INTERVAL_MS)
+                    .suffix(" ms"),
+            );
+        });
+
+        if ui
+            .add_enabled(
+                can_send && self.syx.is_some(),
+                egui::Button::new("Send SysEx file"),
+            )
+            .on_hover_text("The replies are captured on the connected ports")
+            .clicked()
+        {
+            if let Some(msgs) = self.syx.as_ref().map(|syx| syx.msgs.clone()) {
+                self.send(msgs, self.syx_delay_ms);
+            }
+        }
+    }
+
+    #[cfg(feature = "save")]
+    fn load_syx(&self) {
+        let bus = self.bus.clone();
+        let syx_tx = self.syx_tx.clone();
+        std::thread::spawn(move || {
+            use anyhow::Context;
+
+            let file_path = rfd::FileDialog::new()
+                .add_filter("SysEx (syx)", &["syx"])
+                .pick_file();
+
+            if let Some(file_path) = file_path {
+                let res = std::fs::read(&file_path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|data| Ok(crate::midi::syx::split(&data)?))
+                    .with_context(|| format!("Couldn't load {}", file_path.display()));
+                match res {
+                    Ok(msgs) => {
+                        let name = file_path
+                            .file_name()
+                            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+                        let _ = syx_tx.send(SyxFile { name, msgs });
+                    }
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+        });
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            if let Err(err) = player.stop() {
+                self.bus
+                    .publish_err(anyhow::anyhow!("Generator failed: {err}"));
+            }
+        }
+    }
+}
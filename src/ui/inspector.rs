@@ -0,0 +1,152 @@
+use eframe::egui;
+
+use super::{msg_list::MsgParseResult, timestamp};
+use crate::bytes;
+
+const HEX_DUMP_BYTES_PER_LINE: usize = 16;
+const HEX_DUMP_GROUP_LEN: usize = 8;
+
+/// Side panel with the details of the selected message.
+#[derive(Default)]
+pub struct InspectorPanel {
+    pub is_open: bool,
+}
+
+impl InspectorPanel {
+    pub fn show(&mut self, ctx: &egui::Context, selected: Option<&MsgParseResult>) {
+        if !self.is_open {
+            return;
+        }
+
+        egui::SidePanel::right("inspector")
+            .resizable(true)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.heading("Inspector");
+                ui.separator();
+
+                let msg = match selected {
+                    Some(msg) => msg,
+                    None => {
+                        ui.label("Select a message in the list");
+                        return;
+                    }
+                };
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("inspector-summary")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Port");
+                            ui.label(msg.port_nb().as_str());
+                            ui.end_row();
+
+                            ui.label("Timestamp");
+                            ui.label(msg.ts_str());
+                            ui.end_row();
+
+                            ui.label("Raw timestamp");
+                            ui.label(
+                                msg.ts()
+                                    .format(
+                                        timestamp::Mode::Raw,
+                                        timestamp::Resolution::Micros,
+                                        None,
+                                    )
+                                    .as_str(),
+                            );
+                            ui.end_row();
+
+                            ui.label("Repetitions");
+                            ui.label(msg.repetitions().to_string());
+                            ui.end_row();
+
+                            ui.label("Length");
+                            ui.label(format!("{} byte(s)", msg.raw().len()));
+                            ui.end_row();
+                        });
+
+                    // The row was parsed in the context of the previous messages,
+                    // e.g. running status, so it can't be parsed again on its own.
+                    ui.separator();
+                    ui.strong("Parsed");
+                    if msg.is_err() {
+                        ui.colored_label(egui::Color32::RED, msg.parsed_str());
+                    } else {
+                        ui.monospace(msg.parsed_str());
+                    }
+
+                    ui.separator();
+                    ui.strong("Raw");
+                    ui.label(hex_dump(ui, msg.raw(), msg.err_offset()));
+                });
+            });
+    }
+}
+
+/// Formats `raw` as offset | hex | ASCII lines, revealing the strings embedded in SysEx,
+/// e.g. patch names.
+///
+/// The byte at offset `highlighted` is emphasized, e.g. the cause of a parse error.
+fn hex_dump(ui: &egui::Ui, raw: &[u8], highlighted: Option<usize>) -> egui::text::LayoutJob {
+    use std::fmt::Write;
+
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let normal = egui::TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let highlight = egui::TextFormat {
+        background: egui::Color32::DARK_RED,
+        ..egui::TextFormat::simple(font_id, egui::Color32::WHITE)
+    };
+
+    let hex_width = bytes::Displayable::from(&[0; HEX_DUMP_BYTES_PER_LINE][..])
+        .grouped(HEX_DUMP_GROUP_LEN)
+        .len();
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut text = String::new();
+    for (line_idx, line) in raw.chunks(HEX_DUMP_BYTES_PER_LINE).enumerate() {
+        let line_offset = line_idx * HEX_DUMP_BYTES_PER_LINE;
+        if line_idx > 0 {
+            text.push('\n');
+        }
+        let _ = write!(text, "{line_offset:04x} | ");
+
+        let mut hex_len = 0;
+        for (idx, byte) in line.iter().enumerate() {
+            if idx > 0 {
+                let sep = if idx % HEX_DUMP_GROUP_LEN == 0 {
+                    ",  "
+                } else {
+                    ", "
+                };
+                text.push_str(sep);
+                hex_len += sep.len();
+            }
+
+            let hex = format!("{byte:02x}");
+            hex_len += hex.len();
+            if highlighted == Some(line_offset + idx) {
+                job.append(&text, 0.0, normal.clone());
+                text.clear();
+                job.append(&hex, 0.0, highlight.clone());
+            } else {
+                text.push_str(&hex);
+            }
+        }
+
+        let ascii: String = line
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let _ = write!(text, "{:pad$} | {ascii}", "", pad = hex_width - hex_len);
+    }
+    job.append(&text, 0.0, normal);
+
+    job
+}
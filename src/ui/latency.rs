@@ -0,0 +1,241 @@
+use eframe::{self, egui};
+use std::sync::Arc;
+
+use super::{bus, output::OutputSelector};
+use crate::{
+    midi::{
+        latency::{Results, Settings, Tester},
+        PortNb,
+    },
+    time::Duration,
+};
+
+const STORAGE_LATENCY_OUTPUT: &str = "latency_output";
+
+const MAX_ITERATIONS: u32 = 10_000;
+const HISTOGRAM_BINS: usize = 20;
+
+/// Measures the round-trip latency from an output port to an input port.
+pub struct LatencyPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    output: OutputSelector,
+    port_nb: PortNb,
+    iterations: u32,
+    timeout_ms: u32,
+    interval_ms: u32,
+    tester: Option<Tester>,
+    /// Results of the current or last measurement.
+    results: Results,
+}
+
+impl LatencyPanel {
+    pub fn new(
+        bus: &bus::Bus,
+        client_name: Arc<str>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> Self {
+        let output = storage.and_then(|storage| storage.get_string(STORAGE_LATENCY_OUTPUT));
+
+        Self {
+            is_open: false,
+            bus: bus.clone(),
+            output: OutputSelector::new("latency-output", client_name, output),
+            port_nb: PortNb::One,
+            iterations: 100,
+            timeout_ms: 1_000,
+            interval_ms: 10,
+            tester: None,
+            results: Results::default(),
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if let Some(ref tester) = self.tester {
+            self.results = tester.results();
+            if tester.is_finished() {
+                self.stop();
+            }
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Latency")
+            .open(&mut is_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(self.tester.is_none(), |ui| self.show_settings(ui));
+
+                ui.separator();
+                ui.horizontal(|ui| match self.tester {
+                    Some(ref tester) => {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.stop();
+                            return;
+                        }
+
+                        let iterations = tester.settings().iterations;
+                        let done = self.results.samples.len() as u32 + self.results.lost;
+                        ui.add(
+                            egui::ProgressBar::new(done as f32 / iterations.max(1) as f32)
+                                .text(format!("{done} / {iterations}")),
+                        );
+                        // Keep repainting to update the results
+                        ui.ctx().request_repaint();
+                    }
+                    None => {
+                        if ui
+                            .add_enabled(self.output.selected.is_some(), egui::Button::new("Start"))
+                            .on_hover_text(format!(
+                                "The output must be looped back to {}",
+                                self.port_nb
+                            ))
+                            .clicked()
+                        {
+                            self.start();
+                        }
+                    }
+                });
+
+                self.show_results(ui);
+            });
+        self.is_open = is_open;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_LATENCY_OUTPUT, self.output.storage_string());
+    }
+}
+
+impl LatencyPanel {
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("latency-settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Output");
+                self.output.show(ui, &self.bus);
+                ui.end_row();
+
+                ui.label("Input");
+                egui::ComboBox::from_id_source("latency-input")
+                    .selected_text(self.port_nb.as_str())
+                    .show_ui(ui, |ui| {
+                        for port_nb in [PortNb::One, PortNb::Two] {
+                            ui.selectable_value(&mut self.port_nb, port_nb, port_nb.as_str());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Iterations");
+                ui.add(egui::DragValue::new(&mut self.iterations).clamp_range(1..=MAX_ITERATIONS));
+                ui.end_row();
+
+                ui.label("Timeout");
+                ui.add(
+                    egui::DragValue::new(&mut self.timeout_ms)
+                        .clamp_range(1..=10_000)
+                        .suffix(" ms"),
+                );
+                ui.end_row();
+
+                ui.label("Interval");
+                ui.add(
+                    egui::DragValue::new(&mut self.interval_ms)
+                        .clamp_range(0..=10_000)
+                        .suffix(" ms"),
+                );
+                ui.end_row();
+            });
+    }
+
+    fn show_results(&self, ui: &mut egui::Ui) {
+        let stats = match self.results.stats() {
+            Some(stats) => stats,
+            None => {
+                if self.results.lost > 0 {
+                    ui.label(format!("{} marker(s) lost", self.results.lost));
+                }
+                return;
+            }
+        };
+
+        let ms = |duration: Duration| format!("{:.3} ms", duration.as_secs_f64() * 1_000.0);
+        egui::Grid::new("latency-results")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in [
+                    ("Min", ms(stats.min)),
+                    ("Mean", ms(stats.mean)),
+                    ("Median", ms(stats.median)),
+                    ("95th percentile", ms(stats.p95)),
+                    ("Max", ms(stats.max)),
+                    ("Std deviation", ms(stats.std_dev)),
+                    ("Received", self.results.samples.len().to_string()),
+                    ("Lost", self.results.lost.to_string()),
+                ] {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+
+        self.show_histogram(ui, stats.min, stats.max);
+    }
+
+    fn show_histogram(&self, ui: &mut egui::Ui, min: Duration, max: Duration) {
+        use egui::plot::{Bar, BarChart, Plot};
+
+        let min_ms = min.as_secs_f64() * 1_000.0;
+        let width = ((max - min).as_secs_f64() * 1_000.0 / HISTOGRAM_BINS as f64).max(0.001);
+
+        let mut bins = [0u32; HISTOGRAM_BINS];
+        for sample in self.results.samples.iter() {
+            let idx = ((sample.as_secs_f64() * 1_000.0 - min_ms) / width) as usize;
+            bins[idx.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+
+        let bars = bins
+            .iter()
+            .enumerate()
+            .map(|(idx, count)| {
+                Bar::new(min_ms + (idx as f64 + 0.5) * width, f64::from(*count)).width(width)
+            })
+            .collect();
+
+        Plot::new("latency-histogram")
+            .height(120.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars).name("ms"))
+            });
+    }
+
+    fn start(&mut self) {
+        let midi_out = match self.output.connect(&self.bus) {
+            Some(midi_out) => midi_out,
+            None => return,
+        };
+
+        let settings = Settings {
+            port_nb: self.port_nb,
+            iterations: self.iterations,
+            timeout: Duration::from_millis(self.timeout_ms.into()),
+            interval: Duration::from_millis(self.interval_ms.into()),
+        };
+
+        self.results = Results::default();
+        self.tester = Some(Tester::spawn(midi_out, &self.bus, settings));
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut tester) = self.tester.take() {
+            let res = tester.stop();
+            self.results = tester.results();
+            if let Err(err) = res {
+                self.bus
+                    .publish_err(anyhow::anyhow!("Latency measurement failed: {err}"));
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+
+use super::bus;
+use crate::{
+    midi::learn::Learner,
+    time::{Duration, Instant},
+};
+
+/// Duration of the highlight of the last moved control.
+const HIGHLIGHT: Duration = Duration::from_millis(500);
+
+/// Maps the controls of a device as they are moved one at a time.
+pub struct LearnPanel {
+    pub is_open: bool,
+    is_learning: bool,
+    learner: Learner,
+    /// Last moved control.
+    last: Option<(usize, Instant)>,
+    msg_rx: channel::Receiver<bus::Event>,
+    #[cfg_attr(not(feature = "save"), allow(dead_code))]
+    bus: bus::Bus,
+}
+
+impl LearnPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        Self {
+            is_open: false,
+            is_learning: false,
+            learner: Learner::default(),
+            last: None,
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+            bus: bus.clone(),
+        }
+    }
+
+    /// Records the controls moved while learning.
+    pub fn update(&mut self) {
+        if !self.is_learning {
+            self.msg_rx.try_iter().for_each(drop);
+            return;
+        }
+
+        bus::drain_msgs(&self.msg_rx, |origin| {
+            if let Some(idx) = self.learner.handle(origin.port_nb, &origin.buffer) {
+                self.last = Some((idx, Instant::now()));
+            }
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.is_open;
+        egui::Window::new("MIDI learn")
+            .open(&mut is_open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let text = if self.is_learning {
+                        "⏸ Pause"
+                    } else {
+                        "⏺ Learn"
+                    };
+                    if ui
+                        .button(text)
+                        .on_hover_text("Move one control at a time to map it")
+                        .clicked()
+                    {
+                        self.is_learning = !self.is_learning;
+                    }
+
+                    if ui
+                        .add_enabled(
+                            !self.learner.mappings.is_empty(),
+                            egui::Button::new("Clear"),
+                        )
+                        .clicked()
+                    {
+                        self.learner.clear();
+                        self.last = None;
+                    }
+
+                    #[cfg(feature = "save")]
+                    if ui
+                        .add_enabled(
+                            !self.learner.mappings.is_empty(),
+                            egui::Button::new("Export JSON…"),
+                        )
+                        .clicked()
+                    {
+                        self.export();
+                    }
+                });
+                ui.separator();
+
+                if self.learner.mappings.is_empty() {
+                    ui.label("No controls mapped");
+                    return;
+                }
+
+                self.show_mappings(ui);
+            });
+        self.is_open = is_open;
+    }
+}
+
+impl LearnPanel {
+    fn show_mappings(&mut self, ui: &mut egui::Ui) {
+        let highlighted = match self.last {
+            Some((idx, at)) if at.elapsed() < HIGHLIGHT => {
+                // Keep repainting until the highlight is off
+                ui.ctx().request_repaint();
+                Some(idx)
+            }
+            _ => None,
+        };
+
+        let mut removed = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("learn-mappings")
+                .striped(true)
+                .num_columns(6)
+                .show(ui, |ui| {
+                    ui.strong("Name");
+                    ui.strong("Port");
+                    ui.strong("Channel");
+                    ui.strong("Control");
+                    ui.strong("Range");
+                    // Remove buttons
+                    ui.label("");
+                    ui.end_row();
+
+                    for (idx, mapping) in self.learner.mappings.iter_mut().enumerate() {
+                        ui.add(egui::TextEdit::singleline(&mut mapping.name).desired_width(100.0));
+                        ui.label(mapping.port.as_str());
+                        ui.label(mapping.channel.to_string());
+                        let control = egui::RichText::new(mapping.control.to_string());
+                        if highlighted == Some(idx) {
+                            ui.label(control.color(egui::Color32::GREEN));
+                        } else {
+                            ui.label(control);
+                        }
+                        ui.label(format!("{}..{}", mapping.min, mapping.max));
+                        if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                            removed = Some(idx);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(idx) = removed {
+            self.learner.mappings.remove(idx);
+            self.last = None;
+        }
+    }
+
+    #[cfg(feature = "save")]
+    fn export(&self) {
+        let bus = self.bus.clone();
+        let mappings = self.learner.mappings.clone();
+        std::thread::spawn(move || {
+            use anyhow::Context;
+
+            let file_path = rfd::FileDialog::new()
+                .add_filter("JSON", &["json"])
+                .set_file_name("midi_mapping.json")
+                .save_file();
+
+            if let Some(file_path) = file_path {
+                let res = serde_json::to_string_pretty(&mappings)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| Ok(std::fs::write(&file_path, json)?))
+                    .with_context(|| format!("Couldn't export to {}", file_path.display()));
+                match res {
+                    Ok(()) => log::debug!("Exported the mapping to {}", file_path.display()),
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+        });
+    }
+}
@@ -1,13 +1,102 @@
+/// Name under which the app data are stored.
+pub const APP_ID: &str = "midi-sniffer";
+
+pub mod activity;
+pub use activity::ActivityPanel;
+
+pub mod alert;
+pub use alert::AlertsPanel;
+
+pub mod anatomy;
+pub use anatomy::AnatomyPanel;
+
 pub mod app;
 pub use app::App;
 
-pub mod controller;
+#[cfg(feature = "save")]
+pub mod autolog;
+#[cfg(feature = "save")]
+pub use autolog::AutoLogPanel;
+
+pub mod bend;
+pub use bend::BendPanel;
+
+pub mod bookmarks;
+pub use bookmarks::BookmarksPanel;
+
+pub use crate::{bus, controller};
+
+pub mod column;
+
+#[cfg(feature = "save")]
+pub mod diff;
+#[cfg(feature = "save")]
+pub use diff::DiffPanel;
 
 pub mod dispatcher;
-pub use dispatcher::Dispatcher;
+pub use dispatcher::{Dispatch, Dispatcher};
+
+pub mod filter;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fuzz;
+#[cfg(not(target_arch = "wasm32"))]
+pub use fuzz::FuzzPanel;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod generator;
+#[cfg(not(target_arch = "wasm32"))]
+pub use generator::GeneratorPanel;
+
+pub mod inspector;
+pub use inspector::InspectorPanel;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod latency;
+#[cfg(not(target_arch = "wasm32"))]
+pub use latency::LatencyPanel;
+
+pub mod learn;
+pub use learn::LearnPanel;
 
 pub mod msg_list;
 pub use msg_list::MsgListPanel;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod output;
+
+pub mod piano;
+pub use piano::PianoPanel;
+
 pub mod port;
 pub use port::PortsPanel;
+
+pub mod query;
+
+#[cfg(feature = "save")]
+pub mod replay;
+#[cfg(feature = "save")]
+pub use replay::ReplayPanel;
+
+#[cfg(feature = "save")]
+pub mod report;
+
+pub mod settings;
+pub use settings::SettingsPanel;
+
+pub mod stats;
+
+pub mod stuck_notes;
+pub use stuck_notes::StuckNotesPanel;
+
+pub mod template;
+pub use template::TemplatesPanel;
+
+pub mod timestamp;
+
+pub mod toast;
+
+pub mod trigger;
+
+pub mod velocity;
+pub use velocity::VelocityPanel;
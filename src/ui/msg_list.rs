@@ -1,45 +1,172 @@
 use crossbeam_channel as channel;
 use eframe::{self, egui};
 use egui_extras::{Size, TableBuilder};
-use std::{fmt, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    sync::Arc,
+};
 
 #[cfg(feature = "save")]
 use std::{path::PathBuf, sync::Mutex};
 
+use super::{
+    bus,
+    column::{self, Column},
+    filter::Filter,
+    timestamp,
+    trigger::Trigger,
+};
 use crate::{
-    bytes,
-    midi::{self, PortNb},
+    bytes, format,
+    intern::Interner,
+    midi::{
+        self, annotator::Annotator, backpressure, batching, chord, notes, template::Template,
+        PortNb,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-const MAX_REPETITIONS: u8 = 99;
+/// Minimum interval between two viewport updates in steady follow mode.
+const STEADY_FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_REPETITIONS: u32 = 99;
+/// Number of bytes between the gaps of the raw column.
+const RAW_GROUP_LEN: usize = 8;
+/// Number of bytes displayed in the raw column for long messages, e.g. SysEx dumps.
+const RAW_TRUNCATE_LEN: usize = 32;
+const ANOMALY_COLOR: egui::Color32 = egui::Color32::from_rgb(0xa0, 0x50, 0x00);
 const MAX_REPETITIONS_EXCEEDED: &str = ">99";
+/// Maximum number of messages kept pending while the list is paused.
+const MAX_PAUSED_MSGS: usize = 100_000;
+/// Maximum number of rows to look back for a repeated message.
+const MAX_REPETITION_WINDOW: usize = 16;
+const STORAGE_MSG_LIST_COLUMNS: &str = "msg_list_columns";
+// Column visibility before the columns layout
 const STORAGE_MSG_LIST_DISPLAY_PARSED: &str = "msg_list_must_display_parsed";
 const STORAGE_MSG_LIST_DISPLAY_RAW: &str = "msg_list_must_display_raw";
+const STORAGE_MSG_LIST_DISPLAY_DELTA: &str = "msg_list_must_display_delta";
+const STORAGE_MSG_LIST_DISPLAY_DURATION: &str = "msg_list_must_display_duration";
+const STORAGE_MSG_LIST_DETECT_CHORDS: &str = "msg_list_must_detect_chords";
+const STORAGE_MSG_LIST_GM_NAMES: &str = "msg_list_gm_names";
+const STORAGE_MSG_LIST_STEADY_FOLLOW: &str = "msg_list_steady_follow";
+const STORAGE_MSG_LIST_SPLIT_VIEW: &str = "msg_list_split_view";
+const STORAGE_MSG_LIST_HIDDEN_PORTS: &str = "msg_list_hidden_ports";
+const STORAGE_MSG_LIST_HIDDEN_NOISE: &str = "msg_list_hidden_noise";
+const STORAGE_MSG_LIST_ONLY_CHANGES: &str = "msg_list_only_changes";
+const STORAGE_MSG_LIST_REP_WINDOW: &str = "msg_list_rep_window";
+const STORAGE_MSG_LIST_REP_IGNORES_TS: &str = "msg_list_rep_ignores_ts";
+const STORAGE_MSG_LIST_REP_MAX_GAP: &str = "msg_list_rep_max_gap";
+const STORAGE_MSG_LIST_DELTA_MODE: &str = "msg_list_delta_mode";
+const STORAGE_MSG_LIST_TS_MODE: &str = "msg_list_ts_mode";
+const STORAGE_MSG_LIST_TS_RESOLUTION: &str = "msg_list_ts_resolution";
+const STORAGE_MSG_LIST_RAW_RADIX: &str = "msg_list_raw_radix";
+const STORAGE_MSG_LIST_FILTER_A: &str = "msg_list_filter_a";
+const STORAGE_MSG_LIST_FILTER_B: &str = "msg_list_filter_b";
+const STORAGE_MSG_LIST_FILTER_SET: &str = "msg_list_filter_set";
+const STORAGE_PORT_1_TEMPLATE: &str = "port_1_template";
+const STORAGE_PORT_2_TEMPLATE: &str = "port_2_template";
+/// Toggles between filter sets A and B.
+const FILTER_TOGGLE_KEY: egui::Key = egui::Key::F2;
 
 #[cfg(feature = "save")]
 const STORAGE_MSG_LIST_DIR: &str = "msg_list_dir";
+#[cfg(feature = "save")]
+const STORAGE_MSG_LIST_RESTORE_SESSION: &str = "msg_list_restore_session";
+#[cfg(feature = "save")]
+const STORAGE_MSG_LIST_SESSION_MAX_MSGS: &str = "msg_list_session_max_msgs";
+#[cfg(feature = "save")]
+const DEFAULT_SESSION_MAX_MSGS: usize = 10_000;
+#[cfg(feature = "save")]
+const MAX_SESSION_MAX_MSGS: usize = 1_000_000;
+#[cfg(feature = "save")]
+const SESSION_FILE_NAME: &str = "session.msnf";
+/// Loaded messages pushed per update, so large captures don't freeze the UI.
+#[cfg(feature = "save")]
+const LOAD_CHUNK_LEN: usize = 20_000;
 
 #[derive(Clone)]
 #[cfg_attr(feature = "save", derive(serde::Serialize))]
 pub struct MsgParseResult {
+    ts: timestamp::Timestamp,
+
+    /// `ts.wall_ts` in µs since the Unix epoch, so saved captures can be merged.
+    #[cfg_attr(feature = "save", serde(rename = "wall_ts"))]
+    wall_ts_us: u64,
+
     #[cfg_attr(feature = "save", serde(rename = "timestamp"))]
     ts_str: String,
 
+    #[cfg_attr(feature = "save", serde(skip))]
+    delta_global_str: String,
+
+    #[cfg_attr(feature = "save", serde(skip))]
+    delta_port_str: String,
+
     #[cfg_attr(feature = "save", serde(rename = "port"))]
     port_nb: PortNb,
 
-    repetitions: u8,
+    /// Alias of the port at the time the message was received.
+    #[cfg_attr(
+        feature = "save",
+        serde(rename = "alias", skip_serializing_if = "String::is_empty")
+    )]
+    port_alias: String,
+
+    /// Capture file the message was loaded from, when merging captures.
+    #[cfg_attr(feature = "save", serde(skip_serializing_if = "Option::is_none"))]
+    source: Option<Arc<str>>,
+
+    repetitions: u32,
+
+    /// Timestamp of the last repetition (µs).
+    #[cfg_attr(feature = "save", serde(rename = "last_ts"))]
+    last_rep_ts: u64,
+
+    /// A controller or aftertouch message with the same value as the previous one.
+    #[cfg_attr(feature = "save", serde(skip))]
+    is_unchanged: bool,
 
     is_err: bool,
 
+    /// Offset of the byte which caused the parse error, see `midi::msg::Error::offset`.
+    #[cfg_attr(feature = "save", serde(skip))]
+    err_offset: Option<usize>,
+
+    /// Interned, see `MsgListPanel::intern`.
     #[cfg_attr(feature = "save", serde(rename = "parsed"))]
-    parsed_res_str: String,
+    parsed_res_str: Arc<str>,
 
     #[cfg_attr(feature = "save", serde(skip))]
-    raw_str: String,
+    raw_str: Arc<str>,
 
     #[cfg_attr(feature = "save", serde(rename = "raw"))]
     raw: Buffer,
+
+    #[cfg_attr(
+        feature = "save",
+        serde(rename = "bookmark", skip_serializing_if = "std::ops::Not::not")
+    )]
+    is_bookmarked: bool,
+
+    #[cfg_attr(feature = "save", serde(skip_serializing_if = "String::is_empty"))]
+    note: String,
+
+    /// The message is outside the expected traffic template.
+    #[cfg_attr(
+        feature = "save",
+        serde(rename = "anomaly", skip_serializing_if = "std::ops::Not::not")
+    )]
+    is_anomaly: bool,
+
+    /// Duration of the note released by this message.
+    #[cfg_attr(
+        feature = "save",
+        serde(rename = "duration", skip_serializing_if = "String::is_empty")
+    )]
+    duration_str: String,
+
+    /// Analysis results, such as the recognized chord or the MPE role.
+    #[cfg_attr(feature = "save", serde(skip_serializing_if = "Vec::is_empty"))]
+    annotations: Vec<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -71,52 +198,524 @@ impl<'a> serde::Serialize for Buffer {
     }
 }
 
+impl MsgParseResult {
+    pub fn ts(&self) -> timestamp::Timestamp {
+        self.ts
+    }
+
+    pub fn ts_str(&self) -> &str {
+        &self.ts_str
+    }
+
+    pub fn delta_str(&self, mode: DeltaMode) -> &str {
+        match mode {
+            DeltaMode::Global => &self.delta_global_str,
+            DeltaMode::PerPort => &self.delta_port_str,
+        }
+    }
+
+    fn set_deltas(&mut self, delta_global: Option<u64>, delta_port: Option<u64>) {
+        if let Some(delta) = delta_global {
+            self.delta_global_str = format!("+{delta}");
+        }
+        if let Some(delta) = delta_port {
+            self.delta_port_str = format!("+{delta}");
+        }
+    }
+
+    pub fn port_nb(&self) -> PortNb {
+        self.port_nb
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn repetitions(&self) -> u32 {
+        self.repetitions
+    }
+
+    pub fn is_err(&self) -> bool {
+        self.is_err
+    }
+
+    pub fn err_offset(&self) -> Option<usize> {
+        self.err_offset
+    }
+
+    pub fn parsed_str(&self) -> &str {
+        &self.parsed_res_str
+    }
+
+    /// Returns the parsed message followed by its annotations, if any.
+    fn annotated_str(&self) -> String {
+        if self.annotations.is_empty() {
+            self.parsed_res_str.to_string()
+        } else {
+            format!("{}  [{}]", self.parsed_res_str, self.annotations.join(", "))
+        }
+    }
+
+    fn repetitions_str(&self) -> String {
+        if self.repetitions == 1 {
+            String::new()
+        } else if self.repetitions <= MAX_REPETITIONS {
+            format!("x{}", self.repetitions)
+        } else {
+            MAX_REPETITIONS_EXCEEDED.to_string()
+        }
+    }
+
+    /// Returns the channel of channel messages, from 1 to 16.
+    fn channel_str(&self) -> String {
+        match self.raw().first() {
+            Some(status) if (0x80..0xf0).contains(status) => format!("{}", (status & 0x0f) + 1),
+            _ => String::new(),
+        }
+    }
+
+    pub fn raw_str(&self) -> &str {
+        &self.raw_str
+    }
+
+    pub fn raw(&self) -> &[u8] {
+        self.raw.0.as_ref()
+    }
+
+    pub fn duration_str(&self) -> &str {
+        &self.duration_str
+    }
+
+    pub fn annotations(&self) -> &[String] {
+        &self.annotations
+    }
+
+    pub fn is_anomaly(&self) -> bool {
+        self.is_anomaly
+    }
+
+    pub fn is_bookmarked(&self) -> bool {
+        self.is_bookmarked
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    /// Returns the `Origin` of the first repetition.
+    pub fn origin(&self) -> midi::msg::Origin {
+        midi::msg::Origin {
+            ts: self.ts.ts,
+            wall_ts: self.ts.wall_ts,
+            port_nb: self.port_nb,
+            buffer: self.raw.0.clone(),
+        }
+    }
+}
+
+impl MsgParseResult {
+    /// Builds a row reporting messages dropped by the backpressure policy.
+    fn summary(port_nb: PortNb, count: u64) -> Self {
+        let ts = timestamp::Timestamp::new(0, SystemTime::now());
+        let raw = Buffer::from(Arc::<[u8]>::from([]));
+
+        Self {
+            ts,
+            wall_ts_us: ts.wall_ts_us(),
+            ts_str: String::new(),
+            delta_global_str: String::new(),
+            delta_port_str: String::new(),
+            port_nb,
+            port_alias: String::new(),
+            source: None,
+            repetitions: 1,
+            last_rep_ts: 0,
+            is_unchanged: false,
+            parsed_res_str: format!("{count} message(s) dropped (backpressure)").into(),
+            raw_str: "".into(),
+            raw,
+            is_err: true,
+            err_offset: None,
+            is_bookmarked: false,
+            is_anomaly: false,
+            duration_str: String::new(),
+            annotations: Vec::new(),
+            note: String::new(),
+        }
+    }
+}
+
 impl PartialEq<midi::msg::Result> for MsgParseResult {
     fn eq(&self, other: &midi::msg::Result) -> bool {
-        let other_origin = match other {
-            Ok(ok) => &ok.origin,
-            Err(err) => &err.origin,
-        };
+        let other_origin = midi::msg::origin(other);
         self.port_nb == other_origin.port_nb && self.raw == *other_origin.buffer
     }
 }
 
-impl From<midi::msg::Result> for MsgParseResult {
-    fn from(res: midi::msg::Result) -> Self {
+impl From<&midi::msg::Result> for MsgParseResult {
+    fn from(res: &midi::msg::Result) -> Self {
         match res {
             Ok(ok) => {
                 let mut parsed_str = String::new();
-                write_midi_msg(&mut parsed_str, &ok.msg).unwrap();
+                format::write_midi_msg(&mut parsed_str, &ok.msg).unwrap();
 
-                let raw: Buffer = ok.origin.buffer.into();
+                let raw: Buffer = ok.origin.buffer.clone().into();
+                let ts = timestamp::Timestamp::new(ok.origin.ts, ok.origin.wall_ts);
 
                 Self {
+                    ts,
+                    wall_ts_us: ts.wall_ts_us(),
                     ts_str: format!("{}", ok.origin.ts),
+                    delta_global_str: String::new(),
+                    delta_port_str: String::new(),
                     port_nb: ok.origin.port_nb,
+                    port_alias: String::new(),
+                    source: None,
                     repetitions: 1,
-                    parsed_res_str: parsed_str,
-                    raw_str: format!("{}", raw.display()),
+                    last_rep_ts: ok.origin.ts,
+                    is_unchanged: false,
+                    parsed_res_str: parsed_str.into(),
+                    raw_str: raw.display().to_string().into(),
                     raw,
                     is_err: false,
+                    err_offset: None,
+                    is_bookmarked: false,
+                    is_anomaly: false,
+                    duration_str: String::new(),
+                    annotations: Vec::new(),
+                    note: String::new(),
                 }
             }
             Err(err) => {
-                let raw: Buffer = err.origin.buffer.into();
+                let raw: Buffer = err.origin.buffer.clone().into();
+                let ts = timestamp::Timestamp::new(err.origin.ts, err.origin.wall_ts);
 
                 Self {
+                    ts,
+                    wall_ts_us: ts.wall_ts_us(),
                     ts_str: format!("{}", err.origin.ts),
+                    delta_global_str: String::new(),
+                    delta_port_str: String::new(),
                     port_nb: err.origin.port_nb,
+                    port_alias: String::new(),
+                    source: None,
                     repetitions: 1,
-                    parsed_res_str: format!("{}", err.err),
-                    raw_str: format!("{}", raw.display()),
+                    last_rep_ts: err.origin.ts,
+                    is_unchanged: false,
+                    parsed_res_str: err.description().into(),
+                    raw_str: raw.display().to_string().into(),
                     raw,
                     is_err: true,
+                    err_offset: err.offset,
+                    is_bookmarked: false,
+                    is_anomaly: false,
+                    duration_str: String::new(),
+                    annotations: Vec::new(),
+                    note: String::new(),
                 }
             }
         }
     }
 }
 
+/// Realtime messages which dominate most captures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Noise {
+    Clock,
+    ActiveSensing,
+    Mtc,
+}
+
+impl Noise {
+    pub const ALL: [Noise; 3] = [Noise::Clock, Noise::ActiveSensing, Noise::Mtc];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Noise::Clock => "Clock",
+            Noise::ActiveSensing => "Active sensing",
+            Noise::Mtc => "MTC",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Noise::Clock => "clock",
+            Noise::ActiveSensing => "active_sensing",
+            Noise::Mtc => "mtc",
+        }
+    }
+
+    pub fn from_storage_str(val: &str) -> Option<Self> {
+        Noise::ALL
+            .into_iter()
+            .find(|noise| noise.storage_str() == val)
+    }
+
+    fn from_status(status: u8) -> Option<Self> {
+        match status {
+            0xf8 => Some(Noise::Clock),
+            0xfe => Some(Noise::ActiveSensing),
+            0xf1 => Some(Noise::Mtc),
+            _ => None,
+        }
+    }
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+}
+
+/// Reference for the time difference displayed in the delta column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeltaMode {
+    /// Since the previous message, whatever the port.
+    Global,
+    /// Since the previous message on the same port.
+    PerPort,
+}
+
+impl DeltaMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeltaMode::Global => "Global",
+            DeltaMode::PerPort => "Per port",
+        }
+    }
+
+    fn storage_str(self) -> &'static str {
+        match self {
+            DeltaMode::Global => "global",
+            DeltaMode::PerPort => "per_port",
+        }
+    }
+
+    fn from_storage_str(mode: &str) -> Option<Self> {
+        match mode {
+            "global" => Some(DeltaMode::Global),
+            "per_port" => Some(DeltaMode::PerPort),
+            _ => None,
+        }
+    }
+}
+
+/// Messages to save.
+#[cfg(feature = "save")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SaveScope {
+    All,
+    Selection,
+    Filtered,
+}
+
+/// Messages loaded from files, along with their source file when merging captures.
+#[cfg(feature = "save")]
+struct Loaded {
+    description: String,
+    msgs: Vec<(midi::msg::Origin, Option<Arc<str>>)>,
+}
+
+/// A `Loaded` capture being pushed to the list, see `MsgListPanel::pop_loaded`.
+#[cfg(feature = "save")]
+struct Loading {
+    description: String,
+    len: usize,
+    msgs: std::vec::IntoIter<(midi::msg::Origin, Option<Arc<str>>)>,
+    /// Running status & pending SysEx don't carry over from one file to another.
+    parsers: HashMap<Option<Arc<str>>, midi::msg::Parser>,
+    /// The aliases of the connected ports, which don't apply to the loaded messages.
+    port_aliases: [Option<Arc<str>>; 2],
+}
+
+/// Actions requested while showing a table, applied once it is rendered.
+#[derive(Default)]
+struct TableActions {
+    zero_at: Option<SystemTime>,
+    toggle_bookmark: Option<usize>,
+    toggle_raw_expanded: Option<usize>,
+    clicked: Option<usize>,
+}
+
+impl TableActions {
+    fn merge(&mut self, other: TableActions) {
+        self.zero_at = self.zero_at.or(other.zero_at);
+        self.toggle_bookmark = self.toggle_bookmark.or(other.toggle_bookmark);
+        self.toggle_raw_expanded = self.toggle_raw_expanded.or(other.toggle_raw_expanded);
+        self.clicked = self.clicked.or(other.clicked);
+    }
+}
+
+/// Text format of the rows copied to the clipboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CopyFormat {
+    Csv,
+    Markdown,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 2] = [CopyFormat::Csv, CopyFormat::Markdown];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CopyFormat::Csv => "CSV",
+            CopyFormat::Markdown => "Markdown table",
+        }
+    }
+
+    fn row(self, cells: &[String]) -> String {
+        match self {
+            CopyFormat::Csv => {
+                let cells: Vec<String> = cells.iter().map(|cell| csv_field(cell)).collect();
+                format!("{}\n", cells.join(","))
+            }
+            CopyFormat::Markdown => {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+                    .collect();
+                format!("| {} |\n", cells.join(" | "))
+            }
+        }
+    }
+}
+
+/// The messages & display settings of the list, see `MsgListPanel::table_snapshot`.
+struct TableSnapshot {
+    list: Vec<Arc<MsgParseResult>>,
+    displayed_len: usize,
+    columns: Vec<Column>,
+    delta_mode: DeltaMode,
+    raw_radix: bytes::Radix,
+    hidden_ports: [bool; 2],
+    hidden_noise: [bool; 3],
+    only_changes: bool,
+    filter: Filter,
+}
+
+impl TableSnapshot {
+    /// Same as `MsgListPanel::is_visible` for all ports.
+    fn is_visible(&self, msg: &MsgParseResult) -> bool {
+        !self.hidden_ports[msg.port_nb.idx()]
+            && !is_hidden_noise(&self.hidden_noise, msg.raw())
+            && !(self.only_changes && msg.is_unchanged)
+            && self.filter.matches(msg)
+    }
+
+    /// Returns the header & the cells of the visible rows & columns.
+    fn table(&self) -> (Vec<String>, Vec<VisibleRow>) {
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Raw => format!("{} ({})", column.as_str(), self.raw_radix.as_str()),
+                _ => column.as_str().to_string(),
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        for msg in self.list[..self.displayed_len]
+            .iter()
+            .filter(|msg| self.is_visible(msg))
+        {
+            let cells: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| match column {
+                    Column::Timestamp => msg.ts_str.clone(),
+                    Column::Delta => msg.delta_str(self.delta_mode).to_string(),
+                    Column::Port if msg.port_alias.is_empty() => msg.port_nb.as_char().to_string(),
+                    Column::Port => msg.port_alias.clone(),
+                    Column::Source => msg.source.as_deref().unwrap_or_default().to_string(),
+                    Column::Repetitions => msg.repetitions_str(),
+                    Column::Channel => msg.channel_str(),
+                    Column::Duration => msg.duration_str.clone(),
+                    Column::Parsed => msg.annotated_str(),
+                    Column::Raw => msg.raw.display().radix(self.raw_radix).to_string(),
+                })
+                .collect();
+            rows.push(VisibleRow {
+                port_nb: msg.port_nb,
+                is_err: msg.is_err,
+                cells,
+            });
+        }
+
+        (header, rows)
+    }
+
+    /// Formats the visible rows & columns as text.
+    fn copy(&self, format: CopyFormat) -> String {
+        let (header, rows) = self.table();
+
+        let mut text = format.row(&header);
+        if format == CopyFormat::Markdown {
+            text.push_str(&format.row(&vec!["---".to_string(); header.len()]));
+        }
+        for row in rows {
+            text.push_str(&format.row(&row.cells));
+        }
+
+        text
+    }
+}
+
+/// Cells of a row as displayed in the list.
+pub struct VisibleRow {
+    pub port_nb: PortNb,
+    pub is_err: bool,
+    pub cells: Vec<String>,
+}
+
+/// Whether the noise kind of the message starting with `raw` is hidden.
+fn is_hidden_noise(hidden_noise: &[bool; 3], raw: &[u8]) -> bool {
+    raw.first()
+        .and_then(|status| Noise::from_status(*status))
+        .map_or(false, |noise| hidden_noise[noise.idx()])
+}
+
+/// Quotes `field` if needed.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// How SysEx messages are exported.
+#[cfg(feature = "save")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyxExport {
+    /// All the messages in a single `.syx` file.
+    Concatenated,
+    /// One `.syx` file per message in a directory.
+    OnePerMessage,
+}
+
+#[cfg(feature = "save")]
+impl SyxExport {
+    pub const ALL: [SyxExport; 2] = [SyxExport::Concatenated, SyxExport::OnePerMessage];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyxExport::Concatenated => "Single .syx",
+            SyxExport::OnePerMessage => "One .syx per msg",
+        }
+    }
+}
+
+#[cfg(feature = "save")]
+impl SaveScope {
+    pub const ALL: [SaveScope; 3] = [SaveScope::All, SaveScope::Selection, SaveScope::Filtered];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SaveScope::All => "All",
+            SaveScope::Selection => "Selection",
+            SaveScope::Filtered => "Filtered",
+        }
+    }
+}
+
 pub enum Status {
     Unchanged,
     Updated,
@@ -134,183 +733,974 @@ impl Status {
 
 pub struct MsgListPanel {
     pub list: Vec<Arc<MsgParseResult>>,
+    /// The focused row, displayed in the inspector.
+    selected: Option<usize>,
+    selection: BTreeSet<usize>,
+    /// Rows displaying all the bytes of a long message in the raw column.
+    expanded_raw: BTreeSet<usize>,
+    /// Row to scroll to in next update.
+    scroll_to: Option<usize>,
+    /// Filter sets A and B.
+    filters: [Filter; 2],
+    filter_idx: usize,
+    pub trigger: Trigger,
+    /// Expected traffic per port.
+    pub templates: [Template; 2],
     follows_cursor: bool,
-    must_display_parsed: bool,
-    must_display_raw: bool,
+    /// Limits the viewport updates when following during floods.
+    steady_follow: bool,
+    /// Stops handling the incoming messages, which are kept pending.
+    is_paused: bool,
+    /// Events received while paused, handled first when resuming.
+    paused_events: VecDeque<bus::Event>,
+    /// Messages in `paused_events`.
+    paused_msgs: usize,
+    /// Accounts for the messages dropped while paused.
+    backpressure: Arc<backpressure::State>,
+    /// Shows one list per port side by side.
+    split_view: bool,
+    /// Ports whose messages are captured but not displayed.
+    hidden_ports: [bool; 2],
+    /// `Noise` messages which are captured but not displayed.
+    hidden_noise: [bool; 3],
+    /// Number of `Noise` messages received, including repetitions.
+    noise_counts: [u64; 3],
+    /// Hides the controller & aftertouch messages which don't change the value.
+    only_changes: bool,
+    /// Last value by port, status byte & controller.
+    last_values: HashMap<(PortNb, u8, u8), u8>,
+    /// Number of rows to look back for a repeated message.
+    repetition_window: usize,
+    /// Collapse repetitions whatever the time since the previous one.
+    repetition_ignores_ts: bool,
+    /// Maximum time since the previous repetition, unless `repetition_ignores_ts`.
+    repetition_max_gap_ms: u32,
+    /// Number of rows displayed in steady follow mode.
+    steady_len: usize,
+    last_steady_update: Instant,
+    columns: column::Layout,
+    /// Shares the strings & buffers repeated across the list.
+    interner: Interner,
+    /// Pairs Note Off with Note On messages.
+    notes: notes::Tracker,
+    must_detect_chords: bool,
+    chords: chord::Detector,
+    annotator: Annotator,
+    delta_mode: DeltaMode,
+    last_ts: Option<u64>,
+    last_port_ts: [Option<u64>; 2],
+    /// Alias of the port connected to each `PortNb`.
+    port_aliases: [Option<Arc<str>>; 2],
+    /// Widens the Port column when some messages display an alias.
+    has_port_aliases: bool,
+    /// Source of the messages being loaded, see `MsgParseResult::source`.
+    source: Option<Arc<str>>,
+    /// Shows the Source column when some messages were merged from captures.
+    has_sources: bool,
+    ts_mode: timestamp::Mode,
+    ts_resolution: timestamp::Resolution,
+    raw_radix: bytes::Radix,
+    /// Reference for `timestamp::Mode::Relative`.
+    start_wall_ts: Option<SystemTime>,
+    /// Messages pushed since the list was cleared, including the repetitions.
+    msg_count: u64,
+    /// Wall time of the first & last messages pushed since the list was cleared.
+    wall_span: Option<(SystemTime, SystemTime)>,
+    msg_rx: channel::Receiver<bus::Event>,
+    /// Limits the number of batches handled per update.
+    batching: Arc<batching::State>,
     #[cfg_attr(not(feature = "save"), allow(dead_code))]
-    err_tx: channel::Sender<anyhow::Error>,
+    bus: bus::Bus,
+    /// Text formatted off the UI thread, to be copied to the clipboard.
+    copy_tx: channel::Sender<String>,
+    copy_rx: channel::Receiver<String>,
+    #[cfg(feature = "save")]
+    save_scope: SaveScope,
+    #[cfg(feature = "save")]
+    syx_export: SyxExport,
     #[cfg(feature = "save")]
     msg_list_dir: Arc<Mutex<PathBuf>>,
+    #[cfg(feature = "save")]
+    load_tx: channel::Sender<Loaded>,
+    #[cfg(feature = "save")]
+    load_rx: channel::Receiver<Loaded>,
+    #[cfg(feature = "save")]
+    loading: Option<Loading>,
+    /// Saves the last messages when closing and restores them on next launch.
+    #[cfg(feature = "save")]
+    restores_session: bool,
+    #[cfg(feature = "save")]
+    session_max_msgs: usize,
 }
 
 impl MsgListPanel {
-    pub fn new(err_tx: channel::Sender<anyhow::Error>, cc: &eframe::CreationContext) -> Self {
-        let mut must_display_parsed = true;
-        let mut must_display_raw = false;
+    pub fn new(
+        bus: &bus::Bus,
+        batching: Arc<batching::State>,
+        backpressure: Arc<backpressure::State>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> Self {
+        let mut columns = column::Layout::default();
+        let mut must_detect_chords = false;
+        let mut annotator = Annotator::default();
+        let mut steady_follow = false;
+        let mut split_view = false;
+        let mut hidden_ports = [false; 2];
+        let mut hidden_noise = [false; 3];
+        let mut only_changes = false;
+        let mut repetition_window = 1;
+        let mut repetition_ignores_ts = true;
+        let mut repetition_max_gap_ms = 100;
+        let mut filters = [Filter::default(), Filter::default()];
+        let mut filter_idx = 0;
+        let mut templates = [Template::default(), Template::default()];
+        let mut delta_mode = DeltaMode::Global;
+        let mut ts_mode = timestamp::Mode::Raw;
+        let mut ts_resolution = timestamp::Resolution::Micros;
+        let mut raw_radix = bytes::Radix::default();
+        let (copy_tx, copy_rx) = channel::unbounded();
 
         #[cfg(feature = "save")]
         let mut msg_list_dir = PathBuf::from(".");
-
-        if let Some(storage) = cc.storage {
-            if let Some(display_parsed) = storage.get_string(STORAGE_MSG_LIST_DISPLAY_PARSED) {
-                must_display_parsed = display_parsed == "true";
+        #[cfg(feature = "save")]
+        let (load_tx, load_rx) = channel::unbounded();
+        #[cfg(feature = "save")]
+        let mut restores_session = false;
+        #[cfg(feature = "save")]
+        let mut session_max_msgs = DEFAULT_SESSION_MAX_MSGS;
+
+        if let Some(storage) = storage {
+            if let Some(layout) = storage.get_string(STORAGE_MSG_LIST_COLUMNS) {
+                columns = column::Layout::from_storage_str(&layout);
+            } else {
+                for (column, key) in [
+                    (Column::Parsed, STORAGE_MSG_LIST_DISPLAY_PARSED),
+                    (Column::Raw, STORAGE_MSG_LIST_DISPLAY_RAW),
+                    (Column::Delta, STORAGE_MSG_LIST_DISPLAY_DELTA),
+                    (Column::Duration, STORAGE_MSG_LIST_DISPLAY_DURATION),
+                ] {
+                    if let Some(is_visible) = storage.get_string(key) {
+                        *columns.visible_mut(column) = is_visible == "true";
+                    }
+                }
+            }
+            if let Some(detect_chords) = storage.get_string(STORAGE_MSG_LIST_DETECT_CHORDS) {
+                must_detect_chords = detect_chords == "true";
+            }
+            if let Some(gm_names) = storage.get_string(STORAGE_MSG_LIST_GM_NAMES) {
+                annotator.gm_names = gm_names == "true";
+            }
+            if let Some(steady) = storage.get_string(STORAGE_MSG_LIST_STEADY_FOLLOW) {
+                steady_follow = steady == "true";
+            }
+            if let Some(split) = storage.get_string(STORAGE_MSG_LIST_SPLIT_VIEW) {
+                split_view = split == "true";
+            }
+            if let Some(stored) = storage.get_string(STORAGE_MSG_LIST_HIDDEN_PORTS) {
+                for port_nb in [PortNb::One, PortNb::Two] {
+                    hidden_ports[port_nb.idx()] = stored
+                        .split(',')
+                        .any(|hidden| hidden == port_nb.as_char().to_string());
+                }
+            }
+            if let Some(stored) = storage.get_string(STORAGE_MSG_LIST_HIDDEN_NOISE) {
+                for noise in stored.split(',').filter_map(Noise::from_storage_str) {
+                    hidden_noise[noise.idx()] = true;
+                }
+            }
+            if let Some(stored) = storage.get_string(STORAGE_MSG_LIST_ONLY_CHANGES) {
+                only_changes = stored == "true";
+            }
+            if let Some(window) = storage
+                .get_string(STORAGE_MSG_LIST_REP_WINDOW)
+                .and_then(|window| window.parse().ok())
+            {
+                repetition_window = usize::clamp(window, 1, MAX_REPETITION_WINDOW);
+            }
+            if let Some(ignores_ts) = storage.get_string(STORAGE_MSG_LIST_REP_IGNORES_TS) {
+                repetition_ignores_ts = ignores_ts == "true";
+            }
+            if let Some(max_gap) = storage
+                .get_string(STORAGE_MSG_LIST_REP_MAX_GAP)
+                .and_then(|max_gap| max_gap.parse().ok())
+            {
+                repetition_max_gap_ms = max_gap;
+            }
+            for (filter, key) in filters
+                .iter_mut()
+                .zip([STORAGE_MSG_LIST_FILTER_A, STORAGE_MSG_LIST_FILTER_B])
+            {
+                if let Some(text) = storage.get_string(key) {
+                    *filter = Filter::new(text);
+                }
+            }
+            if let Some(set) = storage.get_string(STORAGE_MSG_LIST_FILTER_SET) {
+                filter_idx = usize::from(set == "b");
             }
-            if let Some(display_raw) = storage.get_string(STORAGE_MSG_LIST_DISPLAY_RAW) {
-                must_display_raw = display_raw == "true";
+            for (template, key) in templates
+                .iter_mut()
+                .zip([STORAGE_PORT_1_TEMPLATE, STORAGE_PORT_2_TEMPLATE])
+            {
+                if let Some(stored) = storage
+                    .get_string(key)
+                    .as_deref()
+                    .and_then(Template::from_storage_str)
+                {
+                    *template = stored;
+                }
+            }
+            if let Some(mode) = storage
+                .get_string(STORAGE_MSG_LIST_DELTA_MODE)
+                .as_deref()
+                .and_then(DeltaMode::from_storage_str)
+            {
+                delta_mode = mode;
+            }
+            if let Some(mode) = storage
+                .get_string(STORAGE_MSG_LIST_TS_MODE)
+                .as_deref()
+                .and_then(timestamp::Mode::from_storage_str)
+            {
+                ts_mode = mode;
+            }
+            if let Some(resolution) = storage
+                .get_string(STORAGE_MSG_LIST_TS_RESOLUTION)
+                .as_deref()
+                .and_then(timestamp::Resolution::from_storage_str)
+            {
+                ts_resolution = resolution;
+            }
+            if let Some(radix) = storage
+                .get_string(STORAGE_MSG_LIST_RAW_RADIX)
+                .as_deref()
+                .and_then(bytes::Radix::from_storage_str)
+            {
+                raw_radix = radix;
             }
 
             #[cfg(feature = "save")]
             if let Some(dir) = storage.get_string(STORAGE_MSG_LIST_DIR) {
                 msg_list_dir = dir.into();
             }
+            #[cfg(feature = "save")]
+            if let Some(restores) = storage.get_string(STORAGE_MSG_LIST_RESTORE_SESSION) {
+                restores_session = restores == "true";
+            }
+            #[cfg(feature = "save")]
+            if let Some(max_msgs) = storage
+                .get_string(STORAGE_MSG_LIST_SESSION_MAX_MSGS)
+                .and_then(|max_msgs| max_msgs.parse().ok())
+            {
+                session_max_msgs = usize::clamp(max_msgs, 1, MAX_SESSION_MAX_MSGS);
+            }
+        }
+
+        #[cfg(feature = "save")]
+        if restores_session {
+            let load_tx = load_tx.clone();
+            std::thread::spawn(move || {
+                if let Some(loaded) = load_session() {
+                    let _ = load_tx.send(loaded);
+                }
+            });
         }
 
         Self {
             list: Vec::new(),
+            selected: None,
+            selection: BTreeSet::new(),
+            expanded_raw: BTreeSet::new(),
+            scroll_to: None,
+            filters,
+            filter_idx,
+            trigger: Trigger::default(),
+            templates,
             follows_cursor: true,
-            must_display_parsed,
-            must_display_raw,
-            err_tx,
+            steady_follow,
+            split_view,
+            hidden_ports,
+            hidden_noise,
+            noise_counts: [0; 3],
+            only_changes,
+            last_values: HashMap::new(),
+            repetition_window,
+            repetition_ignores_ts,
+            repetition_max_gap_ms,
+            steady_len: 0,
+            last_steady_update: Instant::now(),
+            columns,
+            interner: Interner::default(),
+            notes: notes::Tracker::default(),
+            must_detect_chords,
+            chords: chord::Detector::default(),
+            annotator,
+            delta_mode,
+            last_ts: None,
+            last_port_ts: [None, None],
+            port_aliases: [None, None],
+            has_port_aliases: false,
+            source: None,
+            has_sources: false,
+            ts_mode,
+            ts_resolution,
+            raw_radix,
+            start_wall_ts: None,
+            msg_count: 0,
+            wall_span: None,
+            msg_rx: bus.subscribe(|event| event.is_msg_batch() || event.is_msgs_summarized()),
+            batching,
+            is_paused: false,
+            paused_events: VecDeque::new(),
+            paused_msgs: 0,
+            backpressure,
+            bus: bus.clone(),
+            copy_tx,
+            copy_rx,
+            #[cfg(feature = "save")]
+            save_scope: SaveScope::All,
+            #[cfg(feature = "save")]
+            syx_export: SyxExport::Concatenated,
             #[cfg(feature = "save")]
             msg_list_dir: Arc::new(Mutex::new(msg_list_dir)),
+            #[cfg(feature = "save")]
+            load_tx,
+            #[cfg(feature = "save")]
+            load_rx,
+            #[cfg(feature = "save")]
+            loading: None,
+            #[cfg(feature = "save")]
+            restores_session,
+            #[cfg(feature = "save")]
+            session_max_msgs,
         }
     }
 }
 
 impl MsgListPanel {
-    pub fn show(&mut self, ui: &mut egui::Ui) {
+    pub fn show(&mut self, ui: &mut egui::Ui, port_colors: &super::settings::PortColors) {
+        self.pop_msgs(ui.ctx());
+        if let Some(text) = self.copy_rx.try_iter().last() {
+            ui.output().copied_text = text;
+        }
+
+        let must_toggle_filter = ui.input().key_pressed(FILTER_TOGGLE_KEY);
+        if must_toggle_filter {
+            self.filter_idx = 1 - self.filter_idx;
+        }
+
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
+                self.trigger.show_state(ui);
+                ui.toggle_value(&mut self.is_paused, "⏸ Pause")
+                    .on_hover_text(
+                        "Stop updating the list, the messages are still captured \
+                     and shown when resuming",
+                    );
+                #[cfg(feature = "save")]
+                if let Some(ref loading) = self.loading {
+                    ui.label(format!(
+                        "Loading {} / {} messages",
+                        thousands((loading.len - loading.msgs.len()) as u64),
+                        thousands(loading.len as u64),
+                    ));
+                }
+                if self.is_paused && self.paused_msgs > 0 {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{} pending messages",
+                            thousands(self.paused_msgs as u64)
+                        ))
+                        .color(egui::Color32::YELLOW),
+                    )
+                    .on_hover_text(format!(
+                        "The oldest messages are dropped past {}",
+                        thousands(MAX_PAUSED_MSGS as u64)
+                    ));
+                }
                 ui.checkbox(&mut self.follows_cursor, "Follow");
+                ui.add_enabled_ui(self.follows_cursor, |ui| {
+                    ui.checkbox(&mut self.steady_follow, "Steady")
+                        .on_hover_text("Limit scrolling when messages flood in");
+                });
+                ui.checkbox(&mut self.split_view, "Split")
+                    .on_hover_text("Show one list per port side by side");
+                ui.menu_button("Repetitions", |ui| self.show_repetition_settings(ui));
+                ui.separator();
+                self.show_noise_toggles(ui);
+                ui.checkbox(&mut self.only_changes, "Only changes")
+                    .on_hover_text(
+                        "Hide the controller & aftertouch messages which don't change the value",
+                    );
+                for port_nb in [PortNb::One, PortNb::Two] {
+                    let is_hidden = &mut self.hidden_ports[port_nb.idx()];
+                    let mut text = egui::RichText::new(port_nb.as_char())
+                        .color(egui::Color32::WHITE)
+                        .background_color(port_colors.get(port_nb));
+                    if *is_hidden {
+                        text = text.strikethrough();
+                    }
+                    if ui
+                        .selectable_label(!*is_hidden, text)
+                        .on_hover_text(format!(
+                            "Show / hide the messages from {port_nb}, they are still captured"
+                        ))
+                        .clicked()
+                    {
+                        *is_hidden = !*is_hidden;
+                    }
+                }
+                let newer = self.list.len() - self.displayed_len();
+                if newer > 0 {
+                    ui.label(
+                        egui::RichText::new(format!("+{} newer", thousands(newer as u64)))
+                            .color(egui::Color32::YELLOW),
+                    );
+                }
                 ui.add_enabled_ui(!self.list.is_empty(), |ui| {
                     if ui.button("Clear").clicked() {
-                        self.list.clear();
+                        self.clear();
                     }
 
                     ui.separator();
 
-                    ui.checkbox(&mut self.must_display_parsed, "Parsed");
-                    ui.checkbox(&mut self.must_display_raw, "Raw");
+                    let prev_ts_fmt = (self.ts_mode, self.ts_resolution);
+                    egui::ComboBox::from_id_source("ts-mode")
+                        .selected_text(self.ts_mode.as_str())
+                        .show_ui(ui, |ui| {
+                            for mode in timestamp::Mode::ALL {
+                                ui.selectable_value(&mut self.ts_mode, mode, mode.as_str());
+                            }
+                        });
+                    egui::ComboBox::from_id_source("ts-resolution")
+                        .selected_text(self.ts_resolution.as_str())
+                        .show_ui(ui, |ui| {
+                            for resolution in timestamp::Resolution::ALL {
+                                ui.selectable_value(
+                                    &mut self.ts_resolution,
+                                    resolution,
+                                    resolution.as_str(),
+                                );
+                            }
+                        });
+                    if (self.ts_mode, self.ts_resolution) != prev_ts_fmt {
+                        self.refresh_ts_strs();
+                    }
+                    if ui
+                        .button("Zero here")
+                        .on_hover_text("Display timestamps relative to now")
+                        .clicked()
+                    {
+                        self.zero_at(SystemTime::now());
+                    }
+
+                    ui.checkbox(self.columns.visible_mut(Column::Delta), "Δt");
+                    ui.add_enabled_ui(self.columns.is_visible(Column::Delta), |ui| {
+                        egui::ComboBox::from_id_source("delta-mode")
+                            .selected_text(self.delta_mode.as_str())
+                            .show_ui(ui, |ui| {
+                                for mode in [DeltaMode::Global, DeltaMode::PerPort] {
+                                    ui.selectable_value(&mut self.delta_mode, mode, mode.as_str());
+                                }
+                            });
+                    });
+                    ui.checkbox(self.columns.visible_mut(Column::Duration), "Duration")
+                        .on_hover_text("Duration of the notes, on the Note Off rows");
+                    ui.checkbox(self.columns.visible_mut(Column::Parsed), "Parsed");
+                    ui.add_enabled_ui(self.columns.is_visible(Column::Parsed), |ui| {
+                        ui.checkbox(&mut self.must_detect_chords, "Chords")
+                            .on_hover_text("Annotate Note On messages with the recognized chord");
+                        ui.checkbox(&mut self.annotator.gm_names, "GM")
+                            .on_hover_text("Annotate with the General MIDI names");
+                    });
+                    ui.checkbox(self.columns.visible_mut(Column::Raw), "Raw");
+                    ui.add_enabled_ui(self.columns.is_visible(Column::Raw), |ui| {
+                        egui::ComboBox::from_id_source("raw-radix")
+                            .selected_text(self.raw_radix.as_str())
+                            .show_ui(ui, |ui| {
+                                for radix in bytes::Radix::ALL {
+                                    ui.selectable_value(&mut self.raw_radix, radix, radix.as_str());
+                                }
+                            });
+                    });
+                    ui.menu_button("Columns", |ui| self.columns.show(ui))
+                        .response
+                        .on_hover_text("Show, hide & reorder the columns");
+                    ui.menu_button("Copy", |ui| {
+                        for format in CopyFormat::ALL {
+                            if ui
+                                .button(format!("Copy all ({})", format.as_str()))
+                                .clicked()
+                            {
+                                self.copy_visible(format, ui.ctx());
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Copy the visible rows & columns to the clipboard");
 
                     #[cfg(feature = "save")]
                     {
                         ui.separator();
+                        egui::ComboBox::from_id_source("save-scope")
+                            .selected_text(self.save_scope.as_str())
+                            .show_ui(ui, |ui| {
+                                for scope in SaveScope::ALL {
+                                    ui.selectable_value(
+                                        &mut self.save_scope,
+                                        scope,
+                                        scope.as_str(),
+                                    );
+                                }
+                            });
+                        if ui
+                            .button("Open")
+                            .on_hover_text(
+                                "Replace the list with a saved capture \
+                                 or a MIDI-OX / ReceiveMIDI log",
+                            )
+                            .clicked()
+                        {
+                            self.open();
+                        }
+                        if ui
+                            .button("Merge")
+                            .on_hover_text(
+                                "Replace the list with several captures in chronological order",
+                            )
+                            .clicked()
+                        {
+                            self.merge();
+                        }
                         if ui.button("Save").clicked() {
                             self.save_list();
                         }
+                        ui.checkbox(&mut self.restores_session, "Restore")
+                            .on_hover_text("Restore the last messages on next launch");
+                        ui.add_enabled(
+                            self.restores_session,
+                            egui::DragValue::new(&mut self.session_max_msgs)
+                                .clamp_range(1..=MAX_SESSION_MAX_MSGS)
+                                .suffix(" msgs"),
+                        );
+
+                        egui::ComboBox::from_id_source("syx-export")
+                            .selected_text(self.syx_export.as_str())
+                            .show_ui(ui, |ui| {
+                                for export in SyxExport::ALL {
+                                    ui.selectable_value(
+                                        &mut self.syx_export,
+                                        export,
+                                        export.as_str(),
+                                    );
+                                }
+                            });
+                        if ui
+                            .button("Export SysEx")
+                            .on_hover_text("Save the raw SysEx messages, e.g. patch dumps")
+                            .clicked()
+                        {
+                            self.export_syx();
+                        }
                     }
                 });
+
+                ui.separator();
+                for (idx, name) in ["A", "B"].into_iter().enumerate() {
+                    ui.selectable_value(&mut self.filter_idx, idx, name)
+                        .on_hover_text(format!("Filter set {name} ({FILTER_TOGGLE_KEY:?})"));
+                }
+                self.filters[self.filter_idx].show(ui);
             });
 
             ui.separator();
 
-            let mut table_builder = TableBuilder::new(ui)
-                .striped(true)
-                .column(Size::exact(80.0))
-                .column(Size::exact(25.0))
-                .column(Size::exact(30.0));
-
-            if self.must_display_parsed {
-                table_builder = table_builder.column(Size::remainder());
+            let modifiers = ui.input().modifiers;
+            let actions = if self.split_view {
+                let mut actions = TableActions::default();
+                ui.columns(2, |columns| {
+                    for (ui, port_nb) in columns.iter_mut().zip([PortNb::One, PortNb::Two]) {
+                        ui.push_id(port_nb.idx(), |ui| {
+                            actions.merge(self.show_table(ui, port_colors, Some(port_nb)));
+                        });
+                    }
+                });
+                actions
+            } else {
+                self.show_table(ui, port_colors, None)
+            };
+
+            self.scroll_to = None;
+            if let Some(wall_ts) = actions.zero_at {
+                self.zero_at(wall_ts);
+            }
+            if let Some(idx) = actions.toggle_bookmark {
+                self.toggle_bookmark(idx);
+            }
+            if let Some(idx) = actions.toggle_raw_expanded {
+                if !self.expanded_raw.remove(&idx) {
+                    self.expanded_raw.insert(idx);
+                }
             }
-            if self.must_display_raw {
-                table_builder = table_builder.column(Size::remainder());
+            if let Some(idx) = actions.clicked {
+                self.click(idx, modifiers);
             }
+        });
+    }
 
-            table_builder
-                .header(25.0, |mut header| {
-                    header.col(|ui| {
-                        ui.label("Timestamp");
-                    });
-                    header.col(|ui| {
-                        ui.label("Port");
-                    });
-                    header.col(|ui| {
-                        ui.label("Rep.");
-                    });
-                    if self.must_display_parsed {
-                        header.col(|ui| {
-                            ui.label("Parsed msg");
-                        });
-                    }
-                    if self.must_display_raw {
-                        header.col(|ui| {
-                            ui.label("Raw msg (hex)");
-                        });
-                    }
-                })
-                .body(|mut body| {
-                    let len = self.list.len();
-                    for (idx, msg) in self.list.iter().enumerate() {
-                        body.row(20.0, |mut row| {
-                            let row_color = match msg.port_nb {
-                                midi::PortNb::One => egui::Color32::from_rgb(0, 0, 0x64),
-                                midi::PortNb::Two => egui::Color32::from_rgb(0, 0x48, 0),
-                            };
+    fn show_noise_toggles(&mut self, ui: &mut egui::Ui) {
+        for noise in Noise::ALL {
+            let is_hidden = &mut self.hidden_noise[noise.idx()];
+            let count = self.noise_counts[noise.idx()];
+            let text = if *is_hidden && count > 0 {
+                format!("{} ({})", noise.as_str(), thousands(count))
+            } else {
+                noise.as_str().to_string()
+            };
+            if ui
+                .selectable_label(*is_hidden, text)
+                .on_hover_text(format!(
+                    "Hide the {} messages, they are still captured",
+                    noise.as_str()
+                ))
+                .clicked()
+            {
+                *is_hidden = !*is_hidden;
+            }
+        }
+    }
 
-                            row.col(|ui| {
-                                let _ = ui.selectable_label(false, &msg.ts_str);
-                                if self.follows_cursor && idx + 1 == len {
-                                    ui.scroll_to_cursor(None);
-                                }
-                            });
+    fn show_repetition_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Look back");
+            ui.add(
+                egui::DragValue::new(&mut self.repetition_window)
+                    .clamp_range(1..=MAX_REPETITION_WINDOW)
+                    .suffix(" row(s)"),
+            )
+            .on_hover_text("e.g. 2 to collapse identical floods from two interleaved ports");
+        });
+        ui.checkbox(&mut self.repetition_ignores_ts, "Ignore timestamp");
+        ui.add_enabled_ui(!self.repetition_ignores_ts, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max gap");
+                ui.add(
+                    egui::DragValue::new(&mut self.repetition_max_gap_ms)
+                        .clamp_range(0..=60_000)
+                        .suffix(" ms"),
+                )
+                .on_hover_text("Maximum time since the previous repetition");
+            });
+        });
+    }
 
-                            row.col(|ui| {
-                                let _ = ui.selectable_label(
-                                    false,
-                                    egui::RichText::new(msg.port_nb.as_char())
-                                        .color(egui::Color32::WHITE)
-                                        .background_color(row_color),
-                                );
-                            });
+    /// Shows the messages from `port_nb`, from all the ports if `None`.
+    fn show_table(
+        &self,
+        ui: &mut egui::Ui,
+        port_colors: &super::settings::PortColors,
+        port_nb: Option<PortNb>,
+    ) -> TableActions {
+        let mut zero_at = None;
+        let mut toggle_bookmark = None;
+        let mut toggle_raw_expanded = None;
+        let mut clicked = None;
+
+        let columns: Vec<Column> = self
+            .columns
+            .visible()
+            .filter(|column| *column != Column::Source || self.has_sources)
+            .collect();
+
+        let mut table_builder = TableBuilder::new(ui).striped(true);
+        for column in columns.iter() {
+            let size = match column {
+                Column::Timestamp => Size::exact(self.ts_mode.column_width(self.ts_resolution)),
+                Column::Delta | Column::Duration => Size::exact(80.0),
+                Column::Port if self.has_port_aliases => Size::exact(90.0),
+                Column::Port => Size::exact(25.0),
+                Column::Source => Size::exact(120.0),
+                Column::Repetitions | Column::Channel => Size::exact(30.0),
+                Column::Parsed | Column::Raw => Size::remainder(),
+            };
+            table_builder = table_builder.column(size);
+        }
 
+        table_builder
+            .header(25.0, |mut header| {
+                for column in columns.iter() {
+                    header.col(|ui| {
+                        if *column == Column::Raw {
+                            ui.label(format!("{} ({})", column.as_str(), self.raw_radix.as_str()));
+                        } else {
+                            ui.label(column.as_str());
+                        }
+                    });
+                }
+            })
+            .body(|mut body| {
+                let displayed = &self.list[..self.displayed_len()];
+                let is_visible = |msg: &MsgParseResult| self.is_visible(msg, port_nb);
+                let last_visible = displayed.iter().rposition(|msg| is_visible(msg));
+                let visible = displayed
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, msg)| is_visible(msg));
+                for (idx, msg) in visible {
+                    body.row(20.0, |mut row| {
+                        let row_color = port_colors.get(msg.port_nb);
+                        let is_selected = self.selection.contains(&idx);
+
+                        // Scroll along the first column
+                        let mut must_scroll = true;
+                        for column in columns.iter() {
                             row.col(|ui| {
-                                let repetitions: egui::WidgetText = if msg.repetitions == 1 {
-                                    "".into()
-                                } else if msg.repetitions <= MAX_REPETITIONS {
-                                    format!("x{}", msg.repetitions).into()
-                                } else {
-                                    MAX_REPETITIONS_EXCEEDED.into()
+                                let resp = match column {
+                                    Column::Timestamp => {
+                                        let ts_txt: egui::WidgetText = if msg.is_bookmarked {
+                                            format!("★ {}", msg.ts_str).into()
+                                        } else {
+                                            msg.ts_str.as_str().into()
+                                        };
+                                        ui.selectable_label(is_selected, ts_txt).context_menu(
+                                            |ui| {
+                                                if ui.button("Zero here").clicked() {
+                                                    zero_at = Some(msg.ts.wall_ts);
+                                                    ui.close_menu();
+                                                }
+                                                let bookmark_txt = if msg.is_bookmarked {
+                                                    "Remove bookmark"
+                                                } else {
+                                                    "Bookmark"
+                                                };
+                                                if ui.button(bookmark_txt).clicked() {
+                                                    toggle_bookmark = Some(idx);
+                                                    ui.close_menu();
+                                                }
+                                            },
+                                        )
+                                    }
+                                    Column::Delta => ui.selectable_label(
+                                        is_selected,
+                                        msg.delta_str(self.delta_mode),
+                                    ),
+                                    Column::Port => {
+                                        let port_text = if msg.port_alias.is_empty() {
+                                            egui::RichText::new(msg.port_nb.as_char())
+                                        } else {
+                                            egui::RichText::new(&msg.port_alias)
+                                        };
+                                        ui.selectable_label(
+                                            is_selected,
+                                            port_text
+                                                .color(egui::Color32::WHITE)
+                                                .background_color(row_color),
+                                        )
+                                        .on_hover_text(msg.port_nb.as_str())
+                                    }
+                                    Column::Source => {
+                                        let source = msg.source.as_deref().unwrap_or_default();
+                                        ui.selectable_label(is_selected, source)
+                                    }
+                                    Column::Repetitions => {
+                                        ui.selectable_label(is_selected, msg.repetitions_str())
+                                    }
+                                    Column::Channel => {
+                                        ui.selectable_label(is_selected, msg.channel_str())
+                                    }
+                                    Column::Duration => {
+                                        ui.selectable_label(is_selected, msg.duration_str.as_str())
+                                    }
+                                    Column::Parsed => {
+                                        let msg_txt = egui::RichText::new(msg.annotated_str())
+                                            .color(egui::Color32::WHITE);
+                                        let msg_txt = if msg.is_err {
+                                            msg_txt.background_color(egui::Color32::DARK_RED)
+                                        } else if msg.is_anomaly {
+                                            msg_txt.background_color(ANOMALY_COLOR)
+                                        } else {
+                                            msg_txt.background_color(row_color)
+                                        };
+                                        let resp = ui.selectable_label(is_selected, msg_txt);
+                                        let resp = if msg.is_anomaly {
+                                            resp.on_hover_text("Outside expected traffic")
+                                        } else {
+                                            resp
+                                        };
+                                        if msg.note.is_empty() {
+                                            resp
+                                        } else {
+                                            resp.on_hover_text(msg.note.as_str())
+                                        }
+                                    }
+                                    Column::Raw => {
+                                        let raw = msg.raw();
+                                        let is_long = raw.len() > RAW_TRUNCATE_LEN;
+                                        let is_truncated =
+                                            is_long && !self.expanded_raw.contains(&idx);
+                                        let raw_str = if is_truncated {
+                                            format!(
+                                                "{} … (+{} bytes)",
+                                                bytes::Displayable::from(&raw[..RAW_TRUNCATE_LEN])
+                                                    .radix(self.raw_radix)
+                                                    .grouped(RAW_GROUP_LEN),
+                                                raw.len() - RAW_TRUNCATE_LEN,
+                                            )
+                                        } else {
+                                            msg.raw
+                                                .display()
+                                                .radix(self.raw_radix)
+                                                .grouped(RAW_GROUP_LEN)
+                                        };
+                                        let raw_txt = egui::RichText::new(raw_str)
+                                            .monospace()
+                                            .color(egui::Color32::WHITE)
+                                            .background_color(row_color);
+                                        let resp = ui.selectable_label(is_selected, raw_txt);
+                                        if is_long {
+                                            if resp.double_clicked() {
+                                                toggle_raw_expanded = Some(idx);
+                                            }
+                                            resp.on_hover_text(if is_truncated {
+                                                "Double-click to show all the bytes, \
+                                                 or see the Inspector"
+                                            } else {
+                                                "Double-click to truncate"
+                                            })
+                                        } else {
+                                            resp
+                                        }
+                                    }
                                 };
-                                let _ = ui.selectable_label(false, repetitions);
-                            });
+                                if resp.clicked() {
+                                    clicked = Some(idx);
+                                }
 
-                            if self.must_display_parsed {
-                                row.col(|ui| {
-                                    let msg_txt = egui::RichText::new(&msg.parsed_res_str)
-                                        .color(egui::Color32::WHITE);
-                                    let msg_txt = if msg.is_err {
-                                        msg_txt.background_color(egui::Color32::DARK_RED)
-                                    } else {
-                                        msg_txt.background_color(row_color)
-                                    };
-                                    let _ = ui.selectable_label(false, msg_txt);
-                                });
-                            }
+                                if must_scroll {
+                                    must_scroll = false;
+                                    if self.scroll_to == Some(idx) {
+                                        ui.scroll_to_cursor(Some(egui::Align::Center));
+                                    } else if self.follows_cursor && Some(idx) == last_visible {
+                                        ui.scroll_to_cursor(None);
+                                    }
+                                }
+                            });
+                        }
+                    });
+                }
+            });
 
-                            if self.must_display_raw {
-                                row.col(|ui| {
-                                    let raw_txt = egui::RichText::new(&msg.raw_str)
-                                        .color(egui::Color32::WHITE)
-                                        .background_color(row_color);
-                                    let _ = ui.selectable_label(false, raw_txt);
-                                });
-                            }
-                        });
-                    }
-                });
-        });
+        TableActions {
+            zero_at,
+            toggle_bookmark,
+            toggle_raw_expanded,
+            clicked,
+        }
     }
 
     pub fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_MSG_LIST_COLUMNS, self.columns.storage_string());
+
+        storage.set_string(
+            STORAGE_MSG_LIST_DETECT_CHORDS,
+            format!("{}", self.must_detect_chords),
+        );
+
         storage.set_string(
-            STORAGE_MSG_LIST_DISPLAY_PARSED,
-            format!("{}", self.must_display_parsed),
+            STORAGE_MSG_LIST_GM_NAMES,
+            format!("{}", self.annotator.gm_names),
         );
 
         storage.set_string(
-            STORAGE_MSG_LIST_DISPLAY_RAW,
-            format!("{}", self.must_display_raw),
+            STORAGE_MSG_LIST_STEADY_FOLLOW,
+            format!("{}", self.steady_follow),
+        );
+
+        storage.set_string(STORAGE_MSG_LIST_SPLIT_VIEW, format!("{}", self.split_view));
+
+        storage.set_string(
+            STORAGE_MSG_LIST_HIDDEN_PORTS,
+            [PortNb::One, PortNb::Two]
+                .into_iter()
+                .filter(|port_nb| self.hidden_ports[port_nb.idx()])
+                .map(|port_nb| port_nb.as_char().to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_HIDDEN_NOISE,
+            Noise::ALL
+                .into_iter()
+                .filter(|noise| self.hidden_noise[noise.idx()])
+                .map(Noise::storage_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_ONLY_CHANGES,
+            format!("{}", self.only_changes),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_REP_WINDOW,
+            format!("{}", self.repetition_window),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_REP_IGNORES_TS,
+            format!("{}", self.repetition_ignores_ts),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_REP_MAX_GAP,
+            format!("{}", self.repetition_max_gap_ms),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_FILTER_A,
+            self.filters[0].text().to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_FILTER_B,
+            self.filters[1].text().to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_FILTER_SET,
+            if self.filter_idx == 0 { "a" } else { "b" }.to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_PORT_1_TEMPLATE,
+            self.templates[0].to_storage_string(),
+        );
+
+        storage.set_string(
+            STORAGE_PORT_2_TEMPLATE,
+            self.templates[1].to_storage_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_DELTA_MODE,
+            self.delta_mode.storage_str().to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_TS_MODE,
+            self.ts_mode.storage_str().to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_TS_RESOLUTION,
+            self.ts_resolution.storage_str().to_string(),
+        );
+
+        storage.set_string(
+            STORAGE_MSG_LIST_RAW_RADIX,
+            self.raw_radix.storage_str().to_string(),
         );
 
         #[cfg(feature = "save")]
@@ -318,23 +1708,416 @@ impl MsgListPanel {
             STORAGE_MSG_LIST_DIR,
             self.msg_list_dir.lock().unwrap().display().to_string(),
         );
+
+        #[cfg(feature = "save")]
+        {
+            storage.set_string(
+                STORAGE_MSG_LIST_RESTORE_SESSION,
+                format!("{}", self.restores_session),
+            );
+            storage.set_string(
+                STORAGE_MSG_LIST_SESSION_MAX_MSGS,
+                self.session_max_msgs.to_string(),
+            );
+        }
     }
 }
 
 impl MsgListPanel {
+    fn pop_msgs(&mut self, ctx: &egui::Context) {
+        #[cfg(feature = "save")]
+        if self.pop_loaded() {
+            // The captured messages are kept pending until the capture is loaded
+            ctx.request_repaint();
+            return;
+        }
+
+        if self.is_paused {
+            self.keep_paused_events();
+            return;
+        }
+
+        for _ in 0..self.batching.settings().max_batches_per_update {
+            let event = match self.paused_events.pop_front() {
+                Some(event) => {
+                    if let bus::Event::MsgBatch(ref batch) = event {
+                        self.paused_msgs -= batch.len();
+                    }
+                    Ok(event)
+                }
+                None => self.msg_rx.try_recv(),
+            };
+            match event {
+                Ok(bus::Event::MsgBatch(batch)) => {
+                    for msg in batch.iter() {
+                        if self.trigger.is_enabled()
+                            && !self.trigger.accept(&MsgParseResult::from(msg))
+                        {
+                            continue;
+                        }
+                        let _ = self.push(msg);
+                    }
+                }
+                Ok(bus::Event::MsgsSummarized { port_nb, count }) => {
+                    let mut summary = MsgParseResult::summary(port_nb, count);
+                    summary.ts_str =
+                        summary
+                            .ts
+                            .format(self.ts_mode, self.ts_resolution, self.start_wall_ts);
+                    self.list.push(summary.into());
+                }
+                Ok(_) => (),
+                Err(_) => return,
+            }
+        }
+
+        if self.last_steady_update.elapsed() >= STEADY_FOLLOW_INTERVAL {
+            self.steady_len = self.list.len();
+            self.last_steady_update = Instant::now();
+        }
+
+        // Handle pending batches or catch up with steady follow in next update
+        if !self.paused_events.is_empty()
+            || !self.msg_rx.is_empty()
+            || self.displayed_len() < self.list.len()
+        {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Keeps the incoming events pending, dropping the oldest messages past `MAX_PAUSED_MSGS`.
+    fn keep_paused_events(&mut self) {
+        for event in self.msg_rx.try_iter() {
+            if let bus::Event::MsgBatch(ref batch) = event {
+                self.paused_msgs += batch.len();
+            }
+            self.paused_events.push_back(event);
+        }
+
+        let mut overflow = 0;
+        while self.paused_msgs > MAX_PAUSED_MSGS {
+            match self.paused_events.pop_front() {
+                Some(bus::Event::MsgBatch(batch)) => {
+                    self.paused_msgs -= batch.len();
+                    overflow += batch.len() as u64;
+                }
+                Some(_) => (),
+                None => break,
+            }
+        }
+        if overflow > 0 {
+            self.backpressure.add_paused_overflow(overflow);
+        }
+    }
+
+    /// Returns whether `msg` passes the display filters, `port_nb` for a split view table.
+    fn is_visible(&self, msg: &MsgParseResult, port_nb: Option<PortNb>) -> bool {
+        port_nb.map_or(true, |port_nb| msg.port_nb == port_nb)
+            && !self.hidden_ports[msg.port_nb.idx()]
+            && !self.is_hidden_noise(msg.raw())
+            && !(self.only_changes && msg.is_unchanged)
+            && self.filter().matches(msg)
+    }
+
+    /// Copies the visible rows & columns to the clipboard, e.g. to paste them in an issue.
+    ///
+    /// The text is formatted off the UI thread and copied on a subsequent update.
+    fn copy_visible(&self, format: CopyFormat, ctx: &egui::Context) {
+        let snapshot = self.table_snapshot();
+        let copy_tx = self.copy_tx.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = copy_tx.send(snapshot.copy(format));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Returns what's needed to format the visible rows & columns off the UI thread.
+    fn table_snapshot(&self) -> TableSnapshot {
+        TableSnapshot {
+            list: self.list.clone(),
+            displayed_len: self.displayed_len(),
+            columns: self
+                .columns
+                .visible()
+                .filter(|column| *column != Column::Source || self.has_sources)
+                .collect(),
+            delta_mode: self.delta_mode,
+            raw_radix: self.raw_radix,
+            hidden_ports: self.hidden_ports,
+            hidden_noise: self.hidden_noise,
+            only_changes: self.only_changes,
+            filter: Filter::new(self.filter().text()),
+        }
+    }
+
+    /// The active filter set.
+    fn filter(&self) -> &Filter {
+        &self.filters[self.filter_idx]
+    }
+
+    /// Replaces the text of the active filter set.
+    pub fn set_filter(&mut self, text: &str) {
+        self.filters[self.filter_idx] = Filter::new(text);
+    }
+
+    #[cfg(feature = "config")]
+    pub fn set_columns(&mut self, columns: &crate::config::Columns) {
+        for (column, is_visible) in [
+            (Column::Timestamp, columns.timestamp),
+            (Column::Delta, columns.delta),
+            (Column::Port, columns.port),
+            (Column::Repetitions, columns.repetitions),
+            (Column::Channel, columns.channel),
+            (Column::Duration, columns.duration),
+            (Column::Parsed, columns.parsed),
+            (Column::Raw, columns.raw),
+        ] {
+            if let Some(is_visible) = is_visible {
+                *self.columns.visible_mut(column) = is_visible;
+            }
+        }
+    }
+
+    /// Number of messages in the list, including the repetitions.
+    pub fn msg_count(&self) -> u64 {
+        self.msg_count
+    }
+
+    /// Duration between the first & the last messages of the list.
+    ///
+    /// Based on the wall-clock arrival time, which, unlike the backend timestamps,
+    /// shares the same base for both ports and loaded captures.
+    pub fn capture_duration(&self) -> Duration {
+        self.wall_span
+            .and_then(|(first, last)| last.duration_since(first).ok())
+            .unwrap_or_default()
+    }
+
+    /// Number of messages hidden by the `Noise` toggles.
+    pub fn hidden_noise_count(&self) -> u64 {
+        Noise::ALL
+            .into_iter()
+            .filter(|noise| self.hidden_noise[noise.idx()])
+            .map(|noise| self.noise_counts[noise.idx()])
+            .sum()
+    }
+
+    /// Number of rows to display, starting from the first one.
+    fn displayed_len(&self) -> usize {
+        if self.follows_cursor && self.steady_follow {
+            self.steady_len.min(self.list.len())
+        } else {
+            self.list.len()
+        }
+    }
+
+    pub fn bookmarks(&self) -> impl Iterator<Item = (usize, &MsgParseResult)> {
+        self.list
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| msg.is_bookmarked)
+            .map(|(idx, msg)| (idx, msg.as_ref()))
+    }
+
+    pub fn toggle_bookmark(&mut self, idx: usize) {
+        if let Some(msg) = self.list.get_mut(idx) {
+            let msg = Arc::make_mut(msg);
+            msg.is_bookmarked = !msg.is_bookmarked;
+        }
+    }
+
+    pub fn set_note(&mut self, idx: usize, note: String) {
+        if let Some(msg) = self.list.get_mut(idx) {
+            Arc::make_mut(msg).note = note;
+        }
+    }
+
+    /// Selects row `idx` and scrolls to it.
+    pub fn focus(&mut self, idx: usize) {
+        self.follows_cursor = false;
+        self.selection.clear();
+        self.selection.insert(idx);
+        self.selected = Some(idx);
+        self.scroll_to = Some(idx);
+    }
+
+    /// Updates the selection after a click on row `idx`.
+    ///
+    /// Ctrl / Cmd toggles the row, Shift extends from the focused row.
+    fn click(&mut self, idx: usize, modifiers: egui::Modifiers) {
+        if modifiers.command {
+            if !self.selection.remove(&idx) {
+                self.selection.insert(idx);
+            }
+        } else if let (true, Some(focused)) = (modifiers.shift, self.selected) {
+            let range = focused.min(idx)..=focused.max(idx);
+            self.selection = range
+                .filter(|idx| self.is_visible(&self.list[*idx], None))
+                .collect();
+            // Keep the anchor
+            return;
+        } else {
+            self.selection.clear();
+            self.selection.insert(idx);
+        }
+
+        self.selected = Some(idx);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn selected(&self) -> Option<&MsgParseResult> {
+        self.selected
+            .and_then(|idx| self.list.get(idx))
+            .map(Arc::as_ref)
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.steady_len = 0;
+        self.selected = None;
+        self.selection.clear();
+        self.expanded_raw.clear();
+        self.last_ts = None;
+        self.last_port_ts = [None, None];
+        self.has_port_aliases = false;
+        self.has_sources = false;
+        self.noise_counts = [0; 3];
+        self.last_values.clear();
+        self.start_wall_ts = None;
+        self.msg_count = 0;
+        self.wall_span = None;
+        self.notes.clear();
+        self.chords.clear();
+        self.annotator.reset();
+        self.interner.clear();
+    }
+
+    /// Replaces the strings & buffer of `msg` with the shared ones.
+    fn intern(&mut self, msg: &mut MsgParseResult) {
+        msg.parsed_res_str = self.interner.str(&msg.parsed_res_str);
+        msg.raw_str = self.interner.str(&msg.raw_str);
+        msg.raw = self.interner.buf(&msg.raw.0).into();
+    }
+
+    /// Sets `wall_ts` as t=0 and switches to relative timestamps.
+    fn zero_at(&mut self, wall_ts: SystemTime) {
+        self.start_wall_ts = Some(wall_ts);
+        self.ts_mode = timestamp::Mode::Relative;
+        self.refresh_ts_strs();
+    }
+
+    /// Sets the control surface protocol used to decode the messages from `port_nb`.
+    pub fn set_surface(&mut self, port_nb: PortNb, protocol: midi::surface::Protocol) {
+        self.annotator.surfaces[port_nb.idx()].protocol = protocol;
+    }
+
+    /// Sets the alias of the port connected to `port_nb`, used for the next messages.
+    pub fn set_port_alias(&mut self, port_nb: PortNb, alias: Option<&Arc<str>>) {
+        self.port_aliases[port_nb.idx()] = alias.cloned();
+    }
+
+    /// Sets the device profile used to annotate the messages from `port_nb`.
+    #[cfg(feature = "profile")]
+    pub fn set_profile(&mut self, port_nb: PortNb, profile: Option<Arc<midi::profile::Profile>>) {
+        self.annotator.profiles[port_nb.idx()] = profile;
+    }
+
+    /// Checks the whole list against the templates after a change.
+    pub fn refresh_anomalies(&mut self) {
+        for msg in self.list.iter_mut() {
+            let is_anomaly = !self.templates[msg.port_nb.idx()].accepts(msg.raw());
+            if msg.is_anomaly != is_anomaly {
+                Arc::make_mut(msg).is_anomaly = is_anomaly;
+            }
+        }
+    }
+
+    /// Formats the timestamps of the whole list after a mode change.
+    fn refresh_ts_strs(&mut self) {
+        for msg in self.list.iter_mut() {
+            let ts_str = msg
+                .ts
+                .format(self.ts_mode, self.ts_resolution, self.start_wall_ts);
+            Arc::make_mut(msg).ts_str = ts_str;
+        }
+    }
+
     #[must_use]
-    pub fn push(&mut self, msg: midi::msg::Result) -> Status {
+    pub fn push(&mut self, msg: &midi::msg::Result) -> Status {
         let mut status = Status::Unchanged;
 
-        match self.list.last_mut() {
-            Some(last) if last.as_ref() == &msg => {
-                if last.repetitions <= MAX_REPETITIONS {
-                    Arc::make_mut(last).repetitions += 1;
+        let origin = midi::msg::origin(msg);
+        self.msg_count += 1;
+        self.wall_span
+            .get_or_insert((origin.wall_ts, origin.wall_ts))
+            .1 = origin.wall_ts;
+
+        let is_unchanged = self.track_value(origin.port_nb, &origin.buffer);
+        if let Some(noise) = origin
+            .buffer
+            .first()
+            .and_then(|status| Noise::from_status(*status))
+        {
+            self.noise_counts[noise.idx()] += 1;
+        }
+
+        let delta_global = self.last_ts.map(|last| origin.ts.saturating_sub(last));
+        let last_port_ts = &mut self.last_port_ts[origin.port_nb.idx()];
+        let delta_port = last_port_ts.map(|last| origin.ts.saturating_sub(last));
+        *last_port_ts = Some(origin.ts);
+        self.last_ts = Some(origin.ts);
+
+        let released = self
+            .notes
+            .handle(origin.port_nb, origin.wall_ts, &origin.buffer);
+        let annotations = self.annotator.annotate(origin.port_nb, &origin.buffer);
+        let chord = if self.must_detect_chords {
+            self.chords
+                .handle(origin.port_nb, origin.wall_ts, &origin.buffer)
+        } else {
+            None
+        };
+
+        match self.find_repeated(msg) {
+            Some(idx) => {
+                let repeated = Arc::make_mut(&mut self.list[idx]);
+                repeated.repetitions = repeated.repetitions.saturating_add(1);
+                repeated.last_rep_ts = origin.ts;
+                // The displayed count no longer changes past `MAX_REPETITIONS`
+                if repeated.repetitions <= MAX_REPETITIONS + 1 {
                     status.updated();
                 }
             }
-            _ => {
-                let parse_res: MsgParseResult = msg.into();
+            None => {
+                let start_wall_ts = *self.start_wall_ts.get_or_insert(origin.wall_ts);
+
+                let mut parse_res: MsgParseResult = msg.into();
+                parse_res.ts_str =
+                    parse_res
+                        .ts
+                        .format(self.ts_mode, self.ts_resolution, Some(start_wall_ts));
+                parse_res.set_deltas(delta_global, delta_port);
+                parse_res.is_unchanged = is_unchanged;
+                if let Some(ref alias) = self.port_aliases[origin.port_nb.idx()] {
+                    parse_res.port_alias = alias.to_string();
+                    self.has_port_aliases = true;
+                }
+                if let Some(ref source) = self.source {
+                    parse_res.source = Some(source.clone());
+                    self.has_sources = true;
+                }
+                parse_res.is_anomaly =
+                    !self.templates[origin.port_nb.idx()].accepts(&origin.buffer);
+                parse_res.annotations = annotations;
+                parse_res.annotations.extend(chord);
+                if let [(_, _, duration)] = released.as_slice() {
+                    parse_res.duration_str = format!("{:.1} ms", duration.as_secs_f64() * 1_000.0);
+                }
+                self.intern(&mut parse_res);
                 self.list.push(parse_res.into());
                 status.updated();
             }
@@ -343,10 +2126,72 @@ impl MsgListPanel {
         status
     }
 
+    /// Records the value of controller & aftertouch messages.
+    ///
+    /// Returns `true` if the value is the same as the previous one.
+    fn track_value(&mut self, port_nb: PortNb, buffer: &[u8]) -> bool {
+        let (key, value) = match *buffer {
+            // Polyphonic aftertouch & Control Change
+            [status, data1, value] if matches!(status & 0xf0, 0xa0 | 0xb0) => {
+                ((port_nb, status, data1), value)
+            }
+            // Channel aftertouch
+            [status, value] if status & 0xf0 == 0xd0 => ((port_nb, status, 0), value),
+            _ => return false,
+        };
+
+        self.last_values.insert(key, value) == Some(value)
+    }
+
+    fn is_hidden_noise(&self, raw: &[u8]) -> bool {
+        is_hidden_noise(&self.hidden_noise, raw)
+    }
+
+    /// Returns the index of the row `msg` repeats, if any.
+    fn find_repeated(&self, msg: &midi::msg::Result) -> Option<usize> {
+        let ts = midi::msg::origin(msg).ts;
+        let max_gap_us = if self.repetition_ignores_ts {
+            None
+        } else {
+            Some(u64::from(self.repetition_max_gap_ms) * 1_000)
+        };
+
+        let start = self.list.len().saturating_sub(self.repetition_window);
+        self.list[start..]
+            .iter()
+            .rposition(|row| {
+                row.as_ref() == msg
+                    && row.source == self.source
+                    && max_gap_us.map_or(true, |max_gap| {
+                        ts.saturating_sub(row.last_rep_ts) <= max_gap
+                    })
+            })
+            .map(|pos| start + pos)
+    }
+
+    /// Returns the messages in the `SaveScope`.
+    #[cfg(feature = "save")]
+    fn scoped_list(&self) -> Vec<Arc<MsgParseResult>> {
+        match self.save_scope {
+            SaveScope::All => self.list.clone(),
+            SaveScope::Selection => self
+                .selection
+                .iter()
+                .filter_map(|idx| self.list.get(*idx).cloned())
+                .collect(),
+            SaveScope::Filtered => self
+                .list
+                .iter()
+                .filter(|msg| self.is_visible(msg, None))
+                .cloned()
+                .collect(),
+        }
+    }
+
     #[cfg(feature = "save")]
     fn save_list(&self) {
-        let err_tx = self.err_tx.clone();
-        let msg_list = self.list.clone();
+        let bus = self.bus.clone();
+        let msg_list = self.scoped_list();
         let msg_list_dir = self.msg_list_dir.clone();
         std::thread::spawn(move || {
             use anyhow::Context;
@@ -354,6 +2199,8 @@ impl MsgListPanel {
 
             let file_path = rfd::FileDialog::new()
                 .add_filter("Rusty Object Notation (ron)", &["ron"])
+                .add_filter("Wireshark (pcapng)", &["pcapng"])
+                .add_filter("Compressed capture (msnf)", &[midi::capture::EXTENSION])
                 .set_directory(&*msg_list_dir.lock().unwrap().clone())
                 .set_file_name("midi_exchg.ron")
                 .save_file();
@@ -363,19 +2210,23 @@ impl MsgListPanel {
                     .with_context(|| format!("Couldn't create file {}", file_path.display()))
                 {
                     Ok(file) => {
-                        use std::io::{self, Write};
-
-                        let config = ron::ser::PrettyConfig::new();
-                        let new_line = config.new_line.clone();
-                        // Custom config to keep message fields on a single line
-                        // while using spaces between the fields and items.
-                        let config = config.new_line(" ".into()).indentor("".into());
-
-                        let mut writer = io::BufWriter::new(file);
-                        for msg in msg_list {
-                            let config_cl = config.clone();
-                            ron::ser::to_writer_pretty(&mut writer, &msg, config_cl).unwrap();
-                            writer.write_all(new_line.as_bytes()).unwrap();
+                        let writer = std::io::BufWriter::new(file);
+                        let has_ext = |expected: &str| {
+                            file_path
+                                .extension()
+                                .map_or(false, |ext| ext.eq_ignore_ascii_case(expected))
+                        };
+                        let res = if has_ext("pcapng") {
+                            write_pcapng(writer, msg_list.iter())
+                        } else if has_ext(midi::capture::EXTENSION) {
+                            write_capture(writer, msg_list.iter())
+                        } else {
+                            write_ron(writer, msg_list.iter())
+                        }
+                        .with_context(|| format!("Couldn't save to {}", file_path.display()));
+                        if let Err(err) = res {
+                            bus.publish_err(err);
+                            return;
                         }
 
                         *msg_list_dir.lock().unwrap() = file_path
@@ -383,293 +2234,526 @@ impl MsgListPanel {
                             .map_or_else(|| ".".into(), ToOwned::to_owned);
                         log::debug!("Saved Midi messages to: {}", file_path.display());
                     }
-                    Err(err) => {
-                        log::error!("{err}");
-                        let _ = err_tx.send(err);
-                    }
+                    Err(err) => bus.publish_err(err),
                 }
             }
         });
     }
 }
 
-fn write_cc_msg(w: &mut dyn fmt::Write, msg: &midi_msg::ControlChange) -> std::fmt::Result {
-    use midi_msg::ControlChange::*;
-    match msg {
-        BankSelect(val) => write!(w, "Bank Select {val}"),
-        ModWheel(val) => write!(w, "Mod Wheel {val}"),
-        Breath(val) => write!(w, "Breath {val}"),
-        Undefined { control, value } => {
-            write!(w, "Undef ctrl {control} val {value}")
-        }
-        UndefinedHighRes {
-            control1,
-            control2,
-            value,
-        } => write!(
-            w,
-            "Undef High Res ctrl ({control1}, {control2}) val {value}"
-        ),
-        Foot(val) => write!(w, "Foot {val}"),
-        Portamento(val) => write!(w, "Portamento {val}"),
-        Volume(val) => write!(w, "Volume {val}"),
-        Balance(val) => write!(w, "Balance {val}"),
-        Pan(val) => write!(w, "Pan {val}"),
-        Expression(val) => write!(w, "Expression {val}"),
-        Effect1(val) => write!(w, "Effect 1 {val}"),
-        Effect2(val) => write!(w, "Effect 2 {val}"),
-        GeneralPurpose1(val) => write!(w, "General Purpose 1 {val}"),
-        GeneralPurpose2(val) => write!(w, "General Purpose 2 {val}"),
-        GeneralPurpose3(val) => write!(w, "General Purpose 3 {val}"),
-        GeneralPurpose4(val) => write!(w, "General Purpose 4 {val}"),
-        GeneralPurpose5(val) => write!(w, "General Purpose 5 {val}"),
-        GeneralPurpose6(val) => write!(w, "General Purpose 6 {val}"),
-        GeneralPurpose7(val) => write!(w, "General Purpose 7 {val}"),
-        GeneralPurpose8(val) => write!(w, "General Purpose 8 {val}"),
-        Hold(val) => write!(w, "Hold {val}"),
-        Hold2(val) => write!(w, "Hold 2 {val}"),
-        TogglePortamento(val) => write!(w, "Toggle Portamento {val}"),
-        Sostenuto(val) => write!(w, "Sostenuto {val}"),
-        SoftPedal(val) => write!(w, "Soft Pedal {val}"),
-        ToggleLegato(val) => write!(w, "Toggle Legato {val}"),
-        SoundVariation(val) => write!(w, "Sound Variation {val}"),
-        Timbre(val) => write!(w, "Timbre {val}"),
-        ReleaseTime(val) => write!(w, "Release Time {val}"),
-        AttackTime(val) => write!(w, "Attack Time {val}"),
-        Brightness(val) => write!(w, "Brightness {val}"),
-        DecayTime(val) => write!(w, "Decay Time {val}"),
-        VibratoRate(val) => write!(w, "Vibrato Rate {val}"),
-        VibratoDepth(val) => write!(w, "Vibrato Depth {val}"),
-        VibratoDelay(val) => write!(w, "Vibrato Delay {val}"),
-        SoundControl1(val) => write!(w, "Sound Ctrl 1 {val}"),
-        SoundControl2(val) => write!(w, "Sound Ctrl 2 {val}"),
-        SoundControl3(val) => write!(w, "Sound Ctrl 3 {val}"),
-        SoundControl4(val) => write!(w, "Sound Ctrl 4 {val}"),
-        SoundControl5(val) => write!(w, "Sound Ctrl 5 {val}"),
-        SoundControl6(val) => write!(w, "Sound Ctrl 6 {val}"),
-        SoundControl7(val) => write!(w, "Sound Ctrl 7 {val}"),
-        SoundControl8(val) => write!(w, "Sound Ctrl 8 {val}"),
-        SoundControl9(val) => write!(w, "Sound Ctrl 9 {val}"),
-        SoundControl10(val) => write!(w, "Sound Ctrl 10 {val}"),
-        HighResVelocity(val) => write!(w, "High Res Velocity {val}"),
-        PortamentoControl(val) => write!(w, "Portamento Control {val}"),
-        Effects1Depth(val) => write!(w, "Effects 1 Depth {val}"),
-        Effects2Depth(val) => write!(w, "Effects 2 Depth {val}"),
-        Effects3Depth(val) => write!(w, "Effects 3 Depth {val}"),
-        Effects4Depth(val) => write!(w, "Effects 4 Depth {val}"),
-        Effects5Depth(val) => write!(w, "Effects 5 Depth {val}"),
-        ReverbSendLevel(val) => write!(w, "Reverb Send Level {val}"),
-        TremoloDepth(val) => write!(w, "Tremolo Depth {val}"),
-        ChorusSendLevel(val) => write!(w, "Chorus Send Level {val}"),
-        CelesteDepth(val) => write!(w, "Celeste Depth {val}"),
-        PhaserDepth(val) => write!(w, "Phaser Depth {val}"),
-        Parameter(param) => write!(w, "Parameter {param:?}"),
-        DataEntry(val) => write!(w, "Data Entry w{val:04x}"),
-        DataEntry2(val1, val2) => write!(w, "Data Entry 2 x{val1:02x} x{val2:02x}"),
-        DataIncrement(val) => write!(w, "Data Inc {val}"),
-        DataDecrement(val) => write!(w, "Data Dec {val}"),
-    }
-}
-
-fn write_chan_voice_msg(
-    w: &mut dyn fmt::Write,
-    msg: &midi_msg::ChannelVoiceMsg,
-) -> std::fmt::Result {
-    use midi_msg::ChannelVoiceMsg::*;
-    match msg {
-        NoteOn { note, velocity } => write!(w, "Note {note} On vel. {velocity}"),
-        NoteOff { note, velocity } => write!(w, "Note {note} Off vel. {velocity}"),
-        ControlChange { control } => {
-            write!(w, "CC ")?;
-            write_cc_msg(w, control)
-        }
-        HighResNoteOn { note, velocity } => {
-            write!(w, "High Res Note {note} On vel. {velocity}")
-        }
-        HighResNoteOff { note, velocity } => {
-            write!(w, "High Res Note {note} Off vel. {velocity}")
-        }
-        PolyPressure { note, pressure } => {
-            write!(w, "Poly Note {note} Pressure {pressure}")
-        }
-        ChannelPressure { pressure } => write!(w, "Channel Pressure {pressure}"),
-        ProgramChange { program } => write!(w, "Program Change {program}"),
-        PitchBend { bend } => write!(w, "Pitch Bend {bend}"),
-    }
-}
-
-fn write_poly_mode(w: &mut dyn fmt::Write, pm: &midi_msg::PolyMode) -> std::fmt::Result {
-    use midi_msg::PolyMode::*;
-    match pm {
-        Mono(n_chans) => write!(w, "Mono {n_chans} chan(s)"),
-        Poly => w.write_str("Poly"),
-    }
-}
-
-fn write_chan_mode_msg(w: &mut dyn fmt::Write, msg: &midi_msg::ChannelModeMsg) -> std::fmt::Result {
-    use midi_msg::ChannelModeMsg::*;
-    match msg {
-        AllSoundOff => w.write_str("All Sound Off"),
-        AllNotesOff => w.write_str("All Notes Off"),
-        ResetAllControllers => w.write_str("Reset All Controllers"),
-        OmniMode(om) => write!(w, "Onmi Mode {om}"),
-        PolyMode(pm) => {
-            w.write_str("Poly Mode ")?;
-            write_poly_mode(w, pm)
-        }
-        LocalControl(lc) => write!(w, "Local Control {lc}"),
-    }
-}
-
-fn write_time_code_type(w: &mut dyn fmt::Write, tct: &midi_msg::TimeCodeType) -> std::fmt::Result {
-    use midi_msg::TimeCodeType::*;
-    w.write_str(match tct {
-        FPS24 => "24 FPS",
-        FPS25 => "25 FPS",
-        DF30 => "30 FPS D.F.",
-        NDF30 => "30 FPS nD.F.",
-    })
-}
-
-fn write_time_code(w: &mut dyn fmt::Write, tc: &midi_msg::TimeCode) -> std::fmt::Result {
-    write!(
-        w,
-        "{} frame(s) {}:{}:{} ",
-        tc.frames, tc.hours, tc.minutes, tc.seconds,
-    )?;
-    write_time_code_type(w, &tc.code_type)
-}
-
-fn write_sys_com_msg(w: &mut dyn fmt::Write, msg: &midi_msg::SystemCommonMsg) -> std::fmt::Result {
-    use midi_msg::SystemCommonMsg::*;
-    match msg {
-        TimeCodeQuarterFrame1(tc) => {
-            w.write_str("Time Code ¼ Frame 1 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame2(tc) => {
-            w.write_str("Time Code ¼ Frame 2 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame3(tc) => {
-            w.write_str("Time Code ¼ Frame 3 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame4(tc) => {
-            w.write_str("Time Code ¼ Frame 4 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame5(tc) => {
-            w.write_str("Time Code ¼ Frame 5 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame6(tc) => {
-            w.write_str("Time Code ¼ Frame 6 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame7(tc) => {
-            w.write_str("Time Code ¼ Frame 7 ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeQuarterFrame8(tc) => {
-            w.write_str("Time Code ¼ Frame 8 ")?;
-            write_time_code(w, tc)
-        }
-        SongPosition(pos) => write!(w, "Song Pos. {pos}"),
-        SongSelect(sel) => write!(w, "Song Sel. {sel}"),
-        TuneRequest => write!(w, "Tune Req."),
-    }
-}
+impl MsgListPanel {
+    #[cfg(feature = "save")]
+    fn open(&self) {
+        let bus = self.bus.clone();
+        let load_tx = self.load_tx.clone();
+        let msg_list_dir = self.msg_list_dir.clone();
+        std::thread::spawn(move || {
+            let file_path = add_open_filters(rfd::FileDialog::new())
+                .set_directory(&*msg_list_dir.lock().unwrap().clone())
+                .pick_file();
 
-fn write_sys_rt_msg(w: &mut dyn fmt::Write, msg: &midi_msg::SystemRealTimeMsg) -> std::fmt::Result {
-    use midi_msg::SystemRealTimeMsg::*;
-    w.write_str(match msg {
-        TimingClock => "Timing Clock",
-        Start => "Start",
-        Continue => "Continue",
-        Stop => "Stop",
-        ActiveSensing => "Active Sensing",
-        SystemReset => "System Reset",
-    })
-}
-
-fn write_universal_rt_msg(
-    w: &mut dyn fmt::Write,
-    msg: &midi_msg::UniversalRealTimeMsg,
-) -> std::fmt::Result {
-    use midi_msg::UniversalRealTimeMsg::*;
-    match msg {
-        TimeCodeFull(tc) => {
-            write!(w, "Full Time Code ")?;
-            write_time_code(w, tc)
-        }
-        TimeCodeUserBits(user_bits) => write!(w, "Time Code {user_bits:?}"),
-        ShowControl(show_ctrl) => write!(w, "Show Ctrl {show_ctrl:?}"),
-        TimeSignature(t_sign) => write!(w, "Time Sign. {t_sign:?}"),
-        TimeSignatureDelayed(t_sign) => write!(w, "Time Sign. delayed {t_sign:?}"),
-        MasterVolume(val) => write!(w, "Master Vol. {val}"),
-        MasterBalance(val) => write!(w, "Master Balance {val}"),
-        MasterFineTuning(val) => write!(w, "Master fine Tuning {val}"),
-        MasterCoarseTuning(val) => write!(w, "Master coarse Tuning {val}"),
-        other => write!(w, "{:?}", other),
-    }
-}
-
-fn write_sysex_msg(w: &mut dyn fmt::Write, msg: &midi_msg::SystemExclusiveMsg) -> std::fmt::Result {
-    use midi_msg::SystemExclusiveMsg::*;
-    match msg {
-        Commercial { id, data } => {
-            write!(
-                w,
-                "{id:?} data {}",
-                bytes::Displayable::from(data.as_slice())
-            )
+            if let Some(file_path) = file_path {
+                match read_msgs(&file_path) {
+                    Ok(ReadMsgs { origins, err, .. }) => {
+                        if let Some(err) = err {
+                            bus.publish_err(err);
+                        }
+                        *msg_list_dir.lock().unwrap() = file_path
+                            .parent()
+                            .map_or_else(|| ".".into(), ToOwned::to_owned);
+                        let _ = load_tx.send(Loaded {
+                            description: file_path.display().to_string(),
+                            msgs: origins.into_iter().map(|origin| (origin, None)).collect(),
+                        });
+                    }
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+        });
+    }
+
+    /// Replaces the list with the last loaded capture, `LOAD_CHUNK_LEN` messages per update.
+    ///
+    /// Returns `true` while the capture is being loaded.
+    #[cfg(feature = "save")]
+    fn pop_loaded(&mut self) -> bool {
+        if let Some(loaded) = self.load_rx.try_iter().last() {
+            if let Some(loading) = self.loading.take() {
+                self.port_aliases = loading.port_aliases;
+            }
+            self.clear();
+            self.loading = Some(Loading {
+                description: loaded.description,
+                len: loaded.msgs.len(),
+                msgs: loaded.msgs.into_iter(),
+                parsers: HashMap::new(),
+                port_aliases: std::mem::take(&mut self.port_aliases),
+            });
         }
-        NonCommercial { data } => {
-            write!(
-                w,
-                "Non-com. data {}",
-                bytes::Displayable::from(data.as_slice())
-            )
+
+        let mut loading = match self.loading.take() {
+            Some(loading) => loading,
+            None => return false,
+        };
+
+        let mut results = Vec::new();
+        for (origin, source) in loading.msgs.by_ref().take(LOAD_CHUNK_LEN) {
+            loading
+                .parsers
+                .entry(source.clone())
+                .or_default()
+                .parse_resync(origin, &mut results);
+            self.source = source;
+            for res in results.drain(..) {
+                let _ = self.push(&res);
+            }
         }
-        UniversalRealTime { device, msg } => {
-            write!(w, "UniRT {device:?} ")?;
-            write_universal_rt_msg(w, msg)
+        self.source = None;
+        self.steady_len = self.list.len();
+
+        if !loading.msgs.as_slice().is_empty() {
+            self.loading = Some(loading);
+            return true;
         }
-        UniversalNonRealTime { device, msg } => write!(w, "UniNonRT {device:?} {msg:?}"),
+
+        self.port_aliases = loading.port_aliases;
+        log::debug!(
+            "Loaded {} Midi messages from {}",
+            loading.len,
+            loading.description
+        );
+
+        false
     }
-}
 
-fn write_midi_msg(w: &mut dyn fmt::Write, msg: &midi_msg::MidiMsg) -> std::fmt::Result {
-    use midi_msg::MidiMsg::*;
-    match msg {
-        ChannelVoice { channel, msg } => {
-            write!(w, "{channel:?} Voice ")?;
-            write_chan_voice_msg(w, msg)
+    /// Saves the last messages so they are restored on next launch.
+    ///
+    /// This is only called on exit, since writing a large session would stall the UI.
+    #[cfg(feature = "save")]
+    pub fn save_session(&self) {
+        let file_path = match session_path() {
+            Some(file_path) => file_path,
+            None => return,
+        };
+
+        if !self.restores_session {
+            let _ = std::fs::remove_file(&file_path);
+            return;
         }
-        RunningChannelVoice { channel, msg } => {
-            write!(w, "{channel:?} Voice (running) ")?;
-            write_chan_voice_msg(w, msg)
+
+        let start = self.list.len().saturating_sub(self.session_max_msgs);
+        // Don't lose the previous session if writing fails midway
+        let tmp_path = file_path.with_extension("tmp");
+        let res = (|| -> anyhow::Result<()> {
+            if let Some(dir) = file_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let file = std::fs::File::create(&tmp_path)?;
+            write_capture(std::io::BufWriter::new(file), self.list[start..].iter())?;
+            std::fs::rename(&tmp_path, &file_path)?;
+
+            Ok(())
+        })();
+
+        if let Err(err) = res {
+            log::warn!("Couldn't save session to {}: {err:#}", file_path.display());
         }
-        ChannelMode { channel, msg } => {
-            write!(w, "{channel:?} Mode ")?;
-            write_chan_mode_msg(w, msg)
+    }
+
+    /// Replaces the list with the messages of several captures in chronological order.
+    ///
+    /// Captures from different machines only share the wall-clock time, so the
+    /// timestamps are rebased on the wall-clock time of the first message.
+    #[cfg(feature = "save")]
+    fn merge(&self) {
+        let bus = self.bus.clone();
+        let load_tx = self.load_tx.clone();
+        let msg_list_dir = self.msg_list_dir.clone();
+        std::thread::spawn(move || {
+            let file_paths = match add_open_filters(rfd::FileDialog::new())
+                .set_directory(&*msg_list_dir.lock().unwrap().clone())
+                .pick_files()
+            {
+                Some(file_paths) => file_paths,
+                None => return,
+            };
+
+            let mut msgs = Vec::new();
+            for file_path in file_paths.iter() {
+                match read_msgs(file_path) {
+                    Ok(ReadMsgs {
+                        has_wall_ts: false, ..
+                    }) => bus.publish_err(anyhow::anyhow!(
+                        "Couldn't merge {}: no wall-clock time recorded. \
+                         Only compressed captures and RON captures saved by this version \
+                         can be merged",
+                        file_path.display(),
+                    )),
+                    Ok(ReadMsgs { origins, err, .. }) => {
+                        if let Some(err) = err {
+                            bus.publish_err(err);
+                        }
+                        let source: Arc<str> = file_path
+                            .file_name()
+                            .map_or_else(
+                                || file_path.display().to_string(),
+                                |name| name.to_string_lossy().to_string(),
+                            )
+                            .into();
+                        msgs.extend(
+                            origins
+                                .into_iter()
+                                .map(|origin| (origin, Some(source.clone()))),
+                        );
+                    }
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+
+            // Stable: keeps the order of the messages of a file with the same time
+            msgs.sort_by_key(|(origin, _)| origin.wall_ts);
+            if let Some(start) = msgs.first().map(|(origin, _)| origin.wall_ts) {
+                for (origin, _) in msgs.iter_mut() {
+                    origin.ts = origin
+                        .wall_ts
+                        .duration_since(start)
+                        .unwrap_or_default()
+                        .as_micros() as u64;
+                }
+            }
+
+            if let Some(dir) = file_paths.first().and_then(|file_path| file_path.parent()) {
+                *msg_list_dir.lock().unwrap() = dir.to_owned();
+            }
+            let _ = load_tx.send(Loaded {
+                description: format!("{} captures", file_paths.len()),
+                msgs,
+            });
+        });
+    }
+
+    /// Saves the statistics & the visible rows & columns as a standalone HTML report.
+    ///
+    /// The report is rendered off the UI thread from a snapshot of the list.
+    #[cfg(feature = "save")]
+    pub fn export_html(
+        &self,
+        ports: Arc<super::port::DirectionalPorts>,
+        backpressure: midi::backpressure::Metrics,
+        port_colors: super::settings::PortColors,
+    ) {
+        let snapshot = self.table_snapshot();
+        let bus = self.bus.clone();
+        let msg_list_dir = self.msg_list_dir.clone();
+        std::thread::spawn(move || {
+            use anyhow::Context;
+
+            let file_path = match rfd::FileDialog::new()
+                .add_filter("HTML report (html)", &["html"])
+                .set_directory(&*msg_list_dir.lock().unwrap().clone())
+                .set_file_name("midi_report.html")
+                .save_file()
+            {
+                Some(file_path) => file_path,
+                None => return,
+            };
+
+            let summary = super::stats::Summary::new(snapshot.list.iter(), &ports, backpressure);
+            let (header, rows) = snapshot.table();
+            let html = super::report::render(&summary, &header, &rows, &port_colors);
+
+            match std::fs::write(&file_path, html)
+                .with_context(|| format!("Couldn't save to {}", file_path.display()))
+            {
+                Ok(()) => {
+                    *msg_list_dir.lock().unwrap() = file_path
+                        .parent()
+                        .map_or_else(|| ".".into(), ToOwned::to_owned);
+                    log::debug!("Exported HTML report to: {}", file_path.display());
+                }
+                Err(err) => bus.publish_err(err),
+            }
+        });
+    }
+
+    /// Exports the SysEx messages in the `SaveScope` as `.syx` files.
+    #[cfg(feature = "save")]
+    fn export_syx(&self) {
+        let msgs: Vec<Arc<[u8]>> = self
+            .scoped_list()
+            .iter()
+            .filter(|msg| midi::syx::is_sysex(msg.raw()))
+            .map(|msg| msg.raw.0.clone())
+            .collect();
+
+        if msgs.is_empty() {
+            self.bus
+                .publish_err(anyhow::anyhow!("No SysEx messages to export"));
+            return;
+        }
+
+        let bus = self.bus.clone();
+        let export = self.syx_export;
+        let msg_list_dir = self.msg_list_dir.clone();
+        std::thread::spawn(move || {
+            use anyhow::Context;
+            use std::fs;
+
+            let dialog =
+                rfd::FileDialog::new().set_directory(&*msg_list_dir.lock().unwrap().clone());
+            let res = match export {
+                SyxExport::Concatenated => {
+                    let file_path = match dialog
+                        .add_filter("SysEx (syx)", &["syx"])
+                        .set_file_name("dump.syx")
+                        .save_file()
+                    {
+                        Some(file_path) => file_path,
+                        None => return,
+                    };
+
+                    fs::write(&file_path, msgs.concat())
+                        .with_context(|| format!("Couldn't save to {}", file_path.display()))
+                        .map(|()| file_path.parent().map(ToOwned::to_owned))
+                }
+                SyxExport::OnePerMessage => {
+                    let dir = match dialog.pick_folder() {
+                        Some(dir) => dir,
+                        None => return,
+                    };
+
+                    msgs.iter()
+                        .enumerate()
+                        .try_for_each(|(idx, msg)| {
+                            let file_path = dir.join(format!("sysex_{:04}.syx", idx + 1));
+                            fs::write(&file_path, msg).with_context(|| {
+                                format!("Couldn't save to {}", file_path.display())
+                            })
+                        })
+                        .map(|()| Some(dir))
+                }
+            };
+
+            match res {
+                Ok(dir) => {
+                    if let Some(dir) = dir {
+                        *msg_list_dir.lock().unwrap() = dir;
+                    }
+                    log::debug!("Exported {} SysEx messages", msgs.len());
+                }
+                Err(err) => bus.publish_err(err),
+            }
+        });
+    }
+}
+
+/// Formats `val` with a comma as thousands separator.
+pub fn thousands(val: u64) -> String {
+    let digits = val.to_string();
+    let mut res = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % 3 == 0 {
+            res.push(',');
         }
-        RunningChannelMode { channel, msg } => {
-            write!(w, "{channel:?} Mode (running) ")?;
-            write_chan_mode_msg(w, msg)
+        res.push(digit);
+    }
+
+    res
+}
+
+/// Writes the messages in RON format, one message per line.
+#[cfg(feature = "save")]
+pub fn write_ron<'a>(
+    mut writer: impl std::io::Write,
+    msg_list: impl Iterator<Item = &'a Arc<MsgParseResult>>,
+) -> anyhow::Result<()> {
+    let config = ron::ser::PrettyConfig::new();
+    let new_line = config.new_line.clone();
+    // Custom config to keep message fields on a single line
+    // while using spaces between the fields and items.
+    let config = config.new_line(" ".into()).indentor("".into());
+
+    for msg in msg_list {
+        ron::ser::to_writer_pretty(&mut writer, msg.as_ref(), config.clone())?;
+        writer.write_all(new_line.as_bytes())?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the messages in pcapng format, one packet per message & repetition.
+///
+/// Each port is an interface. The parsed messages are stored as packet comments.
+#[cfg(feature = "save")]
+pub fn write_pcapng<'a>(
+    writer: impl std::io::Write,
+    msg_list: impl Iterator<Item = &'a Arc<MsgParseResult>>,
+) -> anyhow::Result<()> {
+    let mut writer =
+        midi::pcapng::Writer::new(writer, &[PortNb::One.as_str(), PortNb::Two.as_str()])?;
+
+    for msg in msg_list {
+        // Skip backpressure summaries
+        if msg.raw().is_empty() {
+            continue;
         }
-        SystemCommon { msg } => {
-            w.write_str("SysCom ")?;
-            write_sys_com_msg(w, msg)
+
+        for origin in msg.origin().repeat(msg.repetitions, msg.last_rep_ts) {
+            writer.write_packet(
+                msg.port_nb.idx() as u32,
+                origin.wall_ts,
+                msg.raw(),
+                Some(msg.parsed_str()),
+            )?;
         }
-        SystemRealTime { msg } => {
-            w.write_str("SysRT ")?;
-            write_sys_rt_msg(w, msg)
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Adds the filters for the files supported by `read_msgs`.
+#[cfg(feature = "save")]
+pub fn add_open_filters(dialog: rfd::FileDialog) -> rfd::FileDialog {
+    dialog
+        .add_filter("Compressed capture (msnf)", &[midi::capture::EXTENSION])
+        .add_filter("Rusty Object Notation (ron)", &["ron"])
+        .add_filter("MIDI-OX / ReceiveMIDI log (txt, log)", &["txt", "log"])
+}
+
+/// Messages read by `read_msgs`.
+#[cfg(feature = "save")]
+pub struct ReadMsgs {
+    pub origins: Vec<midi::msg::Origin>,
+    /// The error which truncated the capture, if any.
+    pub err: Option<anyhow::Error>,
+    /// Whether the wall-clock times were recorded, they are derived from
+    /// the time of loading and the backend timestamps otherwise.
+    pub has_wall_ts: bool,
+}
+
+/// Reads the messages of a saved capture or of the log of another Midi monitor.
+///
+/// The messages of a truncated capture are returned along with the error.
+#[cfg(feature = "save")]
+pub fn read_msgs(file_path: &std::path::Path) -> anyhow::Result<ReadMsgs> {
+    use anyhow::Context;
+    use std::{fs, io};
+
+    let has_ext = |expected: &str| {
+        file_path
+            .extension()
+            .map_or(false, |ext| ext.eq_ignore_ascii_case(expected))
+    };
+
+    let res = if has_ext(midi::capture::EXTENSION) {
+        fs::File::open(file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(midi::capture::read_all(file)?))
+            .map(|(origins, err)| {
+                let err = err.map(|err| {
+                    anyhow::Error::from(err).context(format!(
+                        "{} is truncated after {} messages",
+                        file_path.display(),
+                        origins.len(),
+                    ))
+                });
+                ReadMsgs {
+                    origins,
+                    err,
+                    has_wall_ts: true,
+                }
+            })
+    } else if has_ext("ron") {
+        let start = SystemTime::now();
+        fs::File::open(file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| super::replay::read_ron(io::BufReader::new(file)))
+            .map(|loaded| {
+                let has_wall_ts = loaded.iter().all(|loaded| loaded.wall_ts.is_some());
+                let origins = loaded
+                    .into_iter()
+                    .map(|loaded| midi::msg::Origin {
+                        ts: loaded.event.ts,
+                        wall_ts: loaded
+                            .wall_ts
+                            .unwrap_or_else(|| start + Duration::from_micros(loaded.event.ts)),
+                        port_nb: loaded.port_nb,
+                        buffer: loaded.event.buffer,
+                    })
+                    .collect();
+                ReadMsgs {
+                    origins,
+                    err: None,
+                    has_wall_ts,
+                }
+            })
+    } else {
+        fs::read_to_string(file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| {
+                let (format, origins) = midi::import::import(&text, SystemTime::now())?;
+                log::debug!("Importing {} log", format);
+                Ok(ReadMsgs {
+                    origins,
+                    err: None,
+                    has_wall_ts: false,
+                })
+            })
+    };
+
+    res.with_context(|| format!("Couldn't load {}", file_path.display()))
+}
+
+/// Path of the file holding the last messages of the previous session.
+#[cfg(feature = "save")]
+fn session_path() -> Option<PathBuf> {
+    directories_next::ProjectDirs::from("", "", super::APP_ID)
+        .map(|dirs| dirs.data_dir().join(SESSION_FILE_NAME))
+}
+
+#[cfg(feature = "save")]
+fn load_session() -> Option<Loaded> {
+    let file_path = session_path().filter(|file_path| file_path.exists())?;
+    match read_msgs(&file_path) {
+        Ok(ReadMsgs { origins, err, .. }) => {
+            if let Some(err) = err {
+                log::warn!("{err:#}");
+            }
+
+            Some(Loaded {
+                description: "the previous session".to_string(),
+                msgs: origins.into_iter().map(|origin| (origin, None)).collect(),
+            })
         }
-        SystemExclusive { msg } => {
-            w.write_str("SysEx ")?;
-            write_sysex_msg(w, msg)
+        Err(err) => {
+            log::warn!("{err:#}");
+            None
         }
     }
 }
+
+/// Writes the messages in the compressed binary capture format.
+#[cfg(feature = "save")]
+pub fn write_capture<'a>(
+    writer: impl std::io::Write,
+    msg_list: impl Iterator<Item = &'a Arc<MsgParseResult>>,
+) -> anyhow::Result<()> {
+    let mut writer = midi::capture::Writer::new(writer)?;
+
+    for msg in msg_list {
+        // Skip backpressure summaries
+        if msg.raw().is_empty() {
+            continue;
+        }
+
+        writer.write_repeated(&msg.origin(), msg.repetitions, msg.last_rep_ts)?;
+    }
+
+    writer.finish()?.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use eframe::egui;
+use std::sync::Arc;
+
+use super::bus;
+use crate::midi::{self, MidiOut};
+
+/// Selects an output port, the list is refreshed when the selector is clicked.
+pub struct OutputSelector {
+    id: &'static str,
+    client_name: Arc<str>,
+    list: Vec<Arc<str>>,
+    pub selected: Option<Arc<str>>,
+}
+
+impl OutputSelector {
+    pub fn new(id: &'static str, client_name: Arc<str>, selected: Option<String>) -> Self {
+        Self {
+            id,
+            client_name,
+            list: Vec::new(),
+            selected: selected
+                .filter(|selected| !selected.is_empty())
+                .map(Arc::from),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, bus: &bus::Bus) {
+        let resp = egui::ComboBox::from_id_source(self.id)
+            .selected_text(self.selected.as_deref().unwrap_or("Select an output"))
+            .show_ui(ui, |ui| {
+                for output in self.list.iter() {
+                    ui.selectable_value(&mut self.selected, Some(output.clone()), output.as_ref());
+                }
+            });
+
+        if resp.response.clicked() {
+            match midi::output::list(&self.client_name) {
+                Ok(list) => self.list = list,
+                Err(err) => bus.publish_err(anyhow::anyhow!("Failed to list outputs: {err}")),
+            }
+        }
+    }
+
+    /// Connects to the selected output, publishing errors on the `bus`.
+    pub fn connect(&self, bus: &bus::Bus) -> Option<MidiOut> {
+        let output = self.selected.clone()?;
+        MidiOut::connect(&self.client_name, output.clone())
+            .map_err(|err| bus.publish_err(anyhow::anyhow!("Failed to connect to {output}: {err}")))
+            .ok()
+    }
+
+    pub fn storage_string(&self) -> String {
+        self.selected.as_deref().unwrap_or_default().to_string()
+    }
+}
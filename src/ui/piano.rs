@@ -0,0 +1,150 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+
+use super::bus;
+use crate::midi::{
+    anatomy::note_name,
+    keyboard::{self, KeyState, Keyboards},
+    PortNb,
+};
+
+const WHITE_KEYS: usize = 75;
+const KEYBOARD_HEIGHT: f32 = 48.0;
+/// Size of the black keys relative to the white keys.
+const BLACK_KEY_WIDTH: f32 = 0.6;
+const BLACK_KEY_HEIGHT: f32 = 0.6;
+
+const PRESSED_COLOR: egui::Color32 = egui::Color32::from_rgb(0x30, 0x90, 0xff);
+const SUSTAINED_COLOR: egui::Color32 = egui::Color32::from_rgb(0x90, 0xc0, 0xf0);
+
+/// Keyboards lighting up the keys being held for each active port & channel.
+pub struct PianoPanel {
+    pub is_open: bool,
+    keyboards: Keyboards,
+    msg_rx: channel::Receiver<bus::Event>,
+}
+
+impl PianoPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        Self {
+            is_open: false,
+            keyboards: Keyboards::default(),
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+        }
+    }
+
+    pub fn update(&mut self) {
+        bus::drain_msgs(&self.msg_rx, |origin| {
+            self.keyboards.handle(origin.port_nb, &origin.buffer)
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.is_open;
+        egui::Window::new("Keyboard")
+            .open(&mut is_open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                if ui
+                    .button("Reset")
+                    .on_hover_text("Release all the keys & forget the channels")
+                    .clicked()
+                {
+                    self.keyboards.clear();
+                }
+                ui.separator();
+
+                let active: Vec<(PortNb, u8)> = self.keyboards.active().collect();
+                if active.is_empty() {
+                    ui.label("No notes played");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (port_nb, channel) in active {
+                        let state = self.keyboards.get(port_nb, channel);
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} ch{}", port_nb.as_str(), channel + 1));
+                            if state.is_sustained() {
+                                ui.weak("(sustain)");
+                            }
+                        });
+                        show_keyboard(ui, state);
+                    }
+                });
+            });
+        self.is_open = is_open;
+    }
+}
+
+fn is_black(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+fn key_color(state: KeyState, is_black: bool) -> egui::Color32 {
+    match state {
+        KeyState::Pressed(_) => PRESSED_COLOR,
+        KeyState::Sustained(_) => SUSTAINED_COLOR,
+        KeyState::Released if is_black => egui::Color32::BLACK,
+        KeyState::Released => egui::Color32::WHITE,
+    }
+}
+
+fn show_keyboard(ui: &mut egui::Ui, channel: &keyboard::Channel) {
+    let width = ui.available_width();
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width, KEYBOARD_HEIGHT), egui::Sense::hover());
+    let white_width = width / WHITE_KEYS as f32;
+    let black_size = egui::vec2(
+        white_width * BLACK_KEY_WIDTH,
+        KEYBOARD_HEIGHT * BLACK_KEY_HEIGHT,
+    );
+
+    // Compute the key rects, white keys first so the black keys are painted on top.
+    let mut white_keys = Vec::with_capacity(WHITE_KEYS);
+    let mut black_keys = Vec::with_capacity(128 - WHITE_KEYS);
+    let mut white_idx = 0;
+    for note in 0..128u8 {
+        if is_black(note) {
+            let center = rect.left() + white_idx as f32 * white_width;
+            let key_rect = egui::Rect::from_min_size(
+                egui::pos2(center - black_size.x / 2.0, rect.top()),
+                black_size,
+            );
+            black_keys.push((note, key_rect));
+        } else {
+            let key_rect = egui::Rect::from_min_size(
+                egui::pos2(rect.left() + white_idx as f32 * white_width, rect.top()),
+                egui::vec2(white_width, KEYBOARD_HEIGHT),
+            );
+            white_keys.push((note, key_rect));
+            white_idx += 1;
+        }
+    }
+
+    let painter = ui.painter_at(rect);
+    let stroke = egui::Stroke::new(1.0, egui::Color32::GRAY);
+    for &(note, key_rect) in white_keys.iter() {
+        painter.rect(key_rect, 0.0, key_color(channel.key(note), false), stroke);
+    }
+    for &(note, key_rect) in black_keys.iter() {
+        painter.rect(key_rect, 0.0, key_color(channel.key(note), true), stroke);
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let hovered = black_keys
+            .iter()
+            .chain(white_keys.iter())
+            .find(|(_, key_rect)| key_rect.contains(pos));
+        if let Some(&(note, _)) = hovered {
+            let text = match channel.key(note) {
+                KeyState::Pressed(velocity) => format!("{} vel {velocity}", note_name(note)),
+                KeyState::Sustained(velocity) => {
+                    format!("{} vel {velocity} (sustained)", note_name(note))
+                }
+                KeyState::Released => note_name(note),
+            };
+            response.on_hover_text(text);
+        }
+    }
+}
@@ -1,12 +1,31 @@
+use crossbeam_channel as channel;
 use eframe::{self, egui};
-use once_cell::sync::Lazy;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use crate::midi;
+use super::bus;
+pub use crate::ports::{DirectionalPorts, DISCONNECTED};
+use crate::{
+    midi,
+    time::{Duration, Instant},
+};
 
-static DISCONNECTED: Lazy<Arc<str>> = Lazy::new(|| "Disconnected".into());
 const STORAGE_PORT_1: &str = "port_1";
 const STORAGE_PORT_2: &str = "port_2";
+const STORAGE_PORT_1_SURFACE: &str = "port_1_surface";
+const STORAGE_PORT_2_SURFACE: &str = "port_2_surface";
+const STORAGE_PORT_ALIASES: &str = "port_aliases";
+const STORAGE_PORT_1_PATTERN: &str = "port_1_pattern";
+const STORAGE_PORT_2_PATTERN: &str = "port_2_pattern";
+#[cfg(feature = "profile")]
+const STORAGE_PORT_1_PROFILE: &str = "port_1_profile";
+#[cfg(feature = "profile")]
+const STORAGE_PORT_2_PROFILE: &str = "port_2_profile";
+#[cfg(feature = "profile")]
+const NO_PROFILE: &str = "No profile";
+
+/// Duration of the activity LED blink after a message is received.
+const ACTIVITY_BLINK: Duration = Duration::from_millis(150);
+const ACTIVITY_LED_RADIUS: f32 = 4.0;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -32,6 +51,8 @@ impl UniquePort {
 #[derive(Debug)]
 pub struct DirectionalPortView<'a> {
     pub list: &'a Vec<Arc<str>>,
+    caps: &'a BTreeMap<Arc<str>, midi::port::Capabilities>,
+    descriptions: &'a BTreeMap<Arc<str>, Arc<str>>,
     port_nb: midi::PortNb,
     cur: Arc<str>,
 }
@@ -44,6 +65,14 @@ impl<'a> DirectionalPortView<'a> {
         })
     }
 
+    fn caps(&self, port_name: &str) -> midi::port::Capabilities {
+        self.caps.get(port_name).copied().unwrap_or_default()
+    }
+
+    fn description(&self, port_name: &str) -> Option<&Arc<str>> {
+        self.descriptions.get(port_name)
+    }
+
     fn cur(&self) -> UniquePort {
         UniquePort {
             nb: self.port_nb,
@@ -52,59 +81,165 @@ impl<'a> DirectionalPortView<'a> {
     }
 }
 
+fn port_view(ports: &DirectionalPorts, port_nb: midi::PortNb) -> DirectionalPortView {
+    DirectionalPortView {
+        list: &ports.list,
+        caps: ports.caps(),
+        descriptions: ports.descriptions(),
+        port_nb,
+        cur: ports.cur(port_nb).clone(),
+    }
+}
+
 #[derive(Debug)]
-pub struct DirectionalPorts {
-    pub list: Vec<Arc<str>>,
-    cur: [Arc<str>; 2],
+pub enum Response {
+    Connect((midi::PortNb, Arc<str>)),
+    Disconnect(midi::PortNb),
+    CheckingList,
+    Identify(midi::PortNb),
+    /// Auto-connect pattern, empty to disable.
+    Pattern((midi::PortNb, String)),
+    Surface((midi::PortNb, midi::surface::Protocol)),
+    #[cfg(feature = "profile")]
+    Profile((midi::PortNb, Option<Arc<midi::profile::Profile>>)),
+}
+
+pub struct PortsPanel {
+    pub ports: Arc<DirectionalPorts>,
+    ports_rx: channel::Receiver<bus::Event>,
+    /// Backend in use according to the last ports received, see `take_backend`.
+    backend: Option<midi::port::Backend>,
+    activity_rx: channel::Receiver<bus::Event>,
+    last_activity: [Option<Instant>; 2],
+    /// Identity Reply received on each port.
+    identities: [Option<midi::identity::Reply>; 2],
+    pub aliases: midi::port::Aliases,
+    /// Alias being edited for the port connected to `PortNb`.
+    alias_edit: Option<(midi::PortNb, String)>,
+    /// Auto-connect patterns.
+    patterns: [String; 2],
+    surfaces: [midi::surface::Protocol; 2],
+    #[cfg(feature = "profile")]
+    profiles: Vec<Arc<midi::profile::Profile>>,
+    #[cfg(feature = "profile")]
+    cur_profiles: [Option<Arc<midi::profile::Profile>>; 2],
 }
 
-impl DirectionalPorts {
-    fn view(&self, port_nb: midi::PortNb) -> DirectionalPortView {
-        DirectionalPortView {
-            list: &self.list,
-            port_nb,
-            cur: self.cur[port_nb.idx()].clone(),
+impl PortsPanel {
+    pub fn new(bus: &bus::Bus, storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut surfaces = [midi::surface::Protocol::None; 2];
+        let mut aliases = midi::port::Aliases::default();
+        let mut patterns = [String::new(), String::new()];
+        if let Some(storage) = storage {
+            if let Some(stored) = storage.get_string(STORAGE_PORT_ALIASES) {
+                aliases = midi::port::Aliases::from_storage_str(&stored);
+            }
+            for (pattern, key) in patterns
+                .iter_mut()
+                .zip([STORAGE_PORT_1_PATTERN, STORAGE_PORT_2_PATTERN])
+            {
+                if let Some(stored) = storage.get_string(key) {
+                    *pattern = stored;
+                }
+            }
+            for (surface, key) in surfaces
+                .iter_mut()
+                .zip([STORAGE_PORT_1_SURFACE, STORAGE_PORT_2_SURFACE])
+            {
+                if let Some(protocol) = storage
+                    .get_string(key)
+                    .as_deref()
+                    .and_then(midi::surface::Protocol::from_storage_str)
+                {
+                    *surface = protocol;
+                }
+            }
+        }
+
+        #[cfg(feature = "profile")]
+        let (profiles, cur_profiles) = {
+            let (profiles, errors) = midi::profile::load_dir(&midi::profile::dir());
+            for err in errors {
+                bus.publish_err(err.into());
+            }
+
+            let mut cur_profiles = [None, None];
+            if let Some(storage) = storage {
+                for (cur, key) in cur_profiles
+                    .iter_mut()
+                    .zip([STORAGE_PORT_1_PROFILE, STORAGE_PORT_2_PROFILE])
+                {
+                    if let Some(name) = storage.get_string(key) {
+                        *cur = profiles.iter().find(|prof| prof.name == name).cloned();
+                    }
+                }
+            }
+
+            (profiles, cur_profiles)
+        };
+
+        Self {
+            ports: Arc::new(DirectionalPorts::default()),
+            ports_rx: bus.subscribe(bus::Event::is_ports_changed),
+            backend: None,
+            activity_rx: bus.subscribe(bus::Event::is_msg_batch),
+            last_activity: [None, None],
+            identities: [None, None],
+            aliases,
+            alias_edit: None,
+            patterns,
+            surfaces,
+            #[cfg(feature = "profile")]
+            profiles,
+            #[cfg(feature = "profile")]
+            cur_profiles,
         }
     }
 
-    fn update_from(&mut self, ports: &midi::Ports) {
-        self.list.clear();
-        self.list.extend(ports.list().cloned());
+    /// Returns the backend in use if ports were received since last call.
+    pub fn take_backend(&mut self) -> Option<midi::port::Backend> {
+        self.backend.take()
+    }
 
-        self.update_cur(midi::PortNb::One, ports);
-        self.update_cur(midi::PortNb::Two, ports);
+    /// Returns the alias of the port connected to `port_nb`, if any.
+    pub fn alias(&self, port_nb: midi::PortNb) -> Option<&Arc<str>> {
+        self.ports
+            .connected(port_nb)
+            .and_then(|port_name| self.aliases.get(port_name))
     }
 
-    fn update_cur(&mut self, port_nb: midi::PortNb, ports: &midi::Ports) {
-        self.cur[port_nb.idx()] = ports
-            .cur(port_nb)
-            .cloned()
-            .unwrap_or_else(|| DISCONNECTED.clone());
+    /// Returns the control surface protocol selected for `port_nb`.
+    pub fn surface(&self, port_nb: midi::PortNb) -> midi::surface::Protocol {
+        self.surfaces[port_nb.idx()]
     }
-}
 
-impl Default for DirectionalPorts {
-    fn default() -> Self {
-        Self {
-            list: Vec::new(),
-            cur: [DISCONNECTED.clone(), DISCONNECTED.clone()],
-        }
+    /// Returns the device profile selected for `port_nb`.
+    #[cfg(feature = "profile")]
+    pub fn profile(&self, port_nb: midi::PortNb) -> Option<&Arc<midi::profile::Profile>> {
+        self.cur_profiles[port_nb.idx()].as_ref()
     }
-}
 
-#[derive(Debug)]
-pub enum Response {
-    Connect((midi::PortNb, Arc<str>)),
-    Disconnect(midi::PortNb),
-    CheckingList,
-}
+    pub fn set_pattern(&mut self, port_nb: midi::PortNb, pattern: String) {
+        self.patterns[port_nb.idx()] = pattern;
+    }
 
-#[derive(Default)]
-pub struct PortsPanel {
-    pub ports: DirectionalPorts,
-}
+    /// Selects the device profile named `name` for `port_nb`, if it exists.
+    #[cfg(feature = "profile")]
+    pub fn select_profile(
+        &mut self,
+        port_nb: midi::PortNb,
+        name: &str,
+    ) -> Option<Arc<midi::profile::Profile>> {
+        let profile = self
+            .profiles
+            .iter()
+            .find(|prof| prof.name == name)
+            .cloned()?;
+        self.cur_profiles[port_nb.idx()] = Some(profile.clone());
+
+        Some(profile)
+    }
 
-impl PortsPanel {
     pub fn setup(storage: Option<&dyn eframe::Storage>) -> impl Iterator<Item = Response> {
         use Response::*;
 
@@ -120,6 +255,14 @@ impl PortsPanel {
                     resp.push(Connect((midi::PortNb::Two, port.into())));
                 }
             }
+            for (port_nb, key) in [
+                (midi::PortNb::One, STORAGE_PORT_1_PATTERN),
+                (midi::PortNb::Two, STORAGE_PORT_2_PATTERN),
+            ] {
+                if let Some(pattern) = storage.get_string(key).filter(|p| !p.trim().is_empty()) {
+                    resp.push(Pattern((port_nb, pattern)));
+                }
+            }
         }
 
         resp.into_iter()
@@ -129,11 +272,20 @@ impl PortsPanel {
     pub fn show(&mut self, port_nb: midi::PortNb, ui: &mut egui::Ui) -> Option<Response> {
         use Response::*;
 
-        let view = self.ports.view(port_nb);
+        self.pop_ports();
+        self.pop_activity();
+
+        self.show_activity(port_nb, ui);
+
+        let view = port_view(&self.ports, port_nb);
         let mut selected = view.cur();
 
-        let resp = egui::ComboBox::from_label(port_nb.as_str())
-            .selected_text(view.cur.as_ref())
+        let identity = self.identities[port_nb.idx()]
+            .as_ref()
+            .map(ToString::to_string);
+        let aliases = &self.aliases;
+        let combo = egui::ComboBox::from_label(port_nb.as_str())
+            .selected_text(aliases.display(&view.cur))
             .show_ui(ui, |ui| {
                 let mut resp = None;
 
@@ -149,17 +301,76 @@ impl PortsPanel {
                 }
 
                 for port in view.unique_ports_iter() {
-                    if ui
-                        .selectable_value(&mut selected, port.clone(), port.name.as_ref())
-                        .clicked()
-                    {
+                    let caps = view.caps(&port.name);
+                    let mut caps_str = caps.to_string();
+                    if aliases.get(&port.name).is_some() {
+                        caps_str = format!("{}\n{caps_str}", port.name);
+                    }
+                    let mut label = aliases.display(&port.name).to_string();
+                    if let Some(desc) = view.description(&port.name) {
+                        // Endpoint names alone are ambiguous, e.g. "Port 1"
+                        label = format!("{label} ({})", desc.replace('\n', ", "));
+                        caps_str = format!("{desc}\n{caps_str}");
+                    }
+                    let port_resp = ui
+                        .add_enabled_ui(!caps.is_refused, |ui| {
+                            ui.selectable_value(&mut selected, port.clone(), label)
+                        })
+                        .inner
+                        .on_hover_text(caps_str.as_str())
+                        .on_disabled_hover_text(caps_str.as_str());
+
+                    if port_resp.clicked() {
                         resp = Some(Connect((port_nb, port.name)));
                     }
                 }
 
                 resp
-            })
-            .inner;
+            });
+        if let Some(identity) = identity {
+            combo.response.on_hover_text(identity);
+        }
+        let resp = combo.inner;
+        if let Some(Some(Disconnect(_))) = resp {
+            // The Controller also drops the pattern so as not to reconnect right away.
+            self.patterns[port_nb.idx()].clear();
+        }
+
+        self.show_alias_edit(port_nb, ui);
+
+        let pattern_resp = ui
+            .add(
+                egui::TextEdit::singleline(&mut self.patterns[port_nb.idx()])
+                    .hint_text("Auto-connect")
+                    .desired_width(100.0),
+            )
+            .on_hover_text(
+                "Connect to the first port matching this pattern when disconnected\n\
+                 e.g. *Launchpad* or /^Keystep.*/ for a regex",
+            );
+        if pattern_resp.lost_focus() {
+            return Some(Pattern((port_nb, self.patterns[port_nb.idx()].clone())));
+        }
+
+        if ui
+            .add_enabled(
+                self.ports.connected(port_nb).is_some(),
+                egui::Button::new("Identify"),
+            )
+            .on_hover_text("Send an Identity Request to the device")
+            .clicked()
+        {
+            return Some(Identify(port_nb));
+        }
+
+        if let Some(surface_resp) = self.show_surface(port_nb, ui) {
+            return Some(surface_resp);
+        }
+
+        #[cfg(feature = "profile")]
+        if let Some(profile_resp) = self.show_profile(port_nb, ui) {
+            return Some(profile_resp);
+        }
 
         if let Some(None) = resp {
             Some(CheckingList)
@@ -168,20 +379,175 @@ impl PortsPanel {
         }
     }
 
+    fn show_alias_edit(&mut self, port_nb: midi::PortNb, ui: &mut egui::Ui) {
+        let port_name = match self.ports.connected(port_nb) {
+            Some(port_name) => port_name.clone(),
+            None => {
+                if matches!(self.alias_edit, Some((nb, _)) if nb == port_nb) {
+                    self.alias_edit = None;
+                }
+                ui.add_enabled(false, egui::Button::new("✏"));
+                return;
+            }
+        };
+
+        match self.alias_edit {
+            Some((nb, ref mut alias)) if nb == port_nb => {
+                let resp = ui.add(
+                    egui::TextEdit::singleline(alias)
+                        .hint_text(port_name.as_ref())
+                        .desired_width(120.0),
+                );
+                let is_validated = resp.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                if ui.button("✔").clicked() || is_validated {
+                    self.aliases.set(port_name, alias);
+                    self.alias_edit = None;
+                }
+            }
+            _ => {
+                if ui
+                    .button("✏")
+                    .on_hover_text("Rename the port, leave empty to use the port name")
+                    .clicked()
+                {
+                    let alias = self
+                        .aliases
+                        .get(&port_name)
+                        .map_or_else(String::new, ToString::to_string);
+                    self.alias_edit = Some((port_nb, alias));
+                }
+            }
+        }
+    }
+
+    fn show_surface(&mut self, port_nb: midi::PortNb, ui: &mut egui::Ui) -> Option<Response> {
+        use midi::surface::Protocol;
+
+        let cur = &mut self.surfaces[port_nb.idx()];
+        let prev = *cur;
+        egui::ComboBox::from_id_source(("surface", port_nb.idx()))
+            .selected_text(cur.as_str())
+            .show_ui(ui, |ui| {
+                for protocol in Protocol::ALL {
+                    ui.selectable_value(cur, protocol, protocol.as_str());
+                }
+            })
+            .response
+            .on_hover_text("Control surface protocol");
+
+        (*cur != prev).then(|| Response::Surface((port_nb, *cur)))
+    }
+
+    #[cfg(feature = "profile")]
+    fn show_profile(&mut self, port_nb: midi::PortNb, ui: &mut egui::Ui) -> Option<Response> {
+        if self.profiles.is_empty() {
+            return None;
+        }
+
+        let cur = &mut self.cur_profiles[port_nb.idx()];
+        let prev = cur.clone();
+        egui::ComboBox::from_id_source(("profile", port_nb.idx()))
+            .selected_text(cur.as_ref().map_or(NO_PROFILE, |prof| prof.name.as_str()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(cur, None, NO_PROFILE);
+                for profile in self.profiles.iter() {
+                    ui.selectable_value(cur, Some(profile.clone()), profile.name.as_str());
+                }
+            })
+            .response
+            .on_hover_text("Device profile");
+
+        (*cur != prev).then(|| Response::Profile((port_nb, cur.clone())))
+    }
+
     pub fn save(&mut self, storage: &mut dyn eframe::Storage) {
         storage.set_string(
             STORAGE_PORT_1,
-            self.ports.cur[midi::PortNb::One.idx()].to_string(),
+            self.ports.persistent_name(midi::PortNb::One).to_string(),
         );
         storage.set_string(
             STORAGE_PORT_2,
-            self.ports.cur[midi::PortNb::Two.idx()].to_string(),
+            self.ports.persistent_name(midi::PortNb::Two).to_string(),
         );
+
+        storage.set_string(STORAGE_PORT_ALIASES, self.aliases.storage_string());
+        for (pattern, key) in self
+            .patterns
+            .iter()
+            .zip([STORAGE_PORT_1_PATTERN, STORAGE_PORT_2_PATTERN])
+        {
+            storage.set_string(key, pattern.clone());
+        }
+
+        for (surface, key) in self
+            .surfaces
+            .iter()
+            .zip([STORAGE_PORT_1_SURFACE, STORAGE_PORT_2_SURFACE])
+        {
+            storage.set_string(key, surface.storage_str().to_string());
+        }
+
+        #[cfg(feature = "profile")]
+        for (cur, key) in self
+            .cur_profiles
+            .iter()
+            .zip([STORAGE_PORT_1_PROFILE, STORAGE_PORT_2_PROFILE])
+        {
+            let name = cur.as_ref().map_or("", |prof| prof.name.as_str());
+            storage.set_string(key, name.to_string());
+        }
     }
 }
 
 impl PortsPanel {
-    pub fn update(&mut self, midi_ports: &midi::Ports) {
-        self.ports.update_from(midi_ports);
+    fn pop_ports(&mut self) {
+        for event in self.ports_rx.try_iter() {
+            if let bus::Event::PortsChanged(ports) = event {
+                for port_nb in [midi::PortNb::One, midi::PortNb::Two] {
+                    if ports.cur(port_nb) != self.ports.cur(port_nb) {
+                        self.identities[port_nb.idx()] = None;
+                    }
+                }
+                self.backend = Some(ports.backend);
+                self.ports = ports;
+            }
+        }
+    }
+
+    fn pop_activity(&mut self) {
+        let now = Instant::now();
+        bus::drain_msgs(&self.activity_rx, |origin| {
+            self.last_activity[origin.port_nb.idx()] = Some(now);
+            if let Some(reply) = midi::identity::Reply::parse(&origin.buffer) {
+                self.identities[origin.port_nb.idx()] = Some(reply);
+            }
+        });
+    }
+
+    /// Shows an LED which blinks when messages are received on `port_nb`.
+    fn show_activity(&mut self, port_nb: midi::PortNb, ui: &mut egui::Ui) {
+        let is_active = match self.last_activity[port_nb.idx()] {
+            Some(last) if last.elapsed() < ACTIVITY_BLINK => true,
+            Some(_) => {
+                self.last_activity[port_nb.idx()] = None;
+                false
+            }
+            None => false,
+        };
+
+        let (rect, resp) = ui.allocate_exact_size(
+            egui::Vec2::splat(2.0 * ACTIVITY_LED_RADIUS),
+            egui::Sense::hover(),
+        );
+        let color = if is_active {
+            // Keep repainting until the LED is off
+            ui.ctx().request_repaint();
+            egui::Color32::GREEN
+        } else {
+            egui::Color32::DARK_GRAY
+        };
+        ui.painter()
+            .circle_filled(rect.center(), ACTIVITY_LED_RADIUS, color);
+        resp.on_hover_text("Activity");
     }
 }
@@ -0,0 +1,617 @@
+//! Filter expressions, e.g. `port:1 chan:10 type:cc cc>=70 cc<=79`.
+//!
+//! Consecutive terms must all match, unless separated by `OR`, which binds
+//! less tightly than `AND`. `NOT` negates the next term and parentheses
+//! group terms. Other words are searched in the parsed & raw messages, use
+//! quotes to search for text including spaces or keywords. A regular
+//! expression enclosed between `/` is matched against the parsed message.
+
+use std::{fmt, ops::RangeInclusive};
+
+use super::msg_list::MsgParseResult;
+use crate::midi::PortNb;
+
+/// Maximum nesting of parentheses & `NOT`, so that parsing can't overflow the stack.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Unexpected end of filter")]
+    UnexpectedEnd,
+    #[error("Unexpected `{}` in filter", .0)]
+    Unexpected(String),
+    #[error("Unterminated quote in filter")]
+    UnterminatedQuote,
+    #[error("Unterminated regular expression in filter")]
+    UnterminatedRegex,
+    #[error("Invalid regular expression in filter: {}", .0)]
+    Regex(#[from] regex::Error),
+    #[error("Invalid filter value `{}` for `{}`", .1, .0)]
+    InvalidValue(String, String),
+    #[error("Operator `{}` can't be used with `{}`", .1, .0)]
+    InvalidOperator(String, &'static str),
+}
+
+/// Message fields which can be compared to a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Port,
+    Channel,
+    Controller,
+    Note,
+    Velocity,
+    /// Controller, Aftertouch, Program or Pitch Bend value.
+    Value,
+}
+
+impl Field {
+    fn from_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "port" => Field::Port,
+            "chan" | "ch" | "channel" => Field::Channel,
+            "cc" => Field::Controller,
+            "note" => Field::Note,
+            "vel" | "velocity" => Field::Velocity,
+            "value" | "val" => Field::Value,
+            _ => return None,
+        })
+    }
+
+    fn range(self) -> RangeInclusive<u16> {
+        match self {
+            Field::Port => 1..=2,
+            Field::Channel => 1..=16,
+            Field::Value => 0..=0x3fff,
+            _ => 0..=0x7f,
+        }
+    }
+
+    fn get(self, port_nb: PortNb, buf: &[u8]) -> Option<u16> {
+        if self == Field::Port {
+            return Some(match port_nb {
+                PortNb::One => 1,
+                PortNb::Two => 2,
+            });
+        }
+
+        let status = *buf.first()?;
+        let data = |idx: usize| buf.get(idx).map(|byte| u16::from(*byte));
+        match (self, status & 0xf0) {
+            (Field::Channel, 0x80..=0xe0) => Some(u16::from(status & 0x0f) + 1),
+            (Field::Controller, 0xb0) => data(1),
+            (Field::Note, 0x80 | 0x90 | 0xa0) => data(1),
+            (Field::Velocity, 0x80 | 0x90) => data(2),
+            (Field::Value, 0xa0 | 0xb0) => data(2),
+            (Field::Value, 0xc0 | 0xd0) => data(1),
+            (Field::Value, 0xe0) => Some(data(1)? | (data(2)? << 7)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsgType {
+    Note,
+    NoteOn,
+    NoteOff,
+    PolyPressure,
+    ControlChange,
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+    Channel,
+    SysEx,
+    Common,
+    Realtime,
+    Clock,
+    Error,
+}
+
+impl MsgType {
+    fn from_value(value: &str) -> Option<Self> {
+        Some(match value {
+            "note" => MsgType::Note,
+            "noteon" | "on" => MsgType::NoteOn,
+            "noteoff" | "off" => MsgType::NoteOff,
+            "polyat" | "polypressure" => MsgType::PolyPressure,
+            "cc" => MsgType::ControlChange,
+            "pc" | "program" => MsgType::ProgramChange,
+            "at" | "aftertouch" | "pressure" => MsgType::ChannelPressure,
+            "pb" | "bend" | "pitchbend" => MsgType::PitchBend,
+            "channel" => MsgType::Channel,
+            "sysex" | "syx" => MsgType::SysEx,
+            "common" => MsgType::Common,
+            "realtime" | "rt" => MsgType::Realtime,
+            "clock" => MsgType::Clock,
+            "error" | "err" => MsgType::Error,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, msg: &MsgParseResult) -> bool {
+        let buf = msg.raw();
+        // Note On with a zero velocity is a Note Off
+        let is_note_off =
+            |status: u8| status & 0xf0 == 0x80 || (status & 0xf0 == 0x90 && buf.get(2) == Some(&0));
+
+        match (self, buf.first().copied()) {
+            (MsgType::Error, _) => msg.is_err(),
+            (_, None) => false,
+            (MsgType::Note, Some(status)) => matches!(status & 0xf0, 0x80 | 0x90),
+            (MsgType::NoteOn, Some(status)) => status & 0xf0 == 0x90 && !is_note_off(status),
+            (MsgType::NoteOff, Some(status)) => is_note_off(status),
+            (MsgType::PolyPressure, Some(status)) => status & 0xf0 == 0xa0,
+            (MsgType::ControlChange, Some(status)) => status & 0xf0 == 0xb0,
+            (MsgType::ProgramChange, Some(status)) => status & 0xf0 == 0xc0,
+            (MsgType::ChannelPressure, Some(status)) => status & 0xf0 == 0xd0,
+            (MsgType::PitchBend, Some(status)) => status & 0xf0 == 0xe0,
+            (MsgType::Channel, Some(status)) => (0x80..=0xef).contains(&status),
+            (MsgType::SysEx, Some(status)) => status == 0xf0,
+            (MsgType::Common, Some(status)) => (0xf1..=0xf7).contains(&status),
+            (MsgType::Realtime, Some(status)) => status >= 0xf8,
+            (MsgType::Clock, Some(status)) => status == 0xf8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Operators by decreasing length, so that `<=` is found before `<`.
+    const ALL: [(&'static str, Op); 7] = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("!=", Op::Ne),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+        (":", Op::Eq),
+        ("=", Op::Eq),
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Eq => ":",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+
+    fn compare(self, lhs: u16, rhs: u16) -> bool {
+        match self {
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+        }
+    }
+}
+
+/// A compiled filter expression.
+#[derive(Debug)]
+pub enum Query {
+    /// Lowercase text searched in the parsed & raw message.
+    Text(String),
+    /// Matched against the parsed message, case sensitive.
+    Regex(regex::Regex),
+    Type(MsgType),
+    Compare(Field, Op, u16),
+    Range(Field, RangeInclusive<u16>),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    /// Compiles `text`, returns `None` if it holds no terms.
+    pub fn parse(text: &str) -> Result<Option<Self>, Error> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+            depth: 0,
+        };
+        let query = parser.or()?;
+        match parser.tokens.next() {
+            Some(token) => Err(Error::Unexpected(token.to_string())),
+            None => Ok(Some(query)),
+        }
+    }
+
+    pub fn matches(&self, msg: &MsgParseResult) -> bool {
+        match self {
+            Query::Text(text) => {
+                msg.parsed_str().to_lowercase().contains(text) || msg.raw_str().contains(text)
+            }
+            Query::Regex(regex) => regex.is_match(msg.parsed_str()),
+            Query::Type(msg_type) => msg_type.matches(msg),
+            Query::Compare(field, op, rhs) => field
+                .get(msg.port_nb(), msg.raw())
+                .is_some_and(|lhs| op.compare(lhs, *rhs)),
+            Query::Range(field, range) => field
+                .get(msg.port_nb(), msg.raw())
+                .is_some_and(|val| range.contains(&val)),
+            Query::Not(query) => !query.matches(msg),
+            Query::And(queries) => queries.iter().all(|query| query.matches(msg)),
+            Query::Or(queries) => queries.iter().any(|query| query.matches(msg)),
+        }
+    }
+
+    /// Parses a `key`, operator & value term or falls back to a text search.
+    ///
+    /// Words with an unknown key, e.g. `time:12`, are also searched as text, as in
+    /// the filters saved before keys were supported.
+    fn term(word: &str) -> Result<Self, Error> {
+        let key_len = word
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(word.len());
+        let (key, rest) = word.split_at(key_len);
+        let (op, value) = match Op::ALL
+            .iter()
+            .find_map(|(op_str, op)| Some((*op, rest.strip_prefix(op_str)?)))
+        {
+            Some(op_value) if !key.is_empty() => op_value,
+            _ => return Ok(Query::Text(word.to_string())),
+        };
+        let invalid_value = || Error::InvalidValue(key.to_string(), value.to_string());
+
+        if key == "type" {
+            let msg_type = Query::Type(MsgType::from_value(value).ok_or_else(invalid_value)?);
+            return match op {
+                Op::Eq => Ok(msg_type),
+                Op::Ne => Ok(Query::Not(Box::new(msg_type))),
+                _ => Err(Error::InvalidOperator(key.to_string(), op.as_str())),
+            };
+        }
+
+        let field = match Field::from_key(key) {
+            Some(field) => field,
+            None => return Ok(Query::Text(word.to_string())),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u16>()
+                .ok()
+                .filter(|number| field.range().contains(number))
+                .ok_or_else(invalid_value)
+        };
+
+        match value.split_once('-') {
+            Some((start, end)) if op == Op::Eq => {
+                let range = number(start)?..=number(end)?;
+                if range.is_empty() {
+                    return Err(invalid_value());
+                }
+
+                Ok(Query::Range(field, range))
+            }
+            _ => Ok(Query::Compare(field, op, number(value)?)),
+        }
+    }
+}
+
+/// Words & quoted text are lowercase, regular expressions are kept as is.
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    Quoted(String),
+    Regex(String),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word == keyword)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::Word(word) => f.write_str(word),
+            Token::Quoted(text) => write!(f, "\"{text}\""),
+            Token::Regex(regex) => write!(f, "/{regex}/"),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => quoted.push(c),
+                        None => return Err(Error::UnterminatedQuote),
+                    }
+                }
+                tokens.push(Token::Quoted(quoted.to_lowercase()));
+            }
+            '/' => {
+                let mut regex = String::new();
+                loop {
+                    match chars.next() {
+                        Some('/') => break,
+                        // Escaped delimiter
+                        Some('\\') if chars.peek() == Some(&'/') => {
+                            regex.push('/');
+                            chars.next();
+                        }
+                        Some(c) => regex.push(c),
+                        None => return Err(Error::UnterminatedRegex),
+                    }
+                }
+                tokens.push(Token::Regex(regex));
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word.to_lowercase()));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from the lowest precedence.
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    /// Current nesting of parentheses & `NOT`.
+    depth: usize,
+}
+
+impl Parser {
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|token| token.is_keyword(keyword))
+            .is_some()
+    }
+
+    fn or(&mut self) -> Result<Query, Error> {
+        let mut queries = vec![self.and()?];
+        while self.next_if_keyword("or") {
+            queries.push(self.and()?);
+        }
+
+        Ok(if queries.len() == 1 {
+            queries.pop().unwrap()
+        } else {
+            Query::Or(queries)
+        })
+    }
+
+    fn and(&mut self) -> Result<Query, Error> {
+        let mut queries = vec![self.not()?];
+        loop {
+            match self.tokens.peek() {
+                None | Some(Token::Close) => break,
+                Some(token) if token.is_keyword("or") => break,
+                Some(token) if token.is_keyword("and") => {
+                    self.tokens.next();
+                }
+                Some(_) => (),
+            }
+            queries.push(self.not()?);
+        }
+
+        Ok(if queries.len() == 1 {
+            queries.pop().unwrap()
+        } else {
+            Query::And(queries)
+        })
+    }
+
+    fn not(&mut self) -> Result<Query, Error> {
+        if self.depth == MAX_DEPTH {
+            let token = self
+                .tokens
+                .next()
+                .map_or_else(String::new, |token| token.to_string());
+            return Err(Error::Unexpected(token));
+        }
+
+        self.depth += 1;
+        let res = self.nested();
+        self.depth -= 1;
+
+        res
+    }
+
+    fn nested(&mut self) -> Result<Query, Error> {
+        if self.next_if_keyword("not") {
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+
+        match self.tokens.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(query),
+                    Some(token) => Err(Error::Unexpected(token.to_string())),
+                    None => Err(Error::UnexpectedEnd),
+                }
+            }
+            Some(Token::Word(word)) => Query::term(&word),
+            Some(Token::Quoted(text)) => Ok(Query::Text(text)),
+            Some(Token::Regex(regex)) => Ok(Query::Regex(regex::Regex::new(&regex)?)),
+            Some(Token::Close) => Err(Error::Unexpected(")".to_string())),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi;
+
+    fn msg(port_nb: PortNb, buf: &[u8]) -> MsgParseResult {
+        let origin = midi::msg::Origin::new(0, port_nb, buf);
+        MsgParseResult::from(&midi::msg::parse(origin))
+    }
+
+    fn matches(query: &str, msg: &MsgParseResult) -> bool {
+        Query::parse(query).unwrap().unwrap().matches(msg)
+    }
+
+    #[test]
+    fn empty() {
+        assert!(Query::parse("").unwrap().is_none());
+        assert!(Query::parse("  ").unwrap().is_none());
+    }
+
+    #[test]
+    fn fields() {
+        let query = "port:1 chan:10 type:cc cc>=70 cc<=79";
+        assert!(matches(query, &msg(PortNb::One, &[0xb9, 72, 5])));
+        assert!(!matches(query, &msg(PortNb::One, &[0xb9, 80, 5])));
+        assert!(!matches(query, &msg(PortNb::One, &[0xb8, 72, 5])));
+        assert!(!matches(query, &msg(PortNb::Two, &[0xb9, 72, 5])));
+        assert!(!matches(query, &msg(PortNb::One, &[0x99, 72, 5])));
+    }
+
+    #[test]
+    fn range() {
+        assert!(matches("note:60-72", &msg(PortNb::One, &[0x90, 60, 100])));
+        assert!(matches("note:60-72", &msg(PortNb::One, &[0x80, 72, 0])));
+        assert!(!matches("note:60-72", &msg(PortNb::One, &[0x90, 73, 100])));
+        // No note in a Control Change
+        assert!(!matches("note:60-72", &msg(PortNb::One, &[0xb0, 64, 100])));
+    }
+
+    #[test]
+    fn types() {
+        let note_off = msg(PortNb::One, &[0x90, 60, 0]);
+        assert!(matches("type:note", &note_off));
+        assert!(matches("type:noteoff", &note_off));
+        assert!(!matches("type:noteon", &note_off));
+        assert!(matches("type!=cc", &note_off));
+
+        let clock = msg(PortNb::Two, &[0xf8]);
+        assert!(matches("type:clock", &clock));
+        assert!(matches("type:realtime", &clock));
+        assert!(!matches("type:channel", &clock));
+    }
+
+    #[test]
+    fn precedence() {
+        // AND binds tighter than OR: `port:2 OR (type:cc chan:2)`
+        let query = "port:2 OR type:cc chan:2";
+        assert!(matches(query, &msg(PortNb::Two, &[0x90, 60, 100])));
+        assert!(matches(query, &msg(PortNb::One, &[0xb1, 1, 2])));
+        assert!(!matches(query, &msg(PortNb::One, &[0xb0, 1, 2])));
+
+        let query = "(port:2 OR type:cc) chan:2";
+        assert!(!matches(query, &msg(PortNb::Two, &[0x90, 60, 100])));
+        assert!(matches(query, &msg(PortNb::Two, &[0x91, 60, 100])));
+    }
+
+    #[test]
+    fn not() {
+        let cc = msg(PortNb::One, &[0xb0, 1, 2]);
+        assert!(!matches("NOT (port:1 type:cc)", &cc));
+        assert!(matches("NOT (port:2 type:cc)", &cc));
+        assert!(matches("not not port:1", &cc));
+        assert!(!matches("NOT port:1 AND type:cc", &cc));
+    }
+
+    #[test]
+    fn text() {
+        let note_on = msg(PortNb::One, &[0x90, 60, 100]);
+        assert!(matches("note", &note_on));
+        assert!(matches("NOTE type:note", &note_on));
+        assert!(matches("\"vel. 100\"", &note_on));
+        assert!(!matches("\"vel. 101\"", &note_on));
+        assert!(matches("/Note 6[0-9] On/", &note_on));
+        assert!(!matches("/note 6[0-9] on/", &note_on));
+        assert!(matches("/(?i)note 6[0-9] on/", &note_on));
+
+        // Unknown keys are searched as text
+        assert!(matches!(
+            Query::parse("foo:1"),
+            Ok(Some(Query::Text(text))) if text == "foo:1"
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Query::parse("\"note"),
+            Err(Error::UnterminatedQuote)
+        ));
+        assert!(matches!(Query::parse("(port:1"), Err(Error::UnexpectedEnd)));
+        assert!(matches!(Query::parse("port:1)"), Err(Error::Unexpected(_))));
+        assert!(matches!(
+            Query::parse("port:1 or"),
+            Err(Error::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Query::parse("note:72-60"),
+            Err(Error::InvalidValue(..))
+        ));
+        assert!(matches!(
+            Query::parse("cc:128"),
+            Err(Error::InvalidValue(..))
+        ));
+        assert!(matches!(
+            Query::parse("chan:0"),
+            Err(Error::InvalidValue(..))
+        ));
+        assert!(matches!(
+            Query::parse("type:foo"),
+            Err(Error::InvalidValue(..))
+        ));
+        assert!(matches!(
+            Query::parse("type<cc"),
+            Err(Error::InvalidOperator(..))
+        ));
+        assert!(matches!(
+            Query::parse("/note"),
+            Err(Error::UnterminatedRegex)
+        ));
+        assert!(matches!(Query::parse("/[/"), Err(Error::Regex(_))));
+    }
+
+    #[test]
+    fn depth() {
+        let nested = format!("{}port:1{}", "(".repeat(1_000), ")".repeat(1_000));
+        assert!(matches!(Query::parse(&nested), Err(Error::Unexpected(_))));
+        let negated = format!("{}port:1", "not ".repeat(1_000));
+        assert!(matches!(Query::parse(&negated), Err(Error::Unexpected(_))));
+
+        let nested = format!("{}port:1{}", "(".repeat(8), ")".repeat(8));
+        assert!(Query::parse(&nested).unwrap().is_some());
+    }
+}
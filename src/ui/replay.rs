@@ -0,0 +1,423 @@
+use crossbeam_channel as channel;
+use eframe::{self, egui};
+use std::{
+    io::{BufRead, Read},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use super::{bus, output::OutputSelector};
+use crate::{
+    bytes,
+    midi::{
+        self,
+        replay::{self, Controls, Player},
+        PortNb,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const STORAGE_REPLAY_OUTPUT: &str = "replay_output";
+const STORAGE_REPLAY_SPEED: &str = "replay_speed";
+const STORAGE_REPLAY_LOOP: &str = "replay_loop";
+
+/// A message of a saved capture.
+#[derive(serde::Deserialize)]
+struct Record {
+    /// Missing in captures saved by older versions.
+    #[serde(default)]
+    ts: u64,
+    /// µs since the Unix epoch, missing in captures saved by older versions.
+    #[serde(default)]
+    wall_ts: Option<u64>,
+    port: PortNb,
+    #[serde(default = "one")]
+    repetitions: u32,
+    /// Timestamp of the last repetition, missing in captures saved by older versions.
+    #[serde(default)]
+    last_ts: Option<u64>,
+    raw: String,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// A message loaded from a saved capture.
+#[derive(Clone, Debug)]
+pub struct Loaded {
+    pub port_nb: PortNb,
+    /// Wall-clock arrival time, if it was saved.
+    pub wall_ts: Option<SystemTime>,
+    pub event: replay::Event,
+}
+
+/// Reads the messages saved by `msg_list::write_ron`.
+///
+/// Repeated messages are expanded, see `Origin::repeat`.
+pub fn read_ron(reader: impl BufRead) -> anyhow::Result<Vec<Loaded>> {
+    use anyhow::Context;
+
+    let mut loaded = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record: Record =
+            ron::from_str(&line).with_context(|| format!("Invalid message at line {}", idx + 1))?;
+        let raw = record.raw.trim_start_matches("(hex)");
+        let buffer: Arc<[u8]> = bytes::parse_hex(raw)
+            .with_context(|| format!("Invalid raw message at line {}", idx + 1))?
+            .into();
+
+        let origin = midi::msg::Origin {
+            ts: record.ts,
+            wall_ts: UNIX_EPOCH + Duration::from_micros(record.wall_ts.unwrap_or_default()),
+            port_nb: record.port,
+            buffer,
+        };
+        let last_ts = record.last_ts.unwrap_or(record.ts);
+        for origin in origin.repeat(record.repetitions, last_ts) {
+            loaded.push(Loaded {
+                port_nb: origin.port_nb,
+                wall_ts: record.wall_ts.map(|_| origin.wall_ts),
+                event: replay::Event {
+                    ts: origin.ts,
+                    buffer: origin.buffer,
+                },
+            });
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Reads the messages saved in the compressed capture format.
+///
+/// The messages of a truncated capture are played up to the last complete one.
+pub fn read_capture(reader: impl Read) -> anyhow::Result<Vec<Loaded>> {
+    let (origins, err) = midi::capture::read_all(reader)?;
+    if let Some(err) = err {
+        log::warn!(
+            "Capture truncated after {} messages: {}",
+            origins.len(),
+            err
+        );
+    }
+
+    Ok(origins
+        .into_iter()
+        .map(|origin| Loaded {
+            port_nb: origin.port_nb,
+            wall_ts: Some(origin.wall_ts),
+            event: replay::Event {
+                ts: origin.ts,
+                buffer: origin.buffer,
+            },
+        })
+        .collect())
+}
+
+/// Returns the messages to play from `port_filter`, with timestamps sharing the same base.
+///
+/// The backend timestamps of different ports don't share the same base, so the
+/// wall-clock time is used when both ports are played. For older captures, without
+/// wall-clock time, the messages of each port are timed from the first one of the port.
+fn events(loaded: &[Loaded], port_filter: Option<PortNb>) -> Vec<replay::Event> {
+    let loaded: Vec<&Loaded> = loaded
+        .iter()
+        .filter(|loaded| port_filter.map_or(true, |p| p == loaded.port_nb))
+        .collect();
+
+    let is_single_port = loaded
+        .windows(2)
+        .all(|pair| pair[0].port_nb == pair[1].port_nb);
+    if is_single_port {
+        return loaded.iter().map(|loaded| loaded.event.clone()).collect();
+    }
+
+    let wall_ts: Option<Vec<u64>> = loaded
+        .iter()
+        .map(|loaded| {
+            loaded.wall_ts.map(|wall_ts| {
+                wall_ts
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_micros() as u64
+            })
+        })
+        .collect();
+
+    let mut events: Vec<replay::Event> = match wall_ts {
+        Some(wall_ts) => loaded
+            .iter()
+            .zip(wall_ts)
+            .map(|(loaded, ts)| replay::Event {
+                ts,
+                buffer: loaded.event.buffer.clone(),
+            })
+            .collect(),
+        None => {
+            let mut first_ts = [None; 2];
+            loaded
+                .iter()
+                .map(|loaded| {
+                    let first_ts = *first_ts[loaded.port_nb.idx()].get_or_insert(loaded.event.ts);
+                    replay::Event {
+                        ts: loaded.event.ts.saturating_sub(first_ts),
+                        buffer: loaded.event.buffer.clone(),
+                    }
+                })
+                .collect()
+        }
+    };
+    // Stable: messages with the same timestamp keep their order
+    events.sort_by_key(|event| event.ts);
+
+    events
+}
+
+/// Plays a saved capture back out of an output port.
+pub struct ReplayPanel {
+    pub is_open: bool,
+    bus: bus::Bus,
+    loaded: Arc<[Loaded]>,
+    file_name: String,
+    load_tx: channel::Sender<(PathBuf, Vec<Loaded>)>,
+    load_rx: channel::Receiver<(PathBuf, Vec<Loaded>)>,
+    port_filter: Option<PortNb>,
+    output: OutputSelector,
+    controls: Arc<Controls>,
+    player: Option<Player>,
+    /// Number of messages played by the current `Player`.
+    len: usize,
+}
+
+impl ReplayPanel {
+    pub fn new(
+        bus: &bus::Bus,
+        client_name: Arc<str>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> Self {
+        let controls = Arc::new(Controls::default());
+        let mut output = None;
+        if let Some(storage) = storage {
+            output = storage.get_string(STORAGE_REPLAY_OUTPUT);
+            if let Some(speed) = storage
+                .get_string(STORAGE_REPLAY_SPEED)
+                .and_then(|speed| speed.parse().ok())
+            {
+                controls.set_speed(speed);
+            }
+            if let Some(is_looping) = storage
+                .get_string(STORAGE_REPLAY_LOOP)
+                .and_then(|is_looping| is_looping.parse().ok())
+            {
+                controls.set_looping(is_looping);
+            }
+        }
+
+        let (load_tx, load_rx) = channel::unbounded();
+
+        Self {
+            is_open: false,
+            bus: bus.clone(),
+            loaded: Arc::new([]),
+            file_name: String::new(),
+            load_tx,
+            load_rx,
+            port_filter: None,
+            output: OutputSelector::new("replay-output", client_name, output),
+            controls,
+            player: None,
+            len: 0,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.pop_loaded();
+        self.check_player();
+
+        let mut is_open = self.is_open;
+        egui::Window::new("Replay")
+            .open(&mut is_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.player.is_none(), egui::Button::new("Load…"))
+                        .on_hover_text("Load a saved capture")
+                        .clicked()
+                    {
+                        self.load();
+                    }
+                    if self.loaded.is_empty() {
+                        ui.label("No capture loaded");
+                    } else {
+                        ui.label(format!("{}: {} msg(s)", self.file_name, self.loaded.len()));
+                    }
+                });
+
+                egui::Grid::new("replay-settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Messages from");
+                        egui::ComboBox::from_id_source("replay-ports")
+                            .selected_text(self.port_filter.map_or("All ports", |p| p.as_str()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.port_filter, None, "All ports");
+                                for port_nb in [PortNb::One, PortNb::Two] {
+                                    ui.selectable_value(
+                                        &mut self.port_filter,
+                                        Some(port_nb),
+                                        port_nb.as_str(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Output");
+                        self.output.show(ui, &self.bus);
+                        ui.end_row();
+
+                        ui.label("Speed");
+                        let mut speed = self.controls.speed();
+                        if ui
+                            .add(
+                                egui::Slider::new(
+                                    &mut speed,
+                                    replay::MIN_SPEED..=replay::MAX_SPEED,
+                                )
+                                .logarithmic(true)
+                                .suffix("×"),
+                            )
+                            .changed()
+                        {
+                            self.controls.set_speed(speed);
+                        }
+                        ui.end_row();
+
+                        ui.label("Loop");
+                        let mut is_looping = self.controls.is_looping();
+                        if ui.checkbox(&mut is_looping, "").changed() {
+                            self.controls.set_looping(is_looping);
+                        }
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if self.player.is_some() {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.stop();
+                        }
+
+                        let pos = self.controls.position();
+                        ui.add(
+                            egui::ProgressBar::new(pos as f32 / self.len.max(1) as f32)
+                                .text(format!("{pos} / {}", self.len)),
+                        );
+                        // Keep repainting to update the progress
+                        ui.ctx().request_repaint();
+                    } else {
+                        let can_play = !self.loaded.is_empty() && self.output.selected.is_some();
+                        if ui
+                            .add_enabled(can_play, egui::Button::new("▶ Play"))
+                            .clicked()
+                        {
+                            self.play();
+                        }
+                    }
+                });
+            });
+        self.is_open = is_open;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_REPLAY_OUTPUT, self.output.storage_string());
+        storage.set_string(STORAGE_REPLAY_SPEED, self.controls.speed().to_string());
+        storage.set_string(STORAGE_REPLAY_LOOP, self.controls.is_looping().to_string());
+    }
+}
+
+impl ReplayPanel {
+    fn load(&self) {
+        let bus = self.bus.clone();
+        let load_tx = self.load_tx.clone();
+        std::thread::spawn(move || {
+            use anyhow::Context;
+            use std::{fs, io};
+
+            let file_path = rfd::FileDialog::new()
+                .add_filter("Rusty Object Notation (ron)", &["ron"])
+                .add_filter("Compressed capture (msnf)", &[midi::capture::EXTENSION])
+                .pick_file();
+
+            if let Some(file_path) = file_path {
+                let is_capture = file_path.extension().map_or(false, |ext| {
+                    ext.eq_ignore_ascii_case(midi::capture::EXTENSION)
+                });
+                let res = fs::File::open(&file_path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|file| {
+                        if is_capture {
+                            read_capture(file)
+                        } else {
+                            read_ron(io::BufReader::new(file))
+                        }
+                    })
+                    .with_context(|| format!("Couldn't load {}", file_path.display()));
+                match res {
+                    Ok(loaded) => {
+                        let _ = load_tx.send((file_path, loaded));
+                    }
+                    Err(err) => bus.publish_err(err),
+                }
+            }
+        });
+    }
+
+    fn pop_loaded(&mut self) {
+        if let Some((file_path, loaded)) = self.load_rx.try_iter().last() {
+            log::debug!(
+                "Loaded {} Midi messages from {}",
+                loaded.len(),
+                file_path.display()
+            );
+            self.file_name = file_path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            self.loaded = loaded.into();
+        }
+    }
+
+    fn play(&mut self) {
+        let midi_out = match self.output.connect(&self.bus) {
+            Some(midi_out) => midi_out,
+            None => return,
+        };
+
+        let events: Arc<[replay::Event]> = events(&self.loaded, self.port_filter).into();
+
+        self.len = events.len();
+        self.player = Some(Player::spawn(midi_out, events, self.controls.clone()));
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            if let Err(err) = player.stop() {
+                self.bus
+                    .publish_err(anyhow::anyhow!("Replay failed: {err}"));
+            }
+        }
+    }
+
+    /// Releases the `Player` once the replay is over.
+    fn check_player(&mut self) {
+        if self.player.as_ref().map_or(false, Player::is_finished) {
+            self.stop();
+        }
+    }
+}
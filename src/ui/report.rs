@@ -0,0 +1,91 @@
+//! Standalone HTML report of the message list, e.g. to attach to a support ticket.
+
+use std::fmt::Write;
+
+use super::{
+    msg_list::VisibleRow,
+    settings::{color_to_hex, PortColors},
+    stats::Summary,
+};
+use crate::midi::PortNb;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #202020; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+th { background: #404040; color: #ffffff; }
+table.summary td { border: 1px solid #c0c0c0; }
+table.msgs td { color: #ffffff; font-family: monospace; white-space: pre-wrap; }
+table.msgs tr.error td { color: #ff8080; font-weight: bold; }
+ul.warnings { color: #a00000; }
+";
+
+/// Renders the `summary` followed by the message table.
+pub fn render(
+    summary: &Summary,
+    header: &[String],
+    rows: &[VisibleRow],
+    port_colors: &PortColors,
+) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>MIDI Sniffer report</title>\n<style>\n",
+    );
+    html.push_str(STYLE);
+    for port_nb in [PortNb::One, PortNb::Two] {
+        let _ = writeln!(
+            html,
+            "table.msgs tr.port-{} {{ background: {}; }}",
+            port_nb.as_char(),
+            color_to_hex(port_colors.get(port_nb)),
+        );
+    }
+    html.push_str("</style>\n</head>\n<body>\n<h1>MIDI Sniffer report</h1>\n");
+    let _ = writeln!(
+        html,
+        "<p>Generated on {}, {} message(s).</p>",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        rows.len(),
+    );
+
+    html.push_str("<h2>Summary</h2>\n");
+    html.push_str(&summary.to_html());
+
+    html.push_str("<h2>Messages</h2>\n<table class=\"msgs\">\n<tr>");
+    for title in header {
+        let _ = write!(html, "<th>{}</th>", escape(title));
+    }
+    html.push_str("</tr>\n");
+
+    for row in rows {
+        let _ = write!(html, "<tr class=\"port-{}", row.port_nb.as_char());
+        if row.is_err {
+            html.push_str(" error");
+        }
+        html.push_str("\">");
+        for cell in &row.cells {
+            let _ = write!(html, "<td>{}</td>", escape(cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    html
+}
+
+/// Escapes the characters with a special meaning in HTML text & attributes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,414 @@
+use eframe::{self, egui};
+use std::{sync::Arc, time::Duration};
+
+use crate::midi::{backpressure, batching, port::Backend, PortNb};
+
+const STORAGE_PORT_1_COLOR: &str = "port_1_color";
+const STORAGE_PORT_2_COLOR: &str = "port_2_color";
+const STORAGE_BACKPRESSURE_POLICY: &str = "backpressure_policy";
+const STORAGE_BACKEND: &str = "midi_backend";
+const STORAGE_BATCH_SIZE: &str = "batch_size";
+const STORAGE_BATCH_DELAY: &str = "batch_delay_ms";
+const STORAGE_MAX_BATCHES_PER_UPDATE: &str = "max_batches_per_update";
+const STORAGE_THEME: &str = "theme";
+const STORAGE_ACCENT_COLOR: &str = "accent_color";
+const STORAGE_ERROR_LIFETIME: &str = "error_lifetime_s";
+
+const DEFAULT_ERROR_LIFETIME: Duration = Duration::from_secs(8);
+const MAX_ERROR_LIFETIME: u64 = 600;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    pub fn from_storage_str(theme: &str) -> Option<Self> {
+        Theme::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == theme)
+    }
+
+    fn visuals(self) -> egui::Visuals {
+        match self {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PortColors([egui::Color32; 2]);
+
+impl Default for PortColors {
+    fn default() -> Self {
+        Self([
+            egui::Color32::from_rgb(0, 0, 0x64),
+            egui::Color32::from_rgb(0, 0x48, 0),
+        ])
+    }
+}
+
+impl PortColors {
+    pub fn get(&self, port_nb: PortNb) -> egui::Color32 {
+        self.0[port_nb.idx()]
+    }
+
+    pub fn set(&mut self, port_nb: PortNb, color: egui::Color32) {
+        self.0[port_nb.idx()] = color;
+    }
+
+    fn get_mut(&mut self, port_nb: PortNb) -> &mut egui::Color32 {
+        &mut self.0[port_nb.idx()]
+    }
+}
+
+pub struct SettingsPanel {
+    pub is_open: bool,
+    pub port_colors: PortColors,
+    pub backpressure: Arc<backpressure::State>,
+    pub batching: Arc<batching::State>,
+    pub backend: Backend,
+    pub theme: Theme,
+    /// Overrides the selection & link color of the `theme`.
+    pub accent_color: Option<egui::Color32>,
+    /// Time the errors are shown for, zero keeps them until they are dismissed.
+    pub error_lifetime: Duration,
+}
+
+impl SettingsPanel {
+    pub fn new(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut port_colors = PortColors::default();
+        let mut policy = backpressure::Policy::default();
+        let mut backend = Backend::default();
+        let mut batching = batching::Settings::default();
+        let mut theme = Theme::default();
+        let mut accent_color = None;
+        let mut error_lifetime = DEFAULT_ERROR_LIFETIME;
+
+        if let Some(storage) = storage {
+            for (port_nb, key) in [
+                (PortNb::One, STORAGE_PORT_1_COLOR),
+                (PortNb::Two, STORAGE_PORT_2_COLOR),
+            ] {
+                if let Some(color) = storage.get_string(key).as_deref().and_then(color_from_hex) {
+                    *port_colors.get_mut(port_nb) = color;
+                }
+            }
+
+            if let Some(stored) = storage
+                .get_string(STORAGE_BACKPRESSURE_POLICY)
+                .as_deref()
+                .and_then(backpressure::Policy::from_storage_str)
+            {
+                policy = stored;
+            }
+
+            if let Some(stored) = storage
+                .get_string(STORAGE_BACKEND)
+                .as_deref()
+                .and_then(Backend::from_storage_str)
+            {
+                backend = stored;
+            }
+
+            let get_u64 = |key: &str| storage.get_string(key)?.parse::<u64>().ok();
+            if let Some(batch_size) = get_u64(STORAGE_BATCH_SIZE) {
+                batching.batch_size = batch_size as usize;
+            }
+            if let Some(delay_ms) = get_u64(STORAGE_BATCH_DELAY) {
+                batching.batch_delay = Duration::from_millis(delay_ms);
+            }
+            if let Some(max_batches) = get_u64(STORAGE_MAX_BATCHES_PER_UPDATE) {
+                batching.max_batches_per_update = max_batches as usize;
+            }
+            if let Some(lifetime_s) = get_u64(STORAGE_ERROR_LIFETIME) {
+                error_lifetime = Duration::from_secs(lifetime_s.min(MAX_ERROR_LIFETIME));
+            }
+
+            if let Some(stored) = storage
+                .get_string(STORAGE_THEME)
+                .as_deref()
+                .and_then(Theme::from_storage_str)
+            {
+                theme = stored;
+            }
+            accent_color = storage
+                .get_string(STORAGE_ACCENT_COLOR)
+                .as_deref()
+                .and_then(color_from_hex);
+        }
+
+        Self {
+            is_open: false,
+            port_colors,
+            backpressure: Arc::new(backpressure::State::new(policy)),
+            batching: Arc::new(batching::State::new(batching)),
+            backend,
+            theme,
+            accent_color,
+            error_lifetime,
+        }
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = self.theme.visuals();
+        if let Some(accent_color) = self.accent_color {
+            visuals.selection.bg_fill = accent_color;
+            visuals.hyperlink_color = accent_color;
+        }
+
+        visuals
+    }
+
+    /// Shows the settings, returns the newly selected `Backend` if any.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Backend> {
+        let prev_backend = self.backend;
+        let prev_visuals = (self.theme, self.accent_color);
+
+        egui::Window::new("Settings")
+            .open(&mut self.is_open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Theme");
+                Self::show_theme(&mut self.theme, &mut self.accent_color, ui);
+
+                ui.separator();
+                ui.heading("Errors");
+                Self::show_errors(&mut self.error_lifetime, ui);
+
+                ui.separator();
+                ui.heading("Port colors");
+                egui::Grid::new("port-colors").show(ui, |ui| {
+                    for port_nb in [PortNb::One, PortNb::Two] {
+                        ui.label(port_nb.as_str());
+                        ui.color_edit_button_srgba(self.port_colors.get_mut(port_nb));
+                        ui.end_row();
+                    }
+                });
+
+                if ui.button("Reset to defaults").clicked() {
+                    self.port_colors = PortColors::default();
+                }
+
+                ui.separator();
+                ui.heading("Backpressure");
+                Self::show_backpressure(&self.backpressure, ui);
+
+                ui.separator();
+                ui.heading("Batching");
+                Self::show_batching(&self.batching, ui);
+
+                if Backend::ALL.len() > 1 {
+                    ui.separator();
+                    ui.heading("Backend");
+                    egui::ComboBox::from_label("Midi ports")
+                        .selected_text(self.backend.as_str())
+                        .show_ui(ui, |ui| {
+                            for candidate in Backend::ALL {
+                                ui.selectable_value(
+                                    &mut self.backend,
+                                    *candidate,
+                                    candidate.as_str(),
+                                );
+                            }
+                        });
+                }
+            });
+
+        if (self.theme, self.accent_color) != prev_visuals {
+            ctx.set_visuals(self.visuals());
+        }
+
+        (self.backend != prev_backend).then_some(self.backend)
+    }
+
+    fn show_theme(theme: &mut Theme, accent_color: &mut Option<egui::Color32>, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Theme")
+            .selected_text(theme.as_str())
+            .show_ui(ui, |ui| {
+                for candidate in Theme::ALL {
+                    ui.selectable_value(theme, candidate, candidate.as_str());
+                }
+            });
+
+        ui.horizontal(|ui| {
+            let mut color = accent_color.unwrap_or(theme.visuals().selection.bg_fill);
+            if ui.color_edit_button_srgba(&mut color).changed() {
+                *accent_color = Some(color);
+            }
+            ui.label("Accent color");
+            if ui
+                .add_enabled(accent_color.is_some(), egui::Button::new("Reset"))
+                .clicked()
+            {
+                *accent_color = None;
+            }
+        });
+    }
+
+    fn show_errors(error_lifetime: &mut Duration, ui: &mut egui::Ui) {
+        let mut lifetime_s = error_lifetime.as_secs();
+        ui.horizontal(|ui| {
+            ui.label("Hide errors after")
+                .on_hover_text("0 keeps the errors until they are dismissed");
+            ui.add(
+                egui::DragValue::new(&mut lifetime_s)
+                    .clamp_range(0..=MAX_ERROR_LIFETIME)
+                    .suffix(" s"),
+            );
+        });
+        *error_lifetime = Duration::from_secs(lifetime_s);
+    }
+
+    fn show_backpressure(backpressure: &backpressure::State, ui: &mut egui::Ui) {
+        let mut policy = backpressure.policy();
+        egui::ComboBox::from_label("When the capture can't keep up")
+            .selected_text(policy.as_str())
+            .show_ui(ui, |ui| {
+                for candidate in backpressure::Policy::ALL {
+                    ui.selectable_value(&mut policy, candidate, candidate.as_str());
+                }
+            });
+        if policy != backpressure.policy() {
+            backpressure.set_policy(policy);
+        }
+
+        let metrics = backpressure.metrics();
+        egui::Grid::new("backpressure-metrics").show(ui, |ui| {
+            for (label, val) in [
+                ("Blocked", metrics.blocked),
+                ("Timed out", metrics.timed_out),
+                ("Dropped oldest", metrics.dropped_oldest),
+                ("Dropped newest", metrics.dropped_newest),
+                ("Summarized", metrics.summarized),
+                ("Paused overflow", metrics.paused_overflow),
+            ] {
+                ui.label(label);
+                ui.label(val.to_string());
+                ui.end_row();
+            }
+        });
+
+        if ui.button("Reset counters").clicked() {
+            backpressure.reset_metrics();
+        }
+    }
+
+    fn show_batching(batching: &batching::State, ui: &mut egui::Ui) {
+        let mut settings = batching.settings();
+        let mut delay_ms = settings.batch_delay.as_millis() as u64;
+
+        egui::Grid::new("batching").show(ui, |ui| {
+            ui.label("Messages per batch")
+                .on_hover_text("Larger batches reduce the overhead with high message rates");
+            ui.add(
+                egui::DragValue::new(&mut settings.batch_size)
+                    .clamp_range(1..=batching::MAX_BATCH_SIZE),
+            );
+            ui.end_row();
+
+            ui.label("Batch delay").on_hover_text(
+                "Time to wait for a batch to fill up. Reduces the number of updates \
+                 at the expense of latency",
+            );
+            ui.add(
+                egui::DragValue::new(&mut delay_ms)
+                    .clamp_range(0..=batching::MAX_BATCH_DELAY.as_millis() as u64)
+                    .suffix(" ms"),
+            );
+            ui.end_row();
+
+            ui.label("Batches per update")
+                .on_hover_text("Lower values keep the UI responsive on low-powered machines");
+            ui.add(
+                egui::DragValue::new(&mut settings.max_batches_per_update)
+                    .clamp_range(1..=batching::MAX_BATCHES_PER_UPDATE),
+            );
+            ui.end_row();
+        });
+        settings.batch_delay = Duration::from_millis(delay_ms);
+
+        if ui.button("Reset to defaults").clicked() {
+            settings = batching::Settings::default();
+        }
+
+        if settings != batching.settings() {
+            batching.set_settings(settings);
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(
+            STORAGE_PORT_1_COLOR,
+            color_to_hex(self.port_colors.get(PortNb::One)),
+        );
+        storage.set_string(
+            STORAGE_PORT_2_COLOR,
+            color_to_hex(self.port_colors.get(PortNb::Two)),
+        );
+        storage.set_string(
+            STORAGE_BACKPRESSURE_POLICY,
+            self.backpressure.policy().storage_str().to_string(),
+        );
+        storage.set_string(STORAGE_BACKEND, self.backend.storage_str().to_string());
+        storage.set_string(STORAGE_THEME, self.theme.storage_str().to_string());
+        storage.set_string(
+            STORAGE_ACCENT_COLOR,
+            self.accent_color.map_or_else(String::new, color_to_hex),
+        );
+
+        let batching = self.batching.settings();
+        storage.set_string(STORAGE_BATCH_SIZE, batching.batch_size.to_string());
+        storage.set_string(
+            STORAGE_BATCH_DELAY,
+            batching.batch_delay.as_millis().to_string(),
+        );
+        storage.set_string(
+            STORAGE_MAX_BATCHES_PER_UPDATE,
+            batching.max_batches_per_update.to_string(),
+        );
+        storage.set_string(
+            STORAGE_ERROR_LIFETIME,
+            self.error_lifetime.as_secs().to_string(),
+        );
+    }
+}
+
+pub fn color_to_hex(color: egui::Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+pub fn color_from_hex(hex: &str) -> Option<egui::Color32> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let component = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+
+    Some(egui::Color32::from_rgb(
+        component(0)?,
+        component(2)?,
+        component(4)?,
+    ))
+}
@@ -0,0 +1,204 @@
+use std::{fmt::Write, sync::Arc};
+
+use super::{msg_list::MsgParseResult, port::DirectionalPorts};
+use crate::{
+    midi::{backpressure, PortNb},
+    time::SystemTime,
+};
+
+#[derive(Debug, Default)]
+struct PortStats {
+    msgs: u64,
+    errors: u64,
+    channel: u64,
+    sysex: u64,
+    common: u64,
+    realtime: u64,
+    first_wall_ts: Option<SystemTime>,
+    last_wall_ts: Option<SystemTime>,
+}
+
+impl PortStats {
+    fn add(&mut self, msg: &MsgParseResult) {
+        if msg.raw().is_empty() {
+            // Backpressure summary, accounted for in the metrics
+            return;
+        }
+
+        let count = msg.repetitions() as u64;
+        self.msgs += count;
+
+        if msg.is_err() {
+            self.errors += count;
+        }
+
+        match msg.raw().first() {
+            Some(0x80..=0xef) => self.channel += count,
+            Some(0xf0) => self.sysex += count,
+            Some(0xf1..=0xf7) => self.common += count,
+            Some(0xf8..=0xff) => self.realtime += count,
+            _ => (),
+        }
+
+        let wall_ts = msg.ts().wall_ts;
+        self.first_wall_ts.get_or_insert(wall_ts);
+        self.last_wall_ts = Some(wall_ts);
+    }
+
+    /// Messages per second between the first and the last message.
+    fn rate(&self) -> Option<f64> {
+        let duration = self
+            .last_wall_ts?
+            .duration_since(self.first_wall_ts?)
+            .ok()?
+            .as_secs_f64();
+
+        (duration > 0.0).then(|| self.msgs as f64 / duration)
+    }
+}
+
+/// Statistics about the messages in the list.
+#[derive(Debug, Default)]
+pub struct Summary {
+    ports: [PortStats; 2],
+    device: [Arc<str>; 2],
+    backpressure: backpressure::Metrics,
+}
+
+impl Summary {
+    pub fn new<'a>(
+        msg_list: impl Iterator<Item = &'a Arc<MsgParseResult>>,
+        ports: &DirectionalPorts,
+        backpressure: backpressure::Metrics,
+    ) -> Self {
+        let mut this = Summary {
+            device: [
+                ports.cur(PortNb::One).clone(),
+                ports.cur(PortNb::Two).clone(),
+            ],
+            backpressure,
+            ..Default::default()
+        };
+
+        for msg in msg_list {
+            this.ports[msg.port_nb().idx()].add(msg);
+        }
+
+        this
+    }
+
+    /// Formats the summary as a Markdown table followed by the notable warnings.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        md.push_str("| Port | Device | Messages | Errors | Channel | SysEx | Common | Realtime | Rate (msg/s) |\n");
+        md.push_str("|------|--------|---------:|-------:|--------:|------:|-------:|---------:|-------------:|\n");
+
+        for port_nb in [PortNb::One, PortNb::Two] {
+            let stats = &self.ports[port_nb.idx()];
+            let rate = stats
+                .rate()
+                .map_or_else(|| "-".to_string(), |rate| format!("{rate:.1}"));
+
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+                port_nb.as_char(),
+                self.device[port_nb.idx()],
+                stats.msgs,
+                stats.errors,
+                stats.channel,
+                stats.sysex,
+                stats.common,
+                stats.realtime,
+                rate,
+            );
+        }
+
+        let warnings = self.warnings();
+        if !warnings.is_empty() {
+            md.push_str("\n**Warnings**\n\n");
+            for warning in warnings {
+                let _ = writeln!(md, "- {warning}");
+            }
+        }
+
+        md
+    }
+
+    /// Formats the summary as an HTML table followed by the notable warnings.
+    #[cfg(feature = "save")]
+    pub fn to_html(&self) -> String {
+        use super::report::escape;
+
+        let mut html = String::from("<table class=\"summary\">\n<tr>");
+        for title in [
+            "Port",
+            "Device",
+            "Messages",
+            "Errors",
+            "Channel",
+            "SysEx",
+            "Common",
+            "Realtime",
+            "Rate (msg/s)",
+        ] {
+            let _ = write!(html, "<th>{title}</th>");
+        }
+        html.push_str("</tr>\n");
+
+        for port_nb in [PortNb::One, PortNb::Two] {
+            let stats = &self.ports[port_nb.idx()];
+            let rate = stats
+                .rate()
+                .map_or_else(|| "-".to_string(), |rate| format!("{rate:.1}"));
+
+            let _ = writeln!(
+                html,
+                "<tr class=\"port-{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                port_nb.as_char(),
+                port_nb.as_char(),
+                escape(&self.device[port_nb.idx()]),
+                stats.msgs,
+                stats.errors,
+                stats.channel,
+                stats.sysex,
+                stats.common,
+                stats.realtime,
+                rate,
+            );
+        }
+        html.push_str("</table>\n");
+
+        let warnings = self.warnings();
+        if !warnings.is_empty() {
+            html.push_str("<h2>Warnings</h2>\n<ul class=\"warnings\">\n");
+            for warning in warnings {
+                let _ = writeln!(html, "<li>{}</li>", escape(&warning));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for port_nb in [PortNb::One, PortNb::Two] {
+            let errors = self.ports[port_nb.idx()].errors;
+            if errors > 0 {
+                warnings.push(format!("{errors} message(s) failed to parse on {port_nb}"));
+            }
+        }
+
+        let dropped = self.backpressure.dropped();
+        if dropped > 0 {
+            warnings.push(format!(
+                "{dropped} message(s) dropped because the capture couldn't keep up"
+            ));
+        }
+
+        warnings
+    }
+}
@@ -0,0 +1,133 @@
+use crossbeam_channel as channel;
+use eframe::{self, egui};
+
+use super::bus;
+use crate::{
+    midi::{anatomy::note_name, notes},
+    time::{Duration, SystemTime},
+};
+
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(10);
+const STORAGE_STUCK_NOTE_THRESHOLD: &str = "stuck_note_threshold";
+
+/// Lists the notes held for longer than a threshold.
+pub struct StuckNotesPanel {
+    pub is_open: bool,
+    threshold: Duration,
+    tracker: notes::Tracker,
+    stuck: Vec<(notes::Key, notes::Held)>,
+    msg_rx: channel::Receiver<bus::Event>,
+}
+
+impl StuckNotesPanel {
+    pub fn new(bus: &bus::Bus, storage: Option<&dyn eframe::Storage>) -> Self {
+        let threshold = storage
+            .and_then(|storage| storage.get_string(STORAGE_STUCK_NOTE_THRESHOLD))
+            .and_then(|threshold| threshold.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_THRESHOLD);
+
+        Self {
+            is_open: false,
+            threshold,
+            tracker: notes::Tracker::default(),
+            stuck: Vec::new(),
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+        }
+    }
+
+    /// Tracks the incoming notes and updates the stuck notes.
+    pub fn update(&mut self) {
+        bus::drain_msgs(&self.msg_rx, |origin| {
+            self.tracker
+                .handle(origin.port_nb, origin.wall_ts, &origin.buffer)
+        });
+
+        self.stuck = self
+            .tracker
+            .held_longer_than(self.threshold, SystemTime::now());
+    }
+
+    /// Shows a warning in the top bar if some notes are stuck.
+    pub fn show_indicator(&mut self, ui: &mut egui::Ui) {
+        if self.stuck.is_empty() {
+            return;
+        }
+
+        let text = egui::RichText::new(format!("⚠ {} stuck note(s)", self.stuck.len()))
+            .color(egui::Color32::YELLOW);
+        if ui
+            .add(egui::Label::new(text).sense(egui::Sense::click()))
+            .on_hover_text("Show stuck notes")
+            .clicked()
+        {
+            self.is_open = true;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Stuck notes")
+            .open(&mut self.is_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Held for more than");
+                    let mut secs = self.threshold.as_secs_f32();
+                    let resp = ui.add(
+                        egui::DragValue::new(&mut secs)
+                            .clamp_range(0.1..=3_600.0)
+                            .speed(0.1)
+                            .suffix(" s"),
+                    );
+                    if resp.changed() {
+                        self.threshold = Duration::from_secs_f32(secs);
+                    }
+                });
+                ui.separator();
+
+                if self.stuck.is_empty() {
+                    ui.label("No stuck notes");
+                } else {
+                    let now = SystemTime::now();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("stuck-notes")
+                            .striped(true)
+                            .num_columns(5)
+                            .show(ui, |ui| {
+                                ui.strong("Port");
+                                ui.strong("Channel");
+                                ui.strong("Note");
+                                ui.strong("Velocity");
+                                ui.strong("Held");
+                                ui.end_row();
+
+                                for (key, held) in self.stuck.iter() {
+                                    ui.label(key.port_nb.as_str());
+                                    ui.label((key.channel + 1).to_string());
+                                    ui.label(note_name(key.note));
+                                    ui.label(held.velocity.to_string());
+                                    ui.label(format!("{:.1} s", held.duration(now).as_secs_f32()));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                }
+
+                if ui
+                    .button("Forget held notes")
+                    .on_hover_text("Consider all the notes as released")
+                    .clicked()
+                {
+                    self.tracker.clear();
+                    self.stuck.clear();
+                }
+            });
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(
+            STORAGE_STUCK_NOTE_THRESHOLD,
+            self.threshold.as_millis().to_string(),
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use eframe::egui;
+
+use crate::midi::{
+    template::{Kind, Template},
+    PortNb,
+};
+
+/// Edits the expected traffic templates of the ports.
+#[derive(Default)]
+pub struct TemplatesPanel {
+    pub is_open: bool,
+}
+
+impl TemplatesPanel {
+    /// Returns `true` if a template changed.
+    pub fn show(&mut self, ctx: &egui::Context, templates: &mut [Template; 2]) -> bool {
+        let mut changed = false;
+
+        egui::Window::new("Expected traffic")
+            .open(&mut self.is_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Messages outside the template of their port are flagged");
+                ui.separator();
+
+                ui.columns(2, |columns| {
+                    for ((ui, template), port_nb) in columns
+                        .iter_mut()
+                        .zip(templates.iter_mut())
+                        .zip([PortNb::One, PortNb::Two])
+                    {
+                        ui.heading(port_nb.as_str());
+                        changed |= ui.checkbox(&mut template.is_enabled, "Enabled").changed();
+
+                        ui.add_enabled_ui(template.is_enabled, |ui| {
+                            for kind in Kind::ALL {
+                                changed |= ui
+                                    .checkbox(template.kind_mut(kind), kind.as_str())
+                                    .changed();
+                            }
+
+                            ui.label("Channels");
+                            egui::Grid::new(("template-channels", port_nb.idx())).show(ui, |ui| {
+                                for channel in 0..16u8 {
+                                    changed |= ui
+                                        .toggle_value(
+                                            template.channel_mut(channel),
+                                            format!("{:2}", channel + 1),
+                                        )
+                                        .changed();
+                                    if channel % 4 == 3 {
+                                        ui.end_row();
+                                    }
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+        changed
+    }
+}
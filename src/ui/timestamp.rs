@@ -0,0 +1,179 @@
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How timestamps are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The opaque counter from the Midi backend (µs).
+    Raw,
+    /// Wall-clock time since the first message in the list.
+    Relative,
+    /// Local wall-clock time of day.
+    TimeOfDay,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Raw, Mode::Relative, Mode::TimeOfDay];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Raw => "Raw",
+            Mode::Relative => "Relative",
+            Mode::TimeOfDay => "Time of day",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Mode::Raw => "raw",
+            Mode::Relative => "relative",
+            Mode::TimeOfDay => "time_of_day",
+        }
+    }
+
+    pub fn from_storage_str(mode: &str) -> Option<Self> {
+        match mode {
+            "raw" => Some(Mode::Raw),
+            "relative" => Some(Mode::Relative),
+            "time_of_day" => Some(Mode::TimeOfDay),
+            _ => None,
+        }
+    }
+
+    pub fn column_width(self, resolution: Resolution) -> f32 {
+        match (self, resolution) {
+            (Mode::Raw, Resolution::Micros) => 80.0,
+            _ => 110.0,
+        }
+    }
+}
+
+/// How precisely timestamps are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// Integer microseconds.
+    Micros,
+    /// Milliseconds with 3 decimals.
+    Millis,
+    /// Seconds with 6 decimals.
+    Secs,
+    /// HH:MM:SS.mmm
+    Clock,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [
+        Resolution::Micros,
+        Resolution::Millis,
+        Resolution::Secs,
+        Resolution::Clock,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resolution::Micros => "µs",
+            Resolution::Millis => "ms",
+            Resolution::Secs => "s",
+            Resolution::Clock => "HH:MM:SS.mmm",
+        }
+    }
+
+    pub fn storage_str(self) -> &'static str {
+        match self {
+            Resolution::Micros => "micros",
+            Resolution::Millis => "millis",
+            Resolution::Secs => "secs",
+            Resolution::Clock => "clock",
+        }
+    }
+
+    pub fn from_storage_str(resolution: &str) -> Option<Self> {
+        Resolution::ALL
+            .into_iter()
+            .find(|candidate| candidate.storage_str() == resolution)
+    }
+
+    pub fn format_duration(self, duration: Duration) -> String {
+        match self {
+            Resolution::Micros => format!("{}", duration.as_micros()),
+            Resolution::Millis => format!(
+                "{}.{:03}",
+                duration.as_millis(),
+                duration.subsec_micros() % 1_000
+            ),
+            Resolution::Secs => format!("{}.{:06}", duration.as_secs(), duration.subsec_micros()),
+            Resolution::Clock => {
+                let secs = duration.as_secs();
+                format!(
+                    "{:02}:{:02}:{:02}.{:03}",
+                    secs / 3_600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    duration.subsec_millis(),
+                )
+            }
+        }
+    }
+
+    fn time_of_day_fmt(self) -> &'static str {
+        match self {
+            Resolution::Micros | Resolution::Secs => "%H:%M:%S%.6f",
+            Resolution::Millis | Resolution::Clock => "%H:%M:%S%.3f",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Timestamp {
+    pub ts: u64,
+    pub wall_ts: SystemTime,
+}
+
+/// Serialize as the timestamp provided by the Midi backend (µs),
+/// so that captures can be replayed with the original timing.
+#[cfg(feature = "save")]
+impl serde::Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.ts)
+    }
+}
+
+impl Timestamp {
+    pub fn new(ts: u64, wall_ts: SystemTime) -> Self {
+        Self { ts, wall_ts }
+    }
+
+    /// Returns `wall_ts` in µs since the Unix epoch.
+    pub fn wall_ts_us(&self) -> u64 {
+        self.wall_ts
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64
+    }
+
+    /// Formats the timestamp according to `mode` and `resolution`.
+    ///
+    /// `start` is the reference (t=0) for `Mode::Relative`.
+    pub fn format(&self, mode: Mode, resolution: Resolution, start: Option<SystemTime>) -> String {
+        match mode {
+            Mode::Raw => resolution.format_duration(Duration::from_micros(self.ts)),
+            Mode::Relative => match start.map(|start| self.wall_ts.duration_since(start)) {
+                Some(Ok(elapsed)) => resolution.format_duration(elapsed),
+                // Message received before the reference
+                Some(Err(err)) => format!("-{}", resolution.format_duration(err.duration())),
+                None => resolution.format_duration(Duration::ZERO),
+            },
+            Mode::TimeOfDay => {
+                use chrono::TimeZone;
+
+                // Not using `From<SystemTime>` which is not available for the web target
+                let since_epoch = self.wall_ts.duration_since(UNIX_EPOCH).unwrap_or_default();
+                chrono::Local
+                    .timestamp_opt(since_epoch.as_secs() as i64, since_epoch.subsec_nanos())
+                    .single()
+                    .map_or_else(String::new, |date_time| {
+                        date_time.format(resolution.time_of_day_fmt()).to_string()
+                    })
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use eframe::egui;
+use std::{sync::Arc, time::Duration};
+
+/// Duration of the fade out at the end of the toasts lifetime (s).
+const FADE_OUT: f64 = 1.0;
+const MAX_TOASTS: usize = 5;
+const TOAST_COLOR: egui::Color32 = egui::Color32::DARK_RED;
+
+struct Toast {
+    err: Arc<anyhow::Error>,
+    /// Time the toast was first shown, see `egui::InputState::time`.
+    since: Option<f64>,
+    is_pinned: bool,
+}
+
+/// Errors shown on top of the bottom right corner until they expire.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    /// Time a repaint is already scheduled at, see `egui::InputState::time`.
+    repaint_at: Option<f64>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, err: Arc<anyhow::Error>) {
+        self.toasts.push(Toast {
+            err,
+            since: None,
+            is_pinned: false,
+        });
+
+        if self.toasts.len() > MAX_TOASTS {
+            if let Some(oldest) = self.toasts.iter().position(|toast| !toast.is_pinned) {
+                self.toasts.remove(oldest);
+            }
+        }
+    }
+
+    /// Dismisses the toasts which are not pinned.
+    pub fn clear(&mut self) {
+        self.toasts.retain(|toast| toast.is_pinned);
+    }
+
+    /// Shows the toasts, a zero `lifetime` keeps them until they are dismissed.
+    pub fn show(&mut self, ctx: &egui::Context, lifetime: Duration) {
+        let now = ctx.input().time;
+        let lifetime = lifetime.as_secs_f64();
+        let is_transient = |toast: &Toast| !toast.is_pinned && lifetime > 0.0;
+
+        self.toasts.retain(|toast| {
+            !is_transient(toast) || toast.since.map_or(true, |since| now - since < lifetime)
+        });
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new("error-toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-8.0, -40.0])
+            .show(ctx, |ui| {
+                for (idx, toast) in self.toasts.iter_mut().enumerate() {
+                    let since = *toast.since.get_or_insert(now);
+                    let opacity = if is_transient(toast) {
+                        ((lifetime - (now - since)) / FADE_OUT).clamp(0.0, 1.0) as f32
+                    } else {
+                        1.0
+                    };
+
+                    egui::Frame::popup(ui.style())
+                        .fill(TOAST_COLOR.linear_multiply(opacity))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.toggle_value(&mut toast.is_pinned, "📌")
+                                    .on_hover_text("Keep this error on screen");
+
+                                let text = egui::RichText::new(toast.err.to_string())
+                                    .color(egui::Color32::WHITE.linear_multiply(opacity));
+                                if ui
+                                    .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                    .on_hover_text("Click to dismiss")
+                                    .clicked()
+                                {
+                                    dismissed = Some(idx);
+                                }
+                            });
+                        });
+                }
+            });
+
+        if let Some(idx) = dismissed {
+            self.toasts.remove(idx);
+        }
+
+        let fade_out_at = self
+            .toasts
+            .iter()
+            .filter(|toast| is_transient(toast))
+            .filter_map(|toast| toast.since)
+            .map(|since| since + lifetime - FADE_OUT)
+            .reduce(f64::min);
+        match fade_out_at {
+            Some(fade_out_at) if fade_out_at <= now => {
+                // Fade out & expire the toasts
+                ctx.request_repaint();
+            }
+            Some(fade_out_at) => self.request_repaint_at(ctx, now, fade_out_at),
+            None => (),
+        }
+    }
+
+    /// Requests a repaint at `time` unless one is already scheduled by then.
+    ///
+    /// This spares repainting every frame until a toast starts fading out.
+    fn request_repaint_at(&mut self, ctx: &egui::Context, now: f64, time: f64) {
+        if self.repaint_at.map_or(false, |at| now < at && at <= time) {
+            return;
+        }
+        self.repaint_at = Some(time);
+
+        let ctx = ctx.clone();
+        let remaining = Duration::from_secs_f64(time - now);
+        std::thread::spawn(move || {
+            std::thread::sleep(remaining);
+            ctx.request_repaint();
+        });
+    }
+}
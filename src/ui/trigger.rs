@@ -0,0 +1,143 @@
+use eframe::egui;
+
+use super::{filter::Filter, msg_list::MsgParseResult};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    /// Waiting for the start condition.
+    Armed,
+    Capturing {
+        count: u32,
+    },
+    Stopped,
+}
+
+impl State {
+    fn as_string(self, max_msgs: u32) -> String {
+        match self {
+            State::Armed => "Armed".to_string(),
+            State::Capturing { count } if max_msgs > 0 => format!("Capturing {count}/{max_msgs}"),
+            State::Capturing { count } => format!("Capturing {count}"),
+            State::Stopped => "Stopped".to_string(),
+        }
+    }
+}
+
+/// Capture start and stop conditions, like an oscilloscope trigger.
+#[derive(Debug)]
+pub struct Trigger {
+    pub is_open: bool,
+    is_enabled: bool,
+    /// Capture starts with the first matching message, immediately if inactive.
+    start: Filter,
+    /// Capture stops after the first matching message, if active.
+    stop: Filter,
+    /// Capture stops after this number of messages, `0` for no limit.
+    max_msgs: u32,
+    state: State,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            is_enabled: false,
+            start: Filter::default(),
+            stop: Filter::default(),
+            max_msgs: 0,
+            state: State::Armed,
+        }
+    }
+}
+
+impl Trigger {
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    pub fn rearm(&mut self) {
+        self.state = State::Armed;
+    }
+
+    /// Returns whether `msg` must be captured, updating the trigger state.
+    pub fn accept(&mut self, msg: &MsgParseResult) -> bool {
+        if !self.is_enabled {
+            return true;
+        }
+
+        let count = match self.state {
+            State::Armed => {
+                if self.start.is_active() && !self.start.matches(msg) {
+                    return false;
+                }
+                1
+            }
+            State::Capturing { count } => count + 1,
+            State::Stopped => return false,
+        };
+
+        let must_stop = (self.max_msgs > 0 && count >= self.max_msgs)
+            || (self.stop.is_active() && self.stop.matches(msg));
+
+        self.state = if must_stop {
+            State::Stopped
+        } else {
+            State::Capturing { count }
+        };
+
+        true
+    }
+
+    /// Shows the trigger state in the message list controls.
+    pub fn show_state(&self, ui: &mut egui::Ui) {
+        if self.is_enabled {
+            let color = match self.state {
+                State::Armed => egui::Color32::YELLOW,
+                State::Capturing { .. } => egui::Color32::GREEN,
+                State::Stopped => egui::Color32::RED,
+            };
+            ui.colored_label(color, self.state.as_string(self.max_msgs));
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.is_open;
+        egui::Window::new("Capture trigger")
+            .open(&mut is_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if ui.checkbox(&mut self.is_enabled, "Enabled").changed() {
+                    self.rearm();
+                }
+
+                ui.add_enabled_ui(self.is_enabled, |ui| {
+                    egui::Grid::new("trigger").num_columns(2).show(ui, |ui| {
+                        ui.label("Start on");
+                        self.start.show(ui);
+                        ui.end_row();
+
+                        ui.label("Stop on");
+                        self.stop.show(ui);
+                        ui.end_row();
+
+                        ui.label("Stop after");
+                        ui.add(
+                            egui::DragValue::new(&mut self.max_msgs)
+                                .clamp_range(0..=u32::MAX)
+                                .suffix(" msg(s)"),
+                        )
+                        .on_hover_text("0 for no limit");
+                        ui.end_row();
+                    });
+
+                    ui.horizontal(|ui| {
+                        self.show_state(ui);
+                        if ui.button("Re-arm").clicked() {
+                            self.rearm();
+                        }
+                    });
+                });
+            });
+        self.is_open = is_open;
+    }
+}
@@ -0,0 +1,132 @@
+use crossbeam_channel as channel;
+use eframe::egui;
+
+use super::bus;
+use crate::midi::{
+    velocity::{self, Stats},
+    PortNb,
+};
+
+/// Number of velocities per histogram bar.
+const BIN_WIDTH: usize = 4;
+
+/// Histogram & running average of the Note On velocities per channel.
+pub struct VelocityPanel {
+    pub is_open: bool,
+    stats: Stats,
+    /// Port & channel (0 based) being shown.
+    selected: Option<(PortNb, u8)>,
+    msg_rx: channel::Receiver<bus::Event>,
+}
+
+impl VelocityPanel {
+    pub fn new(bus: &bus::Bus) -> Self {
+        Self {
+            is_open: false,
+            stats: Stats::default(),
+            selected: None,
+            msg_rx: bus.subscribe(bus::Event::is_msg_batch),
+        }
+    }
+
+    pub fn update(&mut self) {
+        bus::drain_msgs(&self.msg_rx, |origin| {
+            if let Some(channel) = self.stats.handle(origin.port_nb, &origin.buffer) {
+                self.selected.get_or_insert((origin.port_nb, channel));
+            }
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.is_open;
+        egui::Window::new("Velocity")
+            .open(&mut is_open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let selected_text = match self.selected {
+                        Some((port_nb, channel)) => channel_label(port_nb, channel),
+                        None => "No Note On".to_string(),
+                    };
+                    egui::ComboBox::from_id_source("velocity-channel")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (port_nb, channel) in self.stats.active() {
+                                ui.selectable_value(
+                                    &mut self.selected,
+                                    Some((port_nb, channel)),
+                                    channel_label(port_nb, channel),
+                                );
+                            }
+                        });
+
+                    if ui.button("Clear").clicked() {
+                        self.stats.clear();
+                        self.selected = None;
+                    }
+                });
+                ui.separator();
+
+                if let Some((port_nb, channel)) = self.selected {
+                    show_histogram(ui, self.stats.get(port_nb, channel));
+                }
+            });
+        self.is_open = is_open;
+    }
+}
+
+fn channel_label(port_nb: PortNb, channel: u8) -> String {
+    format!("{} ch{}", port_nb.as_str(), channel + 1)
+}
+
+fn show_histogram(ui: &mut egui::Ui, histogram: &velocity::Histogram) {
+    use egui::plot::{Bar, BarChart, Legend, Plot, VLine};
+
+    let fmt_avg = |avg: Option<f64>| avg.map_or_else(|| "-".to_string(), |avg| format!("{avg:.1}"));
+    let fmt_velocity = |v: Option<u8>| v.map_or_else(|| "-".to_string(), |v| v.to_string());
+
+    egui::Grid::new("velocity-stats")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (name, value) in [
+                ("Note On", histogram.total().to_string()),
+                ("Min", fmt_velocity(histogram.min())),
+                ("Max", fmt_velocity(histogram.max())),
+                ("Mean", fmt_avg(histogram.mean())),
+                ("Running average", fmt_avg(histogram.running_average())),
+            ] {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+
+    let bars = histogram
+        .counts()
+        .chunks(BIN_WIDTH)
+        .enumerate()
+        .map(|(idx, counts)| {
+            let center = (idx * BIN_WIDTH) as f64 + (BIN_WIDTH as f64 - 1.0) / 2.0;
+            Bar::new(center, counts.iter().sum::<u64>() as f64).width(BIN_WIDTH as f64)
+        })
+        .collect();
+
+    Plot::new("velocity-histogram")
+        .height(160.0)
+        .legend(Legend::default())
+        .include_x(0.0)
+        .include_x(127.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars).name("Note On"));
+            if let Some(mean) = histogram.mean() {
+                plot_ui.vline(VLine::new(mean).name("Mean"));
+            }
+            if let Some(avg) = histogram.running_average() {
+                plot_ui.vline(
+                    VLine::new(avg).name(format!("Last {} average", velocity::RUNNING_AVERAGE_LEN)),
+                );
+            }
+        });
+}
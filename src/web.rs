@@ -0,0 +1,17 @@
+//! Web entry point, using Web MIDI through `midir`.
+
+use wasm_bindgen::prelude::*;
+
+const APP_NAME: &str = "MIDI sniffer";
+
+/// Starts the sniffer in the canvas with id `canvas_id`.
+#[wasm_bindgen]
+pub fn start(canvas_id: &str) -> Result<(), JsValue> {
+    console_error_panic_hook::set_once();
+    let _ = console_log::init_with_level(log::Level::Debug);
+
+    eframe::start_web(
+        canvas_id,
+        Box::new(|cc| Box::new(crate::ui::App::new(APP_NAME, Default::default(), cc))),
+    )
+}
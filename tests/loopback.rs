@@ -0,0 +1,222 @@
+//! End-to-end tests of the capture pipeline using a virtual loopback port.
+//!
+//! These tests require the ALSA sequencer (`snd-seq` kernel module).
+
+#![cfg(all(target_os = "linux", not(feature = "jack")))]
+
+use crossbeam_channel as channel;
+use midir::os::unix::VirtualOutput;
+use std::time::Duration;
+
+use midi_sniffer::{
+    midi::{self, PortNb},
+    ui::{bus, MsgListPanel},
+};
+
+const CLIENT_NAME: &str = "midi-sniffer-test";
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Loopback {
+    out: midir::MidiOutputConnection,
+    // Keep the input connection alive
+    _ports: midi::Ports,
+    origin_rx: channel::Receiver<midi::msg::Origin>,
+    msg_list_panel: MsgListPanel,
+}
+
+impl Loopback {
+    fn new(name: &str) -> Self {
+        let out = midir::MidiOutput::new(&format!("loopback {name}"))
+            .expect("Couldn't create Midi output. Is the snd-seq module loaded?")
+            .create_virtual(name)
+            .expect("Couldn't create virtual output");
+
+        let mut ports = midi::Ports::try_new(CLIENT_NAME.into(), Default::default()).unwrap();
+        ports.refresh().unwrap();
+        let port_name = ports
+            .list()
+            .find(|port_name| port_name.contains(name))
+            .cloned()
+            .expect("virtual port not found");
+
+        let (origin_tx, origin_rx) = channel::unbounded();
+        ports
+            .connect(PortNb::One, port_name, move |ts, buf| {
+                let _ = origin_tx.send(midi::msg::Origin::new(ts, PortNb::One, buf));
+            })
+            .unwrap();
+
+        Loopback {
+            out,
+            _ports: ports,
+            origin_rx,
+            msg_list_panel: MsgListPanel::new(&bus::Bus::default(), Default::default(), None),
+        }
+    }
+
+    /// Sends `msgs` through the loopback and pushes the results to the list.
+    fn exchange(&mut self, msgs: &[&[u8]]) {
+        for msg in msgs {
+            self.out.send(msg).unwrap();
+        }
+
+        for _ in msgs {
+            let origin = self
+                .origin_rx
+                .recv_timeout(RECV_TIMEOUT)
+                .expect("message not received");
+            let _ = self.msg_list_panel.push(&midi::msg::parse(origin));
+        }
+    }
+}
+
+#[test]
+fn note_on_off() {
+    let mut loopback = Loopback::new("note_on_off");
+    loopback.exchange(&[&[0x90, 0x3c, 0x64], &[0x80, 0x3c, 0x40]]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 2);
+
+    assert_eq!(list[0].port_nb(), PortNb::One);
+    assert!(!list[0].is_err());
+    assert_eq!(list[0].parsed_str(), "Ch1 Voice Note 60 On vel. 100");
+    assert_eq!(list[0].raw_str(), "90, 3c, 64");
+
+    assert!(!list[1].is_err());
+    assert_eq!(list[1].parsed_str(), "Ch1 Voice Note 60 Off vel. 64");
+    assert_eq!(list[1].raw_str(), "80, 3c, 40");
+}
+
+#[test]
+fn channel_10() {
+    let mut loopback = Loopback::new("channel_10");
+    loopback.exchange(&[&[0x99, 0x24, 0x7f]]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].parsed_str(), "Ch10 Voice Note 36 On vel. 127");
+    assert_eq!(list[0].raw_str(), "99, 24, 7f");
+}
+
+#[test]
+fn generator() {
+    use midi::generator::Kind;
+
+    let mut loopback = Loopback::new("generator");
+    let cc = Kind::ControlChange.build(2, 74, 127);
+    let bend = Kind::PitchBend.build(0, 8_191, 0);
+    loopback.exchange(&[&cc, &bend]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].raw_str(), "b2, 4a, 7f");
+    assert_eq!(list[1].raw_str(), "e0, 7f, 7f");
+    assert!(!list[1].is_err());
+}
+
+#[test]
+fn repetitions() {
+    let mut loopback = Loopback::new("repetitions");
+    loopback.exchange(&[&[0xf8], &[0xf8], &[0xf8], &[0xfa]]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 2);
+
+    assert_eq!(list[0].parsed_str(), "SysRT Timing Clock");
+    assert_eq!(list[0].repetitions(), 3);
+
+    assert_eq!(list[1].parsed_str(), "SysRT Start");
+    assert_eq!(list[1].repetitions(), 1);
+}
+
+#[test]
+fn sysex() {
+    let mut loopback = Loopback::new("sysex");
+    let sysex: &[u8] = &[0xf0, 0x7d, 0x01, 0x02, 0x03, 0xf7];
+    loopback.exchange(&[sysex]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 1);
+    assert!(!list[0].is_err());
+    assert!(list[0].parsed_str().starts_with("SysEx Non-com."));
+    assert_eq!(list[0].raw_str(), "f0, 7d, 01, 02, 03, f7");
+}
+
+#[test]
+fn identity_reply() {
+    let mut loopback = Loopback::new("identity_reply");
+    let reply: &[u8] = &[
+        0xf0, 0x7e, 0x10, 0x06, 0x02, 0x41, 0x2b, 0x02, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0xf7,
+    ];
+    loopback.exchange(&[reply]);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 1);
+    assert_eq!(
+        list[0].annotations(),
+        ["Identity: Roland, family 012b, model 0000, firmware 1.2.3.4"]
+    );
+}
+
+#[test]
+fn syx_file() {
+    let data = [0xf0, 0x7d, 0x01, 0xf7, 0xf0, 0x7d, 0x02, 0x03, 0xf7];
+    let msgs = midi::syx::split(&data).unwrap();
+    assert_eq!(msgs.len(), 2);
+    assert!(midi::syx::split(&data[..7]).is_err());
+
+    let mut loopback = Loopback::new("syx_file");
+    let msgs: Vec<&[u8]> = msgs.iter().map(AsRef::as_ref).collect();
+    loopback.exchange(&msgs);
+
+    let list = &loopback.msg_list_panel.list;
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].raw_str(), "f0, 7d, 01, f7");
+    assert_eq!(list[1].raw_str(), "f0, 7d, 02, 03, f7");
+}
+
+#[cfg(feature = "save")]
+#[test]
+fn ron_export() {
+    use midi_sniffer::ui::msg_list;
+
+    let mut loopback = Loopback::new("ron_export");
+    loopback.exchange(&[&[0x90, 0x3c, 0x64], &[0xf8], &[0xf8]]);
+
+    let mut output = Vec::new();
+    msg_list::write_ron(&mut output, loopback.msg_list_panel.list.iter()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    assert!(lines[0].contains("port: One"));
+    assert!(lines[0].contains(r#"parsed: "Ch1 Voice Note 60 On vel. 100""#));
+    assert!(lines[0].contains(r#"raw: "(hex) 90, 3c, 64""#));
+
+    assert!(lines[1].contains("repetitions: 2"));
+    assert!(lines[1].contains(r#"parsed: "SysRT Timing Clock""#));
+    assert!(lines[1].contains(r#"raw: "(hex) f8""#));
+}
+
+#[cfg(feature = "save")]
+#[test]
+fn ron_replay_load() {
+    use midi_sniffer::ui::{msg_list, replay};
+
+    let mut loopback = Loopback::new("ron_replay_load");
+    loopback.exchange(&[&[0x90, 0x3c, 0x64], &[0xf8], &[0xf8]]);
+
+    let mut output = Vec::new();
+    msg_list::write_ron(&mut output, loopback.msg_list_panel.list.iter()).unwrap();
+
+    let loaded = replay::read_ron(output.as_slice()).unwrap();
+    assert_eq!(loaded.len(), 3);
+    assert!(loaded.iter().all(|loaded| loaded.port_nb == PortNb::One));
+
+    assert_eq!(loaded[0].event.buffer.as_ref(), &[0x90, 0x3c, 0x64]);
+    assert_eq!(loaded[1].event.buffer.as_ref(), &[0xf8]);
+    assert_eq!(loaded[2].event.buffer.as_ref(), &[0xf8]);
+    assert!(loaded[0].event.ts <= loaded[2].event.ts);
+}